@@ -1,17 +1,168 @@
-use std::{future::Future, sync::Arc, time::Duration};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use anyhow::{Error, Result};
+use anyhow::Result;
+use chrono::Datelike;
 use eframe::egui;
-use egui_async::{Bind, EguiAsyncPlugin};
 use tracing::{error, info};
 
-use crate::config::{self, AppConfig, UserConfig};
-use crate::db::{Credentials, Db, LoginSession};
-use crate::theme::Theme;
+use crate::config::{
+    self, AppConfig, CurrencyDef, DailyReward, DashboardTab, Feature, FontSizes, IntegrityMode,
+    KeyBindings, LaunchMode, ProxyMode, Realm, Renderer, Shortcut, UpdateChannel, UserConfig,
+};
+use crate::crashlog::CrashReport;
+use crate::db::{
+    AccountNote, AuditAction, AuditRecord, Avatar, BalanceSnapshot, Banned, Character,
+    CharacterInfo, Credentials, Db, DbPool, Difficulty, DungeonClear, GuildMember, GuildRank,
+    LoginSession, Mail, ResetPassword, SendRecord, UpdateRequired, AWAKENINGS, MAX_CHAR_SLOTS,
+    PIN_LENGTH, friendly_error, is_connection_error, unreachable_pool,
+};
+use crate::desktop;
+use crate::email::{self, Mailer};
+use crate::equipment::Equipment;
+use crate::hosts::HostsCheck;
+use crate::http;
+use crate::ipc::IpcListener;
+use crate::items::{ItemDb, Rarity};
+use crate::launch::{self, FolderCheck};
+use crate::legacy::{self, LegacyConfig};
+use crate::locale::{self, Clock, NumberFormat};
+use crate::maintenance;
+use crate::markdown;
+use crate::monitor::GameMonitor;
+use crate::news::NewsItem;
+use crate::patcher::{self, LauncherRelease, PatchJob, PatchPlan};
+use crate::ping::{Ping, PingMonitor};
+use crate::protocol::{LaunchLink, LinkAction};
+use crate::registry;
+use crate::service::{
+    ClientMismatch, Command, Event, RemoteManifest, Service,
+};
+use crate::sound::{Sound, SoundPlayer};
+use crate::startup::{Check, CheckState, StartupChecks};
+use crate::support::{self, SupportInfo};
+use crate::taskbar::{Taskbar, TaskbarState};
+use crate::theme::{StatusPalette, Theme, ThemeVariant};
+use crate::watchdog::ConnectionWatchdog;
 
+#[derive(Clone, Copy, PartialEq, Eq)]
 enum Screen {
+    /// Startup checks, shown until they pass or the player continues past a failure.
+    Splash,
     Login,
     Dashboard,
+    Admin,
+    Settings,
+    /// Server rules, accepted before the first account is created.
+    Terms,
+    /// Shown instead of signing in while this build is older than the server allows.
+    UpdateRequired,
+}
+
+/// Where admin account backups are written, relative to the launcher's directory.
+const BACKUP_DIR: &str = "backups";
+
+/// Where crash reports are saved, relative to the launcher's directory.
+const CRASH_DIR: &str = "crash_reports";
+
+/// Where support bundles are saved, relative to the launcher's directory.
+const SUPPORT_DIR: &str = "support";
+
+/// Pause between database probes while the connection is down.
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Typing pause before the sign-in form checks whether the account name is taken.
+const USERNAME_CHECK_DELAY: Duration = Duration::from_millis(500);
+
+/// How long a gold/cera send can still be taken back.
+const UNDO_WINDOW: Duration = Duration::from_secs(30);
+
+/// An account kept signed in while another one is in use, with the client it launched.
+struct ParkedSession {
+    creds: Credentials,
+    session: LoginSession,
+    game: Option<GameMonitor>,
+}
+
+struct LastSend {
+    /// Currency name, as shown in the undo prompt.
+    currency: String,
+    amount: i32,
+    /// Audit id the undo reverses.
+    send_id: i64,
+    at: Instant,
+}
+
+#[derive(Default)]
+struct CeraTransfer {
+    recipient: String,
+    amount: String,
+}
+
+#[derive(Default)]
+struct EmailForm {
+    address: String,
+    code: String,
+}
+
+#[derive(Default)]
+struct PasswordReset {
+    code: String,
+    password: String,
+}
+
+#[derive(Default)]
+struct PinForm {
+    pin: String,
+    confirm: String,
+}
+
+#[derive(Default)]
+struct RecoveryLogin {
+    code: String,
+    password: String,
+}
+
+#[derive(Default)]
+struct AdminForm {
+    account_uid: String,
+    item_id: String,
+    item_quantity: String,
+    creature_id: String,
+    target: Option<CharacterInfo>,
+    level: String,
+    quest_id: String,
+    inventory_slots: String,
+    stash_tabs: String,
+    slot_limit: String,
+    advancement: i32,
+    awakening: i32,
+    guild_id: String,
+    guild_name: String,
+    char_id: String,
+    guild_level: String,
+    guild_funds: String,
+    confirm_disband: bool,
+    transfer_to_uid: String,
+    merge_from_uid: String,
+    merge_to_uid: String,
+    confirm_merge: bool,
+    backup_path: String,
+    confirm_restore: bool,
+    /// Account UIDs for the bulk password reset, separated by commas, spaces or lines.
+    reset_uids: String,
+    confirm_reset: bool,
+    /// Passwords from the last bulk reset; shown until hidden, then only in the export.
+    reset_passwords: Option<Vec<ResetPassword>>,
+    /// Account the note editor belongs to, and the note as last loaded.
+    note_uid: Option<i32>,
+    note: String,
+    note_saved: Option<AccountNote>,
+    audit: Option<Vec<AuditRecord>>,
+    audit_selected: Option<i64>,
 }
 
 enum StatusKind {
@@ -25,40 +176,150 @@ struct Status {
     message: String,
 }
 
-enum AppAction {
-    LoginSuccess {
-        session: LoginSession,
-        remember: bool,
-    },
-    SessionUpdated {
-        session: LoginSession,
-        message: String,
-    },
-    AccountCreated,
-}
-
 pub struct LauncherApp {
     db: Arc<Db>,
+    items: Arc<ItemDb>,
     app_config: AppConfig,
     config: UserConfig,
     screen: Screen,
+    /// Screens to return to with Back/Esc, most recent last.
+    history: Vec<Screen>,
     status: Status,
     creds: Credentials,
     remember: bool,
     amount: String,
     selected_char: Option<usize>,
+    tab: DashboardTab,
+    guild_members: Option<(i32, Vec<GuildMember>)>,
+    /// Balance snapshots of the signed-in account, dropped whenever the session reloads.
+    balance_history: Option<Vec<BalanceSnapshot>>,
+    mail: Option<(i32, Vec<Mail>)>,
+    avatars: Option<(i32, Vec<Avatar>)>,
+    equipment: Vec<Equipment>,
+    dungeon_clears: Option<(i32, Vec<DungeonClear>)>,
+    sends: Option<(i32, Vec<SendRecord>)>,
+    /// Index into the configured currencies; `None` shows the gold and cera buttons.
+    currency: Option<usize>,
+    /// Last loaded balance as (currency name, owner, balance).
+    currency_balance: Option<(String, i32, i64)>,
+    news: Option<Vec<NewsItem>>,
     current_session: Option<LoginSession>,
-    action_bind: Bind<AppAction, Error>,
+    /// Other signed-in accounts when multi-clienting is allowed.
+    parked: Vec<ParkedSession>,
+    ban_notice: Option<Banned>,
+    admin: AdminForm,
+    /// Open "send cera to another account" dialog.
+    transfer: Option<CeraTransfer>,
+    discord_code: String,
+    email_form: EmailForm,
+    pin_form: PinForm,
+    /// Open "forgot password" dialog.
+    password_reset: Option<PasswordReset>,
+    /// Open "sign in with a recovery code" dialog.
+    recovery_login: Option<RecoveryLogin>,
+    /// Set when the pre-launch check found an outdated client; asks before launching.
+    client_mismatch: Option<ClientMismatch>,
+    /// Settings files of older launchers found by the importer.
+    legacy: Option<Vec<LegacyConfig>>,
+    /// Logs gathered after the game crashed, waiting to be saved or sent.
+    crash_report: Option<CrashReport>,
+    /// Last hosts file check, one entry per configured redirect.
+    hosts_checks: Option<Vec<HostsCheck>>,
+    /// Game folder checklist, kept while something is missing to show the guidance dialog.
+    folder_checks: Option<Vec<FolderCheck>>,
+    /// Client executable path being edited in that dialog.
+    game_path_input: String,
+    /// Description being written in the "Report a problem" dialog.
+    problem_report: Option<String>,
+    /// Most recent background error with its cause chain, for support bundles.
+    last_error: Option<String>,
+    /// Also place new launcher shortcuts in the Start menu.
+    shortcut_start_menu: bool,
+    /// Freshly generated recovery codes; shown once and dropped on the next login.
+    recovery_codes: Option<Vec<String>>,
+    /// Most recent gold/cera send, offered for undo within [`UNDO_WINDOW`].
+    last_send: Option<LastSend>,
+    ping: Option<PingMonitor>,
+    /// Running while the database is unreachable; the status bar shows the launcher offline.
+    watchdog: Option<ConnectionWatchdog>,
+    /// Checks behind the splash screen; dropped once they pass or the splash is left.
+    startup: Option<StartupChecks>,
+    game: Option<GameMonitor>,
+    ipc: Option<IpcListener>,
+    update_plan: Option<PatchPlan>,
+    launcher_release: Option<LauncherRelease>,
+    patch: Option<PatchJob>,
+    sounds: SoundPlayer,
+    taskbar: Taskbar,
+    /// Last status message a sound was played for.
+    sounded_status: String,
+    /// Launch the game once the in-flight login from an `adnf://play` link succeeds.
+    pending_play: bool,
+    /// Check the launcher manifest once the startup schema migration has answered.
+    pending_release_check: bool,
+    /// Shortcut waiting for its new key in the settings screen.
+    capturing_key: Option<Shortcut>,
+    /// Window level last sent to the viewport, so the pin setting is applied once per change.
+    pinned: Option<bool>,
+    /// Last keyboard or pointer input, for the idle sign-out.
+    last_input: Instant,
+    /// Last time the session was loaded, for the auto-refresh.
+    last_refresh: Instant,
+    /// Scroll the character list to the remembered offset the next time it is drawn.
+    restore_scroll: bool,
+    /// When the account name was last edited, until its availability check is sent.
+    username_edited: Option<Instant>,
+    /// Result of the last availability check and the name it was for.
+    username_available: Option<(String, bool)>,
+    /// Ticked on the rules screen; registration is blocked until then when rules are set.
+    terms_accepted: bool,
+    /// Minimum launcher version the server or update manifest demands, once it exceeds ours.
+    update_required: Option<String>,
+    /// Launch even though the account is already in game elsewhere.
+    play_anyway: bool,
+    confirm_force_logout: bool,
+    confirm_clear_cache: bool,
+    /// Frame timing and repaint overlay, toggled with F12 to chase idle GPU use.
+    debug_overlay: bool,
+    /// Text layouts egui had cached on the previous frame, for the overlay's churn hint.
+    cached_galleys: usize,
+    service: Service,
 }
 
 impl LauncherApp {
-    pub fn new(app_config: AppConfig, db: Arc<Db>) -> Self {
+    pub fn new(
+        ctx: egui::Context,
+        mut app_config: AppConfig,
+        db: Arc<Db>,
+        items: Arc<ItemDb>,
+        ipc: Option<IpcListener>,
+        link: Option<LaunchLink>,
+    ) -> Self {
         let config: UserConfig =
             config::read_json("config.json").unwrap_or_default();
-        Self {
+        app_config.apply_overrides(&config);
+        let realm = app_config.realm(config.realm.as_deref());
+        db.set_realm(realm);
+        let ping = spawn_ping(&app_config, realm);
+        let mailer = app_config.smtp.as_ref().and_then(|smtp| {
+            Mailer::new(smtp)
+                .inspect_err(|err| tracing::warn!("email: SMTP relay unavailable: {err:#}"))
+                .ok()
+        });
+        locale::install_fallback_font(&ctx);
+        let startup = StartupChecks::spawn(
+            Arc::clone(&db),
+            app_config.dnf_exe_path.clone(),
+            app_config.required_game_files.clone(),
+            ctx.clone(),
+        );
+        let service = Service::spawn(Arc::clone(&db), mailer, ctx);
+        let mut app = Self {
             db,
+            items,
             app_config,
-            screen: Screen::Login,
+            screen: Screen::Splash,
+            history: Vec::new(),
             status: Status {
                 kind: StatusKind::Info,
                 message: "Ready".to_string(),
@@ -71,57 +332,659 @@ impl LauncherApp {
             config,
             amount: String::new(),
             selected_char: None,
+            tab: DashboardTab::Characters,
+            guild_members: None,
+            balance_history: None,
+            mail: None,
+            avatars: None,
+            equipment: Vec::new(),
+            dungeon_clears: None,
+            sends: None,
+            currency: None,
+            currency_balance: None,
+            news: None,
             current_session: None,
-            action_bind: Bind::new(false),
+            parked: Vec::new(),
+            ban_notice: None,
+            admin: AdminForm::default(),
+            transfer: None,
+            discord_code: String::new(),
+            email_form: EmailForm::default(),
+            pin_form: PinForm::default(),
+            password_reset: None,
+            recovery_login: None,
+            client_mismatch: None,
+            legacy: None,
+            crash_report: None,
+            hosts_checks: None,
+            folder_checks: None,
+            game_path_input: String::new(),
+            problem_report: None,
+            last_error: None,
+            shortcut_start_menu: false,
+            recovery_codes: None,
+            last_send: None,
+            ping,
+            watchdog: None,
+            startup: Some(startup),
+            game: None,
+            ipc,
+            update_plan: None,
+            launcher_release: None,
+            patch: None,
+            sounds: SoundPlayer::default(),
+            taskbar: Taskbar::default(),
+            sounded_status: String::new(),
+            pending_play: false,
+            pending_release_check: false,
+            pinned: None,
+            last_input: Instant::now(),
+            last_refresh: Instant::now(),
+            restore_scroll: true,
+            username_edited: None,
+            username_available: None,
+            terms_accepted: false,
+            update_required: None,
+            play_anyway: false,
+            confirm_force_logout: false,
+            confirm_clear_cache: false,
+            debug_overlay: false,
+            cached_galleys: 0,
+            capturing_key: None,
+            service,
+        };
+        if app.app_config.client_registry.is_some() && !app.config.registry_written {
+            app.write_registry();
+        }
+        app.validate_game_folder();
+        if let Some(link) = link {
+            app.handle_link(link);
+        }
+        // A link's login takes precedence; migrations run on the next admin-mode start.
+        if app.app_config.admin_mode && !app.service.is_busy() {
+            let result = app.migrate_schema();
+            app.check_status(result);
+        }
+        // Picks up a minimum version from the launcher manifest before anyone signs in, once
+        // the migration or a link's login has answered.
+        if app.service.is_busy() {
+            app.pending_release_check = true;
+        } else {
+            app.check_launcher_release();
+        }
+        app
+    }
+
+    fn migrate_schema(&mut self) -> Result<(), Status> {
+        tracing::info!("ui: schema migration requested");
+        self.send(Command::MigrateSchema)
+    }
+
+    fn poll_ipc(&mut self, ctx: &egui::Context) {
+        while let Some(args) = self.ipc.as_ref().and_then(IpcListener::try_recv) {
+            info!("ui: second launch forwarded {} argument(s)", args.len());
+            ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+            ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+            if let Some(link) = args.iter().find_map(|arg| LaunchLink::parse(arg)) {
+                self.handle_link(link);
+            }
+        }
+    }
+
+    fn handle_link(&mut self, link: LaunchLink) {
+        info!("ui: handling {:?} link", link.action);
+        if let Some(realm) = link.realm {
+            let current = &self.app_config.realm(self.config.realm.as_deref()).name;
+            if !self.app_config.realms.iter().any(|r| r.name == realm) {
+                self.status = Status::error(format!("Unknown realm: {realm}"));
+                return;
+            }
+            if *current != realm {
+                self.current_session = None;
+                self.reset_screen(Screen::Login);
+                self.switch_realm(realm);
+            }
+        }
+        if let Some(channel) = link.channel {
+            self.config.preferred_channel = Some(channel);
+            let _ = config::write_json("config.json", &self.config);
+        }
+        if link.action != LinkAction::Play {
+            return;
+        }
+        if self.current_session.is_some() {
+            self.launch_game();
+        } else if self.config.remember && !self.config.username.is_empty() {
+            self.creds = Credentials {
+                username: self.config.username.clone(),
+                password: self.config.password.clone(),
+            };
+            let result = self.login();
+            self.pending_play = self.check_status(result).is_some();
+        } else {
+            self.status = Status::info("Sign in to play");
+        }
+    }
+
+    /// Opens `screen`, remembering the current one for Back. Going to a screen that is already
+    /// in the history unwinds to it rather than stacking a loop.
+    fn navigate(&mut self, screen: Screen) {
+        if self.screen == screen {
+            return;
+        }
+        if let Some(pos) = self.history.iter().position(|s| *s == screen) {
+            self.history.truncate(pos);
+        } else {
+            self.history.push(self.screen);
+        }
+        self.screen = screen;
+    }
+
+    /// Returns to the previous screen; form and list state lives on `self`, so it is intact.
+    fn go_back(&mut self) {
+        self.screen = match self.history.pop() {
+            Some(screen) => screen,
+            None if self.current_session.is_some() => Screen::Dashboard,
+            None => Screen::Login,
+        };
+    }
+
+    /// Replaces the screen outright, e.g. after signing in or out, so Back can't lead into a
+    /// stale session.
+    fn reset_screen(&mut self, screen: Screen) {
+        self.history.clear();
+        self.screen = screen;
+    }
+
+    fn dialog_open(&self) -> bool {
+        self.transfer.is_some()
+            || self.password_reset.is_some()
+            || self.recovery_login.is_some()
+            || self.client_mismatch.is_some()
+            || self.crash_report.is_some()
+            || self.folder_checks.is_some()
+            || self.problem_report.is_some()
+    }
+
+    fn handle_back_key(&mut self, ctx: &egui::Context) {
+        // Esc belongs to open dialogs and focused text fields first.
+        let blocked =
+            self.dialog_open() || self.capturing_key.is_some() || ctx.wants_keyboard_input();
+        if self.history.is_empty() || blocked {
+            return;
+        }
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Escape)) {
+            self.go_back();
         }
     }
 
-    fn process_async(&mut self, ctx: &egui::Context) {
-        if let Some(result) = self.action_bind.take() {
+    /// Runs the actions bound in [`KeyBindings`]; typing in a field or a dialog takes priority.
+    fn handle_shortcuts(&mut self, ctx: &egui::Context) {
+        if self.dialog_open() || self.capturing_key.is_some() || ctx.wants_keyboard_input() {
+            return;
+        }
+        let pressed = Shortcut::ALL.into_iter().find(|shortcut| {
+            egui::Key::from_name(self.config.keybindings.key(*shortcut))
+                .is_some_and(|key| ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, key)))
+        });
+        let Some(shortcut) = pressed else {
+            return;
+        };
+        tracing::debug!("ui: shortcut {}", shortcut.label());
+        let signed_in = self.current_session.is_some();
+        match shortcut {
+            Shortcut::Launch if signed_in && !self.service.is_busy() => self.launch_game(),
+            Shortcut::Refresh if signed_in => {
+                let result = self.refresh();
+                self.check_status(result);
+            }
+            Shortcut::NextTab if matches!(self.screen, Screen::Dashboard) => {
+                let tabs = self.dashboard_tabs();
+                let next = tabs.iter().position(|tab| *tab == self.tab).map_or(0, |i| i + 1);
+                self.open_tab(tabs[next % tabs.len()]);
+            }
+            Shortcut::Settings if matches!(self.screen, Screen::Settings) => self.go_back(),
+            Shortcut::Settings => self.navigate(Screen::Settings),
+            _ => {}
+        }
+    }
+
+    /// Plays a click for any widget clicked this frame and a cue when the status changes.
+    fn play_sounds(&mut self, ctx: &egui::Context) {
+        if !self.config.sound.enabled {
+            return;
+        }
+        let volume = self.config.sound.volume;
+        let clicked = ctx.output(|o| {
+            o.events.iter().any(|e| matches!(e, egui::output::OutputEvent::Clicked(_)))
+        });
+        let mut cue = clicked.then_some(Sound::Click);
+        if self.sounded_status != self.status.message {
+            self.sounded_status.clone_from(&self.status.message);
+            match self.status.kind {
+                StatusKind::Success => cue = Some(Sound::Success),
+                StatusKind::Error => cue = Some(Sound::Error),
+                StatusKind::Info => {}
+            }
+        }
+        if let Some(sound) = cue {
+            self.sounds.play(sound, volume);
+        }
+    }
+
+    fn write_registry(&mut self) {
+        let Some(registry) = &self.app_config.client_registry else {
+            return;
+        };
+        let realm = self.app_config.realm(self.config.realm.as_deref());
+        let game_dir = launch::game_dir(&self.app_config.dnf_exe_path);
+        match registry::write(
+            registry,
+            &self.app_config.launch,
+            &game_dir,
+            realm.game_server_addr.as_deref(),
+        ) {
+            Ok(count) => {
+                info!("registry: wrote {count} client value(s) under {}", registry.key);
+                self.config.registry_written = true;
+                let _ = config::write_json("config.json", &self.config);
+                self.status = Status::success("Client registry configured");
+            }
+            Err(err) => {
+                error!("registry: {err:#}");
+                self.status = Status::error(format!("Registry setup failed: {err}"));
+            }
+        }
+    }
+
+    fn process_events(&mut self, ctx: &egui::Context) {
+        while let Some(checked) = self.service.try_recv_username() {
+            self.username_available = Some(checked);
+        }
+        if let Some(result) = self.service.try_recv() {
             match result {
-                Ok(action) => self.apply_action(action),
-                Err(err) => self.status = Status::error(err.to_string()),
+                Ok(event) => self.apply_event(event),
+                Err(err) => {
+                    self.pending_play = false;
+                    self.last_error = Some(format!("{err:#}"));
+                    if let Some(banned) = err.downcast_ref::<Banned>() {
+                        self.ban_notice = Some(banned.clone());
+                        self.status = Status::error("Account banned");
+                    } else if let Some(required) = err.downcast_ref::<UpdateRequired>() {
+                        self.status = Status::error(required.to_string());
+                        self.require_update(required.min_version.clone());
+                    } else {
+                        let friendly = friendly_error(&err).map(str::to_string);
+                        self.status = Status::error(friendly.unwrap_or_else(|| err.to_string()));
+                    }
+                    if is_connection_error(&err) && self.watchdog.is_none() {
+                        tracing::warn!("ui: database unreachable, starting watchdog");
+                        let db = Arc::clone(&self.db);
+                        let pool = unreachable_pool(&err);
+                        let ctx = ctx.clone();
+                        let watchdog =
+                            ConnectionWatchdog::spawn(db, pool, RECONNECT_INTERVAL, ctx);
+                        self.watchdog = Some(watchdog);
+                    }
+                }
+            }
+            if self.pending_release_check && !self.service.is_busy() {
+                self.pending_release_check = false;
+                self.check_launcher_release();
             }
             ctx.request_repaint();
         }
     }
 
-    fn apply_action(&mut self, action: AppAction) {
-        match action {
-            AppAction::LoginSuccess {
+    /// Moves on to the login screen once every startup check has passed. A failed database
+    /// check starts the reconnection watchdog.
+    fn poll_startup(&mut self, ctx: &egui::Context) {
+        let Some(startup) = &self.startup else {
+            return;
+        };
+        if !startup.is_done() {
+            return;
+        }
+        let offline = startup
+            .states()
+            .iter()
+            .any(|(check, state)| *check == Check::Database && *state != CheckState::Passed);
+        if offline && self.watchdog.is_none() {
+            let db = Arc::clone(&self.db);
+            let ctx = ctx.clone();
+            let watchdog = ConnectionWatchdog::spawn(db, DbPool::Main, RECONNECT_INTERVAL, ctx);
+            self.watchdog = Some(watchdog);
+        }
+        // A link or the update check may already have moved past the splash.
+        if self.screen != Screen::Splash {
+            self.startup = None;
+        } else if startup.all_passed() {
+            tracing::info!("ui: startup checks passed");
+            self.startup = None;
+            self.reset_screen(Screen::Login);
+        }
+    }
+
+    fn restart_startup_checks(&mut self, ctx: &egui::Context) {
+        tracing::info!("ui: startup checks restarted");
+        self.startup = Some(StartupChecks::spawn(
+            Arc::clone(&self.db),
+            self.app_config.dnf_exe_path.clone(),
+            self.app_config.required_game_files.clone(),
+            ctx.clone(),
+        ));
+    }
+
+    /// Refreshes the session once the watchdog reaches the database again.
+    fn poll_watchdog(&mut self) {
+        let Some(watchdog) = &self.watchdog else {
+            return;
+        };
+        if !watchdog.is_restored() || self.service.is_busy() {
+            return;
+        }
+        self.watchdog = None;
+        if self.current_session.is_none() {
+            self.status = Status::success("Connection restored");
+            return;
+        }
+        self.status = Status::info("Connection restored, refreshing...");
+        let result = self.refresh();
+        self.check_status(result);
+    }
+
+    fn apply_event(&mut self, event: Event) {
+        match event {
+            Event::LoginSuccess {
                 session,
                 remember,
             } => {
+                self.last_refresh = Instant::now();
                 if remember {
-                    self.config.username = self.creds.username.clone();
-                    self.config.password = self.creds.password.clone();
-                    self.config.remember = true;
+                    self.config.remember_account(&self.creds.username, &self.creds.password);
                     let _ = config::write_json("config.json", &self.config);
                 }
+                // Signing in again to a parked account takes over its running client.
+                let username = &self.creds.username;
+                if let Some(idx) = self.parked.iter().position(|p| p.creds.username == *username)
+                {
+                    let parked = self.parked.remove(idx);
+                    self.game = self.game.take().or(parked.game);
+                }
+                if let Some(online) = session.online.as_ref().filter(|_| self.game.is_none()) {
+                    tracing::info!("ui: account already in game (channel {:?})", online.channel);
+                }
                 self.current_session = Some(session);
-                self.screen = Screen::Dashboard;
+                self.recovery_codes = None;
+                self.ban_notice = None;
+                self.play_anyway = false;
+                self.confirm_force_logout = false;
+                self.reset_screen(Screen::Dashboard);
                 self.status = Status::success("Login successful");
-                self.selected_char = None;
+                self.clear_account_view();
+                if std::mem::take(&mut self.pending_play) {
+                    self.launch_game();
+                }
             }
-            AppAction::SessionUpdated { session, message } => {
+            Event::SessionUpdated { session, message } => {
+                if session.discord_id.is_some() {
+                    self.discord_code.clear();
+                }
+                if session.email.is_some() {
+                    self.email_form = EmailForm::default();
+                }
                 self.current_session = Some(session);
+                self.balance_history = None;
+                self.last_refresh = Instant::now();
                 self.status = Status::success(message);
             }
-            AppAction::AccountCreated => {
+            Event::CurrencySent {
+                session,
+                currency,
+                amount,
+                send_id,
+            } => {
+                self.current_session = Some(session);
+                self.balance_history = None;
+                self.status =
+                    Status::success(format!("Sent {amount} {}! Data refreshed", currency.as_str()));
+                // A dry-run send was rolled back, so there is nothing to undo.
+                if !self.app_config.dry_run {
+                    self.last_send = Some(LastSend {
+                        currency: currency.as_str().to_string(),
+                        amount,
+                        send_id,
+                        at: Instant::now(),
+                    });
+                }
+                self.sends = None;
+            }
+            Event::CurrencyLoaded {
+                name,
+                owner,
+                balance,
+                sent,
+            } => {
+                self.status = match sent {
+                    Some((amount, _)) => Status::success(format!("Sent {amount} {name}")),
+                    None => Status::success(format!("{name} balance loaded")),
+                };
+                if let Some((amount, send_id)) = sent {
+                    self.last_send = Some(LastSend {
+                        currency: name.clone(),
+                        amount,
+                        send_id,
+                        at: Instant::now(),
+                    });
+                }
+                self.currency_balance = Some((name, owner, balance));
+            }
+            Event::AccountCreated => {
+                self.username_available = Some((self.creds.username.clone(), false));
+                if self.screen == Screen::Terms {
+                    self.go_back();
+                }
                 self.status = Status::success("Account created successfully!");
             }
+            Event::Recovered { session, password } => {
+                self.recovery_login = None;
+                self.creds.password = password;
+                self.apply_event(Event::LoginSuccess {
+                    session,
+                    remember: self.remember,
+                });
+                self.status = Status::success("Signed in with a recovery code; password changed");
+            }
+            Event::RecoveryCodes(codes) => {
+                self.status = Status::success("New recovery codes generated; old ones are void");
+                self.recovery_codes = Some(codes);
+            }
+            Event::PasswordReset => {
+                self.password_reset = None;
+                self.creds.password.clear();
+                self.status = Status::success("Password changed, sign in with the new one");
+            }
+            Event::GuildMembersLoaded { guild_id, members } => {
+                self.status = Status::success(format!("Loaded {} guild members", members.len()));
+                self.guild_members = Some((guild_id, members));
+            }
+            Event::BalanceHistoryLoaded(history) => {
+                self.status = Status::success(format!("Loaded {} snapshot(s)", history.len()));
+                self.balance_history = Some(history);
+            }
+            Event::MailLoaded { char_id, mail } => {
+                self.status = Status::success(format!("Loaded {} mail", mail.len()));
+                self.mail = Some((char_id, mail));
+            }
+            Event::SendsLoaded { char_id, sends } => {
+                self.status = Status::success("Send history loaded");
+                self.sends = Some((char_id, sends));
+            }
+            Event::DungeonClearsLoaded { char_id, clears } => {
+                self.status = Status::success("Dungeon stats loaded");
+                self.dungeon_clears = Some((char_id, clears));
+            }
+            Event::AvatarsLoaded { char_id, avatars, equipment } => {
+                self.status = Status::success("Equipment loaded");
+                self.avatars = Some((char_id, avatars));
+                self.equipment = equipment;
+            }
+            Event::CacheScanned(report) => {
+                self.status = Status::info(format!(
+                    "Cache holds {} files ({})",
+                    report.files,
+                    maintenance::format_bytes(report.bytes)
+                ));
+            }
+            Event::CacheCleared(report) => {
+                let message = format!(
+                    "Removed {} files, freed {}",
+                    report.files,
+                    maintenance::format_bytes(report.bytes)
+                );
+                if report.errors.is_empty() {
+                    self.status = Status::success(message);
+                } else {
+                    for err in &report.errors {
+                        tracing::warn!("cache cleanup: {err}");
+                    }
+                    self.status = Status::error(format!(
+                        "{message}; {} could not be removed",
+                        report.errors.len()
+                    ));
+                }
+            }
+            Event::ClientChecked { problems, outdated } => {
+                if problems.is_empty() && outdated.is_none() {
+                    self.start_client();
+                    return;
+                }
+                for problem in &problems {
+                    tracing::warn!("integrity: {problem}");
+                }
+                let summary = format!(
+                    "{} client file(s) failed verification: {}",
+                    problems.len(),
+                    problems.join(", ")
+                );
+                if !problems.is_empty() && self.app_config.integrity.mode == IntegrityMode::Block {
+                    self.status = Status::error(format!("Launch blocked. {summary}"));
+                } else if let Some(mismatch) = outdated {
+                    tracing::warn!(
+                        "ui: client {} is older than {}",
+                        mismatch.local,
+                        mismatch.expected
+                    );
+                    self.status = Status::error("Client outdated — run the patcher");
+                    self.client_mismatch = Some(mismatch);
+                } else {
+                    self.start_client();
+                    self.status = Status::error(format!("Launched anyway. {summary}"));
+                }
+            }
+            #[cfg(windows)]
+            Event::ElevatedLaunched(pid) => {
+                info!("launched game elevated (pid {pid:?})");
+                self.game = pid.map(GameMonitor::attach);
+                self.status = Status::success("Launching Game...");
+            }
+            Event::UpdatesChecked { plan, release } => {
+                let files = plan.as_ref().map_or(0, |plan| plan.files.len());
+                self.status = match (&release, files) {
+                    (Some(release), _) => {
+                        Status::success(format!("Launcher {} is available", release.version))
+                    }
+                    (None, 0) => Status::success("Everything is up to date"),
+                    (None, files) => Status::success(format!("{files} game file(s) to update")),
+                };
+                self.update_plan = plan.filter(|plan| !plan.files.is_empty());
+                let current = env!("CARGO_PKG_VERSION");
+                let min_version = release.as_ref().and_then(|release| release.min_version.clone());
+                self.launcher_release = release;
+                if let Some(min) = min_version.filter(|min| patcher::is_newer(min, current)) {
+                    self.require_update(min);
+                }
+            }
+            Event::ProblemReported => {
+                self.problem_report = None;
+                self.status = Status::success("Report sent, thank you");
+            }
+            Event::HostsChecked(checks) => {
+                let wrong = checks.iter().filter(|check| !check.ok()).count();
+                self.status = match wrong {
+                    0 => Status::success("Server hostnames resolve correctly"),
+                    n => Status::error(format!("{n} server hostname(s) resolve incorrectly")),
+                };
+                self.hosts_checks = Some(checks);
+            }
+            Event::LegacyConfigsFound(found) => {
+                self.status = match found.len() {
+                    0 => Status::info("No settings from older launchers found"),
+                    n => Status::success(format!("Found {n} older launcher config(s)")),
+                };
+                self.legacy = Some(found);
+            }
+            Event::PasswordsReset { accounts, export } => {
+                let count = accounts.len();
+                self.status = match export {
+                    Ok(path) => {
+                        Status::success(format!("Reset {count} password(s); saved to {path}"))
+                    }
+                    Err(_) if self.app_config.dry_run => {
+                        Status::info(format!("Dry run: {count} password(s) not changed or saved"))
+                    }
+                    Err(err) => Status::error(format!(
+                        "Reset {count} password(s) but saving failed ({err}); copy them below"
+                    )),
+                };
+                self.admin.reset_passwords = Some(accounts);
+            }
+            Event::CrashCollected(report) => {
+                tracing::info!(
+                    "ui: crash report with {} log(s), {} event(s)",
+                    report.logs.len(),
+                    report.events.len()
+                );
+                self.status = Status::error(format!("The game crashed ({})", report.exit));
+                self.crash_report = Some(report);
+            }
+            Event::NewsLoaded(news) => {
+                self.status = Status::success(format!("Loaded {} announcement(s)", news.len()));
+                self.news = Some(news);
+            }
+            Event::AuditLoaded { records, message } => {
+                self.status = Status::success(message);
+                self.admin.audit = Some(records);
+                self.admin.audit_selected = None;
+            }
+            Event::AdminCharacterLoaded(info) => {
+                self.status = Status::success(format!("Loaded {}", info.name));
+                self.admin.advancement = info.advancement();
+                self.admin.awakening = info.awakening();
+                let uid = info.uid;
+                self.admin.target = Some(info);
+                if self.admin.note_uid != Some(uid) {
+                    let result = self.send(Command::LoadAccountNote { uid });
+                    self.check_status(result);
+                }
+            }
+            Event::AccountNoteLoaded { uid, note, message } => {
+                if let Some(message) = message {
+                    self.status = Status::success(message);
+                }
+                self.admin.note_uid = Some(uid);
+                self.admin.note = note.as_ref().map(|note| note.text.clone()).unwrap_or_default();
+                self.admin.note_saved = note;
+            }
+            Event::Completed(message) => {
+                self.status = Status::success(message);
+            }
         }
     }
 
-    fn spawn_action<Fut>(&mut self, fut: Fut) -> Result<(), Status>
-    where
-        Fut: Future<Output = Result<AppAction, Error>> + Send + 'static,
-    {
-        if self.action_bind.is_pending() {
-            return Err(Status::error("Operation in progress"));
-        }
-        self.action_bind.request(fut);
-        Ok(())
+    fn send(&mut self, command: Command) -> Result<(), Status> {
+        let creds = self.credentials();
+        self.service.send(creds, command).map_err(|err| Status::error(err.to_string()))
     }
 
     fn credentials(&self) -> Credentials {
@@ -129,40 +992,73 @@ impl LauncherApp {
     }
 
     fn login(&mut self) -> Result<(), Status> {
-        let creds = self.credentials();
-        let db = self.db.clone();
+        if let Some(min) = &self.update_required {
+            let message = format!("Launcher {min} or newer is required");
+            self.navigate(Screen::UpdateRequired);
+            return Err(Status::error(message));
+        }
         let remember = self.remember;
         tracing::info!("ui: login requested");
-        self.spawn_action(async move {
-            let session = db.perform_login(&creds.username, &creds.password).await?;
-            Ok(AppAction::LoginSuccess {
-                session,
-                remember,
-            })
-        })
+        self.send(Command::Login { remember })
+    }
+
+    /// Sends the availability check once typing has paused.
+    fn check_username_soon(&mut self) {
+        let Some(edited) = self.username_edited else {
+            return;
+        };
+        if edited.elapsed() < USERNAME_CHECK_DELAY {
+            return;
+        }
+        self.username_edited = None;
+        let username = self.creds.username.clone();
+        let max_len = *self.app_config.username_policy.length.end();
+        if username.is_empty() || username.chars().count() > max_len {
+            return;
+        }
+        if self.username_available.as_ref().is_some_and(|(name, _)| *name == username) {
+            return;
+        }
+        self.service.check_username(username);
+    }
+
+    /// Inline note under the username field. Taken names are shown neutrally because the
+    /// same field is used to sign in.
+    fn render_username_hint(&self, ui: &mut egui::Ui) {
+        let Some((name, available)) = &self.username_available else {
+            return;
+        };
+        if *name != self.creds.username {
+            return;
+        }
+        let (text, color) = if !available {
+            ("Existing account".to_string(), Theme::text_muted())
+        } else {
+            match self.app_config.username_policy.check(name) {
+                Ok(()) => ("✔ Available to register".to_string(), Theme::success()),
+                Err(err) => (format!("✖ {err}"), Theme::error()),
+            }
+        };
+        ui.label(egui::RichText::new(text).small().color(color));
     }
 
     fn create_account(&mut self) -> Result<(), Status> {
-        let creds = self.credentials();
-        let db = self.db.clone();
+        self.app_config
+            .username_policy
+            .check(&self.creds.username)
+            .map_err(|err| Status::error(err.to_string()))?;
+        if self.app_config.terms.is_some() && !self.terms_accepted {
+            self.navigate(Screen::Terms);
+            return Ok(());
+        }
         tracing::info!("ui: create account requested");
-        self.spawn_action(async move {
-            db.create_account(&creds.username, &creds.password).await?;
-            Ok(AppAction::AccountCreated)
-        })
+        let terms_version = self.app_config.terms.as_ref().map(|terms| terms.version.clone());
+        self.send(Command::CreateAccount { terms_version })
     }
 
     fn refresh(&mut self) -> Result<(), Status> {
-        let creds = self.credentials();
-        let db = self.db.clone();
         tracing::debug!("ui: refresh requested");
-        self.spawn_action(async move {
-            let session = db.perform_login(&creds.username, &creds.password).await?;
-            Ok(AppAction::SessionUpdated {
-                session,
-                message: "Data refreshed".to_string(),
-            })
-        })
+        self.send(Command::Refresh)
     }
 
     fn send_gold(&mut self) -> Result<(), Status> {
@@ -174,18 +1070,8 @@ impl LauncherApp {
             return Err(Status::error("Select a character"));
         };
         let char_id = session.characters[idx].id;
-        let db = self.db.clone();
-        let creds = self.credentials();
         tracing::info!("ui: send gold requested");
-        self.spawn_action(async move {
-            db.send_gold(char_id, amount).await?;
-            tokio::time::sleep(Duration::from_secs(1)).await;
-            let session = db.perform_login(&creds.username, &creds.password).await?;
-            Ok(AppAction::SessionUpdated {
-                session,
-                message: "Gold sent! Data refreshed".to_string(),
-            })
-        })
+        self.send(Command::SendGold { char_id, amount })
     }
 
     fn send_cera(&mut self) -> Result<(), Status> {
@@ -194,192 +1080,4100 @@ impl LauncherApp {
             return Err(Status::error("No session"));
         };
         let uid = session.uid;
-        let db = self.db.clone();
-        let creds = self.credentials();
         tracing::info!("ui: send cera requested");
-        self.spawn_action(async move {
-            db.send_cera(uid, amount).await?;
-            tokio::time::sleep(Duration::from_secs(1)).await;
-            let session = db.perform_login(&creds.username, &creds.password).await?;
-            Ok(AppAction::SessionUpdated {
-                session,
-                message: "Cera sent! Data refreshed".to_string(),
-            })
+        self.send(Command::SendCera { uid, amount })
+    }
+
+    /// The selected configured currency and the UID or character number it applies to.
+    fn currency_target(&self) -> Result<(CurrencyDef, i32), Status> {
+        let Some(currency) = self.currency.and_then(|idx| self.app_config.currencies.get(idx))
+        else {
+            return Err(Status::error("Select a currency"));
+        };
+        let Some(session) = &self.current_session else {
+            return Err(Status::error("No session"));
+        };
+        let owner = if currency.per_character {
+            let Some(idx) = self.selected_char else {
+                return Err(Status::error("Select a character"));
+            };
+            session.characters[idx].id
+        } else {
+            session.uid
+        };
+        Ok((currency.clone(), owner))
+    }
+
+    fn currency_sendable(&self, currency: &CurrencyDef) -> bool {
+        currency.sendable && self.app_config.enabled(Feature::SendCurrency)
+    }
+
+    fn load_currency(&mut self) -> Result<(), Status> {
+        let (currency, owner) = self.currency_target()?;
+        tracing::debug!("ui: {} balance requested", currency.name);
+        self.send(Command::LoadCurrency { currency, owner })
+    }
+
+    fn send_currency(&mut self) -> Result<(), Status> {
+        let amount = self.parse_amount()?;
+        let (currency, owner) = self.currency_target()?;
+        if !self.currency_sendable(&currency) {
+            return Err(Status::error(format!("Sending {} is disabled", currency.name)));
+        }
+        tracing::info!("ui: send {} requested", currency.name);
+        self.send(Command::SendCurrency {
+            currency,
+            owner,
+            amount,
         })
     }
 
-    fn parse_amount(&self) -> Result<i32, Status> {
-        match self.amount.trim().parse::<i32>() {
-            Ok(val) if val > 0 => Ok(val),
-            _ => Err(Status::error("Wrong value!")),
-        }
+    fn undo_last_send(&mut self) -> Result<(), Status> {
+        let Some(last) = self.last_send.as_ref().filter(|l| l.at.elapsed() < UNDO_WINDOW) else {
+            return Err(Status::error("Nothing to undo"));
+        };
+        let send_id = last.send_id;
+        tracing::info!("ui: undo {} send requested", last.currency);
+        self.send(Command::UndoSend { send_id })?;
+        self.last_send = None;
+        self.currency_balance = None;
+        self.sends = None;
+        Ok(())
+    }
+
+    fn transfer_cera(&mut self) -> Result<(), Status> {
+        let Some(session) = &self.current_session else {
+            return Err(Status::error("No session"));
+        };
+        let Some(form) = &self.transfer else {
+            return Ok(());
+        };
+        let recipient = form.recipient.trim().to_string();
+        if recipient.is_empty() {
+            return Err(Status::error("Enter the recipient's username"));
+        }
+        let amount = match form.amount.trim().parse::<i64>() {
+            Ok(val) if val > 0 => val,
+            _ => return Err(Status::error("Wrong value!")),
+        };
+        let Some(balance) = session.cera else {
+            return Err(Status::error("Cera balance unavailable"));
+        };
+        if amount > balance {
+            return Err(Status::error("Not enough cera"));
+        }
+        let uid = session.uid;
+        tracing::info!("ui: cera transfer requested");
+        self.send(Command::TransferCera {
+            uid,
+            recipient,
+            amount,
+        })?;
+        self.transfer = None;
+        Ok(())
+    }
+
+    fn claim_daily_reward(&mut self) -> Result<(), Status> {
+        let Some(session) = &self.current_session else {
+            return Err(Status::error("No session"));
+        };
+        let uid = session.uid;
+        let rewards = self.app_config.daily_rewards.clone();
+        tracing::info!("ui: daily reward claim requested");
+        self.send(Command::ClaimDailyReward { uid, rewards })
+    }
+
+    /// Asks where to save the account's cera and gold history, then exports it as CSV.
+    fn export_billing(&mut self) -> Result<(), Status> {
+        let Some(session) = &self.current_session else {
+            return Err(Status::error("No session"));
+        };
+        let name = format!(
+            "billing_{}_{}.csv",
+            self.creds.username,
+            chrono::Local::now().format("%Y%m%d")
+        );
+        let Some(path) = rfd::FileDialog::new()
+            .set_title("Export billing history")
+            .add_filter("CSV", &["csv"])
+            .set_file_name(name)
+            .save_file()
+        else {
+            return Ok(());
+        };
+        let uid = session.uid;
+        let char_ids = session.characters.iter().map(|c| c.id).collect();
+        tracing::info!("ui: billing history export requested");
+        self.send(Command::ExportBilling { uid, char_ids, path })
+    }
+
+    fn force_logout(&mut self) -> Result<(), Status> {
+        let Some(session) = &self.current_session else {
+            return Err(Status::error("No session"));
+        };
+        if !self.confirm_force_logout {
+            return Err(Status::error("Confirm logout first"));
+        }
+        self.confirm_force_logout = false;
+        let uid = session.uid;
+        tracing::info!("ui: in-game session logout requested");
+        self.send(Command::ForceLogout { uid })
+    }
+
+    fn link_discord(&mut self) -> Result<(), Status> {
+        let Some(session) = &self.current_session else {
+            return Err(Status::error("No session"));
+        };
+        let uid = session.uid;
+        let code = self.discord_code.trim().to_string();
+        if code.is_empty() {
+            return Err(Status::error("Enter the code from the Discord bot"));
+        }
+        tracing::info!("ui: discord link requested");
+        self.send(Command::LinkDiscord { uid, code })
+    }
+
+    fn unlink_discord(&mut self) -> Result<(), Status> {
+        let Some(session) = &self.current_session else {
+            return Err(Status::error("No session"));
+        };
+        let uid = session.uid;
+        tracing::info!("ui: discord unlink requested");
+        self.send(Command::UnlinkDiscord { uid })
+    }
+
+    fn send_email_code(&mut self) -> Result<(), Status> {
+        let Some(session) = &self.current_session else {
+            return Err(Status::error("No session"));
+        };
+        let address = self.email_form.address.trim().to_string();
+        if !email::is_valid_address(&address) {
+            return Err(Status::error("Enter a valid email address"));
+        }
+        let uid = session.uid;
+        tracing::info!("ui: email verification code requested");
+        self.send(Command::SendEmailCode { uid, address })
+    }
+
+    fn verify_email(&mut self) -> Result<(), Status> {
+        let Some(session) = &self.current_session else {
+            return Err(Status::error("No session"));
+        };
+        let code = self.email_form.code.trim().to_string();
+        if code.is_empty() {
+            return Err(Status::error("Enter the code from the email"));
+        }
+        let uid = session.uid;
+        tracing::info!("ui: email verification requested");
+        self.send(Command::VerifyEmail { uid, code })
+    }
+
+    fn send_reset_code(&mut self) -> Result<(), Status> {
+        self.creds.username = self.creds.username.trim().to_string();
+        if self.creds.username.is_empty() {
+            return Err(Status::error("Enter your username"));
+        }
+        tracing::info!("ui: password reset code requested");
+        self.send(Command::SendResetCode)
+    }
+
+    fn reset_password(&mut self) -> Result<(), Status> {
+        let Some(form) = &self.password_reset else {
+            return Ok(());
+        };
+        let code = form.code.trim().to_string();
+        let password = form.password.clone();
+        if code.is_empty() || password.is_empty() {
+            return Err(Status::error("Enter the code and a new password"));
+        }
+        self.creds.username = self.creds.username.trim().to_string();
+        tracing::info!("ui: password reset requested");
+        self.send(Command::ResetPassword { code, password })
+    }
+
+    fn set_secondary_pin(&mut self, clear: bool) -> Result<(), Status> {
+        let Some(session) = &self.current_session else {
+            return Err(Status::error("No session"));
+        };
+        let pin = if clear {
+            None
+        } else {
+            let form = &self.pin_form;
+            if form.pin != form.confirm {
+                return Err(Status::error("PINs do not match"));
+            }
+            let valid = PIN_LENGTH.contains(&form.pin.len())
+                && form.pin.chars().all(|c| c.is_ascii_digit());
+            if !valid {
+                return Err(Status::error(format!(
+                    "PIN must be {} to {} digits",
+                    PIN_LENGTH.start(),
+                    PIN_LENGTH.end()
+                )));
+            }
+            Some(form.pin.clone())
+        };
+        let uid = session.uid;
+        tracing::info!("ui: secondary pin change requested");
+        self.send(Command::SetSecondaryPin { uid, pin })?;
+        self.pin_form = PinForm::default();
+        Ok(())
+    }
+
+    fn recover_login(&mut self) -> Result<(), Status> {
+        let Some(form) = &self.recovery_login else {
+            return Ok(());
+        };
+        let code = form.code.trim().to_string();
+        let password = form.password.clone();
+        self.creds.username = self.creds.username.trim().to_string();
+        if self.creds.username.is_empty() || code.is_empty() || password.is_empty() {
+            return Err(Status::error("Enter your username, a recovery code and a new password"));
+        }
+        tracing::info!("ui: recovery code login requested");
+        self.send(Command::RecoverAccount { code, password })
+    }
+
+    fn generate_recovery_codes(&mut self) -> Result<(), Status> {
+        let Some(session) = &self.current_session else {
+            return Err(Status::error("No session"));
+        };
+        let uid = session.uid;
+        tracing::info!("ui: recovery codes requested");
+        self.send(Command::GenerateRecoveryCodes { uid })
+    }
+
+    fn load_guild_members(&mut self, guild_id: i32) -> Result<(), Status> {
+        tracing::debug!("ui: guild members requested");
+        self.send(Command::LoadGuildMembers { guild_id })
+    }
+
+    fn load_balance_history(&mut self) -> Result<(), Status> {
+        let Some(session) = &self.current_session else {
+            return Err(Status::error("No session"));
+        };
+        let uid = session.uid;
+        tracing::debug!("ui: balance history requested");
+        self.send(Command::LoadBalanceHistory { uid })
+    }
+
+    fn purchase_slot(&mut self) -> Result<(), Status> {
+        let Some(session) = &self.current_session else {
+            return Err(Status::error("No session"));
+        };
+        let uid = session.uid;
+        let price = self.app_config.slot_price_cera;
+        tracing::info!("ui: character slot purchase requested");
+        self.send(Command::PurchaseSlot { uid, price })
+    }
+
+    fn unstick_character(&mut self, char_id: i32) -> Result<(), Status> {
+        let Some(session) = &self.current_session else {
+            return Err(Status::error("No session"));
+        };
+        let uid = session.uid;
+        tracing::info!("ui: unstick character requested");
+        self.send(Command::UnstickCharacter { uid, char_id })
+    }
+
+    fn load_mail(&mut self, char_id: i32) -> Result<(), Status> {
+        tracing::debug!("ui: mail requested");
+        self.send(Command::LoadMail { char_id })
+    }
+
+    fn loaded_mail(&self, char_id: i32) -> Option<&[Mail]> {
+        match &self.mail {
+            Some((id, mail)) if *id == char_id => Some(mail),
+            _ => None,
+        }
+    }
+
+    fn load_avatars(&mut self, char_id: i32) -> Result<(), Status> {
+        tracing::debug!("ui: equipment requested");
+        self.send(Command::LoadAvatars { char_id })
+    }
+
+    fn load_sends(&mut self, char_id: i32) -> Result<(), Status> {
+        tracing::debug!("ui: send history requested");
+        self.send(Command::LoadSends { char_id })
+    }
+
+    fn load_dungeon_clears(&mut self, char_id: i32) -> Result<(), Status> {
+        tracing::debug!("ui: dungeon stats requested");
+        self.send(Command::LoadDungeonClears { char_id })
+    }
+
+    fn loaded_avatars(&self, char_id: i32) -> Option<&[Avatar]> {
+        match &self.avatars {
+            Some((id, avatars)) if *id == char_id => Some(avatars),
+            _ => None,
+        }
+    }
+
+    fn admin_grant_cash_item(&mut self) -> Result<(), Status> {
+        let uid = parse_id(&self.admin.account_uid, "account UID")?;
+        let item_id = parse_id(&self.admin.item_id, "item ID")?;
+        let quantity = match self.admin.item_quantity.trim().parse::<i32>() {
+            Ok(val) if (1..=1000).contains(&val) => val,
+            _ => return Err(Status::error("Quantity must be between 1 and 1000")),
+        };
+        if self.items.is_empty() {
+            return Err(Status::error("Item database not loaded"));
+        }
+        let Some(item) = self.items.get(item_id) else {
+            return Err(Status::error(format!("Unknown item ID {item_id}")));
+        };
+        let item_name = item.name.clone();
+        tracing::info!("ui: admin cash item grant requested");
+        self.send(Command::GrantCashItem {
+            uid,
+            item_id,
+            quantity,
+            item_name,
+        })
+    }
+
+    fn admin_grant_creature(&mut self) -> Result<(), Status> {
+        let char_id = parse_id(&self.admin.char_id, "character ID")?;
+        let creature_id = parse_id(&self.admin.creature_id, "creature ID")?;
+        if self.items.is_empty() {
+            return Err(Status::error("Item database not loaded"));
+        }
+        let Some(creature) = self.items.get(creature_id) else {
+            return Err(Status::error(format!("Unknown creature ID {creature_id}")));
+        };
+        let creature_name = creature.name.clone();
+        tracing::info!("ui: admin creature grant requested");
+        self.send(Command::GrantCreature {
+            char_id,
+            creature_id,
+            creature_name,
+        })
+    }
+
+    fn admin_load_audit(&mut self) -> Result<(), Status> {
+        tracing::debug!("ui: audit log requested");
+        self.send(Command::LoadAudit)
+    }
+
+    fn admin_rollback(&mut self) -> Result<(), Status> {
+        let record = self
+            .admin
+            .audit_selected
+            .and_then(|id| self.admin.audit.as_ref()?.iter().find(|r| r.id == id))
+            .cloned()
+            .ok_or_else(|| Status::error("Select an audit entry first"))?;
+        if !record.action.is_some_and(AuditAction::is_reversible) {
+            return Err(Status::error("This audit entry cannot be rolled back"));
+        }
+        tracing::info!("ui: admin rollback requested");
+        self.send(Command::Rollback(record))
+    }
+
+    fn admin_lookup_character(&mut self) -> Result<(), Status> {
+        let char_id = parse_id(&self.admin.char_id, "character ID")?;
+        tracing::debug!("ui: admin character lookup requested");
+        self.send(Command::LookupCharacter { char_id })
+    }
+
+    /// UID for the note editor: the Account UID field, else the looked-up character's owner.
+    fn admin_note_uid(&self) -> Result<i32, Status> {
+        match &self.admin.target {
+            Some(target) if self.admin.account_uid.trim().is_empty() => Ok(target.uid),
+            _ => parse_id(&self.admin.account_uid, "account UID"),
+        }
+    }
+
+    fn admin_load_note(&mut self) -> Result<(), Status> {
+        let uid = self.admin_note_uid()?;
+        tracing::debug!("ui: admin account note requested");
+        self.send(Command::LoadAccountNote { uid })
+    }
+
+    fn admin_save_note(&mut self) -> Result<(), Status> {
+        let Some(uid) = self.admin.note_uid else {
+            return Err(Status::error("Load the account's note first"));
+        };
+        let note = self.admin.note.trim().to_string();
+        tracing::info!("ui: admin save account note requested");
+        self.send(Command::SaveAccountNote { uid, note })
+    }
+
+    fn admin_set_job_growth(&mut self) -> Result<(), Status> {
+        let char_id = parse_id(&self.admin.char_id, "character ID")?;
+        if self.admin.target.as_ref().map(|t| t.id) != Some(char_id) {
+            return Err(Status::error("Look up the character first"));
+        }
+        let (advancement, awakening) = (self.admin.advancement, self.admin.awakening);
+        tracing::info!("ui: admin set job growth requested");
+        self.send(Command::SetJobGrowth {
+            char_id,
+            advancement,
+            awakening,
+        })
+    }
+
+    fn admin_reset_quests(&mut self, all: bool) -> Result<(), Status> {
+        let char_id = parse_id(&self.admin.char_id, "character ID")?;
+        let quest_id = if all {
+            None
+        } else {
+            Some(parse_id(&self.admin.quest_id, "quest ID")?)
+        };
+        tracing::info!("ui: admin quest reset requested");
+        self.send(Command::ResetQuests { char_id, quest_id })
+    }
+
+    fn admin_expand_inventory(&mut self) -> Result<(), Status> {
+        let char_id = parse_id(&self.admin.char_id, "character ID")?;
+        let slots = parse_count(&self.admin.inventory_slots, "slot count")?;
+        tracing::info!("ui: admin inventory expansion requested");
+        self.send(Command::ExpandInventory { char_id, slots })
+    }
+
+    fn admin_expand_stash(&mut self) -> Result<(), Status> {
+        let uid = parse_id(&self.admin.account_uid, "account UID")?;
+        let tabs = parse_count(&self.admin.stash_tabs, "tab count")?;
+        tracing::info!("ui: admin stash expansion requested");
+        self.send(Command::ExpandStash { uid, tabs })
+    }
+
+    fn admin_set_slot_limit(&mut self) -> Result<(), Status> {
+        let uid = parse_id(&self.admin.account_uid, "account UID")?;
+        let limit = match self.admin.slot_limit.trim().parse::<i32>() {
+            Ok(val) if (1..=MAX_CHAR_SLOTS).contains(&val) => val,
+            _ => {
+                return Err(Status::error(format!(
+                    "Slot limit must be between 1 and {MAX_CHAR_SLOTS}"
+                )));
+            }
+        };
+        tracing::info!("ui: admin slot limit requested");
+        self.send(Command::SetSlotLimit { uid, limit })
+    }
+
+    fn admin_set_level(&mut self) -> Result<(), Status> {
+        let char_id = parse_id(&self.admin.char_id, "character ID")?;
+        let max_level = self.app_config.max_level;
+        let level = match self.admin.level.trim().parse::<i32>() {
+            Ok(val) if (1..=max_level).contains(&val) => val,
+            _ => return Err(Status::error(format!("Level must be between 1 and {max_level}"))),
+        };
+        tracing::info!("ui: admin set level requested");
+        self.send(Command::SetLevel { char_id, level })
+    }
+
+    fn admin_create_guild(&mut self) -> Result<(), Status> {
+        let name = self.admin.guild_name.trim().to_string();
+        if name.is_empty() {
+            return Err(Status::error("Enter a guild name"));
+        }
+        let master = parse_id(&self.admin.char_id, "character ID")?;
+        tracing::info!("ui: admin create guild requested");
+        self.send(Command::CreateGuild { name, master })
+    }
+
+    fn admin_set_guild_master(&mut self) -> Result<(), Status> {
+        let guild_id = parse_id(&self.admin.guild_id, "guild ID")?;
+        let char_id = parse_id(&self.admin.char_id, "character ID")?;
+        tracing::info!("ui: admin set guild master requested");
+        self.send(Command::SetGuildMaster { guild_id, char_id })
+    }
+
+    fn admin_set_guild_level(&mut self) -> Result<(), Status> {
+        let guild_id = parse_id(&self.admin.guild_id, "guild ID")?;
+        let level = parse_id(&self.admin.guild_level, "guild level")?;
+        tracing::info!("ui: admin set guild level requested");
+        self.send(Command::SetGuildLevel { guild_id, level })
+    }
+
+    fn admin_adjust_guild_funds(&mut self) -> Result<(), Status> {
+        let guild_id = parse_id(&self.admin.guild_id, "guild ID")?;
+        let Ok(delta) = self.admin.guild_funds.trim().parse::<i64>() else {
+            return Err(Status::error("Invalid funds amount"));
+        };
+        tracing::info!("ui: admin adjust guild funds requested");
+        self.send(Command::AdjustGuildFunds { guild_id, delta })
+    }
+
+    fn admin_disband_guild(&mut self) -> Result<(), Status> {
+        let guild_id = parse_id(&self.admin.guild_id, "guild ID")?;
+        if !self.admin.confirm_disband {
+            return Err(Status::error("Confirm disband first"));
+        }
+        self.admin.confirm_disband = false;
+        tracing::info!("ui: admin disband guild requested");
+        self.send(Command::DisbandGuild { guild_id })
+    }
+
+    fn admin_transfer_character(&mut self) -> Result<(), Status> {
+        let char_id = parse_id(&self.admin.char_id, "character ID")?;
+        let to_uid = parse_id(&self.admin.transfer_to_uid, "target account UID")?;
+        tracing::info!("ui: admin character transfer requested");
+        self.send(Command::TransferCharacter { char_id, to_uid })
+    }
+
+    fn admin_backup_account(&mut self) -> Result<(), Status> {
+        let uid = parse_id(&self.admin.account_uid, "account UID")?;
+        let path = format!(
+            "{BACKUP_DIR}/account_{uid}_{}.json",
+            chrono::Local::now().format("%Y%m%d_%H%M%S")
+        );
+        self.admin.backup_path = path.clone();
+        tracing::info!("ui: admin account backup requested");
+        self.send(Command::BackupAccount { uid, path })
+    }
+
+    fn admin_restore_account(&mut self) -> Result<(), Status> {
+        let path = self.admin.backup_path.trim().to_string();
+        if path.is_empty() {
+            return Err(Status::error("Enter a backup file"));
+        }
+        if !self.admin.confirm_restore {
+            return Err(Status::error("Confirm restore first"));
+        }
+        self.admin.confirm_restore = false;
+        tracing::info!("ui: admin account restore requested");
+        self.send(Command::RestoreAccount { path })
+    }
+
+    fn admin_merge_accounts(&mut self) -> Result<(), Status> {
+        let from_uid = parse_id(&self.admin.merge_from_uid, "source account UID")?;
+        let to_uid = parse_id(&self.admin.merge_to_uid, "target account UID")?;
+        if !self.admin.confirm_merge {
+            return Err(Status::error("Confirm merge first"));
+        }
+        self.admin.confirm_merge = false;
+        tracing::info!("ui: admin account merge requested");
+        self.send(Command::MergeAccounts { from_uid, to_uid })
+    }
+
+    fn admin_reset_passwords(&mut self) -> Result<(), Status> {
+        let uids = self
+            .admin
+            .reset_uids
+            .split([',', ' ', '\n'])
+            .filter(|uid| !uid.trim().is_empty())
+            .map(|uid| parse_id(uid, "account UID"))
+            .collect::<Result<Vec<_>, _>>()?;
+        if uids.is_empty() {
+            return Err(Status::error("Enter at least one account UID"));
+        }
+        if !self.admin.confirm_reset {
+            return Err(Status::error("Confirm reset first"));
+        }
+        self.admin.confirm_reset = false;
+        self.admin.reset_passwords = None;
+        let path = format!(
+            "{BACKUP_DIR}/password_reset_{}.csv",
+            chrono::Local::now().format("%Y%m%d_%H%M%S")
+        );
+        tracing::info!("ui: admin bulk password reset requested ({} accounts)", uids.len());
+        self.send(Command::ResetPasswords { uids, path })
+    }
+
+    /// Formats a gold/cera amount in the player's number format.
+    fn num(&self, value: i64) -> String {
+        self.config.number_format.format(value)
+    }
+
+    /// Converts server timestamps to the timezone the player chose to see.
+    fn clock(&self) -> Clock {
+        Clock {
+            server: self.app_config.server_timezone,
+            show_server_time: self.config.show_server_time,
+        }
+    }
+
+    fn selected_character(&self) -> Option<&Character> {
+        let session = self.current_session.as_ref()?;
+        session.characters.get(self.selected_char?)
+    }
+
+    /// Selects a character of the current session and loads its avatars the first time.
+    fn select_char(&mut self, idx: usize) {
+        self.selected_char = Some(idx);
+        let Some(char_id) = self.selected_character().map(|c| c.id) else {
+            return;
+        };
+        if self.config.dashboard_char != Some(char_id) {
+            self.config.dashboard_char = Some(char_id);
+            let _ = config::write_json("config.json", &self.config);
+        }
+        if self.loaded_avatars(char_id).is_none() && !self.service.is_busy() {
+            let result = self.load_avatars(char_id);
+            self.check_status(result);
+        }
+    }
+
+    fn parse_amount(&self) -> Result<i32, Status> {
+        match self.amount.trim().parse::<i32>() {
+            Ok(val) if val > 0 => Ok(val),
+            _ => Err(Status::error("Wrong value!")),
+        }
+    }
+
+    fn check_status<T>(&mut self, result: Result<T, Status>) -> Option<T> {
+        match result {
+            Ok(val) => Some(val),
+            Err(status) => {
+                self.status = status;
+                None
+            }
+        }
+    }
+
+    /// Drops per-account dashboard state when another account comes into view.
+    fn clear_account_view(&mut self) {
+        self.selected_char = None;
+        self.tab = DashboardTab::Characters;
+        self.guild_members = None;
+        self.balance_history = None;
+        self.mail = None;
+        self.avatars = None;
+        self.dungeon_clears = None;
+        self.sends = None;
+        self.last_send = None;
+        self.currency_balance = None;
+        self.restore_scroll = true;
+        // Back to the character and tab the player last used, when this account has them.
+        let saved_char = self.current_session.as_ref().and_then(|session| {
+            let char_id = self.config.dashboard_char?;
+            session.characters.iter().position(|c| c.id == char_id)
+        });
+        if let Some(idx) = saved_char {
+            self.select_char(idx);
+        }
+        let saved = self.config.dashboard_tab;
+        if self.current_session.is_some() && self.dashboard_tabs().contains(&saved) {
+            self.open_tab(saved);
+        }
+    }
+
+    /// Signs every account out after the configured idle time, so a shared PC doesn't keep a
+    /// session open. Never while a client is running.
+    fn check_idle(&mut self, ctx: &egui::Context) {
+        let active = ctx.input(|i| !i.events.is_empty() || i.pointer.is_moving());
+        let in_game = self.game.is_some() || self.parked.iter().any(|p| p.game.is_some());
+        if active || in_game || self.current_session.is_none() {
+            self.last_input = Instant::now();
+            return;
+        }
+        let minutes = self.app_config.idle_timeout_mins.unwrap_or(self.config.idle_timeout_mins);
+        if minutes == 0 || self.last_input.elapsed() < Duration::from_secs(u64::from(minutes) * 60)
+        {
+            return;
+        }
+        tracing::info!("ui: signing out after {minutes} idle minute(s)");
+        self.current_session = None;
+        self.parked.clear();
+        self.clear_account_view();
+        if !self.config.remember {
+            self.creds.password.clear();
+        }
+        self.transfer = None;
+        // The admin panel can hold freshly reset passwords and looked-up accounts.
+        self.admin = AdminForm::default();
+        self.recovery_codes = None;
+        self.reset_screen(Screen::Login);
+        self.status = Status::info("Signed out after being idle");
+    }
+
+    /// Refreshes the dashboard on the interval set in the settings, skipping while the game
+    /// runs unless the player opted in.
+    fn auto_refresh(&mut self) {
+        let minutes = self.config.auto_refresh_mins;
+        if minutes == 0 || self.current_session.is_none() || self.service.is_busy() {
+            return;
+        }
+        if self.game.is_some() && !self.config.refresh_in_game {
+            return;
+        }
+        if self.last_refresh.elapsed() < Duration::from_secs(u64::from(minutes) * 60) {
+            return;
+        }
+        self.last_refresh = Instant::now();
+        tracing::debug!("ui: auto-refresh after {minutes} min");
+        let result = self.refresh();
+        self.check_status(result);
+    }
+
+    /// Keeps the signed-in account and its client running in the session list, freeing the
+    /// login screen for another account.
+    fn park_session(&mut self) {
+        let Some(session) = self.current_session.take() else {
+            return;
+        };
+        tracing::info!("ui: parking session of uid {}", session.uid);
+        self.parked.push(ParkedSession {
+            creds: self.creds.clone(),
+            session,
+            game: self.game.take(),
+        });
+        self.creds = Credentials::default();
+        self.reset_screen(Screen::Login);
+    }
+
+    /// Brings a parked account back into view, parking the current one in its place.
+    fn resume_session(&mut self, idx: usize) {
+        if idx >= self.parked.len() {
+            return;
+        }
+        let parked = self.parked.remove(idx);
+        if let Some(session) = self.current_session.take() {
+            self.parked.insert(idx, ParkedSession {
+                creds: std::mem::replace(&mut self.creds, parked.creds),
+                session,
+                game: std::mem::replace(&mut self.game, parked.game),
+            });
+        } else {
+            self.creds = parked.creds;
+            self.game = parked.game;
+        }
+        tracing::info!("ui: switched to session of uid {}", parked.session.uid);
+        self.current_session = Some(parked.session);
+        self.clear_account_view();
+        self.reset_screen(Screen::Dashboard);
+    }
+
+    fn account_label(&self, username: &str) -> String {
+        self.config
+            .accounts
+            .iter()
+            .find(|a| a.username == username)
+            .map_or(username, |a| a.label())
+            .to_string()
+    }
+
+    fn launch_game(&mut self) {
+        if self.current_session.is_none() {
+            return;
+        }
+        if self.online_elsewhere().is_some() && !self.play_anyway {
+            self.status = Status::error("This account is already in game");
+            return;
+        }
+        if self.patch.as_ref().is_some_and(|job| job.progress().finished.is_none()) {
+            self.status = Status::error("Wait for the game update to finish");
+            return;
+        }
+        if !self.validate_game_folder() {
+            self.status = Status::error("The game folder is missing files");
+            return;
+        }
+        let integrity = Some(self.app_config.integrity.clone())
+            .filter(|integrity| integrity.mode != IntegrityMode::Off);
+        let remote = self
+            .app_config
+            .updates
+            .patch_manifest(self.config.update_channel)
+            .map(|url| RemoteManifest {
+                agent: http::agent(&self.config.proxy),
+                url,
+                key: self.app_config.updates.public_key,
+            });
+        if integrity.is_none() && remote.is_none() {
+            self.start_client();
+            return;
+        }
+        tracing::info!("ui: verifying client before launch");
+        let result = self.send(Command::VerifyClient {
+            integrity,
+            remote,
+            exe_path: self.app_config.dnf_exe_path.clone(),
+            version_file: self.app_config.client_version_file.clone(),
+        });
+        if self.check_status(result).is_some() {
+            self.status = Status::info("Checking client...");
+        }
+    }
+
+    /// Checks the game folder, opening the checklist dialog when something is missing.
+    fn validate_game_folder(&mut self) -> bool {
+        let checks = launch::check_game_folder(
+            &self.app_config.dnf_exe_path,
+            &self.app_config.required_game_files,
+        );
+        if checks.iter().all(|check| check.ok) {
+            self.folder_checks = None;
+            return true;
+        }
+        tracing::warn!("ui: game folder incomplete");
+        if self.folder_checks.is_none() {
+            self.game_path_input = self.app_config.dnf_exe_path.clone();
+        }
+        self.folder_checks = Some(checks);
+        false
+    }
+
+    fn set_game_path(&mut self) {
+        let path = self.game_path_input.trim().to_string();
+        tracing::info!("ui: game path changed");
+        self.app_config.dnf_exe_path = path.clone();
+        self.config.game_path = Some(path);
+        let _ = config::write_json("config.json", &self.config);
+        if self.validate_game_folder() {
+            self.status = Status::success("Game folder looks good");
+        }
+    }
+
+    fn cache_dirs(&self) -> Result<Vec<std::path::PathBuf>, Status> {
+        if self.app_config.cache_dirs.is_empty() {
+            return Err(Status::error("No cache directories configured"));
+        }
+        let game_dir = launch::game_dir(&self.app_config.dnf_exe_path);
+        Ok(maintenance::resolve_dirs(&game_dir, &self.app_config.cache_dirs))
+    }
+
+    fn check_updates(&mut self) -> Result<(), Status> {
+        let channel = self.config.update_channel;
+        let patch_url = self.app_config.updates.patch_manifest(channel);
+        let launcher_url = self.app_config.updates.launcher_manifest(channel);
+        if patch_url.is_none() && launcher_url.is_none() {
+            return Err(Status::error("No update server configured"));
+        }
+        tracing::info!("ui: update check requested ({})", channel.as_str());
+        self.send(Command::CheckUpdates {
+            agent: http::agent(&self.config.proxy),
+            patch_url,
+            launcher_url,
+            game_dir: launch::game_dir(&self.app_config.dnf_exe_path),
+            key: self.app_config.updates.public_key,
+        })
+    }
+
+    fn load_news(&mut self) -> Result<(), Status> {
+        let url = self
+            .app_config
+            .news_url
+            .clone()
+            .ok_or_else(|| Status::error("No news feed configured"))?;
+        let agent = http::agent(&self.config.proxy);
+        tracing::info!("ui: news requested");
+        self.send(Command::LoadNews { agent, url })
+    }
+
+    fn start_patch(&mut self) {
+        let Some(plan) = self.update_plan.take() else {
+            return;
+        };
+        tracing::info!("ui: game update started ({} files)", plan.files.len());
+        let game_dir = launch::game_dir(&self.app_config.dnf_exe_path);
+        let limit = u64::from(self.config.download_limit_kbps) * 1024;
+        self.patch = Some(PatchJob::spawn(
+            http::agent(&self.config.proxy),
+            plan,
+            game_dir,
+            limit,
+            self.config.use_mirrors,
+        ));
+    }
+
+    /// Checks only the launcher manifest, skipping the game file scan of a full update check.
+    fn check_launcher_release(&mut self) {
+        let channel = self.config.update_channel;
+        let Some(launcher_url) = self.app_config.updates.launcher_manifest(channel) else {
+            return;
+        };
+        let result = self.send(Command::CheckUpdates {
+            agent: http::agent(&self.config.proxy),
+            patch_url: None,
+            launcher_url: Some(launcher_url),
+            game_dir: launch::game_dir(&self.app_config.dnf_exe_path),
+            key: self.app_config.updates.public_key,
+        });
+        self.check_status(result);
+    }
+
+    /// Blocks sign-in until this build is updated, and fetches the release to install.
+    fn require_update(&mut self, min_version: String) {
+        tracing::warn!("ui: launcher {min_version} or newer required");
+        self.update_required = Some(min_version);
+        if self.current_session.is_none() {
+            self.reset_screen(Screen::UpdateRequired);
+        }
+        if self.launcher_release.is_none() && !self.service.is_busy() {
+            self.check_launcher_release();
+        }
+    }
+
+    fn install_launcher_update(&mut self) -> Result<(), Status> {
+        let Some(release) = self.launcher_release.clone() else {
+            return Ok(());
+        };
+        let agent = http::agent(&self.config.proxy);
+        tracing::info!("ui: launcher update to {} requested", release.version);
+        self.send(Command::InstallLauncher { agent, release })
+    }
+
+    fn find_legacy_configs(&mut self) -> Result<(), Status> {
+        let game_dir = launch::game_dir(&self.app_config.dnf_exe_path);
+        tracing::info!("ui: looking for older launcher configs");
+        self.send(Command::FindLegacyConfigs(legacy::search_dirs(&game_dir)))
+    }
+
+    fn import_legacy(&mut self, idx: usize) {
+        let Some(legacy) = self.legacy.as_mut().map(|found| found.remove(idx)) else {
+            return;
+        };
+        let exe_name = Path::new(&self.app_config.dnf_exe_path)
+            .file_name()
+            .map_or_else(|| "DNF.exe".to_string(), |name| name.to_string_lossy().into_owned());
+        let added = self.config.import_legacy(&legacy, &exe_name);
+        self.app_config.apply_overrides(&self.config);
+        if legacy.server_addr.is_some() {
+            let realm = self.app_config.realm(self.config.realm.as_deref());
+            self.ping = spawn_ping(&self.app_config, realm);
+        }
+        if legacy.game_path.is_some() {
+            self.validate_game_folder();
+        }
+        let _ = config::write_json("config.json", &self.config);
+        tracing::info!("ui: imported settings from {}", legacy.source.display());
+        self.status = Status::success(format!(
+            "Imported {} with {added} new account(s)",
+            legacy.source.display()
+        ));
+    }
+
+    fn support_info(&self) -> SupportInfo {
+        let realm = self.app_config.realm(self.config.realm.as_deref());
+        SupportInfo {
+            created_at: chrono::Local::now().to_rfc3339(),
+            launcher_version: env!("CARGO_PKG_VERSION").to_string(),
+            os: support::os_name(),
+            realm: realm.name.clone(),
+            launch_mode: format!("{:?}", self.app_config.launch.mode),
+            game_path: self.app_config.dnf_exe_path.clone(),
+            account: self.current_session.as_ref().map(|_| self.creds.username.clone()),
+            last_error: self.last_error.clone(),
+            config: support::redacted(&self.config),
+        }
+    }
+
+    fn save_support_bundle(&mut self) -> Result<(), Status> {
+        let info = self.support_info();
+        let path = PathBuf::from(format!(
+            "{SUPPORT_DIR}/support_{}.zip",
+            chrono::Local::now().format("%Y%m%d_%H%M%S")
+        ));
+        tracing::info!("ui: support bundle requested");
+        self.send(Command::SaveSupportBundle { info, path })
+    }
+
+    fn report_problem(&mut self) -> Result<(), Status> {
+        let Some(url) = self.app_config.report_url.clone() else {
+            return Err(Status::error("Problem reports are not configured"));
+        };
+        let description = self.problem_report.as_deref().unwrap_or_default().trim().to_string();
+        if description.is_empty() {
+            return Err(Status::error("Describe the problem first"));
+        }
+        let agent = http::agent(&self.config.proxy);
+        let info = self.support_info();
+        tracing::info!("ui: problem report requested");
+        self.send(Command::ReportProblem {
+            agent,
+            url,
+            description,
+            info,
+        })
+    }
+
+    fn scan_cache(&mut self) -> Result<(), Status> {
+        let dirs = self.cache_dirs()?;
+        self.send(Command::ScanCache(dirs))
+    }
+
+    fn clear_cache(&mut self) -> Result<(), Status> {
+        let dirs = self.cache_dirs()?;
+        if !self.confirm_clear_cache {
+            return Err(Status::error("Confirm cache cleanup first"));
+        }
+        self.confirm_clear_cache = false;
+        tracing::info!("ui: cache cleanup requested");
+        self.send(Command::ClearCache(dirs))
+    }
+
+    fn start_client(&mut self) {
+        let Some(session) = &self.current_session else {
+            return;
+        };
+        let args = launch::client_args(&self.app_config, &self.config, &session.token);
+        #[cfg(windows)]
+        if self.config.launch_elevated && self.app_config.launch.mode == LaunchMode::Native {
+            self.start_client_elevated(args);
+            return;
+        }
+        let mut command =
+            launch::client_command(&self.app_config.launch, &self.app_config.dnf_exe_path);
+        command.args(args);
+        match command.spawn() {
+            Ok(child) => {
+                info!("launching game (pid {}, {:?})", child.id(), self.app_config.launch.mode);
+                self.game = Some(GameMonitor::spawn(child));
+                self.status = Status::success("Launching Game...");
+            }
+            Err(err) => {
+                error!("failed to launch game: {err}");
+                self.status = Status::error(format!("Launch failed: {err}"));
+            }
+        }
+    }
+
+    #[cfg(windows)]
+    fn start_client_elevated(&mut self, args: Vec<String>) {
+        let exe_path = self.app_config.dnf_exe_path.clone();
+        tracing::info!("ui: elevated launch requested");
+        let result = self.send(Command::LaunchElevated { exe_path, args });
+        if self.check_status(result).is_some() {
+            self.status = Status::info("Waiting for administrator permission...");
+        }
+    }
+
+    fn render_login(&mut self, ui: &mut egui::Ui) {
+        let busy = self.service.is_busy();
+        ui.add_space(6.0);
+        ui.heading("Welcome Back");
+        ui.add_space(10.0);
+
+        if !self.parked.is_empty() {
+            self.render_sessions(ui, busy);
+            ui.add_space(10.0);
+        }
+
+        if self.app_config.realms.len() > 1 {
+            let current = self.app_config.realm(self.config.realm.as_deref()).name.clone();
+            let mut selected = current.clone();
+            ui.label(egui::RichText::new("Realm").color(Theme::text_muted()));
+            ui.add_enabled_ui(!busy, |ui| {
+                egui::ComboBox::from_id_salt("realm")
+                    .selected_text(&selected)
+                    .width(ui.available_width())
+                    .show_ui(ui, |ui| {
+                        for realm in &self.app_config.realms {
+                            ui.selectable_value(&mut selected, realm.name.clone(), &realm.name);
+                        }
+                    });
+            });
+            if selected != current {
+                self.switch_realm(selected);
+            }
+            ui.add_space(10.0);
+        }
+
+        if self.config.accounts.len() > 1 {
+            self.render_account_switcher(ui, busy);
+            ui.add_space(10.0);
+        }
+
+        let label = ui.label(egui::RichText::new("Username").color(Theme::text_muted()));
+        let username = ui
+            .add(
+                egui::TextEdit::singleline(&mut self.creds.username)
+                    .hint_text("Account name")
+                    .desired_width(ui.available_width())
+                    .background_color(Theme::surface()),
+            )
+            .labelled_by(label.id);
+        if self.app_config.enabled(Feature::CreateAccount) {
+            if username.changed() {
+                self.username_edited = Some(Instant::now());
+                ui.ctx().request_repaint_after(USERNAME_CHECK_DELAY);
+            }
+            self.check_username_soon();
+            self.render_username_hint(ui);
+        }
+        ui.add_space(10.0);
+        let label = ui.label(egui::RichText::new("Password").color(Theme::text_muted()));
+        ui.add(
+            egui::TextEdit::singleline(&mut self.creds.password)
+                .password(true)
+                .hint_text("Password")
+                .desired_width(ui.available_width())
+                .background_color(Theme::surface()),
+        )
+        .labelled_by(label.id);
+        ui.add_space(8.0);
+        ui.checkbox(&mut self.remember, "Remember me");
+        if let Some(banned) = &self.ban_notice {
+            ui.add_space(8.0);
+            self.render_ban_notice(ui, banned);
+        }
+        ui.add_space(12.0);
+
+        let login_btn = egui::Button::new(egui::RichText::new("SIGN IN").color(Theme::on_accent()))
+            .fill(Theme::accent())
+            .stroke(egui::Stroke::new(1.0, Theme::accent()));
+        if ui.add_enabled(!busy, login_btn).clicked() {
+            let result = self.login();
+            self.check_status(result);
+        }
+
+        if self.app_config.enabled(Feature::CreateAccount) {
+            ui.add_space(8.0);
+            let reg_btn =
+                egui::Button::new(egui::RichText::new("CREATE ACCOUNT").color(Theme::on_accent()))
+                    .fill(Theme::accent_soft())
+                    .stroke(egui::Stroke::new(1.0, Theme::accent()));
+            if ui.add_enabled(!busy, reg_btn).clicked() {
+                let result = self.create_account();
+                self.check_status(result);
+            }
+        }
+
+        ui.add_space(4.0);
+        ui.horizontal(|ui| {
+            if self.service.email_enabled() {
+                let forgot = egui::Button::new("Forgot password?").frame(false);
+                if ui.add_enabled(!busy, forgot).clicked() {
+                    self.password_reset = Some(PasswordReset::default());
+                }
+            }
+            if self.app_config.enabled(Feature::RecoveryCodes) {
+                let recover = egui::Button::new("Use a recovery code").frame(false);
+                if ui.add_enabled(!busy, recover).clicked() {
+                    self.recovery_login = Some(RecoveryLogin::default());
+                }
+            }
+        });
+    }
+
+    /// Picks one of the remembered accounts by its nickname and lets the player rename it.
+    fn render_account_switcher(&mut self, ui: &mut egui::Ui, busy: bool) {
+        let current = self.config.accounts.iter().position(|a| a.username == self.creds.username);
+        let mut picked = current;
+        ui.label(egui::RichText::new("Saved account").color(Theme::text_muted()));
+        ui.add_enabled_ui(!busy, |ui| {
+            let selected = current.map_or("Choose an account", |idx| {
+                self.config.accounts[idx].label()
+            });
+            egui::ComboBox::from_id_salt("saved_account")
+                .selected_text(selected)
+                .width(ui.available_width())
+                .show_ui(ui, |ui| {
+                    for (idx, account) in self.config.accounts.iter().enumerate() {
+                        ui.selectable_value(&mut picked, Some(idx), account.label())
+                            .on_hover_text(&account.username);
+                    }
+                });
+        });
+        if picked != current
+            && let Some(account) = picked.and_then(|idx| self.config.accounts.get(idx))
+        {
+            self.creds = Credentials {
+                username: account.username.clone(),
+                password: account.password.clone(),
+            };
+        }
+        let Some(idx) = picked else {
+            return;
+        };
+        ui.horizontal(|ui| {
+            ui.label(egui::RichText::new("Nickname").color(Theme::text_muted()));
+            let nickname = ui.add(
+                egui::TextEdit::singleline(&mut self.config.accounts[idx].nickname)
+                    .hint_text("e.g. Main")
+                    .char_limit(24)
+                    .desired_width(ui.available_width())
+                    .background_color(Theme::surface()),
+            );
+            if nickname.lost_focus() {
+                let _ = config::write_json("config.json", &self.config);
+            }
+        });
+    }
+
+    fn mail_char(&self) -> Option<i32> {
+        self.selected_character()
+            .map(|c| c.id)
+            .filter(|_| self.app_config.enabled(Feature::Mail))
+    }
+
+    /// Guild of the selected character, when it is the guild master.
+    fn led_guild(&self) -> Option<i32> {
+        self.selected_character()
+            .and_then(|c| c.guild.as_ref())
+            .filter(|g| g.rank == GuildRank::Master)
+            .map(|g| g.guild_id)
+            .filter(|_| self.app_config.enabled(Feature::Guild))
+    }
+
+    /// Dashboard tabs available for the selected character, in display order.
+    fn dashboard_tabs(&self) -> Vec<DashboardTab> {
+        let mut tabs = vec![DashboardTab::Characters];
+        if self.mail_char().is_some() {
+            tabs.push(DashboardTab::Mail);
+        }
+        if self.led_guild().is_some() {
+            tabs.push(DashboardTab::Guild);
+        }
+        tabs.push(DashboardTab::History);
+        if self.app_config.news_url.is_some() {
+            tabs.push(DashboardTab::News);
+        }
+        tabs
+    }
+
+    /// Switches the dashboard tab and loads its contents the first time it is shown.
+    fn open_tab(&mut self, tab: DashboardTab) {
+        self.tab = tab;
+        if self.config.dashboard_tab != tab {
+            self.config.dashboard_tab = tab;
+            let _ = config::write_json("config.json", &self.config);
+        }
+        if self.service.is_busy() {
+            return;
+        }
+        let result = match tab {
+            DashboardTab::Mail => match self.mail_char() {
+                Some(char_id) if self.loaded_mail(char_id).is_none() => self.load_mail(char_id),
+                _ => Ok(()),
+            },
+            DashboardTab::Guild => match self.led_guild() {
+                Some(guild_id)
+                    if !matches!(&self.guild_members, Some((id, _)) if *id == guild_id) =>
+                {
+                    self.load_guild_members(guild_id)
+                }
+                _ => Ok(()),
+            },
+            DashboardTab::History if self.balance_history.is_none() => self.load_balance_history(),
+            DashboardTab::News if self.news.is_none() => self.load_news(),
+            _ => Ok(()),
+        };
+        self.check_status(result);
+    }
+
+    /// Forgets the client once it exits, gathering its logs first if it crashed.
+    fn poll_game(&mut self) {
+        let Some(game) = &self.game else {
+            return;
+        };
+        let state = game.state();
+        if !state.exited {
+            return;
+        }
+        if let Some(status) = state.status.filter(|_| state.crashed()) {
+            // Collect on a later frame if another operation holds the service.
+            if self.service.is_busy() {
+                return;
+            }
+            tracing::warn!("ui: game crashed with {status}");
+            let command = Command::CollectCrashLogs {
+                game_dir: launch::game_dir(&self.app_config.dnf_exe_path),
+                entries: self.app_config.crash_logs.clone(),
+                since: game.started(),
+                exit: status.to_string(),
+            };
+            let result = self.send(command);
+            self.check_status(result);
+        }
+        self.game = None;
+    }
+
+    fn save_crash_report(&mut self) -> Result<(), Status> {
+        let Some(report) = self.crash_report.take() else {
+            return Ok(());
+        };
+        let path = format!(
+            "{CRASH_DIR}/crash_{}.json.gz",
+            chrono::Local::now().format("%Y%m%d_%H%M%S")
+        );
+        tracing::info!("ui: saving crash report");
+        self.send(Command::SaveCrashReport { report, path })
+    }
+
+    fn upload_crash_report(&mut self) -> Result<(), Status> {
+        let (Some(url), Some(report)) =
+            (self.app_config.crash_report_url.clone(), self.crash_report.take())
+        else {
+            return Ok(());
+        };
+        let agent = http::agent(&self.config.proxy);
+        tracing::info!("ui: uploading crash report");
+        self.send(Command::UploadCrashReport { agent, url, report })
+    }
+
+    fn render_sessions(&mut self, ui: &mut egui::Ui, busy: bool) {
+        for parked in &mut self.parked {
+            if parked.game.as_ref().is_some_and(|game| game.state().exited) {
+                parked.game = None;
+            }
+        }
+        if self.parked.is_empty() {
+            return;
+        }
+        let current = self
+            .current_session
+            .is_some()
+            .then(|| self.account_label(&self.creds.username));
+        let labels: Vec<String> =
+            self.parked.iter().map(|p| self.account_label(&p.creds.username)).collect();
+        let numbers = self.config.number_format;
+        let mut resume = None;
+        let mut play = None;
+        let mut sign_out = None;
+        let count = labels.len() + usize::from(current.is_some());
+        egui::CollapsingHeader::new(format!("SESSIONS | {count}"))
+            .id_salt("sessions")
+            .default_open(true)
+            .show(ui, |ui| {
+                if let Some(current) = &current {
+                    ui.horizontal(|ui| {
+                        ui.label(egui::RichText::new(current).strong());
+                        let state = if self.game.is_some() { "in game" } else { "in use" };
+                        ui.label(egui::RichText::new(state).color(Theme::text_muted()));
+                    });
+                }
+                for (idx, (parked, label)) in self.parked.iter().zip(&labels).enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(label);
+                        let cera = parked.session.cera.map_or_else(
+                            || "cera unavailable".to_string(),
+                            |cera| format!("{} cera", numbers.format(cera)),
+                        );
+                        ui.label(egui::RichText::new(cera).color(Theme::text_muted()));
+                        if let Some(game) = &parked.game {
+                            ui.label(egui::RichText::new("in game").color(Theme::success()))
+                                .on_hover_text(format!("Client process {}", game.pid()));
+                        } else if ui.add_enabled(!busy, egui::Button::new("PLAY")).clicked() {
+                            play = Some(idx);
+                        }
+                        if ui.add_enabled(!busy, egui::Button::new("Switch")).clicked() {
+                            resume = Some(idx);
+                        }
+                        if ui.add_enabled(!busy, egui::Button::new("Sign out")).clicked() {
+                            sign_out = Some(idx);
+                        }
+                    });
+                }
+            });
+        if let Some(idx) = sign_out {
+            let parked = self.parked.remove(idx);
+            tracing::info!("ui: signed out parked session of uid {}", parked.session.uid);
+        } else if let Some(idx) = resume {
+            self.resume_session(idx);
+        } else if let Some(idx) = play {
+            self.resume_session(idx);
+            self.launch_game();
+        }
+    }
+
+    fn render_game_usage(&self, ui: &mut egui::Ui) {
+        let Some(game) = &self.game else {
+            return;
+        };
+        let state = game.state();
+        if state.exited {
+            return;
+        }
+        let text = match state.usage {
+            Some(usage) => format!(
+                "Game: CPU {:.0}% | RAM {}",
+                usage.cpu,
+                maintenance::format_bytes(usage.memory)
+            ),
+            None => "Game: starting...".to_string(),
+        };
+        ui.label(egui::RichText::new(text).small().color(Theme::text_muted()))
+            .on_hover_text(format!("Client process {}", game.pid()));
+    }
+
+    fn switch_realm(&mut self, name: String) {
+        let realm = self.app_config.realm(Some(&name));
+        self.db.set_realm(realm);
+        self.ping = spawn_ping(&self.app_config, realm);
+        self.config.realm = Some(name);
+        self.config.preferred_channel = None;
+        // Parked sessions belong to the old realm's database.
+        self.parked.clear();
+        let _ = config::write_json("config.json", &self.config);
+        self.status = Status::success(format!("Realm: {}", realm.name));
+    }
+
+    /// Channel name, if known, when the server has this account in game without a client
+    /// started from this launcher.
+    fn online_elsewhere(&self) -> Option<Option<String>> {
+        if self.game.is_some() {
+            return None;
+        }
+        let session = self.current_session.as_ref()?;
+        let online = session.online.as_ref()?;
+        Some(online.channel.map(|number| {
+            session
+                .channels
+                .iter()
+                .find(|channel| channel.number == number)
+                .map_or_else(|| format!("channel {number}"), |channel| channel.name.clone())
+        }))
+    }
+
+    fn render_online_notice(&mut self, ui: &mut egui::Ui, channel: Option<String>) {
+        egui::Frame::new()
+            .fill(Theme::surface())
+            .stroke(egui::Stroke::new(1.0, Theme::accent()))
+            .corner_radius(egui::CornerRadius::same(8))
+            .inner_margin(egui::Margin::symmetric(10, 8))
+            .show(ui, |ui| {
+                ui.set_width(ui.available_width());
+                let text = match channel {
+                    Some(channel) => format!("This account is currently in game on {channel}"),
+                    None => "This account is currently in game".to_string(),
+                };
+                ui.label(egui::RichText::new(text).strong());
+                ui.label(
+                    egui::RichText::new(
+                        "Starting the game again may disconnect the other session. Refresh once \
+                         it has signed out, or log it out if the game crashed.",
+                    )
+                    .color(Theme::text_muted()),
+                );
+                ui.checkbox(&mut self.play_anyway, "Play anyway");
+                let busy = self.service.is_busy();
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.confirm_force_logout, "Confirm");
+                    let label =
+                        egui::RichText::new("Log out in-game session").color(Theme::on_accent());
+                    let logout_btn = egui::Button::new(label).fill(Theme::accent_soft());
+                    if ui
+                        .add_enabled(!busy, logout_btn)
+                        .on_hover_text(
+                            "Clears the stale in-game flag a crash left behind; a client that is \
+                             really running stays connected",
+                        )
+                        .clicked()
+                    {
+                        let result = self.force_logout();
+                        self.check_status(result);
+                    }
+                });
+            });
+    }
+
+    fn render_ban_notice(&self, ui: &mut egui::Ui, banned: &Banned) {
+        egui::Frame::new()
+            .fill(Theme::surface())
+            .stroke(egui::Stroke::new(1.0, Theme::error()))
+            .corner_radius(egui::CornerRadius::same(8))
+            .inner_margin(egui::Margin::symmetric(10, 8))
+            .show(ui, |ui| {
+                ui.set_width(ui.available_width());
+                ui.label(egui::RichText::new("ACCOUNT BANNED").color(Theme::error()).strong());
+                let expiry = match banned.expires {
+                    Some(expires) => {
+                        format!("Until: {}", self.clock().format(expires, "%Y-%m-%d %H:%M"))
+                    }
+                    None => "Until: permanent".to_string(),
+                };
+                ui.label(egui::RichText::new(expiry).color(Theme::text_muted()));
+                if !banned.reason.is_empty() {
+                    ui.label(format!("Reason: {}", banned.reason));
+                }
+            });
+    }
+
+    fn render_dashboard(&mut self, ui: &mut egui::Ui) {
+        let busy = self.service.is_busy();
+        ui.add_space(4.0);
+        ui.horizontal(|ui| {
+            ui.heading("ACCOUNT DASHBOARD");
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                let refresh_btn =
+                    egui::Button::new(egui::RichText::new("Refresh").color(Theme::text()))
+                        .fill(Theme::surface_alt());
+                if ui.add_enabled(!busy, refresh_btn).clicked() {
+                    let result = self.refresh();
+                    self.check_status(result);
+                }
+            });
+        });
+        ui.add_space(6.0);
+
+        let cera = self.current_session.as_ref().and_then(|s| s.cera);
+        let slot_limit = self.current_session.as_ref().map(|s| s.slot_limit).unwrap_or_default();
+        ui.horizontal(|ui| {
+            match cera {
+                Some(cera) => {
+                    let text = format!("Cera: {}", self.num(cera));
+                    ui.label(egui::RichText::new(text).color(Theme::text_muted()));
+                }
+                None => {
+                    ui.label(egui::RichText::new("Cera: unavailable").color(Theme::error()))
+                        .on_hover_text("The billing database is unreachable; refresh to retry");
+                }
+            }
+            if self.app_config.enabled(Feature::CeraTransfer) {
+                let funded = cera.is_some_and(|cera| cera > 0);
+                let transfer = ui
+                    .add_enabled(!busy && funded, egui::Button::new("Transfer"))
+                    .on_hover_text("Send cera to another account");
+                if transfer.clicked() {
+                    self.transfer = Some(CeraTransfer::default());
+                }
+            }
+            let export = ui
+                .add_enabled(!busy, egui::Button::new("Export"))
+                .on_hover_text("Save your cera and gold history as CSV");
+            if export.clicked() {
+                let result = self.export_billing();
+                self.check_status(result);
+            }
+            let price = self.app_config.slot_price_cera;
+            let for_sale = self.app_config.enabled(Feature::SlotPurchase) && price > 0;
+            if for_sale && slot_limit < MAX_CHAR_SLOTS {
+                let buy = ui
+                    .add_enabled(!busy && cera.is_some(), egui::Button::new("+1 slot"))
+                    .on_hover_text(format!("Buy a character slot for {} cera", self.num(price)));
+                if buy.clicked() {
+                    let result = self.purchase_slot();
+                    self.check_status(result);
+                }
+            }
+        });
+        ui.add_space(6.0);
+        if !self.app_config.daily_rewards.is_empty() {
+            self.render_daily_rewards(ui);
+            ui.add_space(6.0);
+        }
+        if self.app_config.discord_linking {
+            self.render_discord(ui);
+            ui.add_space(6.0);
+        }
+        if self.service.email_enabled() {
+            self.render_email(ui);
+            ui.add_space(6.0);
+        }
+        if self.app_config.enabled(Feature::RecoveryCodes) {
+            self.render_recovery_codes(ui);
+            ui.add_space(6.0);
+        }
+        if self.app_config.enabled(Feature::StoragePin) {
+            self.render_secondary_pin(ui);
+            ui.add_space(6.0);
+        }
+
+        let selected_id = self.selected_character().map(|c| c.id);
+        let led_guild = self.led_guild();
+        let mail_char = self.mail_char();
+        let has_news = self.app_config.news_url.is_some();
+        let tabs = self.dashboard_tabs();
+        if tabs.len() > 1 {
+            ui.horizontal(|ui| {
+                for tab in tabs {
+                    if ui.selectable_label(self.tab == tab, tab.label()).clicked() {
+                        self.open_tab(tab);
+                    }
+                }
+            });
+            ui.add_space(6.0);
+        }
+
+        match (self.tab, mail_char, led_guild) {
+            (DashboardTab::Mail, Some(char_id), _) => self.render_mail(ui, char_id),
+            (DashboardTab::Guild, _, Some(guild_id)) => self.render_guild(ui, guild_id),
+            (DashboardTab::History, _, _) => self.render_balance_history(ui),
+            (DashboardTab::News, _, _) if has_news => self.render_news(ui),
+            _ => self.render_characters(ui),
+        }
+
+        let send_gold = self.app_config.enabled(Feature::SendGold);
+        let send_cera = self.app_config.enabled(Feature::SendCera);
+        if send_gold || send_cera || !self.app_config.currencies.is_empty() {
+            self.render_currency_management(ui, send_gold, send_cera);
+        }
+        if let Some(char_id) = selected_id.filter(|_| send_gold) {
+            self.render_sends(ui, char_id);
+        }
+        if let Some(last) = &self.last_send {
+            let elapsed = last.at.elapsed();
+            if elapsed < UNDO_WINDOW {
+                let remaining = (UNDO_WINDOW - elapsed).as_secs() + 1;
+                let text = format!("Sent {} {}", last.amount, last.currency);
+                let mut undo = false;
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new(text).color(Theme::text_muted()));
+                    undo = ui
+                        .add_enabled(!busy, egui::Button::new(format!("Undo ({remaining}s)")))
+                        .clicked();
+                });
+                if undo {
+                    let result = self.undo_last_send();
+                    self.check_status(result);
+                }
+            } else {
+                self.last_send = None;
+            }
+        }
+
+        ui.add_space(6.0);
+        self.render_channels(ui);
+
+        if let Some(channel) = self.online_elsewhere() {
+            ui.add_space(8.0);
+            self.render_online_notice(ui, channel);
+        }
+
+        ui.add_space(12.0);
+        let play_btn = egui::Button::new(egui::RichText::new("PLAY GAME").color(Theme::on_accent()))
+            .fill(Theme::accent());
+        ui.horizontal(|ui| {
+            if ui.add_enabled(!busy, play_btn).clicked() {
+                self.launch_game();
+            }
+            if let Some(ping) = self.ping.as_ref().and_then(PingMonitor::latest) {
+                let (text, color) = match ping {
+                    Ping::Latency(latency) => {
+                        let ms = latency.as_millis();
+                        let color = match ms {
+                            0..=80 => Theme::success(),
+                            81..=200 => Theme::text_muted(),
+                            _ => Theme::error(),
+                        };
+                        (format!("{ms} ms"), color)
+                    }
+                    Ping::Unreachable => ("Server unreachable".to_string(), Theme::error()),
+                };
+                ui.label(egui::RichText::new(text).color(color))
+                    .on_hover_text("TCP connect time to the game server");
+            }
+        });
+        self.render_game_usage(ui);
+
+        ui.add_space(6.0);
+        self.render_sessions(ui, busy);
+        if self.app_config.multi_client {
+            if ui
+                .add_enabled(!busy, egui::Button::new("ADD ACCOUNT"))
+                .on_hover_text("Sign in another account; this one stays signed in")
+                .clicked()
+            {
+                self.park_session();
+            }
+        } else if ui
+            .add_enabled(!busy, egui::Button::new("SWITCH ACCOUNT"))
+            .clicked()
+        {
+            self.reset_screen(Screen::Login);
+        }
+
+        if self.app_config.admin_mode {
+            ui.add_space(6.0);
+            if ui.button("ADMIN PANEL").clicked() {
+                self.navigate(Screen::Admin);
+            }
+        }
+    }
+
+    fn render_channels(&mut self, ui: &mut egui::Ui) {
+        let Some(session) = &self.current_session else {
+            return;
+        };
+        if session.channels.is_empty() {
+            return;
+        }
+        let preferred = self.config.preferred_channel;
+        let mut picked = None;
+        egui::CollapsingHeader::new("CHANNELS").show(ui, |ui| {
+            for channel in &session.channels {
+                ui.horizontal(|ui| {
+                    let selected = preferred == Some(channel.number);
+                    let label = format!("{} {}", channel.number, channel.name);
+                    if ui.selectable_label(selected, label).clicked() {
+                        picked = Some(if selected { None } else { Some(channel.number) });
+                    }
+                    let color = if channel.load() > 0.9 { Theme::error() } else { Theme::accent() };
+                    ui.add(
+                        egui::ProgressBar::new(channel.load())
+                            .fill(color)
+                            .text(format!("{}/{}", channel.population, channel.capacity)),
+                    );
+                });
+            }
+        });
+        if let Some(channel) = picked {
+            self.config.preferred_channel = channel;
+            let _ = config::write_json("config.json", &self.config);
+        }
+    }
+
+    fn render_splash(&mut self, ui: &mut egui::Ui) {
+        ui.add_space(4.0);
+        ui.heading("STARTING UP");
+        ui.add_space(6.0);
+        // The checks finish while Settings is open too; Back then lands here with nothing left
+        // to show, so move on to the login screen.
+        let Some(startup) = &self.startup else {
+            self.reset_screen(Screen::Login);
+            return;
+        };
+        let states = startup.states();
+        let done = startup.is_done();
+        egui::Frame::new()
+            .fill(Theme::surface())
+            .corner_radius(egui::CornerRadius::same(8))
+            .inner_margin(egui::Margin::symmetric(10, 8))
+            .show(ui, |ui| {
+                ui.set_width(ui.available_width());
+                for (check, state) in &states {
+                    ui.horizontal(|ui| {
+                        match state {
+                            CheckState::Running => {
+                                ui.add(egui::Spinner::new());
+                            }
+                            CheckState::Passed => {
+                                ui.label(egui::RichText::new("✔").color(Theme::success()));
+                            }
+                            CheckState::Failed(_) => {
+                                ui.label(egui::RichText::new("✖").color(Theme::error()));
+                            }
+                        }
+                        ui.label(check.label());
+                    });
+                    if let CheckState::Failed(err) = state {
+                        ui.label(egui::RichText::new(err).small().color(Theme::text_muted()));
+                    }
+                }
+            });
+        if !done {
+            return;
+        }
+        ui.add_space(8.0);
+        ui.horizontal(|ui| {
+            if ui.button("Retry").clicked() {
+                self.restart_startup_checks(ui.ctx());
+            }
+            let proceed = ui
+                .button("Continue")
+                .on_hover_text("Open the login screen anyway; failed parts won't work");
+            if proceed.clicked() {
+                tracing::info!("ui: continuing past failed startup checks");
+                self.startup = None;
+                self.reset_screen(Screen::Login);
+            }
+        });
+    }
+
+    fn render_update_required(&mut self, ui: &mut egui::Ui) {
+        let busy = self.service.is_busy();
+        ui.add_space(4.0);
+        ui.horizontal(|ui| {
+            ui.heading("UPDATE REQUIRED");
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui.button("Settings").clicked() {
+                    self.navigate(Screen::Settings);
+                }
+            });
+        });
+        ui.add_space(6.0);
+        let min = self.update_required.clone().unwrap_or_default();
+        egui::Frame::new()
+            .fill(Theme::surface())
+            .stroke(egui::Stroke::new(1.0, Theme::error()))
+            .corner_radius(egui::CornerRadius::same(8))
+            .inner_margin(egui::Margin::symmetric(10, 8))
+            .show(ui, |ui| {
+                ui.set_width(ui.available_width());
+                ui.label(format!(
+                    "This server requires launcher {min} or newer. You are running {}.",
+                    env!("CARGO_PKG_VERSION")
+                ));
+                ui.label(
+                    egui::RichText::new("Sign-in stays blocked until the launcher is updated.")
+                        .color(Theme::text_muted()),
+                );
+            });
+        ui.add_space(12.0);
+        let manifest = self.app_config.updates.launcher_manifest(self.config.update_channel);
+        if let Some(release) = &self.launcher_release {
+            let label = egui::RichText::new(format!("INSTALL LAUNCHER {}", release.version))
+                .color(Theme::on_accent());
+            let install = egui::Button::new(label)
+                .fill(Theme::accent())
+                .stroke(egui::Stroke::new(1.0, Theme::accent()));
+            if ui.add_enabled(!busy, install).clicked() {
+                let result = self.install_launcher_update();
+                self.check_status(result);
+            }
+        } else if manifest.is_some() {
+            if ui.add_enabled(!busy, egui::Button::new("Check for update")).clicked() {
+                self.check_launcher_release();
+            }
+        } else {
+            ui.label(
+                egui::RichText::new("Download the latest launcher from the server's website.")
+                    .color(Theme::text_muted()),
+            );
+        }
+    }
+
+    fn render_terms(&mut self, ui: &mut egui::Ui) {
+        let busy = self.service.is_busy();
+        ui.add_space(4.0);
+        ui.horizontal(|ui| {
+            ui.heading("SERVER RULES");
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui.button("Back").clicked() {
+                    self.go_back();
+                }
+            });
+        });
+        ui.add_space(6.0);
+        let Some(terms) = &self.app_config.terms else {
+            return;
+        };
+        egui::Frame::new()
+            .fill(Theme::surface())
+            .corner_radius(egui::CornerRadius::same(8))
+            .inner_margin(egui::Margin::symmetric(10, 8))
+            .show(ui, |ui| {
+                ui.set_width(ui.available_width());
+                egui::ScrollArea::vertical().max_height(360.0).show(ui, |ui| {
+                    markdown::render(ui, &terms.text);
+                });
+            });
+        ui.add_space(8.0);
+        ui.checkbox(&mut self.terms_accepted, "I have read and accept the rules");
+        ui.add_space(8.0);
+        let label = egui::RichText::new("CREATE ACCOUNT").color(Theme::on_accent());
+        let create = egui::Button::new(label)
+            .fill(Theme::accent_soft())
+            .stroke(egui::Stroke::new(1.0, Theme::accent()));
+        let username = &self.creds.username;
+        if ui
+            .add_enabled(!busy && self.terms_accepted, create)
+            .on_hover_text(format!("Registers {username}"))
+            .clicked()
+        {
+            let result = self.create_account();
+            self.check_status(result);
+        }
+    }
+
+    fn render_settings(&mut self, ui: &mut egui::Ui) {
+        let busy = self.service.is_busy();
+        ui.add_space(4.0);
+        ui.horizontal(|ui| {
+            ui.heading("SETTINGS");
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui.button("Back").clicked() {
+                    self.go_back();
+                }
+            });
+        });
+        ui.add_space(6.0);
+
+        egui::ScrollArea::vertical().max_height(460.0).show(ui, |ui| {
+            self.render_settings_game(ui);
+            self.render_settings_display(ui);
+            self.render_settings_updates(ui, busy);
+            self.render_settings_network(ui, busy);
+            self.render_settings_sound(ui);
+            self.render_settings_shortcuts(ui);
+            self.render_settings_desktop(ui, busy);
+            self.render_settings_maintenance(ui, busy);
+            self.render_settings_import(ui, busy);
+        });
+    }
+
+    fn render_settings_game(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("GAME")
+            .default_open(true)
+            .show(ui, |ui| {
+                ui.label(
+                    egui::RichText::new(format!(
+                        "Launch mode: {:?}",
+                        self.app_config.launch.mode
+                    ))
+                    .color(Theme::text_muted()),
+                );
+                ui.horizontal(|ui| {
+                    if ui.button("Check game folder").clicked() && self.validate_game_folder() {
+                        self.status = Status::success("Game folder looks good");
+                    }
+                    ui.label(
+                        egui::RichText::new(&self.app_config.dnf_exe_path)
+                            .small()
+                            .color(Theme::text_muted()),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new("Sign out when idle").color(Theme::text_muted()));
+                    if let Some(minutes) = self.app_config.idle_timeout_mins {
+                        ui.label(format!("after {minutes} min (set by the server)"));
+                        return;
+                    }
+                    let idle = ui.add(
+                        egui::DragValue::new(&mut self.config.idle_timeout_mins)
+                            .range(0..=240)
+                            .suffix(" min"),
+                    );
+                    if idle.on_hover_text("0 keeps you signed in").changed() {
+                        let _ = config::write_json("config.json", &self.config);
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new("Auto-refresh every").color(Theme::text_muted()));
+                    let interval = ui.add(
+                        egui::DragValue::new(&mut self.config.auto_refresh_mins)
+                            .range(0..=120)
+                            .suffix(" min"),
+                    );
+                    if interval.on_hover_text("0 only refreshes when you ask").changed() {
+                        let _ = config::write_json("config.json", &self.config);
+                    }
+                });
+                let mut quiet = !self.config.refresh_in_game;
+                let response = ui.add_enabled(
+                    self.config.auto_refresh_mins > 0,
+                    egui::Checkbox::new(&mut quiet, "Don't refresh while the game is running"),
+                );
+                if response.changed() {
+                    self.config.refresh_in_game = !quiet;
+                    let _ = config::write_json("config.json", &self.config);
+                }
+                if cfg!(windows) && self.app_config.launch.mode == LaunchMode::Native {
+                    let response =
+                        ui.checkbox(&mut self.config.launch_elevated, "Run as administrator");
+                    if response
+                        .on_hover_text("Needed by some client builds; shows a UAC prompt")
+                        .changed()
+                    {
+                        let _ = config::write_json("config.json", &self.config);
+                    }
+                }
+                if self.app_config.client_registry.is_some() {
+                    ui.add_space(4.0);
+                    ui.horizontal(|ui| {
+                        if ui.button("Write registry keys").clicked() {
+                            self.write_registry();
+                        }
+                        let text = if self.config.registry_written {
+                            "Configured"
+                        } else {
+                            "Not configured"
+                        };
+                        ui.label(egui::RichText::new(text).color(Theme::text_muted()));
+                    });
+                }
+            });
+    }
+
+    fn render_settings_display(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("DISPLAY")
+            .default_open(true)
+            .show(ui, |ui| {
+                let response = ui
+                    .checkbox(&mut self.config.performance_mode, "Performance mode")
+                    .on_hover_text("Turns off the lightning effect, animations and shadows");
+                let mut changed = response.changed();
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new("Theme").color(Theme::text_muted()));
+                    let theme = &mut self.config.theme;
+                    egui::ComboBox::from_id_salt("theme")
+                        .selected_text(theme.label())
+                        .show_ui(ui, |ui| {
+                            for option in ThemeVariant::ALL {
+                                changed |=
+                                    ui.selectable_value(theme, option, option.label()).changed();
+                            }
+                        });
+                });
+                let fonts = &mut self.config.fonts;
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new("Text size").color(Theme::text_muted()));
+                    let body = ui.add(
+                        egui::DragValue::new(&mut fonts.body)
+                            .range(FontSizes::BODY_RANGE)
+                            .speed(0.25)
+                            .suffix(" pt"),
+                    );
+                    ui.label(egui::RichText::new("Headings").color(Theme::text_muted()));
+                    let heading = ui.add(
+                        egui::DragValue::new(&mut fonts.heading)
+                            .range(FontSizes::HEADING_RANGE)
+                            .speed(0.25)
+                            .suffix(" pt"),
+                    );
+                    changed |= [body, heading]
+                        .iter()
+                        .any(|response| response.drag_stopped() || response.lost_focus());
+                    if ui.small_button("Reset").clicked() {
+                        *fonts = FontSizes::default();
+                        changed = true;
+                    }
+                });
+                let status = &mut self.config.status_colors;
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new("Status colors").color(Theme::text_muted()));
+                    egui::ComboBox::from_id_salt("status_palette")
+                        .selected_text(status.palette.label())
+                        .show_ui(ui, |ui| {
+                            for option in StatusPalette::ALL {
+                                changed |= ui
+                                    .selectable_value(&mut status.palette, option, option.label())
+                                    .changed();
+                            }
+                        });
+                });
+                if status.palette == StatusPalette::Custom {
+                    ui.horizontal(|ui| {
+                        ui.label(egui::RichText::new("Success").color(Theme::text_muted()));
+                        changed |= ui.color_edit_button_srgb(&mut status.success).changed();
+                        ui.label(egui::RichText::new("Error").color(Theme::text_muted()));
+                        changed |= ui.color_edit_button_srgb(&mut status.error).changed();
+                    });
+                }
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new("Numbers").color(Theme::text_muted()));
+                    let format = &mut self.config.number_format;
+                    egui::ComboBox::from_id_salt("number_format")
+                        .selected_text(format.label())
+                        .show_ui(ui, |ui| {
+                            for option in NumberFormat::ALL {
+                                changed |=
+                                    ui.selectable_value(format, option, option.label()).changed();
+                            }
+                        });
+                });
+                let zone = match self.app_config.server_timezone {
+                    Some(zone) => format!("Times are stored in {zone}"),
+                    None => "The server uses this computer's timezone".to_string(),
+                };
+                changed |= ui
+                    .checkbox(&mut self.config.show_server_time, "Show server time")
+                    .on_hover_text(zone)
+                    .changed();
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new("Renderer").color(Theme::text_muted()));
+                    let renderer = &mut self.config.renderer;
+                    egui::ComboBox::from_id_salt("renderer")
+                        .selected_text(renderer.label())
+                        .show_ui(ui, |ui| {
+                            for option in Renderer::ALL {
+                                changed |= ui
+                                    .selectable_value(renderer, option, option.label())
+                                    .changed();
+                            }
+                        });
+                });
+                changed |= ui
+                    .checkbox(&mut self.config.software_rendering, "Software rendering")
+                    .on_hover_text("Draws without the graphics card; slower but avoids driver bugs")
+                    .changed();
+                ui.label(
+                    egui::RichText::new("Renderer changes apply after a restart.")
+                        .small()
+                        .color(Theme::text_muted()),
+                );
+                if changed {
+                    let _ = config::write_json("config.json", &self.config);
+                }
+            });
+    }
+
+    fn render_settings_updates(&mut self, ui: &mut egui::Ui, busy: bool) {
+        egui::CollapsingHeader::new("UPDATES")
+            .default_open(true)
+            .show(ui, |ui| {
+                let current = self.config.update_channel;
+                let mut selected = current;
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new("Channel").color(Theme::text_muted()));
+                    egui::ComboBox::from_id_salt("update_channel")
+                        .selected_text(selected.as_str())
+                        .show_ui(ui, |ui| {
+                            for channel in UpdateChannel::ALL {
+                                ui.selectable_value(&mut selected, channel, channel.as_str());
+                            }
+                        });
+                });
+                if selected != current {
+                    self.config.update_channel = selected;
+                    self.update_plan = None;
+                    self.launcher_release = None;
+                    let _ = config::write_json("config.json", &self.config);
+                }
+                ui.label(
+                    egui::RichText::new(format!("Launcher v{}", env!("CARGO_PKG_VERSION")))
+                        .color(Theme::text_muted()),
+                );
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new("Download limit").color(Theme::text_muted()));
+                    let response = ui.add(
+                        egui::DragValue::new(&mut self.config.download_limit_kbps)
+                            .speed(64)
+                            .range(0..=1_000_000)
+                            .custom_formatter(|value, _| match value as u32 {
+                                0 => "Unlimited".to_string(),
+                                kbps => format!("{kbps} KB/s"),
+                            }),
+                    );
+                    if response.changed()
+                        && let Some(job) = &self.patch
+                    {
+                        job.set_limit(u64::from(self.config.download_limit_kbps) * 1024);
+                    }
+                    if response.drag_stopped() || response.lost_focus() {
+                        let _ = config::write_json("config.json", &self.config);
+                    }
+                });
+                let mirrors = ui
+                    .checkbox(&mut self.config.use_mirrors, "Use community mirrors")
+                    .on_hover_text("Fetch large game files from volunteer mirrors when available");
+                if mirrors.changed() {
+                    if let Some(job) = &self.patch {
+                        job.set_use_mirrors(self.config.use_mirrors);
+                    }
+                    let _ = config::write_json("config.json", &self.config);
+                }
+
+                let patching = self.patch.as_ref().map(PatchJob::progress);
+                if let Some(progress) = &patching {
+                    self.render_patch_progress(ui, progress);
+                }
+                let patch_running = patching.as_ref().is_some_and(|p| p.finished.is_none());
+                ui.horizontal(|ui| {
+                    let enabled = !busy && !patch_running;
+                    if ui.add_enabled(enabled, egui::Button::new("Check for updates")).clicked() {
+                        let result = self.check_updates();
+                        self.check_status(result);
+                    }
+                    if let Some(plan) = &self.update_plan {
+                        let label = format!(
+                            "Update game ({})",
+                            maintenance::format_bytes(plan.total_bytes)
+                        );
+                        if ui.add_enabled(enabled, egui::Button::new(label)).clicked() {
+                            self.start_patch();
+                        }
+                    }
+                });
+                if let Some(release) = &self.launcher_release {
+                    let label = format!("Install launcher {}", release.version);
+                    if ui.add_enabled(!busy, egui::Button::new(label)).clicked() {
+                        let result = self.install_launcher_update();
+                        self.check_status(result);
+                    }
+                }
+            });
+    }
+
+    fn render_settings_network(&mut self, ui: &mut egui::Ui, busy: bool) {
+        egui::CollapsingHeader::new("NETWORK")
+            .default_open(false)
+            .show(ui, |ui| {
+                let proxy = &mut self.config.proxy;
+                let mut changed = false;
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new("Proxy").color(Theme::text_muted()));
+                    egui::ComboBox::from_id_salt("proxy_mode")
+                        .selected_text(proxy.mode.label())
+                        .show_ui(ui, |ui| {
+                            for mode in ProxyMode::ALL {
+                                changed |= ui
+                                    .selectable_value(&mut proxy.mode, mode, mode.label())
+                                    .changed();
+                            }
+                        });
+                });
+                if proxy.mode == ProxyMode::Manual {
+                    ui.horizontal(|ui| {
+                        let host = ui.add(
+                            egui::TextEdit::singleline(&mut proxy.host)
+                                .hint_text("proxy.example.com")
+                                .desired_width(180.0),
+                        );
+                        let port = ui.add(egui::DragValue::new(&mut proxy.port).prefix(":"));
+                        changed |= host.lost_focus() || port.changed();
+                    });
+                    ui.horizontal(|ui| {
+                        let user = ui.add(
+                            egui::TextEdit::singleline(&mut proxy.username)
+                                .hint_text("Username (optional)")
+                                .desired_width(130.0),
+                        );
+                        let pass = ui.add(
+                            egui::TextEdit::singleline(&mut proxy.password)
+                                .password(true)
+                                .hint_text("Password")
+                                .desired_width(130.0),
+                        );
+                        changed |= user.lost_focus() || pass.lost_focus();
+                    });
+                }
+                if changed {
+                    let _ = config::write_json("config.json", &self.config);
+                }
+                if !self.app_config.hosts_redirects.is_empty() {
+                    ui.add_space(6.0);
+                    self.render_hosts_check(ui, busy);
+                }
+            });
+    }
+
+    fn render_hosts_check(&mut self, ui: &mut egui::Ui, busy: bool) {
+        ui.label(egui::RichText::new("Server hostnames").color(Theme::text_muted()));
+        for redirect in &self.app_config.hosts_redirects {
+            let check =
+                self.hosts_checks.iter().flatten().find(|c| c.redirect.host == redirect.host);
+            let (mark, color, resolved) = match check {
+                Some(check) if check.ok() => ("✔", Theme::success(), String::new()),
+                Some(check) => {
+                    let resolved = match check.resolved.first() {
+                        Some(ip) => format!(" (resolves to {ip})"),
+                        None => " (does not resolve)".to_string(),
+                    };
+                    ("✖", Theme::error(), resolved)
+                }
+                None => ("•", Theme::text_muted(), String::new()),
+            };
+            ui.horizontal(|ui| {
+                ui.label(egui::RichText::new(mark).color(color));
+                ui.label(
+                    egui::RichText::new(format!("{} → {}{resolved}", redirect.host, redirect.ip))
+                        .small(),
+                );
+            });
+        }
+        let broken = self.hosts_checks.iter().flatten().any(|check| !check.ok());
+        ui.horizontal(|ui| {
+            if ui.add_enabled(!busy, egui::Button::new("Check")).clicked() {
+                let redirects = self.app_config.hosts_redirects.clone();
+                let result = self.send(Command::CheckHosts(redirects));
+                self.check_status(result);
+            }
+            let fix = ui
+                .add_enabled(!busy && broken, egui::Button::new("Fix hosts file"))
+                .on_hover_text("Rewrite the hosts file entries; needs administrator rights");
+            if fix.clicked() {
+                let redirects = self.app_config.hosts_redirects.clone();
+                self.status = Status::info("Updating the hosts file...");
+                let result = self.send(Command::FixHosts(redirects));
+                self.check_status(result);
+            }
+        });
+    }
+
+    fn render_settings_sound(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("SOUND")
+            .default_open(false)
+            .show(ui, |ui| {
+                if !SoundPlayer::is_supported() {
+                    ui.label(
+                        egui::RichText::new("Sound effects are only available on Windows")
+                            .color(Theme::text_muted()),
+                    );
+                    return;
+                }
+                let sound = &mut self.config.sound;
+                let toggled = ui.checkbox(&mut sound.enabled, "Sound effects").changed();
+                let volume = ui.add_enabled(
+                    sound.enabled,
+                    egui::Slider::new(&mut sound.volume, 0.0..=1.0)
+                        .text("Volume")
+                        .custom_formatter(|v, _| format!("{:.0}%", v * 100.0)),
+                );
+                if volume.drag_stopped() {
+                    self.sounds.play(Sound::Click, sound.volume);
+                }
+                if toggled || volume.drag_stopped() || volume.lost_focus() {
+                    let _ = config::write_json("config.json", &self.config);
+                }
+            });
+    }
+
+    fn render_settings_shortcuts(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("KEYBOARD SHORTCUTS")
+            .default_open(false)
+            .show(ui, |ui| {
+                if let Some(shortcut) = self.capturing_key {
+                    let pressed = ui.input(|i| {
+                        i.events.iter().find_map(|event| match event {
+                            egui::Event::Key { key, pressed: true, .. } => Some(*key),
+                            _ => None,
+                        })
+                    });
+                    match pressed {
+                        Some(egui::Key::Escape) => self.capturing_key = None,
+                        // Taken by the debug overlay, which is checked after the shortcuts.
+                        Some(egui::Key::F12) => {
+                            self.status = Status::error("F12 is reserved for the debug overlay");
+                        }
+                        Some(egui::Key::Backspace | egui::Key::Delete) => {
+                            self.config.keybindings.unbind(shortcut);
+                            self.capturing_key = None;
+                        }
+                        Some(key) => {
+                            self.config.keybindings.bind(shortcut, key.name());
+                            self.capturing_key = None;
+                        }
+                        None => {}
+                    }
+                    if self.capturing_key.is_none() {
+                        let _ = config::write_json("config.json", &self.config);
+                    }
+                }
+                egui::Grid::new("shortcuts").num_columns(2).show(ui, |ui| {
+                    for shortcut in Shortcut::ALL {
+                        ui.label(shortcut.label());
+                        let text = if self.capturing_key == Some(shortcut) {
+                            "Press a key...".to_string()
+                        } else {
+                            match self.config.keybindings.key(shortcut) {
+                                "" => "Unbound".to_string(),
+                                key => key.to_string(),
+                            }
+                        };
+                        if ui.button(text).clicked() {
+                            self.capturing_key = Some(shortcut);
+                        }
+                        ui.end_row();
+                    }
+                });
+                ui.label(
+                    egui::RichText::new("Esc cancels, Backspace unbinds")
+                        .small()
+                        .color(Theme::text_muted()),
+                );
+                let defaults = KeyBindings::default();
+                if ui
+                    .add_enabled(self.config.keybindings != defaults, egui::Button::new("Reset"))
+                    .clicked()
+                {
+                    self.config.keybindings = defaults;
+                    let _ = config::write_json("config.json", &self.config);
+                }
+            });
+    }
+
+    fn render_patch_progress(&mut self, ui: &mut egui::Ui, progress: &patcher::PatchProgress) {
+        match &progress.finished {
+            Some(Ok(())) => {
+                ui.label(egui::RichText::new("Game is up to date").color(Theme::success()));
+            }
+            Some(Err(err)) => {
+                let text = egui::RichText::new(format!("Update failed: {err}"));
+                ui.label(text.color(Theme::error()));
+            }
+            None => {
+                let fraction = if progress.bytes_total > 0 {
+                    progress.bytes_done as f32 / progress.bytes_total as f32
+                } else {
+                    progress.files_done as f32 / progress.files_total.max(1) as f32
+                };
+                ui.add(egui::ProgressBar::new(fraction.min(1.0)).show_percentage());
+                ui.label(
+                    egui::RichText::new(format!(
+                        "{}/{} files | {}",
+                        progress.files_done,
+                        progress.files_total,
+                        progress.current.as_deref().unwrap_or_default()
+                    ))
+                    .small()
+                    .color(Theme::text_muted()),
+                );
+                let Some(job) = &self.patch else {
+                    return;
+                };
+                ui.horizontal(|ui| {
+                    let paused = job.is_paused();
+                    if ui.button(if paused { "Resume" } else { "Pause" }).clicked() {
+                        job.set_paused(!paused);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        job.cancel();
+                    }
+                });
+            }
+        }
+    }
+
+    fn render_settings_desktop(&mut self, ui: &mut egui::Ui, busy: bool) {
+        let mut create = None;
+        egui::CollapsingHeader::new("DESKTOP SHORTCUTS").show(ui, |ui| {
+            let menu = if cfg!(windows) { "Start menu" } else { "applications menu" };
+            ui.checkbox(&mut self.shortcut_start_menu, format!("Also add to the {menu}"));
+            if ui.add_enabled(!busy, egui::Button::new("Create launcher shortcut")).clicked() {
+                create = Some(("ADNF Launcher".to_string(), Vec::new()));
+            }
+            if self.config.accounts.is_empty() {
+                return;
+            }
+            ui.add_space(4.0);
+            ui.label(
+                egui::RichText::new("Quick launch: signs in and starts the game directly")
+                    .color(Theme::text_muted()),
+            );
+            for account in &self.config.accounts {
+                ui.horizontal(|ui| {
+                    ui.label(account.label());
+                    if ui.add_enabled(!busy, egui::Button::new("Create shortcut")).clicked() {
+                        let args = vec!["--quick-launch".to_string(), account.username.clone()];
+                        create = Some((format!("ADNF - {}", account.label()), args));
+                    }
+                });
+            }
+        });
+        if let Some((name, args)) = create {
+            let mut locations = vec![desktop::Location::Desktop];
+            if self.shortcut_start_menu {
+                locations.push(desktop::Location::StartMenu);
+            }
+            tracing::info!("ui: create shortcut requested");
+            let result = self.send(Command::CreateShortcut {
+                name,
+                args,
+                locations,
+            });
+            self.check_status(result);
+        }
+    }
+
+    fn render_settings_maintenance(&mut self, ui: &mut egui::Ui, busy: bool) {
+        egui::CollapsingHeader::new("MAINTENANCE")
+            .default_open(true)
+            .show(ui, |ui| {
+                let bundle = ui
+                    .add_enabled(!busy, egui::Button::new("Save support bundle"))
+                    .on_hover_text("Recent logs, system info and settings without passwords");
+                if bundle.clicked() {
+                    let result = self.save_support_bundle();
+                    self.check_status(result);
+                }
+                ui.add_space(6.0);
+                ui.label(egui::RichText::new("Game cache").color(Theme::text_muted()));
+                if self.app_config.cache_dirs.is_empty() {
+                    ui.label(
+                        egui::RichText::new("No cache directories configured")
+                            .color(Theme::text_muted()),
+                    );
+                    return;
+                }
+                for dir in &self.app_config.cache_dirs {
+                    ui.label(egui::RichText::new(dir).color(Theme::text_muted()));
+                }
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(!busy, egui::Button::new("Check size")).clicked() {
+                        let result = self.scan_cache();
+                        self.check_status(result);
+                    }
+                    ui.checkbox(&mut self.confirm_clear_cache, "Confirm");
+                    let label = egui::RichText::new("Clear cache").color(Theme::on_accent());
+                    let clear_btn = egui::Button::new(label).fill(Theme::accent_soft());
+                    if ui.add_enabled(!busy, clear_btn).clicked() {
+                        let result = self.clear_cache();
+                        self.check_status(result);
+                    }
+                });
+            });
+    }
+
+    fn render_settings_import(&mut self, ui: &mut egui::Ui, busy: bool) {
+        let mut import = None;
+        egui::CollapsingHeader::new("IMPORT").show(ui, |ui| {
+            ui.label(
+                egui::RichText::new("Server, game path and account names from an older launcher")
+                    .color(Theme::text_muted()),
+            );
+            if ui.add_enabled(!busy, egui::Button::new("Look for old launchers")).clicked() {
+                let result = self.find_legacy_configs();
+                self.check_status(result);
+            }
+            for (idx, legacy) in self.legacy.iter().flatten().enumerate() {
+                ui.separator();
+                ui.label(legacy.source.display().to_string());
+                for (label, value) in [
+                    ("Server", legacy.server_addr.clone()),
+                    ("Game", legacy.game_path.clone()),
+                    (
+                        "Accounts",
+                        (!legacy.usernames.is_empty()).then(|| legacy.usernames.join(", ")),
+                    ),
+                ] {
+                    if let Some(value) = value {
+                        ui.label(
+                            egui::RichText::new(format!("{label}: {value}"))
+                                .color(Theme::text_muted()),
+                        );
+                    }
+                }
+                if ui.button("Import").clicked() {
+                    import = Some(idx);
+                }
+            }
+        });
+        if let Some(idx) = import {
+            self.import_legacy(idx);
+        }
+    }
+
+    fn render_admin(&mut self, ui: &mut egui::Ui) {
+        let busy = self.service.is_busy();
+        ui.add_space(4.0);
+        ui.horizontal(|ui| {
+            ui.heading("ADMIN PANEL");
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui.button("Back").clicked() {
+                    self.go_back();
+                }
+            });
+        });
+        ui.add_space(6.0);
+
+        ui.columns(2, |cols| {
+            form_field(&mut cols[0], "Account UID", &mut self.admin.account_uid);
+            form_field(&mut cols[1], "Character ID", &mut self.admin.char_id);
+        });
+        ui.horizontal(|ui| {
+            if ui.add_enabled(!busy, egui::Button::new("Look up character")).clicked() {
+                let result = self.admin_lookup_character();
+                self.check_status(result);
+            }
+            if let Some(target) = &self.admin.target {
+                ui.label(
+                    egui::RichText::new(format!(
+                        "{} | LVL {} {} | UID {}",
+                        target.name, target.level, target.job, target.uid
+                    ))
+                    .color(Theme::text_muted()),
+                );
+            }
+        });
+        let lookup_note = self.admin.target.as_ref().and_then(|target| {
+            let note = self.admin.note_saved.as_ref()?;
+            (self.admin.note_uid == Some(target.uid)).then_some(note)
+        });
+        if let Some(note) = lookup_note {
+            let first = note.text.lines().next().unwrap_or_default();
+            ui.label(egui::RichText::new(format!("Note: {first}")).small().color(Theme::text()))
+                .on_hover_text(&note.text);
+        }
+        ui.add_space(6.0);
+
+        egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+            self.render_admin_notes(ui, busy);
+            self.render_admin_character(ui, busy);
+            self.render_admin_cash_shop(ui, busy);
+            self.render_admin_expansion(ui, busy);
+            self.render_admin_creature(ui, busy);
+            self.render_admin_guild(ui, busy);
+            self.render_admin_merge(ui, busy);
+            self.render_admin_password_reset(ui, busy);
+            self.render_admin_backup(ui, busy);
+            self.render_admin_mail(ui, busy);
+            self.render_admin_audit(ui, busy);
+        });
+    }
+
+    fn render_admin_audit(&mut self, ui: &mut egui::Ui, busy: bool) {
+        egui::CollapsingHeader::new("AUDIT LOG").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                let label = if self.admin.audit.is_some() { "Refresh" } else { "Load" };
+                if ui.add_enabled(!busy, egui::Button::new(label)).clicked() {
+                    let result = self.admin_load_audit();
+                    self.check_status(result);
+                }
+                let reversible = self
+                    .admin
+                    .audit_selected
+                    .and_then(|id| self.admin.audit.as_ref()?.iter().find(|r| r.id == id))
+                    .and_then(|record| record.action)
+                    .is_some_and(AuditAction::is_reversible);
+                let rollback = ui
+                    .add_enabled(!busy && reversible, egui::Button::new("Roll back selected"))
+                    .on_hover_text("Apply the inverse of the selected grant or send");
+                if rollback.clicked() {
+                    let result = self.admin_rollback();
+                    self.check_status(result);
+                }
+            });
+            let clock = self.clock();
+            let Some(records) = &self.admin.audit else {
+                return;
+            };
+            egui::ScrollArea::vertical()
+                .id_salt("audit_log")
+                .max_height(200.0)
+                .show(ui, |ui| {
+                    for record in records {
+                        let time = record
+                            .created_at
+                            .map(|t| clock.format(t, "%m-%d %H:%M"))
+                            .unwrap_or_default();
+                        let action = record.action.map_or("unknown", AuditAction::as_str);
+                        let mut line = format!(
+                            "#{} {time} {} {action} {}",
+                            record.id, record.actor, record.amount
+                        );
+                        if !record.detail.is_empty() {
+                            line.push_str(&format!(" | {}", record.detail));
+                        }
+                        let selected = self.admin.audit_selected == Some(record.id);
+                        let text = egui::RichText::new(line).small();
+                        if ui.selectable_label(selected, text).clicked() {
+                            self.admin.audit_selected = Some(record.id);
+                        }
+                    }
+                });
+        });
+    }
+
+    fn render_admin_notes(&mut self, ui: &mut egui::Ui, busy: bool) {
+        egui::CollapsingHeader::new("ACCOUNT NOTES").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                if ui.add_enabled(!busy, egui::Button::new("Load")).clicked() {
+                    let result = self.admin_load_note();
+                    self.check_status(result);
+                }
+                let Some(uid) = self.admin.note_uid else {
+                    ui.label(
+                        egui::RichText::new("Uses the Account UID, or the looked-up character")
+                            .color(Theme::text_muted()),
+                    );
+                    return;
+                };
+                let saved = match &self.admin.note_saved {
+                    Some(note) => {
+                        let at = note
+                            .updated_at
+                            .map(|t| self.clock().format(t, "%Y-%m-%d %H:%M"))
+                            .unwrap_or_default();
+                        format!("UID {uid} | updated by {} {at}", note.updated_by)
+                    }
+                    None => format!("UID {uid} | no note yet"),
+                };
+                ui.label(egui::RichText::new(saved).small().color(Theme::text_muted()));
+            });
+            if self.admin.note_uid.is_none() {
+                return;
+            }
+            ui.add(
+                egui::TextEdit::multiline(&mut self.admin.note)
+                    .hint_text("Support history, warnings, refunds...")
+                    .desired_rows(4)
+                    .desired_width(ui.available_width())
+                    .background_color(Theme::surface()),
+            );
+            let unchanged = self.admin.note.trim()
+                == self.admin.note_saved.as_ref().map_or("", |note| note.text.as_str());
+            if ui.add_enabled(!busy && !unchanged, egui::Button::new("Save note")).clicked() {
+                let result = self.admin_save_note();
+                self.check_status(result);
+            }
+        });
+    }
+
+    fn render_admin_character(&mut self, ui: &mut egui::Ui, busy: bool) {
+        egui::CollapsingHeader::new("CHARACTER").show(ui, |ui| {
+            form_field(
+                ui,
+                &format!("Level (max {})", self.app_config.max_level),
+                &mut self.admin.level,
+            );
+            if ui.add_enabled(!busy, egui::Button::new("Set level")).clicked() {
+                let result = self.admin_set_level();
+                self.check_status(result);
+            }
+
+            ui.add_space(6.0);
+            form_field(ui, "Quest ID", &mut self.admin.quest_id);
+            ui.horizontal(|ui| {
+                if ui.add_enabled(!busy, egui::Button::new("Reset quest")).clicked() {
+                    let result = self.admin_reset_quests(false);
+                    self.check_status(result);
+                }
+                if ui.add_enabled(!busy, egui::Button::new("Reset all quests")).clicked() {
+                    let result = self.admin_reset_quests(true);
+                    self.check_status(result);
+                }
+            });
+
+            ui.add_space(6.0);
+            form_field(ui, "Move to account UID", &mut self.admin.transfer_to_uid);
+            if ui.add_enabled(!busy, egui::Button::new("Move character")).clicked() {
+                let result = self.admin_transfer_character();
+                self.check_status(result);
+            }
+
+            ui.add_space(6.0);
+            let Some(target) = &self.admin.target else {
+                ui.label(
+                    egui::RichText::new("Look up a character to edit its job")
+                        .color(Theme::text_muted()),
+                );
+                return;
+            };
+            let advancements = target.job.advancements();
+            let advancement_name = |idx: i32| match idx {
+                0 => "None",
+                n => advancements.get(n as usize - 1).copied().unwrap_or("Unknown"),
+            };
+            egui::ComboBox::from_label("Advancement")
+                .selected_text(advancement_name(self.admin.advancement))
+                .show_ui(ui, |ui| {
+                    for idx in 0..=advancements.len() as i32 {
+                        ui.selectable_value(&mut self.admin.advancement, idx, advancement_name(idx));
+                    }
+                });
+            if self.admin.advancement == 0 {
+                self.admin.awakening = 0;
+            }
+            ui.add_enabled_ui(self.admin.advancement > 0, |ui| {
+                egui::ComboBox::from_label("Awakening")
+                    .selected_text(
+                        AWAKENINGS.get(self.admin.awakening as usize).copied().unwrap_or("Unknown"),
+                    )
+                    .show_ui(ui, |ui| {
+                        for (idx, name) in AWAKENINGS.iter().enumerate() {
+                            ui.selectable_value(&mut self.admin.awakening, idx as i32, *name);
+                        }
+                    });
+            });
+            if ui.add_enabled(!busy, egui::Button::new("Set job growth")).clicked() {
+                let result = self.admin_set_job_growth();
+                self.check_status(result);
+            }
+        });
+    }
+
+    fn render_admin_cash_shop(&mut self, ui: &mut egui::Ui, busy: bool) {
+        egui::CollapsingHeader::new("CASH SHOP GRANT").show(ui, |ui| {
+            form_field(ui, "Item ID", &mut self.admin.item_id);
+            if let Ok(item_id) = self.admin.item_id.trim().parse::<i32>() {
+                let name = match self.items.get(item_id) {
+                    Some(item) => item.name.clone(),
+                    None => "Unknown item".to_string(),
+                };
+                ui.label(egui::RichText::new(name).color(Theme::text_muted()));
+            }
+            form_field(ui, "Quantity", &mut self.admin.item_quantity);
+            if ui.add_enabled(!busy, egui::Button::new("Grant to account")).clicked() {
+                let result = self.admin_grant_cash_item();
+                self.check_status(result);
+            }
+        });
+    }
+
+    fn render_admin_expansion(&mut self, ui: &mut egui::Ui, busy: bool) {
+        egui::CollapsingHeader::new("SLOT EXPANSION").show(ui, |ui| {
+            form_field(ui, "Inventory slots to add", &mut self.admin.inventory_slots);
+            if ui.add_enabled(!busy, egui::Button::new("Expand character inventory")).clicked() {
+                let result = self.admin_expand_inventory();
+                self.check_status(result);
+            }
+            form_field(ui, "Stash tabs to add", &mut self.admin.stash_tabs);
+            if ui.add_enabled(!busy, egui::Button::new("Expand account stash")).clicked() {
+                let result = self.admin_expand_stash();
+                self.check_status(result);
+            }
+            form_field(ui, "Character slot limit", &mut self.admin.slot_limit);
+            if ui.add_enabled(!busy, egui::Button::new("Set slot limit")).clicked() {
+                let result = self.admin_set_slot_limit();
+                self.check_status(result);
+            }
+        });
+    }
+
+    fn render_admin_creature(&mut self, ui: &mut egui::Ui, busy: bool) {
+        egui::CollapsingHeader::new("CREATURE GRANT").show(ui, |ui| {
+            form_field(ui, "Creature ID", &mut self.admin.creature_id);
+            if let Ok(creature_id) = self.admin.creature_id.trim().parse::<i32>() {
+                let name = match self.items.get(creature_id) {
+                    Some(item) => item.name.clone(),
+                    None => "Unknown creature".to_string(),
+                };
+                ui.label(egui::RichText::new(name).color(Theme::text_muted()));
+            }
+            if ui.add_enabled(!busy, egui::Button::new("Grant to character")).clicked() {
+                let result = self.admin_grant_creature();
+                self.check_status(result);
+            }
+        });
+    }
+
+    fn render_admin_backup(&mut self, ui: &mut egui::Ui, busy: bool) {
+        egui::CollapsingHeader::new("ACCOUNT BACKUP").show(ui, |ui| {
+            ui.label(
+                egui::RichText::new("Account, characters, inventory and billing rows")
+                    .color(Theme::text_muted()),
+            );
+            if ui.add_enabled(!busy, egui::Button::new("Back up account UID")).clicked() {
+                let result = self.admin_backup_account();
+                self.check_status(result);
+            }
+            form_field(ui, "Backup file", &mut self.admin.backup_path);
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut self.admin.confirm_restore, "Confirm");
+                let restore_btn =
+                    egui::Button::new(egui::RichText::new("Restore").color(Theme::on_accent()))
+                        .fill(Theme::accent_soft());
+                if ui
+                    .add_enabled(!busy, restore_btn)
+                    .on_hover_text("Overwrites the account's current rows")
+                    .clicked()
+                {
+                    let result = self.admin_restore_account();
+                    self.check_status(result);
+                }
+            });
+        });
+    }
+
+    fn render_admin_merge(&mut self, ui: &mut egui::Ui, busy: bool) {
+        egui::CollapsingHeader::new("ACCOUNT MERGE").show(ui, |ui| {
+            form_field(ui, "Old account UID", &mut self.admin.merge_from_uid);
+            form_field(ui, "New account UID", &mut self.admin.merge_to_uid);
+            ui.label(
+                egui::RichText::new("Moves characters, stash, cash items and cera")
+                    .color(Theme::text_muted()),
+            );
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut self.admin.confirm_merge, "Confirm");
+                let label = egui::RichText::new("Merge").color(Theme::on_accent());
+                let merge_btn = egui::Button::new(label).fill(Theme::accent_soft());
+                if ui.add_enabled(!busy, merge_btn).clicked() {
+                    let result = self.admin_merge_accounts();
+                    self.check_status(result);
+                }
+            });
+        });
+    }
+
+    fn render_admin_password_reset(&mut self, ui: &mut egui::Ui, busy: bool) {
+        egui::CollapsingHeader::new("BULK PASSWORD RESET").show(ui, |ui| {
+            ui.label(
+                egui::RichText::new("Account UIDs, separated by commas or lines")
+                    .color(Theme::text_muted()),
+            );
+            ui.add(
+                egui::TextEdit::multiline(&mut self.admin.reset_uids)
+                    .desired_rows(3)
+                    .desired_width(ui.available_width())
+                    .background_color(Theme::surface()),
+            );
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut self.admin.confirm_reset, "Confirm");
+                let label = egui::RichText::new("Reset passwords").color(Theme::on_accent());
+                let reset_btn = egui::Button::new(label).fill(Theme::accent_soft());
+                if ui
+                    .add_enabled(!busy, reset_btn)
+                    .on_hover_text("Signs the accounts out of their old passwords for good")
+                    .clicked()
+                {
+                    let result = self.admin_reset_passwords();
+                    self.check_status(result);
+                }
+            });
+            let Some(accounts) = &self.admin.reset_passwords else {
+                return;
+            };
+            ui.label(
+                egui::RichText::new("New passwords; they won't be shown again")
+                    .color(Theme::error()),
+            );
+            egui::Grid::new("reset_passwords").striped(true).show(ui, |ui| {
+                for account in accounts {
+                    ui.label(&account.username);
+                    ui.label(egui::RichText::new(&account.password).monospace());
+                    ui.end_row();
+                }
+            });
+            let mut hide = false;
+            ui.horizontal(|ui| {
+                if ui.button("Copy all").clicked() {
+                    let text: String = accounts
+                        .iter()
+                        .map(|a| format!("{}\t{}\n", a.username, a.password))
+                        .collect();
+                    ui.ctx().copy_text(text);
+                }
+                hide = ui.button("Hide").clicked();
+            });
+            if hide {
+                self.admin.reset_passwords = None;
+            }
+        });
+    }
+
+    fn render_admin_mail(&mut self, ui: &mut egui::Ui, busy: bool) {
+        egui::CollapsingHeader::new("MAIL LOOKUP").show(ui, |ui| {
+            if ui.add_enabled(!busy, egui::Button::new("Load mailbox")).clicked() {
+                let result =
+                    parse_id(&self.admin.char_id, "character ID").and_then(|id| self.load_mail(id));
+                self.check_status(result);
+            }
+            let target = self.admin.char_id.trim().parse::<i32>().ok();
+            if let Some(mail) = target.and_then(|id| self.loaded_mail(id)) {
+                render_mail_list(ui, mail, self.config.number_format, self.clock());
+            }
+        });
+    }
+
+    fn render_admin_guild(&mut self, ui: &mut egui::Ui, busy: bool) {
+        egui::CollapsingHeader::new("GUILD MANAGEMENT")
+            .default_open(true)
+            .show(ui, |ui| {
+                form_field(ui, "Guild ID", &mut self.admin.guild_id);
+                form_field(ui, "Guild name", &mut self.admin.guild_name);
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(!busy, egui::Button::new("Create guild")).clicked() {
+                        let result = self.admin_create_guild();
+                        self.check_status(result);
+                    }
+                    if ui.add_enabled(!busy, egui::Button::new("Set master")).clicked() {
+                        let result = self.admin_set_guild_master();
+                        self.check_status(result);
+                    }
+                });
+                form_field(ui, "Guild level", &mut self.admin.guild_level);
+                form_field(ui, "Funds change (+/-)", &mut self.admin.guild_funds);
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(!busy, egui::Button::new("Set level")).clicked() {
+                        let result = self.admin_set_guild_level();
+                        self.check_status(result);
+                    }
+                    if ui.add_enabled(!busy, egui::Button::new("Adjust funds")).clicked() {
+                        let result = self.admin_adjust_guild_funds();
+                        self.check_status(result);
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.admin.confirm_disband, "Confirm");
+                    let disband_btn =
+                        egui::Button::new(egui::RichText::new("Disband").color(Theme::on_accent()))
+                            .fill(Theme::accent_soft());
+                    if ui.add_enabled(!busy, disband_btn).clicked() {
+                        let result = self.admin_disband_guild();
+                        self.check_status(result);
+                    }
+                });
+            });
+    }
+
+    fn render_currency_management(&mut self, ui: &mut egui::Ui, send_gold: bool, send_cera: bool) {
+        let busy = self.service.is_busy();
+        ui.add_space(10.0);
+        ui.horizontal(|ui| {
+            ui.label(egui::RichText::new("CURRENCY MANAGEMENT").color(Theme::text_muted()));
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                let toggle = ui
+                    .toggle_value(&mut self.config.amount_slider, "Slider")
+                    .on_hover_text("Pick the amount with a slider instead of typing it");
+                if toggle.changed() {
+                    let _ = config::write_json("config.json", &self.config);
+                }
+            });
+        });
+        ui.add_space(6.0);
+        if !self.app_config.currencies.is_empty() {
+            self.render_currency_picker(ui, send_gold || send_cera);
+            ui.add_space(6.0);
+        }
+        if self.config.amount_slider {
+            let range = self.app_config.amount_range.clone();
+            let mut value = self.amount.trim().parse::<i32>().unwrap_or(*range.start());
+            value = value.clamp(*range.start(), *range.end());
+            ui.scope(|ui| {
+                ui.spacing_mut().slider_width = ui.available_width() - 110.0;
+                ui.add(egui::Slider::new(&mut value, range).logarithmic(true).text("Amount"));
+            });
+            self.amount = value.to_string();
+        } else {
+            let label = ui.label(egui::RichText::new("Amount").color(Theme::text_muted()));
+            ui.add(
+                egui::TextEdit::singleline(&mut self.amount)
+                    .hint_text("Amount")
+                    .desired_width(ui.available_width())
+                    .background_color(Theme::surface()),
+            )
+            .labelled_by(label.id);
+        }
+        if self.currency.is_some() {
+            self.render_currency_send(ui, busy);
+            return;
+        }
+        let quotas = self.current_session.as_ref().map(|s| s.quotas).unwrap_or_default();
+        if !self.app_config.send_limits.is_unlimited() {
+            let mut parts = Vec::new();
+            for (enabled, name, quota) in
+                [(send_gold, "Gold", quotas.gold), (send_cera, "Cera", quotas.cera)]
+            {
+                let Some(remaining) = quota.remaining().filter(|_| enabled) else {
+                    continue;
+                };
+                parts.push(format!("{name}: {} left today", self.num(remaining)));
+            }
+            let wait = quotas.gold.cooldown_left().max(quotas.cera.cooldown_left());
+            if !wait.is_zero() {
+                parts.push(format!("next send in {}s", wait.as_secs() + 1));
+            }
+            if !parts.is_empty() {
+                ui.add_space(4.0);
+                let text = egui::RichText::new(parts.join(" · ")).small();
+                ui.label(text.color(Theme::text_muted()));
+            }
+        }
+        ui.add_space(10.0);
+        let gold_ready = quotas.gold.cooldown_left().is_zero();
+        let cera_ready = quotas.cera.cooldown_left().is_zero();
+        let button_height = ui.spacing().interact_size.y;
+        let columns = usize::from(send_gold) + usize::from(send_cera);
+        ui.columns(columns, |cols| {
+            let mut cols = cols.iter_mut();
+            if send_gold && let Some(col) = cols.next() {
+                let gold_btn =
+                    egui::Button::new(egui::RichText::new("SEND GOLD").color(Theme::on_accent()))
+                        .fill(Theme::accent());
+                let gold_size = egui::vec2(col.available_width(), button_height);
+                let response =
+                    col.add_enabled_ui(!busy && gold_ready, |ui| ui.add_sized(gold_size, gold_btn));
+                if response.inner.on_hover_text("Send gold to selected character").clicked() {
+                    let result = self.send_gold();
+                    self.check_status(result);
+                }
+            }
+            if send_cera && let Some(col) = cols.next() {
+                let cera_btn =
+                    egui::Button::new(egui::RichText::new("SEND CERA").color(Theme::on_accent()))
+                        .fill(Theme::accent());
+                let cera_size = egui::vec2(col.available_width(), button_height);
+                let response =
+                    col.add_enabled_ui(!busy && cera_ready, |ui| ui.add_sized(cera_size, cera_btn));
+                if response.inner.on_hover_text("Send cera to account").clicked() {
+                    let result = self.send_cera();
+                    self.check_status(result);
+                }
+            }
+        });
+    }
+
+    fn render_currency_picker(&mut self, ui: &mut egui::Ui, built_in: bool) {
+        if !built_in && self.currency.is_none() {
+            self.currency = Some(0);
+        }
+        let mut picked = self.currency;
+        let selected = match picked.and_then(|idx| self.app_config.currencies.get(idx)) {
+            Some(currency) => currency.name.clone(),
+            None => "Gold & cera".to_string(),
+        };
+        egui::ComboBox::from_id_salt("currency")
+            .selected_text(selected)
+            .width(ui.available_width())
+            .show_ui(ui, |ui| {
+                if built_in {
+                    ui.selectable_value(&mut picked, None, "Gold & cera");
+                }
+                for (idx, currency) in self.app_config.currencies.iter().enumerate() {
+                    ui.selectable_value(&mut picked, Some(idx), &currency.name);
+                }
+            });
+        if picked != self.currency {
+            self.currency = picked;
+            self.currency_balance = None;
+            if picked.is_some() {
+                let result = self.load_currency();
+                self.check_status(result);
+            }
+        }
+    }
+
+    /// Balance and send button for the configured currency picked in the panel.
+    fn render_currency_send(&mut self, ui: &mut egui::Ui, busy: bool) {
+        let target = self.currency_target();
+        let balance = match (&target, &self.currency_balance) {
+            (Ok((currency, owner)), Some((name, id, balance)))
+                if *name == currency.name && id == owner =>
+            {
+                Some(*balance)
+            }
+            _ => None,
+        };
+        ui.add_space(4.0);
+        match balance {
+            Some(balance) => {
+                let text = format!("Balance: {}", self.num(balance));
+                ui.label(egui::RichText::new(text).small().color(Theme::text_muted()));
+            }
+            None => {
+                let check = ui.add_enabled(
+                    !busy && target.is_ok(),
+                    egui::Button::new(egui::RichText::new("Check balance").small()),
+                );
+                if check.clicked() {
+                    let result = self.load_currency();
+                    self.check_status(result);
+                }
+            }
+        }
+        ui.add_space(10.0);
+        let sendable = target.as_ref().is_ok_and(|(currency, _)| self.currency_sendable(currency));
+        let (label, hover) = match &target {
+            Ok((currency, _)) if !sendable => (
+                format!("SEND {}", currency.name.to_uppercase()),
+                format!("Sending {} is disabled on this server", currency.name),
+            ),
+            Ok((currency, _)) => (
+                format!("SEND {}", currency.name.to_uppercase()),
+                format!("Send {} to this {}", currency.name, currency.owner_label()),
+            ),
+            Err(status) => ("SEND".to_string(), status.message.clone()),
+        };
+        let button = egui::Button::new(egui::RichText::new(label).color(Theme::text()))
+            .fill(Theme::accent());
+        let size = egui::vec2(ui.available_width(), ui.spacing().interact_size.y);
+        let response = ui.add_enabled_ui(!busy && sendable, |ui| ui.add_sized(size, button));
+        if response.inner.on_hover_text(hover).clicked() {
+            let result = self.send_currency();
+            self.check_status(result);
+        }
+    }
+
+    fn render_sends(&mut self, ui: &mut egui::Ui, char_id: i32) {
+        let busy = self.service.is_busy();
+        let mut load = false;
+        egui::CollapsingHeader::new("RECENT SENDS")
+            .id_salt("recent_sends")
+            .show(ui, |ui| {
+                let sends = match &self.sends {
+                    Some((id, sends)) if *id == char_id => Some(sends),
+                    _ => None,
+                };
+                match sends {
+                    Some(sends) if sends.is_empty() => {
+                        ui.label(
+                            egui::RichText::new("No gold sent to this character yet")
+                                .color(Theme::text_muted()),
+                        );
+                    }
+                    Some(sends) => {
+                        for send in sends {
+                            let at = send
+                                .at
+                                .map(|t| self.clock().format(t, "%Y-%m-%d %H:%M:%S"))
+                                .unwrap_or_default();
+                            let (text, color) = if send.undo {
+                                let amount = self.config.number_format.format(-send.amount);
+                                (format!("{at} | undone {amount}"), Theme::text_muted())
+                            } else {
+                                let amount = self.config.number_format.format(send.amount);
+                                (format!("{at} | +{amount} gold"), Theme::text())
+                            };
+                            ui.label(egui::RichText::new(text).small().color(color));
+                        }
+                    }
+                    None => {}
+                }
+                let label = if sends.is_some() { "Refresh" } else { "Load history" };
+                load = ui.add_enabled(!busy, egui::Button::new(label)).clicked();
+            });
+        if load {
+            let result = self.load_sends(char_id);
+            self.check_status(result);
+        }
+    }
+
+    fn render_cera_transfer(&mut self, ctx: &egui::Context) {
+        let busy = self.service.is_busy();
+        let cera = self.current_session.as_ref().and_then(|s| s.cera).unwrap_or(0);
+        let Some(form) = &mut self.transfer else {
+            return;
+        };
+        let mut send = false;
+        let mut cancel = false;
+        let modal = egui::Modal::new(egui::Id::new("cera_transfer")).show(ctx, |ui| {
+            ui.set_width(280.0);
+            ui.heading("Transfer cera");
+            let balance = format!("Balance: {}", self.config.number_format.format(cera));
+            ui.label(egui::RichText::new(balance).color(Theme::text_muted()));
+            form_field(ui, "Recipient username", &mut form.recipient);
+            form_field(ui, "Amount", &mut form.amount);
+            ui.horizontal(|ui| {
+                send = ui.add_enabled(!busy, egui::Button::new("Send")).clicked();
+                cancel = ui.button("Cancel").clicked();
+            });
+        });
+        if cancel || modal.should_close() {
+            self.transfer = None;
+        } else if send {
+            let result = self.transfer_cera();
+            self.check_status(result);
+        }
+    }
+
+    fn render_password_reset(&mut self, ctx: &egui::Context) {
+        let busy = self.service.is_busy();
+        let Some(form) = &mut self.password_reset else {
+            return;
+        };
+        let mut send_code = false;
+        let mut reset = false;
+        let mut cancel = false;
+        let modal = egui::Modal::new(egui::Id::new("password_reset")).show(ctx, |ui| {
+            ui.set_width(280.0);
+            ui.heading("Reset password");
+            form_field(ui, "Username", &mut self.creds.username);
+            send_code = ui
+                .add_enabled(!busy, egui::Button::new("Email me a code"))
+                .on_hover_text("Sent to the email bound to this account")
+                .clicked();
+            form_field(ui, "Code", &mut form.code);
+            password_field(ui, "New password", &mut form.password);
+            ui.horizontal(|ui| {
+                reset = ui.add_enabled(!busy, egui::Button::new("Reset")).clicked();
+                cancel = ui.button("Cancel").clicked();
+            });
+        });
+        if cancel || modal.should_close() {
+            self.password_reset = None;
+        } else if send_code {
+            let result = self.send_reset_code();
+            self.check_status(result);
+        } else if reset {
+            let result = self.reset_password();
+            self.check_status(result);
+        }
+    }
+
+    fn render_recovery_login(&mut self, ctx: &egui::Context) {
+        let busy = self.service.is_busy();
+        let Some(form) = &mut self.recovery_login else {
+            return;
+        };
+        let mut submit = false;
+        let mut cancel = false;
+        let modal = egui::Modal::new(egui::Id::new("recovery_login")).show(ctx, |ui| {
+            ui.set_width(280.0);
+            ui.heading("Sign in with a recovery code");
+            ui.label(
+                egui::RichText::new("Each code works once and replaces your password")
+                    .color(Theme::text_muted()),
+            );
+            form_field(ui, "Username", &mut self.creds.username);
+            form_field(ui, "Recovery code", &mut form.code);
+            password_field(ui, "New password", &mut form.password);
+            ui.horizontal(|ui| {
+                submit = ui.add_enabled(!busy, egui::Button::new("Sign in")).clicked();
+                cancel = ui.button("Cancel").clicked();
+            });
+        });
+        if cancel || modal.should_close() {
+            self.recovery_login = None;
+        } else if submit {
+            let result = self.recover_login();
+            self.check_status(result);
+        }
+    }
+
+    fn render_client_mismatch(&mut self, ctx: &egui::Context) {
+        let Some(mismatch) = &self.client_mismatch else {
+            return;
+        };
+        let mut update = false;
+        let mut launch_anyway = false;
+        let mut cancel = false;
+        let modal = egui::Modal::new(egui::Id::new("client_mismatch")).show(ctx, |ui| {
+            ui.set_width(280.0);
+            ui.heading("Client outdated — run the patcher");
+            let detail = format!(
+                "Installed version {}, the server expects {}",
+                mismatch.local, mismatch.expected
+            );
+            ui.label(egui::RichText::new(detail).color(Theme::text_muted()));
+            ui.horizontal(|ui| {
+                update = ui.button("Update").clicked();
+                launch_anyway = ui.button("Launch anyway").clicked();
+                cancel = ui.button("Cancel").clicked();
+            });
+        });
+        if cancel || modal.should_close() {
+            self.client_mismatch = None;
+            self.status = Status::info("Launch cancelled");
+        } else if update {
+            self.client_mismatch = None;
+            self.navigate(Screen::Settings);
+            let result = self.check_updates();
+            self.check_status(result);
+        } else if launch_anyway {
+            if let Some(mismatch) = self.client_mismatch.take() {
+                tracing::info!("ui: launching outdated client {}", mismatch.local);
+            }
+            self.start_client();
+        }
+    }
+
+    fn render_problem_report(&mut self, ctx: &egui::Context) {
+        let busy = self.service.is_busy();
+        let Some(description) = &mut self.problem_report else {
+            return;
+        };
+        let mut send = false;
+        let mut cancel = false;
+        let modal = egui::Modal::new(egui::Id::new("problem_report")).show(ctx, |ui| {
+            ui.set_width(300.0);
+            ui.heading("Report a problem");
+            ui.add(
+                egui::TextEdit::multiline(description)
+                    .hint_text("What happened, and what were you doing?")
+                    .desired_rows(5)
+                    .desired_width(ui.available_width())
+                    .background_color(Theme::surface()),
+            );
+            ui.label(
+                egui::RichText::new(
+                    "Recent logs, system info and your settings (without passwords) are attached.",
+                )
+                .small()
+                .color(Theme::text_muted()),
+            );
+            ui.horizontal(|ui| {
+                let ready = !busy && !description.trim().is_empty();
+                send = ui.add_enabled(ready, egui::Button::new("Send")).clicked();
+                cancel = ui.button("Cancel").clicked();
+            });
+        });
+        if cancel || (modal.should_close() && !busy) {
+            self.problem_report = None;
+        } else if send {
+            let result = self.report_problem();
+            if self.check_status(result).is_some() {
+                self.status = Status::info("Sending report...");
+            }
+        }
+    }
+
+    fn render_folder_checks(&mut self, ctx: &egui::Context) {
+        let Some(checks) = &self.folder_checks else {
+            return;
+        };
+        let mut save = false;
+        let mut close = false;
+        let modal = egui::Modal::new(egui::Id::new("folder_checks")).show(ctx, |ui| {
+            ui.set_width(300.0);
+            ui.heading("Game folder incomplete");
+            for check in checks {
+                let (mark, color) =
+                    if check.ok { ("✔", Theme::success()) } else { ("✖", Theme::error()) };
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new(mark).color(color));
+                    ui.label(&check.item);
+                });
+            }
+            ui.add_space(4.0);
+            ui.label(
+                egui::RichText::new(
+                    "Point the launcher at the client executable inside the extracted game \
+                     folder. If the executable is found but files are missing, re-extract or \
+                     patch the client.",
+                )
+                .small()
+                .color(Theme::text_muted()),
+            );
+            form_field(ui, "Client executable", &mut self.game_path_input);
+            ui.horizontal(|ui| {
+                save = ui.button("Save and check").clicked();
+                close = ui.button("Close").clicked();
+            });
+        });
+        if close || modal.should_close() {
+            self.folder_checks = None;
+        } else if save {
+            self.set_game_path();
+        }
+    }
+
+    fn render_crash_report(&mut self, ctx: &egui::Context) {
+        let busy = self.service.is_busy();
+        let Some(report) = &self.crash_report else {
+            return;
+        };
+        let can_upload = self.app_config.crash_report_url.is_some();
+        let mut save = false;
+        let mut upload = false;
+        let mut dismiss = false;
+        let modal = egui::Modal::new(egui::Id::new("crash_report")).show(ctx, |ui| {
+            ui.set_width(300.0);
+            ui.heading("The game closed unexpectedly");
+            let detail = format!(
+                "{}. Collected {} log file(s) and {} system event(s) for the server team.",
+                report.exit,
+                report.logs.len(),
+                report.events.len()
+            );
+            ui.label(egui::RichText::new(detail).color(Theme::text_muted()));
+            ui.horizontal(|ui| {
+                save = ui.add_enabled(!busy, egui::Button::new("Save")).clicked();
+                if can_upload {
+                    upload = ui.add_enabled(!busy, egui::Button::new("Send report")).clicked();
+                }
+                dismiss = ui.button("Dismiss").clicked();
+            });
+        });
+        if dismiss || modal.should_close() {
+            self.crash_report = None;
+        } else if save {
+            let result = self.save_crash_report();
+            self.check_status(result);
+        } else if upload {
+            let result = self.upload_crash_report();
+            self.check_status(result);
+        }
+    }
+
+    fn render_secondary_pin(&mut self, ui: &mut egui::Ui) {
+        let busy = self.service.is_busy();
+        let has_pin = self.current_session.as_ref().is_some_and(|s| s.has_pin);
+        let title = if has_pin { "STORAGE PIN | set" } else { "STORAGE PIN | not set" };
+        let mut change = None;
+        egui::CollapsingHeader::new(title).id_salt("storage_pin").show(ui, |ui| {
+            ui.label(
+                egui::RichText::new("Asked for in game before opening your storage")
+                    .color(Theme::text_muted()),
+            );
+            for (hint, value) in [
+                ("New PIN", &mut self.pin_form.pin),
+                ("Repeat PIN", &mut self.pin_form.confirm),
+            ] {
+                ui.horizontal(|ui| {
+                    let label = ui.label(egui::RichText::new(hint).color(Theme::text_muted()));
+                    ui.add(
+                        egui::TextEdit::singleline(value)
+                            .password(true)
+                            .hint_text(hint)
+                            .char_limit(*PIN_LENGTH.end())
+                            .desired_width(120.0)
+                            .background_color(Theme::surface()),
+                    )
+                    .labelled_by(label.id);
+                });
+            }
+            ui.horizontal(|ui| {
+                if ui.add_enabled(!busy, egui::Button::new("Set PIN")).clicked() {
+                    change = Some(false);
+                }
+                if ui.add_enabled(!busy && has_pin, egui::Button::new("Clear PIN")).clicked() {
+                    change = Some(true);
+                }
+            });
+        });
+        if let Some(clear) = change {
+            let result = self.set_secondary_pin(clear);
+            self.check_status(result);
+        }
+    }
+
+    fn render_recovery_codes(&mut self, ui: &mut egui::Ui) {
+        let busy = self.service.is_busy();
+        let mut generate = false;
+        egui::CollapsingHeader::new("RECOVERY CODES")
+            .id_salt("recovery_codes")
+            .show(ui, |ui| {
+                ui.label(
+                    egui::RichText::new(
+                        "Single-use codes to get back in if you lose your password or email",
+                    )
+                    .color(Theme::text_muted()),
+                );
+                if let Some(codes) = &self.recovery_codes {
+                    ui.label(
+                        egui::RichText::new("Write these down now; they are not shown again")
+                            .color(Theme::error()),
+                    );
+                    egui::Grid::new("recovery_code_grid").show(ui, |ui| {
+                        for (idx, code) in codes.iter().enumerate() {
+                            ui.label(egui::RichText::new(code).monospace());
+                            if idx % 2 == 1 {
+                                ui.end_row();
+                            }
+                        }
+                    });
+                    if ui.button("Copy all").clicked() {
+                        ui.ctx().copy_text(codes.join("\n"));
+                    }
+                }
+                generate = ui
+                    .add_enabled(!busy, egui::Button::new("Generate new codes"))
+                    .on_hover_text("Replaces any codes generated before")
+                    .clicked();
+            });
+        if generate {
+            let result = self.generate_recovery_codes();
+            self.check_status(result);
+        }
+    }
+
+    fn render_email(&mut self, ui: &mut egui::Ui) {
+        let busy = self.service.is_busy();
+        let bound = self.current_session.as_ref().and_then(|s| s.email.clone());
+        let title = match &bound {
+            Some(address) => format!("EMAIL | {}", email::mask(address)),
+            None => "EMAIL | not bound".to_string(),
+        };
+        egui::CollapsingHeader::new(title).id_salt("email").show(ui, |ui| {
+            let hint = match &bound {
+                Some(address) => format!("Bound to {address}; used for password resets"),
+                None => "Bind an email to reset your password and get security alerts".to_string(),
+            };
+            ui.label(egui::RichText::new(hint).color(Theme::text_muted()));
+            ui.horizontal(|ui| {
+                let label = ui.label(egui::RichText::new("Email").color(Theme::text_muted()));
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.email_form.address)
+                        .hint_text("Email address")
+                        .desired_width(180.0)
+                        .background_color(Theme::surface()),
+                )
+                .labelled_by(label.id);
+                if ui.add_enabled(!busy, egui::Button::new("Send code")).clicked() {
+                    let result = self.send_email_code();
+                    self.check_status(result);
+                }
+            });
+            ui.horizontal(|ui| {
+                let label = ui.label(egui::RichText::new("Code").color(Theme::text_muted()));
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.email_form.code)
+                        .hint_text("Verification code")
+                        .desired_width(180.0)
+                        .background_color(Theme::surface()),
+                )
+                .labelled_by(label.id);
+                if ui.add_enabled(!busy, egui::Button::new("Verify")).clicked() {
+                    let result = self.verify_email();
+                    self.check_status(result);
+                }
+            });
+        });
     }
 
-    fn check_status<T>(&mut self, result: Result<T, Status>) -> Option<T> {
-        match result {
-            Ok(val) => Some(val),
-            Err(status) => {
-                self.status = status;
-                None
+    fn render_discord(&mut self, ui: &mut egui::Ui) {
+        let busy = self.service.is_busy();
+        let linked = self.current_session.as_ref().and_then(|s| s.discord_id.clone());
+        let title = if linked.is_some() { "DISCORD | linked" } else { "DISCORD" };
+        egui::CollapsingHeader::new(title).id_salt("discord").show(ui, |ui| {
+            match &linked {
+                Some(discord_id) => {
+                    ui.horizontal(|ui| {
+                        ui.label(
+                            egui::RichText::new(format!("Linked to Discord user {discord_id}"))
+                                .color(Theme::text_muted()),
+                        );
+                        if ui.add_enabled(!busy, egui::Button::new("Unlink")).clicked() {
+                            let result = self.unlink_discord();
+                            self.check_status(result);
+                        }
+                    });
+                }
+                None => {
+                    ui.label(
+                        egui::RichText::new("Ask the server's Discord bot for a link code")
+                            .color(Theme::text_muted()),
+                    );
+                    ui.horizontal(|ui| {
+                        let label =
+                            ui.label(egui::RichText::new("Link code").color(Theme::text_muted()));
+                        ui.add(
+                            egui::TextEdit::singleline(&mut self.discord_code)
+                                .hint_text("From the Discord bot")
+                                .desired_width(140.0)
+                                .background_color(Theme::surface()),
+                        )
+                        .labelled_by(label.id);
+                        if ui.add_enabled(!busy, egui::Button::new("Link")).clicked() {
+                            let result = self.link_discord();
+                            self.check_status(result);
+                        }
+                    });
+                }
             }
+        });
+    }
+
+    fn render_daily_rewards(&mut self, ui: &mut egui::Ui) {
+        let busy = self.service.is_busy();
+        let daily = self.current_session.as_ref().map(|s| s.daily).unwrap_or_default();
+        let rewards = &self.app_config.daily_rewards;
+        let cycle = rewards.len() as i32;
+        // Days of the current cycle already claimed.
+        let claimed = match daily.streak {
+            0 => 0,
+            streak if daily.claimed_today => (streak - 1) % cycle + 1,
+            streak => streak % cycle,
+        };
+        let mut claim = false;
+        egui::CollapsingHeader::new(format!("DAILY REWARD | streak {}", daily.streak))
+            .default_open(!daily.claimed_today)
+            .show(ui, |ui| {
+                egui::Grid::new("daily_rewards")
+                    .spacing(egui::vec2(4.0, 4.0))
+                    .show(ui, |ui| {
+                        for (idx, reward) in rewards.iter().enumerate() {
+                            let day = idx as i32 + 1;
+                            let today = !daily.claimed_today && day == claimed + 1;
+                            let (fill, stroke) = if day <= claimed {
+                                (Theme::accent_soft(), egui::Stroke::NONE)
+                            } else if today {
+                                (Theme::surface_alt(), egui::Stroke::new(1.0, Theme::accent()))
+                            } else {
+                                (Theme::surface(), egui::Stroke::NONE)
+                            };
+                            egui::Frame::new()
+                                .fill(fill)
+                                .stroke(stroke)
+                                .corner_radius(egui::CornerRadius::same(4))
+                                .inner_margin(egui::Margin::same(4))
+                                .show(ui, |ui| {
+                                    ui.set_width(40.0);
+                                    let numbers = self.config.number_format;
+                                    ui.vertical_centered(|ui| {
+                                        ui.label(egui::RichText::new(format!("Day {day}")).small());
+                                        ui.label(
+                                            egui::RichText::new(reward_label(reward, numbers))
+                                                .small()
+                                                .color(Theme::text_muted()),
+                                        )
+                                        .on_hover_text(reward_hover(reward, &self.items, numbers));
+                                    });
+                                });
+                            if day % 7 == 0 {
+                                ui.end_row();
+                            }
+                        }
+                    });
+                let label = if daily.claimed_today { "Come back tomorrow" } else { "Claim" };
+                claim = ui
+                    .add_enabled(!busy && !daily.claimed_today, egui::Button::new(label))
+                    .clicked();
+            });
+        if claim {
+            let result = self.claim_daily_reward();
+            self.check_status(result);
         }
     }
 
-    fn launch_game(&mut self) {
-        if let Some(session) = &self.current_session {
-            match std::process::Command::new(&self.app_config.dnf_exe_path)
-                .arg(&session.token)
-                .spawn()
-            {
-                Ok(_) => {
-                    info!("launching game");
-                    self.status = Status::success("Launching Game...");
+    fn render_characters(&mut self, ui: &mut egui::Ui) {
+        let mut clicked = None;
+        let (used_slots, slot_limit) = self
+            .current_session
+            .as_ref()
+            .map(|s| (s.characters.len(), s.slot_limit))
+            .unwrap_or_default();
+        egui::Frame::new()
+            .fill(Theme::surface())
+            .corner_radius(egui::CornerRadius::same(8))
+            .inner_margin(egui::Margin::symmetric(10, 8))
+            .show(ui, |ui| {
+                if used_slots == 0 {
+                    ui.vertical_centered(|ui| {
+                        ui.add_space(12.0);
+                        let text = format!(
+                            "0 of {slot_limit} character slots used — create one in game"
+                        );
+                        ui.label(egui::RichText::new(text).color(Theme::text_muted()));
+                        ui.add_space(12.0);
+                    });
+                    return;
                 }
-                Err(err) => {
-                    error!("failed to launch game: {err}");
-                    self.status = Status::error(format!("Launch failed: {err}"));
+                let mut area = egui::ScrollArea::vertical().max_height(170.0);
+                if std::mem::take(&mut self.restore_scroll) {
+                    area = area.vertical_scroll_offset(self.config.character_scroll);
                 }
-            }
+                let output = area.show(ui, |ui| {
+                    if let Some(session) = &self.current_session {
+                        for (idx, character) in session.characters.iter().enumerate() {
+                            let label = format!(
+                                "LVL {} | {} | {} | Gold: {}",
+                                character.level,
+                                character.job,
+                                character.name,
+                                character
+                                    .money
+                                    .map_or_else(|| "unavailable".to_string(), |g| self.num(g))
+                            );
+                            let selected = self.selected_char == Some(idx);
+                            if ui.selectable_label(selected, label).clicked() {
+                                clicked = Some(idx);
+                            }
+                        }
+                    }
+                });
+                // Saved to config.json on exit.
+                self.config.character_scroll = output.state.offset.y;
+                ui.add_space(4.0);
+                let text = format!("{used_slots} of {slot_limit} character slots used");
+                ui.label(egui::RichText::new(text).small().color(Theme::text_muted()));
+            });
+        if let Some(idx) = clicked {
+            self.select_char(idx);
+        }
+
+        if let Some(character) = self.selected_character().cloned() {
+            ui.add_space(8.0);
+            self.render_character_detail(ui, &character);
         }
     }
 
-    fn render_login(&mut self, ui: &mut egui::Ui) {
-        let busy = self.action_bind.is_pending();
-        ui.add_space(6.0);
-        ui.heading("Welcome Back");
-        ui.add_space(10.0);
+    fn render_character_detail(&mut self, ui: &mut egui::Ui, character: &Character) {
+        let busy = self.service.is_busy();
+        egui::Frame::new()
+            .fill(Theme::surface())
+            .corner_radius(egui::CornerRadius::same(8))
+            .inner_margin(egui::Margin::symmetric(10, 8))
+            .show(ui, |ui| {
+                ui.set_width(ui.available_width());
+                ui.label(egui::RichText::new(&character.name).strong());
+                ui.label(
+                    egui::RichText::new(format!("LVL {} {}", character.level, character.job))
+                        .color(Theme::text_muted()),
+                );
+                let guild = match &character.guild {
+                    Some(guild) => format!("Guild: {} ({})", guild.guild_name, guild.rank),
+                    None => "Guild: none".to_string(),
+                };
+                ui.label(egui::RichText::new(guild).color(Theme::text_muted()));
+                if let Some(pvp) = character.pvp {
+                    let total = pvp.wins + pvp.losses;
+                    let rate = if total > 0 { pvp.wins * 100 / total } else { 0 };
+                    ui.label(
+                        egui::RichText::new(format!(
+                            "PvP: Grade {} | {} pts | {}W {}L ({rate}%)",
+                            pvp.grade, pvp.points, pvp.wins, pvp.losses
+                        ))
+                        .color(Theme::text_muted()),
+                    );
+                }
+                let unstick = ui
+                    .add_enabled(!busy, egui::Button::new("Move to Seria's room"))
+                    .on_hover_text(
+                        "Fixes a character that crashes while loading. Log out of the game first.",
+                    );
+                if unstick.clicked() {
+                    let result = self.unstick_character(character.id);
+                    self.check_status(result);
+                }
 
-        ui.label(egui::RichText::new("Username").color(Theme::TEXT_MUTED));
-        ui.add(
-            egui::TextEdit::singleline(&mut self.creds.username)
-                .hint_text("Account name")
-                .desired_width(ui.available_width())
-                .background_color(Theme::SURFACE),
-        );
-        ui.add_space(10.0);
-        ui.label(egui::RichText::new("Password").color(Theme::TEXT_MUTED));
-        ui.add(
-            egui::TextEdit::singleline(&mut self.creds.password)
-                .password(true)
-                .hint_text("Password")
-                .desired_width(ui.available_width())
-                .background_color(Theme::SURFACE),
-        );
-        ui.add_space(8.0);
-        ui.checkbox(&mut self.remember, "Remember me");
-        ui.add_space(12.0);
+                ui.add_space(4.0);
+                match self.loaded_avatars(character.id) {
+                    Some(avatars) => {
+                        render_equipment(ui, &self.equipment, &self.items);
+                        ui.add_space(4.0);
+                        render_avatars(ui, avatars, &self.items, self.clock());
+                    }
+                    None => {
+                        if ui.add_enabled(!busy, egui::Button::new("Load equipment")).clicked() {
+                            let result = self.load_avatars(character.id);
+                            self.check_status(result);
+                        }
+                    }
+                }
 
-        let login_btn = egui::Button::new(egui::RichText::new("SIGN IN").color(Theme::TEXT))
-            .fill(Theme::ACCENT)
-            .stroke(egui::Stroke::new(1.0, Theme::ACCENT));
-        if ui.add_enabled(!busy, login_btn).clicked() {
-            let result = self.login();
-            self.check_status(result);
-        }
+                ui.add_space(4.0);
+                egui::CollapsingHeader::new("DUNGEON STATS")
+                    .id_salt(("dungeon_stats", character.id))
+                    .show(ui, |ui| {
+                        let clears = match &self.dungeon_clears {
+                            Some((id, clears)) if *id == character.id => Some(clears),
+                            _ => None,
+                        };
+                        match clears {
+                            Some(clears) => render_dungeon_clears(ui, clears),
+                            None => {
+                                let load = egui::Button::new("Load stats");
+                                if ui.add_enabled(!busy, load).clicked() {
+                                    let result = self.load_dungeon_clears(character.id);
+                                    self.check_status(result);
+                                }
+                            }
+                        }
+                    });
+            });
+    }
 
-        ui.add_space(8.0);
-        let reg_btn = egui::Button::new(egui::RichText::new("CREATE ACCOUNT").color(Theme::TEXT))
-            .fill(Theme::ACCENT_SOFT)
-            .stroke(egui::Stroke::new(1.0, Theme::ACCENT));
-        if ui.add_enabled(!busy, reg_btn).clicked() {
-            let result = self.create_account();
-            self.check_status(result);
-        }
+    fn render_mail(&mut self, ui: &mut egui::Ui, char_id: i32) {
+        let busy = self.service.is_busy();
+        egui::Frame::new()
+            .fill(Theme::surface())
+            .corner_radius(egui::CornerRadius::same(8))
+            .inner_margin(egui::Margin::symmetric(10, 8))
+            .show(ui, |ui| {
+                ui.set_width(ui.available_width());
+                match self.loaded_mail(char_id) {
+                    Some(mail) => {
+                        render_mail_list(ui, mail, self.config.number_format, self.clock())
+                    }
+                    None => {
+                        ui.label(egui::RichText::new("Mail not loaded").color(Theme::text_muted()));
+                        if ui.add_enabled(!busy, egui::Button::new("Load mail")).clicked() {
+                            let result = self.load_mail(char_id);
+                            self.check_status(result);
+                        }
+                    }
+                }
+            });
     }
 
-    fn render_dashboard(&mut self, ui: &mut egui::Ui) {
-        let busy = self.action_bind.is_pending();
-        ui.add_space(4.0);
-        ui.horizontal(|ui| {
-            ui.heading("ACCOUNT DASHBOARD");
-            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                let refresh_btn =
-                    egui::Button::new(egui::RichText::new("Refresh").color(Theme::TEXT))
-                        .fill(Theme::SURFACE_ALT);
-                if ui.add_enabled(!busy, refresh_btn).clicked() {
-                    let result = self.refresh();
-                    self.check_status(result);
+    fn render_news(&mut self, ui: &mut egui::Ui) {
+        let busy = self.service.is_busy();
+        egui::Frame::new()
+            .fill(Theme::surface())
+            .corner_radius(egui::CornerRadius::same(8))
+            .inner_margin(egui::Margin::symmetric(10, 8))
+            .show(ui, |ui| {
+                ui.set_width(ui.available_width());
+                let Some(news) = &self.news else {
+                    ui.label(egui::RichText::new("News not loaded").color(Theme::text_muted()));
+                    if ui.add_enabled(!busy, egui::Button::new("Load news")).clicked() {
+                        let result = self.load_news();
+                        self.check_status(result);
+                    }
+                    return;
+                };
+                if news.is_empty() {
+                    ui.label(egui::RichText::new("No announcements").color(Theme::text_muted()));
+                    return;
                 }
+                egui::ScrollArea::vertical().max_height(240.0).show(ui, |ui| {
+                    for (i, item) in news.iter().enumerate() {
+                        if i > 0 {
+                            ui.separator();
+                        }
+                        ui.horizontal(|ui| {
+                            let title = egui::RichText::new(&item.title).strong();
+                            ui.label(title.color(Theme::text()));
+                            if let Some(date) = &item.date {
+                                ui.label(egui::RichText::new(date).color(Theme::text_muted()));
+                            }
+                        });
+                        markdown::render(ui, &item.body);
+                    }
+                });
             });
-        });
-        ui.add_space(6.0);
+    }
 
-        let cera = self.current_session.as_ref().map(|s| s.cera).unwrap_or(0);
-        ui.label(egui::RichText::new(format!("Cera: {cera}")).color(Theme::TEXT_MUTED));
-        ui.add_space(6.0);
+    fn render_balance_history(&mut self, ui: &mut egui::Ui) {
+        let busy = self.service.is_busy();
+        let format = self.config.number_format;
+        let clock = self.clock();
+        egui::Frame::new()
+            .fill(Theme::surface())
+            .corner_radius(egui::CornerRadius::same(8))
+            .inner_margin(egui::Margin::symmetric(10, 8))
+            .show(ui, |ui| {
+                ui.set_width(ui.available_width());
+                let Some(history) = &self.balance_history else {
+                    ui.label(egui::RichText::new("History not loaded").color(Theme::text_muted()));
+                    if ui.add_enabled(!busy, egui::Button::new("Load history")).clicked() {
+                        let result = self.load_balance_history();
+                        self.check_status(result);
+                    }
+                    return;
+                };
+                if history.len() < 2 {
+                    ui.label(
+                        egui::RichText::new(
+                            "Not enough history yet; balances are recorded each time you sign \
+                             in or refresh",
+                        )
+                        .color(Theme::text_muted()),
+                    );
+                    return;
+                }
+                // Seconds since the epoch, read back as the same naive server time.
+                let points = |value: fn(&BalanceSnapshot) -> i64| -> Vec<[f64; 2]> {
+                    history
+                        .iter()
+                        .map(|s| {
+                            let at = clock.display(s.taken_at).and_utc().timestamp();
+                            [at as f64, value(s) as f64]
+                        })
+                        .collect()
+                };
+                let date = |mark: egui_plot::GridMark, _: &std::ops::RangeInclusive<f64>| {
+                    chrono::DateTime::from_timestamp(mark.value as i64, 0)
+                        .map(|t| t.format("%m-%d").to_string())
+                        .unwrap_or_default()
+                };
+                let amount = move |mark: egui_plot::GridMark, _: &std::ops::RangeInclusive<f64>| {
+                    format.format(mark.value as i64)
+                };
+                let series = [
+                    ("Cera", points(|s| s.cera), Theme::accent()),
+                    ("Gold", points(|s| s.gold), Theme::success()),
+                ];
+                for (name, points, color) in series {
+                    ui.label(egui::RichText::new(name).color(Theme::text_muted()));
+                    egui_plot::Plot::new(("balance_history", name))
+                        .height(110.0)
+                        .link_axis("balance_history", [true, false])
+                        .allow_scroll(false)
+                        .x_axis_formatter(date)
+                        .y_axis_formatter(amount)
+                        .label_formatter(move |_, point| {
+                            let time = chrono::DateTime::from_timestamp(point.x as i64, 0)
+                                .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+                                .unwrap_or_default();
+                            format!("{time}\n{}", format.format(point.y as i64))
+                        })
+                        .show(ui, |plot| {
+                            plot.line(egui_plot::Line::new(name, points).color(color).width(2.0));
+                        });
+                }
+            });
+    }
 
+    fn render_guild(&mut self, ui: &mut egui::Ui, guild_id: i32) {
+        let busy = self.service.is_busy();
         egui::Frame::new()
-            .fill(Theme::SURFACE)
+            .fill(Theme::surface())
             .corner_radius(egui::CornerRadius::same(8))
             .inner_margin(egui::Margin::symmetric(10, 8))
             .show(ui, |ui| {
+                let members = match &self.guild_members {
+                    Some((id, members)) if *id == guild_id => Some(members),
+                    _ => None,
+                };
+                let Some(members) = members else {
+                    ui.label(egui::RichText::new("Members not loaded").color(Theme::text_muted()));
+                    if ui.add_enabled(!busy, egui::Button::new("Load members")).clicked() {
+                        let result = self.load_guild_members(guild_id);
+                        self.check_status(result);
+                    }
+                    return;
+                };
+                ui.label(
+                    egui::RichText::new(format!("{} members", members.len()))
+                        .color(Theme::text_muted()),
+                );
                 egui::ScrollArea::vertical()
                     .max_height(170.0)
                     .show(ui, |ui| {
-                        if let Some(session) = &self.current_session {
-                            for (idx, character) in session.characters.iter().enumerate() {
-                                let label = format!(
-                                    "LVL {} | {} | {} | Gold: {}",
-                                    character.level, character.job, character.name, character.money
-                                );
-                                let selected = self.selected_char == Some(idx);
-                                if ui.selectable_label(selected, label).clicked() {
-                                    self.selected_char = Some(idx);
-                                }
-                            }
+                        for member in members {
+                            ui.label(format!(
+                                "LVL {} | {} | {} | {}",
+                                member.level, member.job, member.name, member.rank
+                            ));
                         }
                     });
             });
+    }
 
-        ui.add_space(10.0);
-        ui.label(egui::RichText::new("CURRENCY MANAGEMENT").color(Theme::TEXT_MUTED));
-        ui.add_space(6.0);
-        ui.add(
-            egui::TextEdit::singleline(&mut self.amount)
-                .hint_text("Amount")
-                .desired_width(ui.available_width())
-                .background_color(Theme::SURFACE),
-        );
-        ui.add_space(10.0);
-        let button_height = ui.spacing().interact_size.y;
-        ui.columns(2, |cols| {
-            let gold_btn = egui::Button::new(egui::RichText::new("SEND GOLD").color(Theme::TEXT))
-                .fill(Theme::ACCENT);
-            let gold_size = egui::vec2(cols[0].available_width(), button_height);
-            let response = cols[0].add_enabled_ui(!busy, |ui| {
-                ui.add_sized(gold_size, gold_btn)
-            });
-            if response.inner.on_hover_text("Send gold to selected character").clicked() {
-                let result = self.send_gold();
-                self.check_status(result);
-            }
-
-            let cera_btn = egui::Button::new(egui::RichText::new("SEND CERA").color(Theme::TEXT))
-                .fill(Theme::ACCENT);
-            let cera_size = egui::vec2(cols[1].available_width(), button_height);
-            let response = cols[1].add_enabled_ui(!busy, |ui| {
-                ui.add_sized(cera_size, cera_btn)
-            });
-            if response.inner.on_hover_text("Send cera to account").clicked() {
-                let result = self.send_cera();
-                self.check_status(result);
+    /// What the last frame cost and why egui drew it.
+    fn render_debug_overlay(&mut self, ctx: &egui::Context, frame: &eframe::Frame) {
+        let interval = ctx.input(|i| i.stable_dt);
+        let cpu = frame.info().cpu_usage.unwrap_or_default();
+        let galleys = ctx.fonts(|fonts| fonts.num_galleys_in_cache());
+        let new_galleys = galleys.saturating_sub(self.cached_galleys);
+        self.cached_galleys = galleys;
+        let textures = ctx.tex_manager().read().num_allocated();
+        let mut background = Vec::new();
+        if let (Some(busy), Some(name)) = (self.service.busy_for(), self.service.running()) {
+            background.push(format!("service {name} {:.1} s", busy.as_secs_f32()));
+        }
+        for (running, name) in [
+            (self.watchdog.is_some(), "watchdog"),
+            (self.startup.is_some(), "startup checks"),
+            (self.patch.is_some(), "patch"),
+            (self.game.is_some(), "game monitor"),
+        ] {
+            if running {
+                background.push(name.to_string());
             }
-        });
-
-        ui.add_space(12.0);
-        let play_btn = egui::Button::new(egui::RichText::new("PLAY GAME").color(Theme::TEXT))
-            .fill(Theme::ACCENT);
-        if ui.add_enabled(!busy, play_btn).clicked() {
-            self.launch_game();
         }
+        egui::Area::new(egui::Id::new("debug_overlay"))
+            .order(egui::Order::Foreground)
+            .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-8.0, 40.0))
+            .interactable(false)
+            .show(ctx, |ui| {
+                egui::Frame::new()
+                    .fill(Theme::surface().gamma_multiply(0.9))
+                    .corner_radius(egui::CornerRadius::same(6))
+                    .inner_margin(egui::Margin::symmetric(8, 6))
+                    .show(ui, |ui| {
+                        let line = |ui: &mut egui::Ui, text: String| {
+                            ui.label(egui::RichText::new(text).monospace().small());
+                        };
+                        line(
+                            ui,
+                            format!(
+                                "frame {:.1} ms ({:.0} fps) | cpu {:.1} ms",
+                                interval * 1000.0,
+                                1.0 / interval.max(f32::EPSILON),
+                                cpu * 1000.0
+                            ),
+                        );
+                        let causes = ctx.repaint_causes();
+                        if causes.is_empty() {
+                            line(ui, "repaint: input or window event".to_string());
+                        }
+                        for cause in causes {
+                            line(ui, format!("repaint: {cause}"));
+                        }
+                        if background.is_empty() {
+                            line(ui, "pending: none".to_string());
+                        } else {
+                            line(ui, format!("pending: {}", background.join(", ")));
+                        }
+                        let cache = format!("galleys {galleys} (+{new_galleys}) | textures {textures}");
+                        line(ui, cache);
+                    });
+            });
+    }
 
-        ui.add_space(6.0);
-        if ui
-            .add_enabled(!busy, egui::Button::new("SWITCH ACCOUNT"))
-            .clicked()
-        {
-            self.screen = Screen::Login;
-        }
+    /// Replaces the native window frame, which is disabled in `main`: a drag area with the
+    /// launcher title plus pin, minimize and close buttons.
+    fn render_title_bar(&mut self, ctx: &egui::Context) {
+        egui::TopBottomPanel::top("title_bar")
+            .exact_height(32.0)
+            .frame(
+                egui::Frame::new()
+                    .fill(Theme::bg_alt())
+                    .stroke(egui::Stroke::new(1.0, Theme::accent_soft()))
+                    .inner_margin(egui::Margin::symmetric(10, 0)),
+            )
+            .show(ctx, |ui| {
+                let drag = ui.interact(
+                    ui.max_rect(),
+                    egui::Id::new("title_bar_drag"),
+                    egui::Sense::click_and_drag(),
+                );
+                if drag.drag_started_by(egui::PointerButton::Primary) {
+                    ctx.send_viewport_cmd(egui::ViewportCommand::StartDrag);
+                }
+                ui.horizontal_centered(|ui| {
+                    let title = egui::RichText::new("ADNF LAUNCHER").size(13.0);
+                    ui.label(title.color(Theme::text_muted()));
+                    if self.app_config.dry_run {
+                        ui.label(egui::RichText::new("DRY RUN").size(13.0).color(Theme::accent()))
+                            .on_hover_text("Database writes are logged but not executed");
+                    }
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        ui.spacing_mut().item_spacing.x = 4.0;
+                        ui.spacing_mut().interact_size = egui::vec2(32.0, 24.0);
+                        ui.visuals_mut().widgets.hovered.weak_bg_fill = Theme::accent();
+                        let close = egui::Button::new("✕").frame_when_inactive(false);
+                        let close = icon_button(ui, close, "Close");
+                        if close.clicked() {
+                            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                        }
+                        ui.visuals_mut().widgets.hovered.weak_bg_fill = Theme::surface_alt();
+                        let minimize = egui::Button::new("🗕").frame_when_inactive(false);
+                        let minimize = icon_button(ui, minimize, "Minimize");
+                        if minimize.clicked() {
+                            ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(true));
+                        }
+                        let pinned = self.config.always_on_top;
+                        let pin = egui::Button::new("📌")
+                            .selected(pinned)
+                            .frame_when_inactive(pinned);
+                        let label = if pinned { "Unpin window" } else { "Keep on top" };
+                        let pin = icon_button(ui, pin, label);
+                        if pin.clicked() {
+                            self.config.always_on_top = !pinned;
+                            let _ = config::write_json("config.json", &self.config);
+                        }
+                    });
+                });
+            });
     }
 
     fn paint_lightning(&self, painter: egui::Painter, rect: egui::Rect, time: f32) {
@@ -400,9 +5194,9 @@ impl LauncherApp {
                 points.push(egui::pos2(x, y));
             }
             let alpha = (0.25 + 0.35 * (time * 7.0 + bolt as f32).sin().abs()).clamp(0.2, 0.7);
-            let glow = egui::Stroke::new(4.0, Theme::ACCENT_SOFT.gamma_multiply(alpha * 0.6));
-            let mid = egui::Stroke::new(2.5, Theme::ACCENT.gamma_multiply(alpha * 0.8));
-            let core = egui::Stroke::new(1.2, Theme::ACCENT.gamma_multiply(alpha + 0.2));
+            let glow = egui::Stroke::new(4.0, Theme::accent_soft().gamma_multiply(alpha * 0.6));
+            let mid = egui::Stroke::new(2.5, Theme::accent().gamma_multiply(alpha * 0.8));
+            let core = egui::Stroke::new(1.2, Theme::accent().gamma_multiply(alpha + 0.2));
             painter.add(egui::Shape::line(points.clone(), glow));
             painter.add(egui::Shape::line(points.clone(), mid));
             painter.add(egui::Shape::line(points, core));
@@ -415,24 +5209,70 @@ impl LauncherApp {
 }
 
 impl eframe::App for LauncherApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        ctx.plugin_or_default::<EguiAsyncPlugin>();
-        self.process_async(ctx);
-        Theme::apply(ctx);
-        ctx.request_repaint_after_secs(1.0 / 60.0);
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        self.process_events(ctx);
+        self.poll_watchdog();
+        self.poll_startup(ctx);
+        self.poll_game();
+        self.poll_ipc(ctx);
+        self.handle_back_key(ctx);
+        self.handle_shortcuts(ctx);
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::F12)) {
+            self.debug_overlay = !self.debug_overlay;
+        }
+        self.check_idle(ctx);
+        self.auto_refresh();
+        Theme::apply(ctx, self.config.theme, &self.config.status_colors);
+        // In the background, e.g. behind the game, only keep up with events and the client.
+        let focused = ctx.input(|i| i.viewport().focused).unwrap_or(true);
+        if let Some(ping) = &self.ping {
+            ping.set_paused(!focused);
+        }
+        let performance = self.config.performance_mode;
+        let repaint = match (focused, performance) {
+            (false, _) => 2.0,
+            (true, true) => 1.0,
+            (true, false) => 1.0 / 60.0,
+        };
+        ctx.request_repaint_after_secs(repaint);
         ctx.style_mut(|style| {
+            if performance {
+                style.animation_time = 0.0;
+                style.visuals.window_shadow = egui::Shadow::NONE;
+                style.visuals.popup_shadow = egui::Shadow::NONE;
+            }
             style.spacing.interact_size = egui::vec2(140.0, 32.0);
             style.spacing.item_spacing = egui::vec2(10.0, 10.0);
-            style.text_styles.insert(egui::TextStyle::Body, egui::FontId::proportional(16.0));
-            style.text_styles.insert(egui::TextStyle::Heading, egui::FontId::proportional(22.0));
+            let fonts = self.config.fonts.clamped();
+            // Buttons and small print keep egui's default sizes at the default body size and
+            // grow with it.
+            let scale = fonts.body / FontSizes::default().body;
+            for (text_style, size) in [
+                (egui::TextStyle::Body, fonts.body),
+                (egui::TextStyle::Heading, fonts.heading),
+                (egui::TextStyle::Button, 12.5 * scale),
+                (egui::TextStyle::Small, 9.0 * scale),
+            ] {
+                style.text_styles.insert(text_style, egui::FontId::proportional(size));
+            }
         });
 
+        if self.pinned != Some(self.config.always_on_top) {
+            let level = if self.config.always_on_top {
+                egui::WindowLevel::AlwaysOnTop
+            } else {
+                egui::WindowLevel::Normal
+            };
+            ctx.send_viewport_cmd(egui::ViewportCommand::WindowLevel(level));
+            self.pinned = Some(self.config.always_on_top);
+        }
+        self.render_title_bar(ctx);
         egui::CentralPanel::default().show(ctx, |ui| {
             let max_width = ui.available_width().min(420.0);
             ui.vertical_centered(|ui| {
                 ui.set_max_width(max_width);
                 egui::Frame::new()
-                    .fill(Theme::BG_ALT)
+                    .fill(Theme::bg_alt())
                     .corner_radius(egui::CornerRadius::same(12))
                     .inner_margin(egui::Margin::symmetric(20, 18))
                     .show(ui, |ui| {
@@ -440,54 +5280,317 @@ impl eframe::App for LauncherApp {
                         ui.horizontal(|ui| {
                             ui.label(
                                 egui::RichText::new("DNF")
-                                    .color(Theme::ACCENT)
+                                    .color(Theme::accent())
                                     .strong()
                                     .size(18.0),
                             );
                             ui.label(
                                 egui::RichText::new("LAUNCHER")
-                                    .color(Theme::TEXT)
+                                    .color(Theme::text())
                                     .strong()
                                     .size(18.0),
                             );
+                            ui.with_layout(
+                                egui::Layout::right_to_left(egui::Align::Center),
+                                |ui| {
+                                    let settings = egui::Button::new("⚙").frame(false);
+                                    let settings = icon_button(ui, settings, "Settings");
+                                    if settings.clicked() {
+                                        self.navigate(Screen::Settings);
+                                    }
+                                    if self.app_config.report_url.is_some() {
+                                        let report = egui::Button::new("?").frame(false);
+                                        let report = icon_button(ui, report, "Report a problem");
+                                        if report.clicked() {
+                                            self.problem_report = Some(String::new());
+                                        }
+                                    }
+                                },
+                            );
                         });
                         let lightning_height = 18.0;
                         let (rect, _) = ui.allocate_exact_size(
                             egui::vec2(ui.available_width(), lightning_height),
                             egui::Sense::hover(),
                         );
-                        self.paint_lightning(
-                            ui.painter_at(rect),
-                            rect,
-                            ui.input(|i| i.time) as f32,
-                        );
+                        if performance || !focused {
+                            ui.painter_at(rect).hline(
+                                rect.x_range(),
+                                rect.center().y,
+                                egui::Stroke::new(1.0, Theme::accent_soft()),
+                            );
+                        } else {
+                            self.paint_lightning(
+                                ui.painter_at(rect),
+                                rect,
+                                ui.input(|i| i.time) as f32,
+                            );
+                        }
                         ui.add_space(10.0);
                         match self.screen {
+                            Screen::Splash => self.render_splash(ui),
                             Screen::Login => self.render_login(ui),
                             Screen::Dashboard => self.render_dashboard(ui),
+                            Screen::Admin => self.render_admin(ui),
+                            Screen::Settings => self.render_settings(ui),
+                            Screen::Terms => self.render_terms(ui),
+                            Screen::UpdateRequired => self.render_update_required(ui),
                         }
                     });
             });
         });
 
+        self.render_cera_transfer(ctx);
+        self.render_password_reset(ctx);
+        self.render_recovery_login(ctx);
+        self.render_client_mismatch(ctx);
+        self.render_crash_report(ctx);
+        self.render_folder_checks(ctx);
+        self.render_problem_report(ctx);
+
         egui::TopBottomPanel::bottom("status")
             .frame(
                 egui::Frame::new()
-                    .fill(Theme::BG_ALT)
+                    .fill(Theme::bg_alt())
                     .inner_margin(egui::Margin::symmetric(16, 8)),
             )
             .show(ctx, |ui| {
-                let color = match self.status.kind {
-                    StatusKind::Info => Theme::TEXT_MUTED,
-                    StatusKind::Success => Theme::SUCCESS,
-                    StatusKind::Error => Theme::ERROR,
+                // The icon carries the state for players who can't tell the colours apart.
+                let (icon, color) = match self.status.kind {
+                    StatusKind::Info => ("", Theme::text_muted()),
+                    StatusKind::Success => ("✔ ", Theme::success()),
+                    StatusKind::Error => ("✖ ", Theme::error()),
                 };
-                ui.label(egui::RichText::new(&self.status.message).color(color));
+                ui.horizontal(|ui| {
+                    let text = format!("{icon}{}", self.status.message);
+                    ui.label(egui::RichText::new(text).color(color));
+                    if self.watchdog.is_some() {
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            let badge = egui::RichText::new("OFFLINE").color(Theme::error());
+                            ui.label(badge.small().strong())
+                                .on_hover_text("Database unreachable; retrying in the background");
+                        });
+                    } else if let (Some(last), Some(average)) = self.service.db_latency() {
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            let badge = egui::RichText::new("ONLINE").color(Theme::success());
+                            ui.label(badge.small().strong());
+                            let text = format!(
+                                "DB {} ms · avg {} ms",
+                                last.as_millis(),
+                                average.as_millis()
+                            );
+                            ui.label(egui::RichText::new(text).color(Theme::text_muted()).small())
+                                .on_hover_text("Duration of the last database calls");
+                        });
+                    }
+                });
             });
+        if self.debug_overlay {
+            self.render_debug_overlay(ctx, frame);
+        }
+        self.play_sounds(ctx);
+        let progress = self.patch.as_ref().map(PatchJob::progress);
+        let paused = self.patch.as_ref().is_some_and(PatchJob::is_paused);
+        self.taskbar.show(frame, TaskbarState::from_patch(progress.as_ref(), paused));
+    }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        let _ = config::write_json("config.json", &self.config);
+    }
+}
+
+fn spawn_ping(app_config: &AppConfig, realm: &Realm) -> Option<PingMonitor> {
+    realm
+        .game_server_addr
+        .clone()
+        .or_else(|| app_config.game_server_addr.clone())
+        .map(|addr| PingMonitor::spawn(addr, Duration::from_secs(5)))
+}
+
+fn form_field(ui: &mut egui::Ui, label: &str, value: &mut String) {
+    let label = ui.label(egui::RichText::new(label).color(Theme::text_muted()));
+    ui.add(
+        egui::TextEdit::singleline(value)
+            .desired_width(ui.available_width())
+            .background_color(Theme::surface()),
+    )
+    .labelled_by(label.id);
+}
+
+fn password_field(ui: &mut egui::Ui, label: &str, value: &mut String) {
+    let label = ui.label(egui::RichText::new(label).color(Theme::text_muted()));
+    ui.add(
+        egui::TextEdit::singleline(value)
+            .password(true)
+            .desired_width(ui.available_width())
+            .background_color(Theme::surface()),
+    )
+    .labelled_by(label.id);
+}
+
+/// Adds a symbol-only button with `label` as its tooltip and as the name screen readers announce.
+fn icon_button(ui: &mut egui::Ui, button: egui::Button, label: &str) -> egui::Response {
+    let response = ui.add(button).on_hover_text(label);
+    let enabled = response.enabled();
+    response.widget_info(|| egui::WidgetInfo::labeled(egui::WidgetType::Button, enabled, label));
+    response
+}
+
+fn render_equipment(ui: &mut egui::Ui, equipment: &[Equipment], items: &ItemDb) {
+    if equipment.is_empty() {
+        ui.label(egui::RichText::new("No equipment worn").color(Theme::text_muted()));
+        return;
+    }
+    for item in equipment {
+        let mut line = format!("{}: ", item.slot);
+        if item.reinforce > 0 {
+            line.push_str(&format!("+{} ", item.reinforce));
+        }
+        line.push_str(&items.name(item.item_id));
+        let color = match items.get(item.item_id).map(|i| i.rarity) {
+            Some(Rarity::Epic | Rarity::Legendary) => Theme::accent(),
+            _ => Theme::text_muted(),
+        };
+        ui.label(egui::RichText::new(line).color(color))
+            .on_hover_text(format!("Item ID {}", item.item_id));
+    }
+}
+
+fn reward_label(reward: &DailyReward, format: NumberFormat) -> String {
+    match (reward.cera, reward.item_id) {
+        (0, Some(_)) => "Item".to_string(),
+        (cera, None) => format!("{}c", format.format(cera)),
+        (cera, Some(_)) => format!("{}c+", format.format(cera)),
+    }
+}
+
+fn reward_hover(reward: &DailyReward, items: &ItemDb, format: NumberFormat) -> String {
+    let mut parts = Vec::new();
+    if reward.cera > 0 {
+        parts.push(format!("{} cera", format.format(reward.cera)));
+    }
+    if let Some(item_id) = reward.item_id {
+        parts.push(format!("{}x {}", reward.count, items.name(item_id)));
+    }
+    parts.join(" + ")
+}
+
+fn render_dungeon_clears(ui: &mut egui::Ui, clears: &[DungeonClear]) {
+    if clears.is_empty() {
+        ui.label(egui::RichText::new("No dungeons cleared yet").color(Theme::text_muted()));
+        return;
+    }
+    let total: i64 = clears.iter().map(|c| c.clears).sum();
+    let highest = clears.iter().map(|c| c.highest).max().unwrap_or(Difficulty::Normal);
+    ui.label(format!(
+        "{total} clears across {} dungeons | highest: {highest}",
+        clears.len()
+    ));
+    egui::ScrollArea::vertical()
+        .id_salt("dungeon_clears")
+        .max_height(120.0)
+        .show(ui, |ui| {
+            for clear in clears {
+                ui.label(
+                    egui::RichText::new(format!(
+                        "Dungeon {}: {} clears (best: {})",
+                        clear.dungeon_id, clear.clears, clear.highest
+                    ))
+                    .color(Theme::text_muted()),
+                );
+            }
+        });
+}
+
+fn render_avatars(ui: &mut egui::Ui, avatars: &[Avatar], items: &ItemDb, clock: Clock) {
+    if avatars.is_empty() {
+        ui.label(egui::RichText::new("No avatars equipped").color(Theme::text_muted()));
+        return;
+    }
+    let set_slots = 8;
+    let rare_pieces = avatars
+        .iter()
+        .filter(|a| a.slot.is_set_piece())
+        .filter(|a| items.get(a.item_id).is_some_and(|i| i.rarity >= Rarity::Rare))
+        .count();
+    let set_text = if rare_pieces == set_slots {
+        format!("Rare set: {rare_pieces}/{set_slots} (complete)")
+    } else {
+        format!("Rare set: {rare_pieces}/{set_slots}")
+    };
+    let set_color = if rare_pieces == set_slots { Theme::success() } else { Theme::text_muted() };
+    ui.label(egui::RichText::new(set_text).color(set_color));
+    for avatar in avatars {
+        let mut line = format!("{}: {}", avatar.slot, items.name(avatar.item_id));
+        if let Some(expires) = avatar.expires.filter(|e| e.year() < 9999) {
+            line.push_str(&format!(" (until {})", clock.format(expires, "%Y-%m-%d")));
+        }
+        ui.label(egui::RichText::new(line).color(Theme::text_muted()));
+    }
+}
+
+fn render_mail_list(ui: &mut egui::Ui, mail: &[Mail], format: NumberFormat, clock: Clock) {
+    if mail.is_empty() {
+        ui.label(egui::RichText::new("Mailbox is empty").color(Theme::text_muted()));
+        return;
+    }
+    egui::ScrollArea::vertical()
+        .id_salt("mail_list")
+        .max_height(170.0)
+        .show(ui, |ui| {
+            for item in mail {
+                let color = if item.read { Theme::text_muted() } else { Theme::text() };
+                let sent = item
+                    .sent_at
+                    .map(|t| clock.format(t, "%Y-%m-%d %H:%M"))
+                    .unwrap_or_default();
+                ui.label(
+                    egui::RichText::new(format!("#{} {} | {}", item.id, item.sender, sent))
+                        .color(color),
+                );
+                if !item.subject.is_empty() {
+                    ui.label(egui::RichText::new(&item.subject).color(Theme::text_muted()));
+                }
+                let mut attachments = Vec::new();
+                if item.item_id > 0 {
+                    attachments.push(format!("Item {} x{}", item.item_id, item.item_count.max(1)));
+                }
+                if item.gold > 0 {
+                    attachments.push(format!("Gold {}", format.format(item.gold)));
+                }
+                if !attachments.is_empty() {
+                    ui.label(
+                        egui::RichText::new(format!("Attached: {}", attachments.join(", ")))
+                            .color(Theme::success()),
+                    );
+                }
+                ui.separator();
+            }
+        });
+}
+
+fn parse_count(value: &str, field: &str) -> Result<i32, Status> {
+    match value.trim().parse::<i32>() {
+        Ok(val) if val > 0 => Ok(val),
+        _ => Err(Status::error(format!("Invalid {field}"))),
+    }
+}
+
+fn parse_id(value: &str, field: &str) -> Result<i32, Status> {
+    match value.trim().parse::<i32>() {
+        Ok(val) if val >= 0 => Ok(val),
+        _ => Err(Status::error(format!("Invalid {field}"))),
     }
 }
 
 impl Status {
+    fn info(message: impl Into<String>) -> Self {
+        Self {
+            kind: StatusKind::Info,
+            message: message.into(),
+        }
+    }
+
     fn success(message: impl Into<String>) -> Self {
         Self {
             kind: StatusKind::Success,
@@ -0,0 +1,191 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result, bail};
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use serde::{Deserialize, Serialize};
+use sqlx::mysql::{MySqlArguments, MySqlRow};
+use sqlx::{Column, MySql, Row, TypeInfo};
+
+use crate::db::DbPool;
+
+/// Bumped when the bundle layout changes; older launchers refuse newer bundles.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Databases in the order [`TABLES`] visits them.
+pub const POOLS: [DbPool; 5] = [
+    DbPool::Main,
+    DbPool::Login,
+    DbPool::Billing,
+    DbPool::Chara,
+    DbPool::Inventory,
+];
+
+/// How a table's rows are tied to the account.
+#[derive(Clone, Copy)]
+pub enum Owner {
+    /// Column holding the account UID.
+    Account(&'static str),
+    /// Column holding a character number of one of the account's characters.
+    Character(&'static str),
+}
+
+/// Everything a backup covers, in export and restore order. `charac_info` must come before
+/// the per-character tables, since it is where the character numbers are read from.
+pub const TABLES: &[(DbPool, &str, Owner)] = &[
+    (DbPool::Main, "accounts", Owner::Account("uid")),
+    (DbPool::Main, "limit_create_character", Owner::Account("m_id")),
+    (DbPool::Main, "member_info", Owner::Account("m_id")),
+    (DbPool::Main, "member_white_account", Owner::Account("m_id")),
+    (DbPool::Login, "member_login", Owner::Account("m_id")),
+    (DbPool::Billing, "cash_cera", Owner::Account("account")),
+    (DbPool::Billing, "cash_inventory", Owner::Account("account")),
+    (DbPool::Chara, "charac_info", Owner::Account("m_id")),
+    (DbPool::Chara, "charac_quest", Owner::Character("charac_no")),
+    (DbPool::Chara, "charac_quest_clear", Owner::Character("charac_no")),
+    (DbPool::Chara, "charac_dungeon_clear", Owner::Character("charac_no")),
+    (DbPool::Chara, "pvp_result", Owner::Character("charac_no")),
+    (DbPool::Inventory, "account_cargo", Owner::Account("m_id")),
+    (DbPool::Inventory, "inventory", Owner::Character("charac_no")),
+    (DbPool::Inventory, "user_items", Owner::Character("charac_no")),
+    (DbPool::Inventory, "creature_items", Owner::Character("charac_no")),
+];
+
+/// A column value that survives the round trip through JSON. Dates are kept as MySQL
+/// literals and binary columns as base64.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Value {
+    Null,
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    Text(String),
+    Bytes(String),
+}
+
+pub type Rows = Vec<BTreeMap<String, Value>>;
+
+/// All rows of one account, as written to a backup file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AccountBackup {
+    pub version: u32,
+    pub uid: i32,
+    pub created_at: String,
+    /// Rows per table name.
+    pub tables: BTreeMap<String, Rows>,
+}
+
+impl AccountBackup {
+    /// Character numbers found in the backed-up `charac_info` rows.
+    pub fn char_ids(&self) -> Vec<i64> {
+        let Some(rows) = self.tables.get("charac_info") else {
+            return Vec::new();
+        };
+        rows.iter()
+            .filter_map(|row| match row.get("charac_no") {
+                Some(Value::Int(id)) => Some(*id),
+                Some(Value::UInt(id)) => i64::try_from(*id).ok(),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn row_count(&self) -> usize {
+        self.tables.values().map(Vec::len).sum()
+    }
+
+    pub fn to_json(&self) -> Result<Vec<u8>> {
+        Ok(serde_json::to_vec(self)?)
+    }
+
+    pub fn from_json(bytes: &[u8]) -> Result<Self> {
+        let backup: Self = serde_json::from_slice(bytes).context("parse account backup")?;
+        if backup.version > FORMAT_VERSION {
+            bail!("Backup format {} is newer than this launcher supports", backup.version);
+        }
+        Ok(backup)
+    }
+}
+
+/// Reads every column of a row without knowing the table's schema up front.
+pub fn read_row(row: &MySqlRow) -> Result<BTreeMap<String, Value>> {
+    row.columns()
+        .iter()
+        .map(|column| {
+            let idx = column.ordinal();
+            let value = read_value(row, idx)
+                .with_context(|| format!("unsupported type in column {}", column.name()))?;
+            Ok((column.name().to_string(), value))
+        })
+        .collect()
+}
+
+fn read_value(row: &MySqlRow, idx: usize) -> Option<Value> {
+    // NULL decodes as `None` for any type, so the first attempt settles it.
+    if let Ok(value) = row.try_get::<Option<i64>, _>(idx) {
+        return Some(value.map_or(Value::Null, Value::Int));
+    }
+    if let Ok(Some(value)) = row.try_get::<Option<u64>, _>(idx) {
+        return Some(Value::UInt(value));
+    }
+    if let Ok(Some(value)) = row.try_get::<Option<f64>, _>(idx) {
+        return Some(Value::Float(value));
+    }
+    if let Ok(Some(value)) = row.try_get::<Option<NaiveDateTime>, _>(idx) {
+        return Some(Value::Text(value.format("%Y-%m-%d %H:%M:%S%.f").to_string()));
+    }
+    if let Ok(Some(value)) = row.try_get::<Option<NaiveDate>, _>(idx) {
+        return Some(Value::Text(value.to_string()));
+    }
+    if let Ok(Some(value)) = row.try_get::<Option<NaiveTime>, _>(idx) {
+        return Some(Value::Text(value.to_string()));
+    }
+    // DECIMAL arrives as text but no typed decode accepts it; keep the digits exactly.
+    if row.column(idx).type_info().name() == "DECIMAL"
+        && let Ok(Some(value)) = row.try_get_unchecked::<Option<String>, _>(idx)
+    {
+        return Some(Value::Text(value));
+    }
+    if let Ok(Some(value)) = row.try_get::<Option<String>, _>(idx) {
+        return Some(Value::Text(value));
+    }
+    // Legacy latin1 columns often hold non-UTF-8 names; keep their bytes as-is.
+    if let Ok(Some(value)) = row.try_get::<Option<Vec<u8>>, _>(idx) {
+        return Some(Value::Bytes(BASE64.encode(value)));
+    }
+    None
+}
+
+/// Why `value` can't be written to a column of the given MySQL `DATA_TYPE`, if it can't.
+pub fn check_value(value: &Value, data_type: &str) -> Option<String> {
+    let numeric = matches!(
+        data_type,
+        "tinyint" | "smallint" | "mediumint" | "int" | "bigint" | "decimal" | "float" | "double"
+    );
+    match value {
+        Value::Text(text) if numeric && text.trim().parse::<f64>().is_err() => {
+            Some(format!("{text:?} is not a number"))
+        }
+        Value::Float(value) if !value.is_finite() => Some(format!("{value} is not a number")),
+        Value::Bytes(value) if BASE64.decode(value).is_err() => {
+            Some("corrupt binary value".to_string())
+        }
+        Value::Bytes(_) if numeric => Some("binary value in a numeric column".to_string()),
+        _ => None,
+    }
+}
+
+/// Binds a restored value to the next placeholder.
+pub fn bind_value<'q>(
+    query: sqlx::query::Query<'q, MySql, MySqlArguments>,
+    value: &Value,
+) -> Result<sqlx::query::Query<'q, MySql, MySqlArguments>> {
+    Ok(match value {
+        Value::Null => query.bind(None::<String>),
+        Value::Int(value) => query.bind(*value),
+        Value::UInt(value) => query.bind(*value),
+        Value::Float(value) => query.bind(*value),
+        Value::Text(value) => query.bind(value.clone()),
+        Value::Bytes(value) => query.bind(BASE64.decode(value).context("corrupt binary column")?),
+    })
+}
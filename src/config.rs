@@ -1,18 +1,415 @@
+use std::collections::BTreeMap;
 use std::env;
 use std::fs;
+use std::ops::RangeInclusive;
 use std::path::Path;
+use std::time::Duration;
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::hosts::Redirect;
+use crate::legacy::LegacyConfig;
+use crate::locale::NumberFormat;
+use crate::theme::{StatusColors, ThemeVariant};
 
 #[derive(Clone, Debug)]
 pub struct AppConfig {
     pub db_main_url: String,
     pub db_billing_url: String,
-    pub db_char_url: String,
-    pub db_inventory_url: String,
     pub db_login_url: String,
+    /// Connection charset for the character and inventory databases.
+    pub db_charset: String,
+    /// Encoding of names older servers store as raw bytes, used when they aren't UTF-8.
+    pub legacy_encoding: Option<&'static encoding_rs::Encoding>,
+    /// Timezone the database stores times in; `None` means the same as this machine.
+    pub server_timezone: Option<chrono_tz::Tz>,
     pub dnf_exe_path: String,
+    /// Text file in the game directory holding the installed client version.
+    pub client_version_file: String,
+    /// Files and folders the game directory must contain, checked at startup and before PLAY.
+    pub required_game_files: Vec<String>,
+    pub item_db_path: String,
+    pub exp_table_path: String,
+    pub max_level: i32,
+    pub slot_price_cera: i64,
+    /// Bounds of the gold/cera amount slider.
+    pub amount_range: RangeInclusive<i32>,
+    pub send_limits: SendLimits,
+    pub username_policy: UsernamePolicy,
+    /// Rules shown before registration, loaded from `terms.md`; no gate when absent.
+    pub terms: Option<Terms>,
+    /// Extra client argument carrying the preferred channel, e.g. `--channel={channel}`.
+    pub channel_arg: Option<String>,
+    /// `host:port` of the game server used for latency probes.
+    pub game_server_addr: Option<String>,
+    /// Game worlds sharing the account DB. Never empty; the first entry is the default.
+    pub realms: Vec<Realm>,
+    pub integrity: IntegrityConfig,
+    /// Client cache/temp directories the cleanup tool may empty.
+    pub cache_dirs: Vec<String>,
+    /// Client log files or directories gathered into a report when the game crashes.
+    pub crash_logs: Vec<String>,
+    /// Endpoint crash reports are POSTed to, as gzipped JSON.
+    pub crash_report_url: Option<String>,
+    /// Endpoint or Discord webhook that "Report a problem" posts to, with a support bundle.
+    pub report_url: Option<String>,
+    /// Hostnames the client needs redirected through the hosts file, from `host=ip` entries.
+    pub hosts_redirects: Vec<Redirect>,
+    pub launch: LaunchConfig,
+    /// Registry values written on first run, loaded from `client_registry.json`.
+    pub client_registry: Option<ClientRegistry>,
+    /// Loopback port held by the running launcher; later launches forward their arguments here.
+    pub ipc_port: u16,
+    pub updates: UpdateConfig,
+    /// JSON list of Markdown announcements shown on the dashboard's News tab.
+    pub news_url: Option<String>,
+    /// Login reward cycle, one entry per consecutive day, loaded from `daily_rewards.json`.
+    pub daily_rewards: Vec<DailyReward>,
+    /// Currencies offered next to gold and cera, loaded from `currencies.json`.
+    pub currencies: Vec<CurrencyDef>,
+    /// Email binding and password resets are only offered when a relay is configured.
+    pub smtp: Option<SmtpConfig>,
+    /// Let players redeem link codes from the server's Discord bot.
+    pub discord_linking: bool,
+    /// Let several accounts stay signed in at once, for servers that allow dual-clienting.
+    pub multi_client: bool,
+    /// Idle minutes before the dashboard signs out, enforced for every player; set on shared
+    /// PCs. Without it players choose their own timeout in settings.
+    pub idle_timeout_mins: Option<u32>,
+    pub disabled_features: Vec<Feature>,
+    pub admin_mode: bool,
+    /// Log database writes instead of executing them, for demos against production data.
+    pub dry_run: bool,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    Beta,
+}
+
+impl UpdateChannel {
+    pub const ALL: [Self; 2] = [Self::Stable, Self::Beta];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Stable => "stable",
+            Self::Beta => "beta",
+        }
+    }
+}
+
+/// Graphics backend for the launcher window. `Auto` tries glow first and falls back to wgpu.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Renderer {
+    #[default]
+    Auto,
+    Glow,
+    Wgpu,
+}
+
+impl Renderer {
+    pub const ALL: [Self; 3] = [Self::Auto, Self::Glow, Self::Wgpu];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Auto => "Automatic",
+            Self::Glow => "OpenGL (glow)",
+            Self::Wgpu => "wgpu",
+        }
+    }
+}
+
+/// Manifest URL templates; `{channel}` is replaced with the user's update channel.
+#[derive(Clone, Debug)]
+pub struct UpdateConfig {
+    pub patch_manifest_url: Option<String>,
+    pub launcher_manifest_url: Option<String>,
+    /// When set, manifests must carry a valid signature from this key.
+    pub public_key: Option<ed25519_dalek::VerifyingKey>,
+}
+
+impl UpdateConfig {
+    pub fn patch_manifest(&self, channel: UpdateChannel) -> Option<String> {
+        let template = self.patch_manifest_url.as_ref()?;
+        Some(template.replace("{channel}", channel.as_str()))
+    }
+
+    pub fn launcher_manifest(&self, channel: UpdateChannel) -> Option<String> {
+        let template = self.launcher_manifest_url.as_ref()?;
+        Some(template.replace("{channel}", channel.as_str()))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LaunchMode {
+    /// Run the client executable directly (Windows).
+    Native,
+    Wine,
+    /// Proton's `proton run` wrapper, with the prefix passed as the compat data path.
+    Proton,
+}
+
+/// How the client is started; non-Windows hosts default to Wine.
+#[derive(Clone, Debug)]
+pub struct LaunchConfig {
+    pub mode: LaunchMode,
+    /// Path to the `wine` or `proton` binary.
+    pub wine_binary: String,
+    pub wine_prefix: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IntegrityMode {
+    Off,
+    Warn,
+    Block,
+}
+
+/// Launcher features an operator can switch off with `DISABLED_FEATURES`; disabled ones are
+/// hidden from the UI.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Feature {
+    SendGold,
+    SendCera,
+    CeraTransfer,
+    SlotPurchase,
+    CreateAccount,
+    RecoveryCodes,
+    StoragePin,
+    Mail,
+    Guild,
+    /// Sends of the configured currencies marked `sendable`.
+    SendCurrency,
+}
+
+impl Feature {
+    const ALL: [Self; 10] = [
+        Self::SendGold,
+        Self::SendCera,
+        Self::CeraTransfer,
+        Self::SlotPurchase,
+        Self::CreateAccount,
+        Self::RecoveryCodes,
+        Self::StoragePin,
+        Self::Mail,
+        Self::Guild,
+        Self::SendCurrency,
+    ];
+
+    fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|feature| feature.as_str() == value)
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::SendGold => "send_gold",
+            Self::SendCera => "send_cera",
+            Self::CeraTransfer => "cera_transfer",
+            Self::SlotPurchase => "slot_purchase",
+            Self::CreateAccount => "create_account",
+            Self::RecoveryCodes => "recovery_codes",
+            Self::StoragePin => "storage_pin",
+            Self::Mail => "mail",
+            Self::Guild => "guild",
+            Self::SendCurrency => "send_currency",
+        }
+    }
+}
+
+/// Server rules new players accept before registering.
+#[derive(Clone, Debug)]
+pub struct Terms {
+    /// Markdown from the operator.
+    pub text: String,
+    /// Hash of `text`, recorded with each acceptance so revisions can be told apart.
+    pub version: String,
+}
+
+impl Terms {
+    fn load(path: &str) -> Option<Self> {
+        let text = fs::read_to_string(path).ok().filter(|text| !text.trim().is_empty())?;
+        let version = hex::encode(&Sha256::digest(text.as_bytes())[..8]);
+        Some(Self { text, version })
+    }
+}
+
+/// Names reserved for staff unless `USERNAME_RESERVED` lists others.
+const DEFAULT_RESERVED_NAMES: &[&str] =
+    &["admin", "administrator", "gm", "gamemaster", "moderator", "support", "system", "root"];
+
+/// Rules a new account name has to pass, checked in the UI and again by `Db::create_account`.
+#[derive(Clone, Debug)]
+pub struct UsernamePolicy {
+    /// Allowed length in characters.
+    pub length: RangeInclusive<usize>,
+    /// Lowercase names that may not be registered as they are.
+    pub reserved: Vec<String>,
+    /// Lowercase words that may not appear anywhere in a name, from `username_blocklist.txt`.
+    pub blocked: Vec<String>,
+}
+
+impl UsernamePolicy {
+    /// Explains the first rule `name` breaks.
+    pub fn check(&self, name: &str) -> Result<()> {
+        let len = name.chars().count();
+        if len == 0 {
+            anyhow::bail!("Enter an account name");
+        }
+        if !self.length.contains(&len) {
+            anyhow::bail!(
+                "Account names must be {} to {} characters long",
+                self.length.start(),
+                self.length.end()
+            );
+        }
+        if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            anyhow::bail!("Account names must start with a letter");
+        }
+        if let Some(c) = name.chars().find(|c| !c.is_ascii_alphanumeric() && *c != '_') {
+            anyhow::bail!("Account names may only use letters, digits and _ (not {c:?})");
+        }
+        let lower = name.to_ascii_lowercase();
+        if self.reserved.contains(&lower) {
+            anyhow::bail!("\"{name}\" is reserved");
+        }
+        if self.blocked.iter().any(|word| lower.contains(word.as_str())) {
+            anyhow::bail!("That account name is not allowed");
+        }
+        Ok(())
+    }
+}
+
+/// Operator limits on players' gold and cera sends; caps of `None` are unlimited.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SendLimits {
+    pub gold_daily: Option<i64>,
+    pub cera_daily: Option<i64>,
+    /// Minimum time between two sends of the same currency by one account.
+    pub cooldown: Duration,
+}
+
+impl SendLimits {
+    pub fn is_unlimited(&self) -> bool {
+        self.gold_daily.is_none() && self.cera_daily.is_none() && self.cooldown.is_zero()
+    }
+}
+
+/// SMTP relay for verification codes and security notifications.
+#[derive(Clone, Debug)]
+pub struct SmtpConfig {
+    pub host: String,
+    /// 465 uses implicit TLS; any other port upgrades with STARTTLS.
+    pub port: u16,
+    pub username: String,
+    pub password: String,
+    pub from: String,
+}
+
+/// Pre-launch client file verification against the published manifest.
+#[derive(Clone, Debug)]
+pub struct IntegrityConfig {
+    pub mode: IntegrityMode,
+    pub manifest_path: String,
+    /// Files to hash, relative to the game directory. Empty means every manifest entry.
+    pub files: Vec<String>,
+}
+
+/// A game world with its own character/inventory databases, loaded from `realms.json`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Realm {
+    pub name: String,
+    pub db_char_url: String,
+    pub db_inventory_url: String,
+    /// Extra arguments passed to the client after the login token.
+    #[serde(default)]
+    pub launch_args: Vec<String>,
+    /// Overrides the trailing server block of the login token.
+    #[serde(default)]
+    pub token_tail: Option<String>,
+    #[serde(default)]
+    pub game_server_addr: Option<String>,
+}
+
+/// Reward for one day of the login streak: cera, a cash-shop item, or both.
+#[derive(Clone, Debug, Deserialize)]
+pub struct DailyReward {
+    #[serde(default)]
+    pub cera: i64,
+    #[serde(default)]
+    pub item_id: Option<i32>,
+    #[serde(default = "default_reward_count")]
+    pub count: i32,
+}
+
+fn default_reward_count() -> i32 {
+    1
+}
+
+/// Database a [`CurrencyDef`] lives in.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CurrencyDb {
+    Main,
+    Billing,
+    Chara,
+    Inventory,
+}
+
+/// A server-specific currency such as cera points or event medals, stored as a numeric
+/// column keyed by account UID or, for per-character currencies, by character number.
+#[derive(Clone, Debug, Deserialize)]
+pub struct CurrencyDef {
+    pub name: String,
+    pub db: CurrencyDb,
+    pub table: String,
+    pub column: String,
+    /// Column matched against the UID or character number.
+    pub key: String,
+    #[serde(default)]
+    pub per_character: bool,
+    /// Players may send themselves this currency; off unless the operator opts in.
+    #[serde(default)]
+    pub sendable: bool,
+    /// Most a player may send per day; unlimited when unset. The send cooldown applies too.
+    #[serde(default)]
+    pub daily_cap: Option<i64>,
+}
+
+impl CurrencyDef {
+    pub fn owner_label(&self) -> &'static str {
+        if self.per_character { "character" } else { "account" }
+    }
+}
+
+/// Registry values the client reads at startup, all stored as strings under
+/// `HKEY_CURRENT_USER\{key}`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ClientRegistry {
+    #[serde(default = "default_registry_key")]
+    pub key: String,
+    #[serde(default)]
+    pub locale: Option<String>,
+    /// Value name to template; `{game_dir}`, `{locale}` and `{server}` are substituted.
+    #[serde(default = "default_registry_values")]
+    pub values: BTreeMap<String, String>,
+}
+
+fn default_registry_key() -> String {
+    "Software\\Neople\\DNF".to_string()
+}
+
+fn default_registry_values() -> BTreeMap<String, String> {
+    BTreeMap::from([
+        ("InstallPath".to_string(), "{game_dir}".to_string()),
+        ("Locale".to_string(), "{locale}".to_string()),
+        ("Server".to_string(), "{server}".to_string()),
+    ])
 }
 
 #[derive(Serialize, Deserialize, Default, Clone)]
@@ -20,6 +417,344 @@ pub struct UserConfig {
     pub username: String,
     pub password: String,
     pub remember: bool,
+    #[serde(default)]
+    pub preferred_channel: Option<i32>,
+    #[serde(default)]
+    pub realm: Option<String>,
+    /// Start the client through the UAC prompt (Windows only).
+    #[serde(default)]
+    pub launch_elevated: bool,
+    #[serde(default)]
+    pub registry_written: bool,
+    /// Every remembered account, for `--quick-launch <account>` shortcuts.
+    #[serde(default)]
+    pub accounts: Vec<SavedAccount>,
+    #[serde(default)]
+    pub update_channel: UpdateChannel,
+    /// Patch download cap in KB/s; zero is unlimited.
+    #[serde(default)]
+    pub download_limit_kbps: u32,
+    /// Download large patch files from community mirrors when the manifest lists any.
+    #[serde(default)]
+    pub use_mirrors: bool,
+    #[serde(default)]
+    pub proxy: ProxySettings,
+    #[serde(default)]
+    pub sound: SoundSettings,
+    /// Drops animations and idles at a low repaint rate for weak GPUs and battery use.
+    #[serde(default)]
+    pub performance_mode: bool,
+    #[serde(default)]
+    pub always_on_top: bool,
+    #[serde(default)]
+    pub keybindings: KeyBindings,
+    /// Enter gold/cera amounts with a slider instead of typing them.
+    #[serde(default)]
+    pub amount_slider: bool,
+    #[serde(default)]
+    pub number_format: NumberFormat,
+    /// Show times as the server has them instead of converting to this machine's timezone.
+    #[serde(default)]
+    pub show_server_time: bool,
+    /// Client executable imported from an older launcher; overrides `DNF_EXE_PATH`.
+    #[serde(default)]
+    pub game_path: Option<String>,
+    /// Game server `host:port` imported from an older launcher; overrides `GAME_SERVER_ADDR`.
+    #[serde(default)]
+    pub server_addr: Option<String>,
+    /// Sign out after this many idle minutes on the dashboard; 0 never does.
+    #[serde(default)]
+    pub idle_timeout_mins: u32,
+    /// Reload the dashboard every this many minutes while signed in; 0 never does.
+    #[serde(default)]
+    pub auto_refresh_mins: u32,
+    /// Keep auto-refreshing while a client started from the launcher is running.
+    #[serde(default)]
+    pub refresh_in_game: bool,
+    /// Read by `main` before the window opens, so changes apply after a restart.
+    #[serde(default)]
+    pub renderer: Renderer,
+    /// Turns off hardware acceleration for GPU drivers that fail to open a window.
+    #[serde(default)]
+    pub software_rendering: bool,
+    #[serde(default)]
+    pub theme: ThemeVariant,
+    #[serde(default)]
+    pub fonts: FontSizes,
+    #[serde(default)]
+    pub status_colors: StatusColors,
+    /// Dashboard tab, selected character and character list offset the player last had,
+    /// restored after sign-in. Mail and Guild need the character, so it is kept by id.
+    #[serde(default)]
+    pub dashboard_tab: DashboardTab,
+    #[serde(default)]
+    pub dashboard_char: Option<i32>,
+    #[serde(default)]
+    pub character_scroll: f32,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DashboardTab {
+    #[default]
+    Characters,
+    Mail,
+    Guild,
+    History,
+    News,
+}
+
+impl DashboardTab {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Characters => "Characters",
+            Self::Mail => "Mail",
+            Self::Guild => "Guild",
+            Self::History => "History",
+            Self::News => "News",
+        }
+    }
+}
+
+/// Launcher actions that can be bound to a key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shortcut {
+    Launch,
+    Refresh,
+    NextTab,
+    Settings,
+}
+
+impl Shortcut {
+    pub const ALL: [Self; 4] = [Self::Launch, Self::Refresh, Self::NextTab, Self::Settings];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Launch => "Launch game",
+            Self::Refresh => "Refresh",
+            Self::NextTab => "Next tab",
+            Self::Settings => "Open settings",
+        }
+    }
+}
+
+/// Key names as egui spells them (`F5`, `R`, ...); an empty name leaves the action unbound.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct KeyBindings {
+    pub launch: String,
+    pub refresh: String,
+    pub next_tab: String,
+    pub settings: String,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            launch: "F9".to_string(),
+            refresh: "F5".to_string(),
+            next_tab: "F6".to_string(),
+            settings: "F10".to_string(),
+        }
+    }
+}
+
+impl KeyBindings {
+    pub fn key(&self, shortcut: Shortcut) -> &str {
+        match shortcut {
+            Shortcut::Launch => &self.launch,
+            Shortcut::Refresh => &self.refresh,
+            Shortcut::NextTab => &self.next_tab,
+            Shortcut::Settings => &self.settings,
+        }
+    }
+
+    /// Binds `key` to `shortcut`, unbinding any other action that had it.
+    pub fn bind(&mut self, shortcut: Shortcut, key: &str) {
+        for other in Shortcut::ALL {
+            if other != shortcut && self.key(other) == key {
+                self.slot(other).clear();
+            }
+        }
+        *self.slot(shortcut) = key.to_string();
+    }
+
+    pub fn unbind(&mut self, shortcut: Shortcut) {
+        self.slot(shortcut).clear();
+    }
+
+    fn slot(&mut self, shortcut: Shortcut) -> &mut String {
+        match shortcut {
+            Shortcut::Launch => &mut self.launch,
+            Shortcut::Refresh => &mut self.refresh,
+            Shortcut::NextTab => &mut self.next_tab,
+            Shortcut::Settings => &mut self.settings,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct SoundSettings {
+    pub enabled: bool,
+    /// 0.0 to 1.0.
+    pub volume: f32,
+}
+
+impl Default for SoundSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            volume: 0.5,
+        }
+    }
+}
+
+/// Text sizes in points; button and small text scale with the body size.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct FontSizes {
+    pub body: f32,
+    pub heading: f32,
+}
+
+impl FontSizes {
+    pub const BODY_RANGE: RangeInclusive<f32> = 12.0..=28.0;
+    pub const HEADING_RANGE: RangeInclusive<f32> = 16.0..=40.0;
+
+    /// Sizes pulled into range, for hand-edited config files.
+    pub fn clamped(&self) -> Self {
+        let clamp =
+            |size: f32, range: RangeInclusive<f32>| size.clamp(*range.start(), *range.end());
+        Self {
+            body: clamp(self.body, Self::BODY_RANGE),
+            heading: clamp(self.heading, Self::HEADING_RANGE),
+        }
+    }
+}
+
+impl Default for FontSizes {
+    fn default() -> Self {
+        Self {
+            body: 16.0,
+            heading: 22.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProxyMode {
+    Off,
+    /// `HTTPS_PROXY`/`ALL_PROXY`, then the Windows Internet Options proxy.
+    #[default]
+    System,
+    Manual,
+}
+
+impl ProxyMode {
+    pub const ALL: [Self; 3] = [Self::Off, Self::System, Self::Manual];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Off => "No proxy",
+            Self::System => "System proxy",
+            Self::Manual => "Manual",
+        }
+    }
+}
+
+/// Proxy used for news, patch and launcher update downloads.
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+pub struct ProxySettings {
+    #[serde(default)]
+    pub mode: ProxyMode,
+    #[serde(default)]
+    pub host: String,
+    #[serde(default)]
+    pub port: u16,
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub password: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SavedAccount {
+    pub username: String,
+    pub password: String,
+    /// Player-chosen label such as "Main"; empty shows the username.
+    #[serde(default)]
+    pub nickname: String,
+}
+
+impl SavedAccount {
+    pub fn label(&self) -> &str {
+        if self.nickname.is_empty() { &self.username } else { &self.nickname }
+    }
+}
+
+impl UserConfig {
+    /// Makes the account the default login and adds or refreshes it in the saved list.
+    pub fn remember_account(&mut self, username: &str, password: &str) {
+        self.username = username.to_string();
+        self.password = password.to_string();
+        self.remember = true;
+        match self.accounts.iter_mut().find(|a| a.username == username) {
+            Some(saved) => saved.password = password.to_string(),
+            None => self.accounts.push(SavedAccount {
+                username: username.to_string(),
+                password: password.to_string(),
+                nickname: String::new(),
+            }),
+        }
+    }
+
+    /// Takes over what an older launcher knew. Imported accounts have no password yet; it
+    /// is saved on their first "Remember me" sign-in. Returns the number of new accounts.
+    pub fn import_legacy(&mut self, legacy: &LegacyConfig, exe_name: &str) -> usize {
+        if let Some(path) = &legacy.game_path {
+            let path = Path::new(path);
+            let exe = if path.is_dir() { path.join(exe_name) } else { path.to_path_buf() };
+            self.game_path = Some(exe.display().to_string());
+        }
+        if let Some(addr) = &legacy.server_addr {
+            self.server_addr = Some(addr.clone());
+        }
+        let mut added = 0;
+        for username in &legacy.usernames {
+            if self.accounts.iter().any(|a| a.username.eq_ignore_ascii_case(username)) {
+                continue;
+            }
+            self.accounts.push(SavedAccount {
+                username: username.clone(),
+                password: String::new(),
+                nickname: String::new(),
+            });
+            added += 1;
+        }
+        added
+    }
+
+    /// Saved credentials for `name` (a username or nickname), or the default account when no
+    /// name is given.
+    pub fn saved_account(&self, name: Option<&str>) -> Option<SavedAccount> {
+        let default = (self.remember && !self.username.is_empty()).then(|| SavedAccount {
+            username: self.username.clone(),
+            password: self.password.clone(),
+            nickname: String::new(),
+        });
+        match name {
+            None => default,
+            Some(name) => self
+                .accounts
+                .iter()
+                .find(|a| a.username == name)
+                .or_else(|| self.accounts.iter().find(|a| a.nickname.eq_ignore_ascii_case(name)))
+                .cloned()
+                .or_else(|| default.filter(|a| a.username == name)),
+        }
+    }
 }
 
 impl AppConfig {
@@ -27,35 +762,306 @@ impl AppConfig {
         let _ = dotenvy::dotenv();
 
         let dnf_exe_path = env::var("DNF_EXE_PATH").unwrap_or_else(|_| "ADNF.exe".to_string());
+        let client_version_file =
+            env::var("CLIENT_VERSION_FILE").unwrap_or_else(|_| "version.txt".to_string());
+        let mut required_game_files = env_list("REQUIRED_GAME_FILES");
+        if required_game_files.is_empty() {
+            required_game_files = vec!["Script.pvf".to_string(), "ImagePacks2".to_string()];
+        }
+        let item_db_path = env::var("ITEM_DB_PATH").unwrap_or_else(|_| "items.tsv".to_string());
+        let exp_table_path =
+            env::var("EXP_TABLE_PATH").unwrap_or_else(|_| "exp_table.txt".to_string());
+        let max_level = env_parse("MAX_LEVEL").unwrap_or(85);
+        let slot_price_cera = env_parse("SLOT_PRICE_CERA").unwrap_or(0);
+        let amount_min = env_parse("AMOUNT_MIN").unwrap_or(1).max(1);
+        let amount_max = env_parse("AMOUNT_MAX").unwrap_or(1_000_000).max(amount_min);
+        let send_limits = SendLimits {
+            gold_daily: env_parse("GOLD_DAILY_CAP").filter(|cap| *cap > 0),
+            cera_daily: env_parse("CERA_DAILY_CAP").filter(|cap| *cap > 0),
+            cooldown: Duration::from_secs(env_parse("SEND_COOLDOWN_SECS").unwrap_or(0)),
+        };
+        let username_min = env_parse("USERNAME_MIN_LEN").unwrap_or(4).max(1);
+        let mut reserved = env_list("USERNAME_RESERVED");
+        if reserved.is_empty() {
+            reserved = DEFAULT_RESERVED_NAMES.iter().map(|name| name.to_string()).collect();
+        }
+        let blocklist_path = env::var("USERNAME_BLOCKLIST_PATH")
+            .unwrap_or_else(|_| "username_blocklist.txt".to_string());
+        let username_policy = UsernamePolicy {
+            length: username_min..=env_parse("USERNAME_MAX_LEN").unwrap_or(20).max(username_min),
+            reserved: reserved.iter().map(|name| name.to_ascii_lowercase()).collect(),
+            // One word per line; `#` starts a comment.
+            blocked: fs::read_to_string(&blocklist_path)
+                .unwrap_or_default()
+                .lines()
+                .map(|line| line.split('#').next().unwrap_or_default().trim().to_ascii_lowercase())
+                .filter(|word| !word.is_empty())
+                .collect(),
+        };
+        let terms = Terms::load(&env::var("TERMS_PATH").unwrap_or_else(|_| "terms.md".to_string()));
+        let channel_arg = env::var("CHANNEL_ARG").ok().filter(|v| !v.trim().is_empty());
+        let game_server_addr =
+            env::var("GAME_SERVER_ADDR").ok().filter(|v| !v.trim().is_empty());
+        let integrity = IntegrityConfig {
+            mode: match env::var("INTEGRITY_MODE").unwrap_or_default().trim() {
+                "warn" => IntegrityMode::Warn,
+                "block" => IntegrityMode::Block,
+                _ => IntegrityMode::Off,
+            },
+            manifest_path: env::var("INTEGRITY_MANIFEST")
+                .unwrap_or_else(|_| "manifest.json".to_string()),
+            files: env_list("INTEGRITY_FILES"),
+        };
+        let cache_dirs = env_list("CACHE_DIRS");
+        let crash_logs = env_list("CRASH_LOGS");
+        let crash_report_url =
+            env::var("CRASH_REPORT_URL").ok().filter(|v| !v.trim().is_empty());
+        let report_url = env::var("REPORT_URL").ok().filter(|v| !v.trim().is_empty());
+        let hosts_redirects = env_list("HOSTS_REDIRECTS")
+            .iter()
+            .filter_map(|entry| {
+                let redirect = Redirect::parse(entry);
+                if redirect.is_none() {
+                    tracing::warn!("config: ignoring hosts redirect {entry:?}, expected host=ip");
+                }
+                redirect
+            })
+            .collect();
+        let launch_mode = match env::var("LAUNCH_MODE").unwrap_or_default().trim() {
+            "native" => LaunchMode::Native,
+            "wine" => LaunchMode::Wine,
+            "proton" => LaunchMode::Proton,
+            _ if cfg!(windows) => LaunchMode::Native,
+            _ => LaunchMode::Wine,
+        };
+        let launch = LaunchConfig {
+            mode: launch_mode,
+            wine_binary: env::var("WINE_BINARY").unwrap_or_else(|_| match launch_mode {
+                LaunchMode::Proton => "proton".to_string(),
+                _ => "wine".to_string(),
+            }),
+            wine_prefix: env::var("WINE_PREFIX").ok().filter(|v| !v.trim().is_empty()),
+        };
+        let ipc_port = env_parse("LAUNCHER_IPC_PORT").unwrap_or(47613);
+        let updates = UpdateConfig {
+            patch_manifest_url: env::var("PATCH_MANIFEST_URL")
+                .ok()
+                .filter(|v| !v.trim().is_empty()),
+            launcher_manifest_url: env::var("LAUNCHER_MANIFEST_URL")
+                .ok()
+                .filter(|v| !v.trim().is_empty()),
+            public_key: env::var("MANIFEST_PUBLIC_KEY")
+                .ok()
+                .filter(|v| !v.trim().is_empty())
+                .map(|v| parse_public_key(&v))
+                .transpose()
+                .context("MANIFEST_PUBLIC_KEY is not a base64 Ed25519 public key")?,
+        };
+        let news_url = env::var("NEWS_URL").ok().filter(|v| !v.trim().is_empty());
+        let smtp = env::var("SMTP_HOST")
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+            .map(|host| {
+                let username = env::var("SMTP_USERNAME").unwrap_or_default();
+                SmtpConfig {
+                    host,
+                    port: env_parse("SMTP_PORT").unwrap_or(587),
+                    from: env::var("SMTP_FROM").unwrap_or_else(|_| username.clone()),
+                    username,
+                    password: env::var("SMTP_PASSWORD").unwrap_or_default(),
+                }
+            });
+        let discord_linking = env_flag("DISCORD_LINKING");
+        let multi_client = env_flag("MULTI_CLIENT");
+        let idle_timeout_mins = env_parse("IDLE_TIMEOUT_MINS");
+        let disabled_features = env_list("DISABLED_FEATURES")
+            .iter()
+            .filter_map(|name| {
+                let feature = Feature::parse(name);
+                if feature.is_none() {
+                    tracing::warn!("config: unknown feature in DISABLED_FEATURES: {name}");
+                }
+                feature
+            })
+            .collect();
+        let admin_mode = env_flag("LAUNCHER_ADMIN");
+        let dry_run = env_flag("LAUNCHER_DRY_RUN");
+
+        let (db_main_url, db_billing_url, db_char_url, db_inventory_url, db_login_url) =
+            if let Ok(base_url) = env::var("DFO_DB_BASE_URL") {
+                let base = base_url.trim_end_matches('/');
+                (
+                    format!("{base}/d_taiwan"),
+                    format!("{base}/taiwan_billing"),
+                    format!("{base}/taiwan_cain"),
+                    format!("{base}/taiwan_cain_2nd"),
+                    format!("{base}/taiwan_login"),
+                )
+            } else {
+                (
+                    env::var("DFO_DB_MAIN_URL").context("DFO_DB_MAIN_URL missing")?,
+                    env::var("DFO_DB_BILLING_URL").context("DFO_DB_BILLING_URL missing")?,
+                    env::var("DFO_DB_CHAR_URL").context("DFO_DB_CHAR_URL missing")?,
+                    env::var("DFO_DB_INVENTORY_URL").context("DFO_DB_INVENTORY_URL missing")?,
+                    env::var("DFO_DB_LOGIN_URL").context("DFO_DB_LOGIN_URL missing")?,
+                )
+            };
 
-        if let Ok(base_url) = env::var("DFO_DB_BASE_URL") {
-            let base = base_url.trim_end_matches('/');
-            return Ok(Self {
-                db_main_url: format!("{base}/d_taiwan"),
-                db_billing_url: format!("{base}/taiwan_billing"),
-                db_char_url: format!("{base}/taiwan_cain"),
-                db_inventory_url: format!("{base}/taiwan_cain_2nd"),
-                db_login_url: format!("{base}/taiwan_login"),
-                dnf_exe_path,
+        // Servers keeping EUC-KR or GBK names in latin1 columns need DB_CHARSET=latin1, so the
+        // bytes arrive unconverted, plus DB_LEGACY_ENCODING to decode them.
+        let db_charset = env::var("DB_CHARSET").unwrap_or_else(|_| "utf8mb4".to_string());
+        let legacy_encoding = match env::var("DB_LEGACY_ENCODING") {
+            Ok(label) => Some(
+                encoding_rs::Encoding::for_label(label.trim().as_bytes())
+                    .with_context(|| format!("unknown DB_LEGACY_ENCODING: {label}"))?,
+            ),
+            Err(_) => None,
+        };
+        let server_timezone = match env::var("SERVER_TIMEZONE") {
+            Ok(name) => Some(
+                name.trim()
+                    .parse::<chrono_tz::Tz>()
+                    .map_err(|_| anyhow::anyhow!("unknown SERVER_TIMEZONE: {name}"))?,
+            ),
+            Err(_) => None,
+        };
+
+        let registry_path = env::var("CLIENT_REGISTRY_PATH")
+            .unwrap_or_else(|_| "client_registry.json".to_string());
+        let client_registry = read_json::<ClientRegistry>(&registry_path);
+
+        let rewards_path =
+            env::var("DAILY_REWARDS_PATH").unwrap_or_else(|_| "daily_rewards.json".to_string());
+        let daily_rewards = read_json::<Vec<DailyReward>>(&rewards_path).unwrap_or_default();
+
+        let currencies_path =
+            env::var("CURRENCIES_PATH").unwrap_or_else(|_| "currencies.json".to_string());
+        let currencies = read_json::<Vec<CurrencyDef>>(&currencies_path).unwrap_or_default();
+
+        let realms_path = env::var("REALMS_PATH").unwrap_or_else(|_| "realms.json".to_string());
+        let realms = read_json::<Vec<Realm>>(&realms_path)
+            .filter(|realms| !realms.is_empty())
+            .unwrap_or_else(|| {
+                vec![Realm {
+                    name: "Main".to_string(),
+                    db_char_url,
+                    db_inventory_url,
+                    launch_args: Vec::new(),
+                    token_tail: None,
+                    game_server_addr: game_server_addr.clone(),
+                }]
             });
-        }
 
         Ok(Self {
-            db_main_url: env::var("DFO_DB_MAIN_URL").context("DFO_DB_MAIN_URL missing")?,
-            db_billing_url: env::var("DFO_DB_BILLING_URL").context("DFO_DB_BILLING_URL missing")?,
-            db_char_url: env::var("DFO_DB_CHAR_URL").context("DFO_DB_CHAR_URL missing")?,
-            db_inventory_url: env::var("DFO_DB_INVENTORY_URL")
-                .context("DFO_DB_INVENTORY_URL missing")?,
-            db_login_url: env::var("DFO_DB_LOGIN_URL").context("DFO_DB_LOGIN_URL missing")?,
+            db_main_url,
+            db_billing_url,
+            db_login_url,
+            db_charset,
+            legacy_encoding,
+            server_timezone,
             dnf_exe_path,
+            client_version_file,
+            required_game_files,
+            item_db_path,
+            exp_table_path,
+            max_level,
+            slot_price_cera,
+            amount_range: amount_min..=amount_max,
+            send_limits,
+            username_policy,
+            terms,
+            channel_arg,
+            game_server_addr,
+            realms,
+            integrity,
+            cache_dirs,
+            crash_logs,
+            hosts_redirects,
+            crash_report_url,
+            report_url,
+            launch,
+            client_registry,
+            ipc_port,
+            updates,
+            news_url,
+            daily_rewards,
+            currencies,
+            smtp,
+            discord_linking,
+            multi_client,
+            idle_timeout_mins,
+            disabled_features,
+            admin_mode,
+            dry_run,
         })
     }
+
+    /// Applies the per-user settings that take precedence over the environment.
+    pub fn apply_overrides(&mut self, user: &UserConfig) {
+        if let Some(path) = &user.game_path {
+            self.dnf_exe_path = path.clone();
+        }
+        if let Some(addr) = &user.server_addr {
+            self.game_server_addr = Some(addr.clone());
+        }
+    }
+
+    pub fn enabled(&self, feature: Feature) -> bool {
+        !self.disabled_features.contains(&feature)
+    }
+
+    /// Looks up a realm by name, falling back to the first configured one.
+    pub fn realm(&self, name: Option<&str>) -> &Realm {
+        name.and_then(|name| self.realms.iter().find(|r| r.name == name))
+            .unwrap_or(&self.realms[0])
+    }
+}
+
+fn parse_public_key(encoded: &str) -> Result<ed25519_dalek::VerifyingKey> {
+    use base64::Engine;
+
+    let raw = base64::engine::general_purpose::STANDARD.decode(encoded.trim())?;
+    let bytes: [u8; 32] = raw.try_into().map_err(|_| anyhow::anyhow!("expected 32 bytes"))?;
+    Ok(ed25519_dalek::VerifyingKey::from_bytes(&bytes)?)
+}
+
+fn env_parse<T: std::str::FromStr>(key: &str) -> Option<T> {
+    env::var(key).ok().and_then(|v| v.trim().parse().ok())
+}
+
+fn env_list(key: &str) -> Vec<String> {
+    env::var(key)
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn env_flag(key: &str) -> bool {
+    env::var(key)
+        .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
 }
 
 pub fn read_json<T: for<'de> Deserialize<'de>>(path: impl AsRef<Path>) -> Option<T> {
     fs::read_to_string(path).ok().and_then(|s| serde_json::from_str(&s).ok())
 }
 
+/// Fails when `path` exists but isn't valid JSON for `T`; [`read_json`] would silently fall
+/// back to defaults for it. A missing file passes.
+pub fn check_json<T: for<'de> Deserialize<'de>>(path: impl AsRef<Path>) -> Result<()> {
+    let path = path.as_ref();
+    let data = match fs::read_to_string(path) {
+        Ok(data) => data,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err).with_context(|| format!("read {}", path.display())),
+    };
+    serde_json::from_str::<T>(&data).with_context(|| format!("parse {}", path.display()))?;
+    Ok(())
+}
+
 pub fn write_json<T: Serialize>(path: impl AsRef<Path>, value: &T) -> Result<()> {
     let data = serde_json::to_string(value)?;
     fs::write(path, data)?;
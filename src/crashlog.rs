@@ -0,0 +1,131 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use anyhow::{Context, Result};
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use serde::Serialize;
+
+/// Only the tail of each log is kept; the lines before a crash are the useful ones.
+const MAX_LOG_BYTES: usize = 256 * 1024;
+
+/// Client logs and system error events gathered after the game exited abnormally.
+#[derive(Clone, Debug, Serialize)]
+pub struct CrashReport {
+    pub created_at: String,
+    pub launcher_version: String,
+    /// Exit status as the OS reported it, e.g. `exit code: 0xc0000005`.
+    pub exit: String,
+    pub logs: Vec<LogFile>,
+    /// Application errors from the Windows Event Log around the crash.
+    pub events: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct LogFile {
+    pub path: String,
+    pub contents: String,
+}
+
+impl CrashReport {
+    /// Gzipped JSON, as saved to disk and uploaded.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        serde_json::to_writer(&mut encoder, self)?;
+        encoder.flush()?;
+        Ok(encoder.finish()?)
+    }
+}
+
+/// Collects the configured log files and directories (relative entries are taken from the
+/// game directory) written since the client started.
+pub fn collect(
+    game_dir: &Path,
+    entries: &[String],
+    since: SystemTime,
+    exit: String,
+) -> CrashReport {
+    let mut logs = Vec::new();
+    for entry in entries {
+        let path = Path::new(entry);
+        let path = if path.is_absolute() { path.to_path_buf() } else { game_dir.join(path) };
+        for file in log_files(&path) {
+            let modified = fs::metadata(&file).and_then(|meta| meta.modified());
+            if modified.is_ok_and(|modified| modified < since) {
+                continue;
+            }
+            match read_tail(&file) {
+                Ok(contents) => logs.push(LogFile {
+                    path: file.display().to_string(),
+                    contents,
+                }),
+                Err(err) => tracing::warn!("crash log: {}: {err:#}", file.display()),
+            }
+        }
+    }
+    CrashReport {
+        created_at: chrono::Local::now().to_rfc3339(),
+        launcher_version: env!("CARGO_PKG_VERSION").to_string(),
+        exit,
+        logs,
+        events: system_events(since),
+    }
+}
+
+fn log_files(path: &Path) -> Vec<PathBuf> {
+    if !path.is_dir() {
+        return if path.is_file() { vec![path.to_path_buf()] } else { Vec::new() };
+    }
+    let Ok(entries) = fs::read_dir(path) else {
+        return Vec::new();
+    };
+    entries.flatten().map(|entry| entry.path()).filter(|path| path.is_file()).collect()
+}
+
+fn read_tail(path: &Path) -> Result<String> {
+    let bytes = fs::read(path).context("read log")?;
+    let start = bytes.len().saturating_sub(MAX_LOG_BYTES);
+    // Client logs are often in the system code page; keep what decodes.
+    Ok(String::from_utf8_lossy(&bytes[start..]).into_owned())
+}
+
+/// Error and critical Application events since `since`, via `wevtutil`.
+#[cfg(windows)]
+fn system_events(since: SystemTime) -> Vec<String> {
+    use std::os::windows::process::CommandExt;
+
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    let window_ms = since.elapsed().map_or(0, |elapsed| elapsed.as_millis()) + 60_000;
+    let query = format!(
+        "*[System[(Level=1 or Level=2) and TimeCreated[timediff(@SystemTime) <= {window_ms}]]]"
+    );
+    let output = std::process::Command::new("wevtutil")
+        .args(["qe", "Application", "/rd:true", "/c:20", "/f:text"])
+        .arg(format!("/q:{query}"))
+        .creation_flags(CREATE_NO_WINDOW)
+        .output();
+    match output {
+        Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout)
+            .split("Event[")
+            .map(str::trim)
+            .filter(|event| !event.is_empty())
+            .map(|event| format!("Event[{event}"))
+            .collect(),
+        Ok(output) => {
+            tracing::warn!("crash log: wevtutil exited with {}", output.status);
+            Vec::new()
+        }
+        Err(err) => {
+            tracing::warn!("crash log: wevtutil failed: {err}");
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(not(windows))]
+fn system_events(_since: SystemTime) -> Vec<String> {
+    Vec::new()
+}
@@ -1,21 +1,246 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
 use anyhow::{Context, Result, bail};
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use chrono::{Datelike, NaiveDateTime};
+use encoding_rs::Encoding;
 use rsa::traits::{PrivateKeyParts, PublicKeyParts};
 use rsa::{pkcs8::DecodePrivateKey, BigUint, RsaPrivateKey};
-use sqlx::{Connection, MySqlConnection, Row};
+use sqlx::mysql::{MySqlConnectOptions, MySqlDatabaseError, MySqlRow};
+use sqlx::{Connection, MySql, MySqlConnection, Row, Transaction};
+use tokio::sync::OnceCell;
+
+use crate::backup::{self, AccountBackup, Owner};
+use crate::config::{
+    AppConfig, CurrencyDb, CurrencyDef, DailyReward, Feature, Realm, SendLimits, UsernamePolicy,
+};
+use crate::equipment::{self, Equipment};
+use crate::exp::ExpTable;
+use crate::patcher;
+use crate::recovery;
+
+/// Village and area index of Seria's room, the safe spawn point.
+const SERIA_ROOM: (i32, i32) = (0, 0);
+
+/// Tables holding per-character quest state: in-progress quests and cleared quests.
+const QUEST_TABLES: [&str; 2] = ["charac_quest", "charac_quest_clear"];
+
+/// Character slots an account gets when `limit_create_character` has no row.
+const DEFAULT_CHAR_SLOTS: i32 = 4;
+pub const MAX_CHAR_SLOTS: i32 = 24;
+
+/// Allowed length of the secondary (storage) PIN, digits only.
+pub const PIN_LENGTH: std::ops::RangeInclusive<usize> = 4..=8;
+
+/// Upper bounds for expansion grants, matching the client's UI limits.
+const MAX_INVENTORY_SLOTS: i32 = 128;
+const MAX_STASH_TABS: i32 = 8;
+
+/// Number of slots in a character's creature (pet) inventory.
+const CREATURE_SLOTS: i32 = 14;
+
+const AUDIT_TABLE: &str = "CREATE TABLE IF NOT EXISTS `launcher_audit` ( \
+    `id` BIGINT NOT NULL AUTO_INCREMENT PRIMARY KEY, \
+    `created_at` DATETIME NOT NULL, \
+    `actor` VARCHAR(64) NOT NULL, \
+    `action` VARCHAR(32) NOT NULL, \
+    `account_uid` INT NULL, \
+    `charac_no` INT NULL, \
+    `item_id` INT NULL, \
+    `amount` BIGINT NOT NULL DEFAULT 0, \
+    `detail` TEXT NOT NULL)";
+
+/// Links a reversal to the entry it undoes; the unique index allows one reversal per entry.
+/// Also applied lazily by [`Db::ensure_audit_table`] on installs that never ran migrations.
+const AUDIT_REVERSES: &str = "ALTER TABLE `launcher_audit` ADD COLUMN `reverses` BIGINT NULL, \
+    ADD UNIQUE INDEX `idx_reverses` (`reverses`)";
+
+const DAILY_REWARD_TABLE: &str = "CREATE TABLE IF NOT EXISTS `launcher_daily_reward` ( \
+    `account_uid` INT NOT NULL PRIMARY KEY, \
+    `streak` INT NOT NULL DEFAULT 0, \
+    `last_claim` DATE NOT NULL)";
+
+/// Link codes issued by the Discord bot. The bot inserts `code`, `discord_id` and `created_at`;
+/// redeeming a code in the launcher fills in `account_uid` and `linked_at`.
+const DISCORD_LINK_TABLE: &str = "CREATE TABLE IF NOT EXISTS `launcher_discord_link` ( \
+    `code` VARCHAR(16) NOT NULL PRIMARY KEY, \
+    `discord_id` VARCHAR(32) NOT NULL, \
+    `created_at` DATETIME NOT NULL, \
+    `account_uid` INT NULL, \
+    `linked_at` DATETIME NULL, \
+    INDEX `idx_account` (`account_uid`))";
+
+/// Minutes a Discord link code stays redeemable.
+const DISCORD_CODE_TTL_MINUTES: i32 = 15;
+
+const EMAIL_TABLE: &str = "CREATE TABLE IF NOT EXISTS `launcher_email` ( \
+    `account_uid` INT NOT NULL PRIMARY KEY, \
+    `email` VARCHAR(254) NOT NULL, \
+    `bound_at` DATETIME NOT NULL)";
+
+/// Pending verification codes, at most one per account and purpose.
+const EMAIL_CODE_TABLE: &str = "CREATE TABLE IF NOT EXISTS `launcher_email_code` ( \
+    `account_uid` INT NOT NULL, \
+    `purpose` VARCHAR(16) NOT NULL, \
+    `email` VARCHAR(254) NOT NULL, \
+    `code` CHAR(6) NOT NULL, \
+    `expires_at` DATETIME NOT NULL, \
+    `attempts` INT NOT NULL DEFAULT 0, \
+    PRIMARY KEY (`account_uid`, `purpose`))";
+
+/// Minutes an emailed code stays valid, and wrong guesses allowed before it is burned.
+const EMAIL_CODE_TTL_MINUTES: i32 = 15;
+const MAX_CODE_ATTEMPTS: i32 = 5;
 
-use crate::config::AppConfig;
+/// Wrong guesses per account for codes that have no pending row to count them on, such as
+/// Discord link and recovery codes. A streak older than `LOCKOUT_MINUTES` starts over.
+const ATTEMPT_TABLE: &str = "CREATE TABLE IF NOT EXISTS `launcher_attempt` ( \
+    `account_uid` INT NOT NULL, \
+    `purpose` VARCHAR(16) NOT NULL, \
+    `attempts` INT NOT NULL DEFAULT 0, \
+    `last_at` DATETIME NOT NULL, \
+    PRIMARY KEY (`account_uid`, `purpose`))";
+
+/// Minutes an account stays locked out after `MAX_CODE_ATTEMPTS` wrong codes in a row.
+const LOCKOUT_MINUTES: i32 = 15;
+
+/// `launcher_attempt.purpose` for Discord link and recovery codes.
+const DISCORD_ATTEMPTS: &str = "discord";
+const RECOVERY_ATTEMPTS: &str = "recovery";
+
+/// Single-use login recovery codes; only salted SHA-256 hashes are kept (see migration 14).
+const RECOVERY_CODE_TABLE: &str = "CREATE TABLE IF NOT EXISTS `launcher_recovery_code` ( \
+    `id` BIGINT NOT NULL AUTO_INCREMENT PRIMARY KEY, \
+    `account_uid` INT NOT NULL, \
+    `code_hash` CHAR(64) NOT NULL, \
+    `created_at` DATETIME NOT NULL, \
+    `used_at` DATETIME NULL, \
+    INDEX `idx_account` (`account_uid`))";
+
+/// Per-code salt; also applied lazily by [`Db::ensure_recovery_table`].
+const RECOVERY_CODE_SALT: &str =
+    "ALTER TABLE `launcher_recovery_code` ADD COLUMN `salt` CHAR(32) NOT NULL DEFAULT ''";
+
+/// Free-text support notes, one per account, edited from the admin panel.
+const ACCOUNT_NOTE_TABLE: &str = "CREATE TABLE IF NOT EXISTS `launcher_account_note` ( \
+    `account_uid` INT NOT NULL PRIMARY KEY, \
+    `note` TEXT NOT NULL, \
+    `updated_by` VARCHAR(64) NOT NULL, \
+    `updated_at` DATETIME NOT NULL)";
+
+const TERMS_ACCEPTANCE_TABLE: &str = "CREATE TABLE IF NOT EXISTS `launcher_terms_acceptance` ( \
+    `account_uid` INT NOT NULL, \
+    `version` VARCHAR(64) NOT NULL, \
+    `accepted_at` DATETIME NOT NULL, \
+    PRIMARY KEY (`account_uid`, `version`))";
+
+/// Operator switches as name/value rows, e.g. `min_launcher_version`.
+const LAUNCHER_SETTINGS_TABLE: &str = "CREATE TABLE IF NOT EXISTS `launcher_settings` ( \
+    `name` VARCHAR(64) NOT NULL PRIMARY KEY, \
+    `value` VARCHAR(255) NOT NULL)";
+
+/// Cera and total gold of an account, recorded at sign-in and refresh when they change.
+const BALANCE_SNAPSHOT_TABLE: &str = "CREATE TABLE IF NOT EXISTS `launcher_balance_snapshot` ( \
+    `id` BIGINT NOT NULL AUTO_INCREMENT PRIMARY KEY, \
+    `account_uid` INT NOT NULL, \
+    `taken_at` DATETIME NOT NULL, \
+    `cera` BIGINT NOT NULL, \
+    `gold` BIGINT NOT NULL, \
+    INDEX `idx_account` (`account_uid`, `id`))";
+
+/// Most snapshots the balance chart shows.
+const BALANCE_HISTORY_LIMIT: i64 = 500;
+
+/// Schema changes for the launcher's own tables in the main DB, applied in order by
+/// [`Db::ensure_launcher_schema`]. Append new entries; never edit or reorder shipped ones.
+const MIGRATIONS: &[(i32, &str, &str)] = &[
+    (1, "audit log", AUDIT_TABLE),
+    (2, "daily rewards", DAILY_REWARD_TABLE),
+    (
+        3,
+        "audit log indexes",
+        "ALTER TABLE `launcher_audit` ADD INDEX `idx_charac_action` (`charac_no`, `action`)",
+    ),
+    (4, "discord links", DISCORD_LINK_TABLE),
+    (5, "email binding", EMAIL_TABLE),
+    (6, "email codes", EMAIL_CODE_TABLE),
+    (7, "recovery codes", RECOVERY_CODE_TABLE),
+    (8, "account notes", ACCOUNT_NOTE_TABLE),
+    (9, "terms acceptance", TERMS_ACCEPTANCE_TABLE),
+    (10, "launcher settings", LAUNCHER_SETTINGS_TABLE),
+    (11, "balance snapshots", BALANCE_SNAPSHOT_TABLE),
+    (12, "audit reversals", AUDIT_REVERSES),
+    (13, "code attempts", ATTEMPT_TABLE),
+    (14, "recovery code salts", RECOVERY_CODE_SALT),
+];
 
 pub struct Db {
     main_url: String,
     billing_url: String,
-    chara_url: String,
-    inventory_url: String,
+    realm: RwLock<RealmUrls>,
     login_url: String,
+    /// Applied to the character and inventory connections.
+    charset: String,
+    legacy_encoding: Option<&'static Encoding>,
     private_key: RsaPrivateKey,
+    exp_table: ExpTable,
+    max_level: i32,
+    audit_table: OnceCell<()>,
+    daily_table: OnceCell<()>,
+    discord_table: OnceCell<()>,
+    email_tables: OnceCell<()>,
+    recovery_table: OnceCell<()>,
+    attempt_table: OnceCell<()>,
+    note_table: OnceCell<()>,
+    terms_table: OnceCell<()>,
+    balance_table: OnceCell<()>,
+    discord_linking: bool,
+    email_binding: bool,
+    send_limits: SendLimits,
+    /// Configured currencies, to roll back their sends by name.
+    currencies: Vec<CurrencyDef>,
+    currency_sends: bool,
+    username_policy: UsernamePolicy,
+    dry_run: bool,
+}
+
+/// Connection targets that change with the selected realm.
+#[derive(Clone)]
+struct RealmUrls {
+    chara_url: String,
+    inventory_url: String,
+    token_tail: Option<String>,
 }
 
-#[derive(Clone, Copy)]
+impl From<&Realm> for RealmUrls {
+    fn from(realm: &Realm) -> Self {
+        Self {
+            chara_url: realm.db_char_url.clone(),
+            inventory_url: realm.db_inventory_url.clone(),
+            token_tail: realm.token_tail.clone(),
+        }
+    }
+}
+
+impl RealmUrls {
+    /// Schema name of the inventory DB, used for cross-database joins.
+    fn inventory_schema(&self) -> &str {
+        schema_name(&self.inventory_url, "taiwan_cain_2nd")
+    }
+}
+
+/// Database name at the end of a MySQL URL, or `default` when the URL has none.
+fn schema_name<'a>(url: &'a str, default: &'a str) -> &'a str {
+    let path = url.split('?').next().unwrap_or_default();
+    match path.rsplit('/').next() {
+        Some(name) if !name.is_empty() => name,
+        _ => default,
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum DbPool {
     Main,
     Billing,
@@ -24,28 +249,530 @@ pub enum DbPool {
     Login,
 }
 
+impl DbPool {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Main => "main",
+            Self::Billing => "billing",
+            Self::Chara => "character",
+            Self::Inventory => "inventory",
+            Self::Login => "login",
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Character {
     pub id: i32,
     pub name: String,
     pub level: i32,
     pub job: JobName,
-    pub money: i64,
+    /// `None` while the inventory database is unreachable.
+    pub money: Option<i64>,
+    pub guild: Option<GuildMembership>,
+    pub pvp: Option<PvpRecord>,
+}
+
+/// Duel record from `pvp_result`; characters that never fought have no row.
+#[derive(Clone, Copy, Debug)]
+pub struct PvpRecord {
+    pub grade: i32,
+    pub points: i32,
+    pub wins: i32,
+    pub losses: i32,
+}
+
+/// Lightweight character row used by admin lookups.
+#[derive(Clone, Debug)]
+pub struct CharacterInfo {
+    pub id: i32,
+    pub uid: i32,
+    pub name: String,
+    pub level: i32,
+    pub job: JobName,
+    pub grow_type: i32,
+}
+
+impl CharacterInfo {
+    /// `grow_type` packs the advancement in the low nibble and the awakening stage above it.
+    pub fn advancement(&self) -> i32 {
+        self.grow_type & 0x0F
+    }
+
+    pub fn awakening(&self) -> i32 {
+        self.grow_type >> 4
+    }
+}
+
+pub const AWAKENINGS: [&str; 3] = ["None", "Awakening", "Second Awakening"];
+
+#[derive(Clone, Debug)]
+pub struct GuildMembership {
+    pub guild_id: i32,
+    pub guild_name: String,
+    pub rank: GuildRank,
+}
+
+#[derive(Clone, Debug)]
+pub struct GuildMember {
+    pub name: String,
+    pub level: i32,
+    pub job: JobName,
+    pub rank: GuildRank,
+}
+
+/// An account's balances at one point in time, for the History tab.
+#[derive(Clone, Debug)]
+pub struct BalanceSnapshot {
+    pub taken_at: NaiveDateTime,
+    pub cera: i64,
+    /// Sum over the account's characters.
+    pub gold: i64,
 }
 
 pub struct LoginSession {
     pub uid: i32,
     pub token: String,
     pub characters: Vec<Character>,
-    pub cera: i64,
+    /// `None` while the billing database is unreachable; the rest of the session still loads.
+    pub cera: Option<i64>,
+    pub slot_limit: i32,
+    pub channels: Vec<Channel>,
+    pub daily: DailyStatus,
+    /// Linked Discord user ID; always `None` while Discord linking is disabled.
+    pub discord_id: Option<String>,
+    /// Verified email; always `None` while no SMTP relay is configured.
+    pub email: Option<String>,
+    /// Whether a secondary (storage) PIN is set in `member_info`.
+    pub has_pin: bool,
+    /// Send limits left today; unlimited while no limits are configured.
+    pub quotas: SendQuotas,
+    /// Set while the game server has the account signed in.
+    pub online: Option<OnlineStatus>,
+}
+
+/// An account's game-server sign-in, from `login_account_3`.
+#[derive(Clone, Debug)]
+pub struct OnlineStatus {
+    /// Channel number, on schemas that record it.
+    pub channel: Option<i32>,
+}
+
+/// Currencies players can send themselves from the launcher.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Currency {
+    Gold,
+    Cera,
+}
+
+impl Currency {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Gold => "gold",
+            Self::Cera => "cera",
+        }
+    }
+
+    fn send_kind(self, limits: &SendLimits) -> SendKind<'static> {
+        let (action, daily_cap) = match self {
+            Self::Gold => (AuditAction::GoldSend, limits.gold_daily),
+            Self::Cera => (AuditAction::CeraSend, limits.cera_daily),
+        };
+        SendKind {
+            action,
+            detail: "",
+            name: self.as_str(),
+            daily_cap,
+        }
+    }
+}
+
+/// The audit rows a send is counted from, and its daily cap.
+struct SendKind<'a> {
+    action: AuditAction,
+    /// `detail` of the send rows; configured currencies record their name there.
+    detail: &'a str,
+    /// Currency name, which undo rows record in `detail`.
+    name: &'a str,
+    daily_cap: Option<i64>,
+}
+
+impl<'a> SendKind<'a> {
+    fn currency(currency: &'a CurrencyDef) -> Self {
+        Self {
+            action: AuditAction::CurrencySend,
+            detail: &currency.name,
+            name: &currency.name,
+            daily_cap: currency.daily_cap,
+        }
+    }
+}
+
+/// One currency's daily cap usage and per-send cooldown, counted from the audit log.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SendQuota {
+    /// Sent since midnight (database time), minus undone sends.
+    pub sent_today: i64,
+    pub daily_cap: Option<i64>,
+    /// When the cooldown after the last send runs out.
+    pub ready_at: Option<Instant>,
+}
+
+impl SendQuota {
+    pub fn remaining(&self) -> Option<i64> {
+        self.daily_cap.map(|cap| (cap - self.sent_today).max(0))
+    }
+
+    pub fn cooldown_left(&self) -> Duration {
+        self.ready_at.map_or(Duration::ZERO, |at| at.saturating_duration_since(Instant::now()))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SendQuotas {
+    pub gold: SendQuota,
+    pub cera: SendQuota,
+}
+
+/// What an emailed verification code unlocks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EmailPurpose {
+    Bind,
+    PasswordReset,
+}
+
+impl EmailPurpose {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Bind => "bind",
+            Self::PasswordReset => "password_reset",
+        }
+    }
+}
+
+/// Login streak from `launcher_daily_reward`; a missed day resets `streak` to 0.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DailyStatus {
+    pub streak: i32,
+    pub claimed_today: bool,
 }
 
 #[derive(Clone, Debug)]
+pub struct Channel {
+    pub number: i32,
+    pub name: String,
+    pub population: i32,
+    pub capacity: i32,
+}
+
+impl Channel {
+    pub fn load(&self) -> f32 {
+        if self.capacity <= 0 {
+            return 0.0;
+        }
+        (self.population as f32 / self.capacity as f32).clamp(0.0, 1.0)
+    }
+}
+
+/// Outcome of [`Db::restore_account`]: databases restored in order, and where it stopped.
+pub struct RestoreReport {
+    pub rows: usize,
+    pub restored: Vec<DbPool>,
+    pub failed: Option<(DbPool, anyhow::Error)>,
+}
+
+#[derive(Clone, Debug, Default)]
 pub struct Credentials {
     pub username: String,
     pub password: String,
 }
 
+#[derive(Clone, Debug)]
+pub struct Banned {
+    pub reason: String,
+    pub expires: Option<NaiveDateTime>,
+}
+
+impl std::fmt::Display for Banned {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.expires {
+            Some(expires) => write!(f, "Account banned until {}", expires.format("%Y-%m-%d %H:%M"))?,
+            None => f.write_str("Account permanently banned")?,
+        }
+        if !self.reason.is_empty() {
+            write!(f, ": {}", self.reason)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Banned {}
+
+/// Sign-in refused because the server requires a newer launcher than this build.
+#[derive(Clone, Debug)]
+pub struct UpdateRequired {
+    pub min_version: String,
+}
+
+impl std::fmt::Display for UpdateRequired {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Launcher {} or newer is required (this is {})",
+            self.min_version,
+            env!("CARGO_PKG_VERSION")
+        )
+    }
+}
+
+impl std::error::Error for UpdateRequired {}
+
+/// Context on a failed connection naming the database, so the watchdog probes the one that's
+/// actually down.
+#[derive(Clone, Copy, Debug)]
+pub struct Unreachable(pub DbPool);
+
+impl std::fmt::Display for Unreachable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Cannot connect to the {} database", self.0.label())
+    }
+}
+
+/// The database a connection error came from; the main one when the error doesn't say.
+pub fn unreachable_pool(err: &anyhow::Error) -> DbPool {
+    err.downcast_ref::<Unreachable>().map_or(DbPool::Main, |unreachable| unreachable.0)
+}
+
+/// Plain-language explanation with a suggested fix for common database failures, or `None`
+/// for errors that are shown as they are.
+pub fn friendly_error(err: &anyhow::Error) -> Option<&'static str> {
+    err.chain().find_map(|cause| match cause.downcast_ref::<sqlx::Error>()? {
+        sqlx::Error::Database(err) => {
+            match err.try_downcast_ref::<MySqlDatabaseError>()?.number() {
+                1044 | 1045 => Some(
+                    "The database refused the launcher's login. Ask the server admin to check \
+                     the user and password in the DFO_DB_* settings.",
+                ),
+                1049 => Some(
+                    "A game database doesn't exist on the server. Check the database names in \
+                     the DFO_DB_* settings.",
+                ),
+                1040 | 1203 => Some(
+                    "The database server has too many connections right now. Wait a minute and \
+                     try again.",
+                ),
+                1146 => Some(
+                    "A database table is missing. The server may run a game build this \
+                     launcher doesn't support.",
+                ),
+                _ => None,
+            }
+        }
+        sqlx::Error::PoolTimedOut => Some(TIMEOUT_HINT),
+        sqlx::Error::Io(err) => match err.kind() {
+            std::io::ErrorKind::TimedOut => Some(TIMEOUT_HINT),
+            std::io::ErrorKind::ConnectionRefused => Some(
+                "Nothing answered on the database port. Make sure the server is running and \
+                 the address is right.",
+            ),
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+const TIMEOUT_HINT: &str =
+    "The database server didn't answer in time. Check your internet connection and try again.";
+
+/// Whether `err` means a database could not be reached, as opposed to a query failing.
+pub fn is_connection_error(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        matches!(
+            cause.downcast_ref::<sqlx::Error>(),
+            Some(
+                sqlx::Error::Io(_)
+                    | sqlx::Error::Tls(_)
+                    | sqlx::Error::PoolTimedOut
+                    | sqlx::Error::PoolClosed
+                    | sqlx::Error::WorkerCrashed
+            )
+        )
+    })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuditAction {
+    CashItemGrant,
+    CreatureGrant,
+    SetLevel,
+    SetJobGrowth,
+    QuestReset,
+    InventoryExpand,
+    StashExpand,
+    SlotLimit,
+    SlotPurchase,
+    DailyReward,
+    CeraTransfer,
+    GoldSend,
+    CeraSend,
+    SendUndo,
+    Rollback,
+    AccountMerge,
+    CharacterTransfer,
+    AccountBackup,
+    AccountRestore,
+    PasswordReset,
+    CurrencySend,
+    AccountNote,
+    ForceLogout,
+}
+
+impl AuditAction {
+    const ALL: [Self; 23] = [
+        Self::CashItemGrant,
+        Self::CreatureGrant,
+        Self::SetLevel,
+        Self::SetJobGrowth,
+        Self::QuestReset,
+        Self::InventoryExpand,
+        Self::StashExpand,
+        Self::SlotLimit,
+        Self::SlotPurchase,
+        Self::DailyReward,
+        Self::CeraTransfer,
+        Self::GoldSend,
+        Self::CeraSend,
+        Self::SendUndo,
+        Self::Rollback,
+        Self::AccountMerge,
+        Self::CharacterTransfer,
+        Self::AccountBackup,
+        Self::AccountRestore,
+        Self::PasswordReset,
+        Self::CurrencySend,
+        Self::AccountNote,
+        Self::ForceLogout,
+    ];
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.as_str() == value)
+    }
+
+    /// Whether [`Db::rollback_audit`] knows the inverse of this action.
+    pub fn is_reversible(self) -> bool {
+        matches!(
+            self,
+            Self::CashItemGrant
+                | Self::CreatureGrant
+                | Self::GoldSend
+                | Self::CeraSend
+                | Self::CurrencySend
+        )
+    }
+
+    /// Whether the action moved cera or gold, for the player's billing history export.
+    pub fn is_billing(self) -> bool {
+        matches!(
+            self,
+            Self::SlotPurchase
+                | Self::DailyReward
+                | Self::CeraTransfer
+                | Self::GoldSend
+                | Self::CeraSend
+                | Self::SendUndo
+        )
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::CashItemGrant => "cash_item_grant",
+            Self::CreatureGrant => "creature_grant",
+            Self::SetLevel => "set_level",
+            Self::SetJobGrowth => "set_job_growth",
+            Self::QuestReset => "quest_reset",
+            Self::InventoryExpand => "inventory_expand",
+            Self::StashExpand => "stash_expand",
+            Self::SlotLimit => "slot_limit",
+            Self::SlotPurchase => "slot_purchase",
+            Self::DailyReward => "daily_reward",
+            Self::CeraTransfer => "cera_transfer",
+            Self::GoldSend => "gold_send",
+            Self::CeraSend => "cera_send",
+            Self::SendUndo => "send_undo",
+            Self::Rollback => "rollback",
+            Self::AccountMerge => "account_merge",
+            Self::CharacterTransfer => "character_transfer",
+            Self::AccountBackup => "account_backup",
+            Self::AccountRestore => "account_restore",
+            Self::PasswordReset => "password_reset",
+            Self::CurrencySend => "currency_send",
+            Self::AccountNote => "account_note",
+            Self::ForceLogout => "force_logout",
+        }
+    }
+}
+
+/// One row of the launcher's own audit log (`launcher_audit` in the main DB).
+#[derive(Clone, Debug)]
+pub struct AuditEntry {
+    pub actor: String,
+    pub action: AuditAction,
+    pub account_uid: Option<i32>,
+    pub char_id: Option<i32>,
+    pub item_id: Option<i32>,
+    pub amount: i64,
+    pub detail: String,
+}
+
+/// A stored audit row, as listed in the admin audit viewer.
+#[derive(Clone, Debug)]
+pub struct AuditRecord {
+    pub id: i64,
+    pub created_at: Option<NaiveDateTime>,
+    pub actor: String,
+    /// `None` for actions written by a newer launcher version.
+    pub action: Option<AuditAction>,
+    pub account_uid: Option<i32>,
+    pub char_id: Option<i32>,
+    pub item_id: Option<i32>,
+    pub amount: i64,
+    pub detail: String,
+}
+
+/// Support note attached to an account.
+#[derive(Clone, Debug)]
+pub struct AccountNote {
+    pub text: String,
+    pub updated_by: String,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+/// What [`Db::merge_accounts`] moved to the target account.
+#[derive(Clone, Copy, Debug)]
+pub struct MergeReport {
+    pub characters: i64,
+    pub cera: i64,
+    pub cash_items: u64,
+    /// `false` when the target already had a stash; the source stash is then left in place.
+    pub stash_moved: bool,
+}
+
+/// New password set by [`Db::reset_passwords`]; shown to the admin once and never stored.
+#[derive(Clone, Debug)]
+pub struct ResetPassword {
+    pub uid: i32,
+    pub username: String,
+    pub password: String,
+}
+
+/// A gold send (or its undo) read back from the audit log.
+#[derive(Clone, Debug)]
+pub struct SendRecord {
+    pub amount: i64,
+    pub at: Option<NaiveDateTime>,
+    pub undo: bool,
+}
+
 #[derive(Clone, Copy, Debug)]
 pub enum JobName {
     MaleSlayer,
@@ -96,113 +823,2618 @@ impl JobName {
             Self::Unknown => "Unknown",
         }
     }
+
+    /// First-advancement classes, in `grow_type` order (index 0 is growth 1).
+    pub fn advancements(self) -> &'static [&'static str] {
+        match self {
+            Self::MaleSlayer => &["Weapon Master", "Soul Bender", "Berserker", "Asura"],
+            Self::FemaleFighter | Self::MaleFighter => {
+                &["Nen Master", "Striker", "Brawler", "Grappler"]
+            }
+            Self::MaleGunner | Self::FemaleGunner => {
+                &["Ranger", "Launcher", "Mechanic", "Spitfire"]
+            }
+            Self::FemaleMage => &["Elementalist", "Summoner", "Battle Mage", "Witch", "Enchantress"],
+            Self::MalePriest => &["Crusader", "Monk", "Exorcist", "Avenger"],
+            Self::Thief => &["Rogue", "Necromancer", "Kunoichi", "Shadow Dancer"],
+            Self::MaleMage => &[
+                "Elemental Bomber",
+                "Glacial Master",
+                "Blood Mage",
+                "Swift Master",
+                "Dimension Walker",
+            ],
+            Self::FemalePriest => &["Crusader", "Inquisitor", "Shaman", "Mistress"],
+            Self::FemaleSlayer => {
+                &["Sword Master", "Dark Templar", "Demon Slayer", "Vagabond", "Spectre"]
+            }
+            Self::Unknown => &[],
+        }
+    }
 }
 
-impl std::fmt::Display for JobName {
+#[derive(Clone, Debug)]
+pub struct Mail {
+    pub id: i64,
+    pub sender: String,
+    pub subject: String,
+    pub item_id: i32,
+    pub item_count: i32,
+    pub gold: i64,
+    pub sent_at: Option<NaiveDateTime>,
+    pub read: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Difficulty {
+    Normal,
+    Expert,
+    Master,
+    King,
+    Hell,
+}
+
+impl Difficulty {
+    pub fn from_id(id: i64) -> Self {
+        match id {
+            1 => Self::Expert,
+            2 => Self::Master,
+            3 => Self::King,
+            4.. => Self::Hell,
+            _ => Self::Normal,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Normal => "Normal",
+            Self::Expert => "Expert",
+            Self::Master => "Master",
+            Self::King => "King",
+            Self::Hell => "Hell",
+        }
+    }
+}
+
+impl std::fmt::Display for Difficulty {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_str(self.as_str())
     }
 }
 
-impl Db {
-    pub fn new(cfg: &AppConfig) -> Result<Self> {
-        let private_key_pem = include_str!("key.txt");
-        let private_key = RsaPrivateKey::from_pkcs8_pem(private_key_pem)?;
-        Ok(Self {
-            main_url: cfg.db_main_url.clone(),
-            billing_url: cfg.db_billing_url.clone(),
-            chara_url: cfg.db_char_url.clone(),
-            inventory_url: cfg.db_inventory_url.clone(),
-            login_url: cfg.db_login_url.clone(),
-            private_key,
+/// Per-dungeon totals from `charac_dungeon_clear`.
+#[derive(Clone, Debug)]
+pub struct DungeonClear {
+    pub dungeon_id: i32,
+    pub clears: i64,
+    pub highest: Difficulty,
+}
+
+#[derive(Clone, Debug)]
+pub struct Avatar {
+    pub slot: AvatarSlot,
+    pub item_id: i32,
+    pub expires: Option<NaiveDateTime>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AvatarSlot {
+    Hat,
+    Hair,
+    Face,
+    Neck,
+    Top,
+    Bottom,
+    Belt,
+    Shoes,
+    Skin,
+    Aura,
+    Weapon,
+}
+
+impl AvatarSlot {
+    /// Equipped avatars live in `user_items` slots 10..=20; anything else is wardrobe storage.
+    pub fn from_slot(slot: i32) -> Option<Self> {
+        Some(match slot {
+            10 => Self::Hat,
+            11 => Self::Hair,
+            12 => Self::Face,
+            13 => Self::Neck,
+            14 => Self::Top,
+            15 => Self::Bottom,
+            16 => Self::Belt,
+            17 => Self::Shoes,
+            18 => Self::Skin,
+            19 => Self::Aura,
+            20 => Self::Weapon,
+            _ => return None,
         })
     }
 
-    pub async fn send_gold(&self, char_id: i32, amount: i32) -> Result<()> {
-        tracing::info!("db: send gold request");
-        let mut conn = self.get_conn(DbPool::Inventory).await?;
-        sqlx::query("UPDATE `inventory` SET money = money + ? WHERE charac_no = ?")
-            .bind(amount)
-            .bind(char_id)
-            .execute(&mut conn)
-            .await?;
-        Ok(())
+    /// Whether the slot counts towards a rare avatar set.
+    pub fn is_set_piece(self) -> bool {
+        !matches!(self, Self::Skin | Self::Aura | Self::Weapon)
     }
 
-    pub async fn send_cera(&self, uid: i32, amount: i32) -> Result<()> {
-        tracing::info!("db: send cera request");
-        let mut conn = self.get_conn(DbPool::Billing).await?;
-        sqlx::query(
-            "INSERT INTO `cash_cera` (`account`, `cera`, `mod_tran`, `mod_date`, `reg_date`) \
-             VALUES (?, ?, 1, NOW(), NOW()) \
-             ON DUPLICATE KEY UPDATE cera = cera + ?",
-        )
-        .bind(uid)
-        .bind(amount)
-        .bind(amount)
-        .execute(&mut conn)
-        .await?;
-        Ok(())
-    }
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Hat => "Hat",
+            Self::Hair => "Hair",
+            Self::Face => "Face",
+            Self::Neck => "Neck",
+            Self::Top => "Top",
+            Self::Bottom => "Bottom",
+            Self::Belt => "Belt",
+            Self::Shoes => "Shoes",
+            Self::Skin => "Skin",
+            Self::Aura => "Aura",
+            Self::Weapon => "Weapon",
+        }
+    }
+}
+
+impl std::fmt::Display for AvatarSlot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GuildRank {
+    Master,
+    SubMaster,
+    Officer,
+    Member,
+}
+
+impl GuildRank {
+    pub fn from_grade(grade: i32) -> Self {
+        match grade {
+            1 => Self::Master,
+            2 => Self::SubMaster,
+            3 => Self::Officer,
+            _ => Self::Member,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Master => "Guild Master",
+            Self::SubMaster => "Sub Master",
+            Self::Officer => "Officer",
+            Self::Member => "Member",
+        }
+    }
+}
+
+impl std::fmt::Display for GuildRank {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::fmt::Display for JobName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Db {
+    pub fn new(cfg: &AppConfig) -> Result<Self> {
+        let private_key_pem = include_str!("key.txt");
+        let private_key = RsaPrivateKey::from_pkcs8_pem(private_key_pem)?;
+        let exp_table = ExpTable::load(&cfg.exp_table_path).unwrap_or_else(|err| {
+            tracing::warn!("exp table unavailable: {err:#}");
+            ExpTable::default()
+        });
+        Ok(Self {
+            main_url: cfg.db_main_url.clone(),
+            billing_url: cfg.db_billing_url.clone(),
+            realm: RwLock::new(RealmUrls::from(cfg.realm(None))),
+            login_url: cfg.db_login_url.clone(),
+            charset: cfg.db_charset.clone(),
+            legacy_encoding: cfg.legacy_encoding,
+            private_key,
+            exp_table,
+            max_level: cfg.max_level,
+            audit_table: OnceCell::new(),
+            daily_table: OnceCell::new(),
+            discord_table: OnceCell::new(),
+            email_tables: OnceCell::new(),
+            recovery_table: OnceCell::new(),
+            attempt_table: OnceCell::new(),
+            note_table: OnceCell::new(),
+            terms_table: OnceCell::new(),
+            balance_table: OnceCell::new(),
+            discord_linking: cfg.discord_linking,
+            email_binding: cfg.smtp.is_some(),
+            send_limits: cfg.send_limits,
+            currencies: cfg.currencies.clone(),
+            currency_sends: cfg.enabled(Feature::SendCurrency),
+            username_policy: cfg.username_policy.clone(),
+            dry_run: cfg.dry_run,
+        })
+    }
+
+    /// Returns the send's audit id, which [`Db::undo_send`] takes.
+    pub async fn send_gold(&self, actor: &str, char_id: i32, amount: i32) -> Result<i64> {
+        tracing::info!("db: send gold request");
+        let entry = AuditEntry {
+            actor: actor.to_string(),
+            action: AuditAction::GoldSend,
+            account_uid: None,
+            char_id: Some(char_id),
+            item_id: None,
+            amount: i64::from(amount),
+            detail: String::new(),
+        };
+        let kind = Currency::Gold.send_kind(&self.send_limits);
+        self.send_limited(entry, kind, Adjustment::Gold { char_id, amount }).await
+    }
+
+    pub async fn send_cera(&self, actor: &str, uid: i32, amount: i32) -> Result<i64> {
+        tracing::info!("db: send cera request");
+        let entry = AuditEntry {
+            actor: actor.to_string(),
+            action: AuditAction::CeraSend,
+            account_uid: Some(uid),
+            char_id: None,
+            item_id: None,
+            amount: i64::from(amount),
+            detail: String::new(),
+        };
+        let kind = Currency::Cera.send_kind(&self.send_limits);
+        self.send_limited(entry, kind, Adjustment::Cera { uid, amount }).await
+    }
+
+    /// Checks the sender's limits, writes the send's audit row and applies it. The sender's
+    /// account row stays locked until the audit row commits, so concurrent sends can't both
+    /// pass the check; the balance change commits after it and is rolled back on failure.
+    async fn send_limited(
+        &self,
+        entry: AuditEntry,
+        kind: SendKind<'_>,
+        adjustment: Adjustment,
+    ) -> Result<i64> {
+        let mut conn = self.get_conn(DbPool::Main).await?;
+        self.ensure_audit_table(&mut conn).await?;
+        let mut tx = conn.begin().await?;
+        // A locking read, so the quota query below sees every send committed before ours.
+        sqlx::query("SELECT uid FROM accounts WHERE accountname = ? FOR UPDATE")
+            .bind(&entry.actor)
+            .fetch_optional(&mut *tx)
+            .await?;
+        self.check_send_limits(&mut tx, &entry.actor, &kind, entry.amount).await?;
+        let id = insert_audit(&mut tx, &entry).await?;
+        self.apply_recorded(tx, &entry, adjustment).await?;
+        Ok(id)
+    }
+
+    /// Applies `adjustment` in its own transaction, committing it right after `tx`, which
+    /// holds the audit row. A failed change rolls back both; should the second commit fail,
+    /// the change is lost rather than applied twice.
+    async fn apply_recorded(
+        &self,
+        tx: Transaction<'_, MySql>,
+        entry: &AuditEntry,
+        adjustment: Adjustment,
+    ) -> Result<()> {
+        let mut target = self.get_conn(adjustment.pool()).await?;
+        let mut change = target.begin().await?;
+        adjustment.apply(&mut change).await?;
+        self.commit(tx).await?;
+        if let Err(err) = self.commit(change).await {
+            tracing::error!("db: {} recorded but not applied: {err:#}", entry.action.as_str());
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    /// Balance of a configured currency for an account or character; missing rows read as 0.
+    pub async fn currency_balance(&self, currency: &CurrencyDef, owner: i32) -> Result<i64> {
+        tracing::debug!("db: currency balance");
+        let (table, column, key) = currency_columns(currency)?;
+        let mut conn = self.get_conn(currency_pool(currency.db)).await?;
+        let balance: Option<i64> = sqlx::query_scalar(&format!(
+            "SELECT CAST(`{column}` AS SIGNED) FROM `{table}` WHERE `{key}` = ? LIMIT 1"
+        ))
+        .bind(owner)
+        .fetch_optional(&mut conn)
+        .await?;
+        Ok(balance.unwrap_or_default())
+    }
+
+    /// Adds `amount` of a configured currency, under the same limits as gold and cera. The
+    /// row must already exist, since other columns of an arbitrary table may have no defaults.
+    /// Returns the send's audit id.
+    pub async fn send_currency(
+        &self,
+        actor: &str,
+        currency: &CurrencyDef,
+        owner: i32,
+        amount: i32,
+    ) -> Result<i64> {
+        tracing::info!("db: send {} request", currency.name);
+        if !self.currency_sends || !currency.sendable {
+            bail!("Sending {} is disabled on this server", currency.name);
+        }
+        if amount <= 0 {
+            bail!("Amount must be positive");
+        }
+        let (account_uid, char_id) =
+            if currency.per_character { (None, Some(owner)) } else { (Some(owner), None) };
+        let entry = AuditEntry {
+            actor: actor.to_string(),
+            action: AuditAction::CurrencySend,
+            account_uid,
+            char_id,
+            item_id: None,
+            amount: i64::from(amount),
+            detail: currency.name.clone(),
+        };
+        let adjustment = Adjustment::Currency {
+            currency: currency.clone(),
+            owner,
+            amount,
+        };
+        self.send_limited(entry, SendKind::currency(currency), adjustment).await
+    }
+
+    /// Moves cera from one account to another by username in a single billing transaction,
+    /// returning the recipient's UID.
+    pub async fn transfer_cera(
+        &self,
+        from_uid: i32,
+        to_username: &str,
+        amount: i64,
+    ) -> Result<i32> {
+        tracing::info!("db: transfer cera request");
+        if amount <= 0 {
+            bail!("Amount must be positive");
+        }
+        let mut conn = self.get_conn(DbPool::Main).await?;
+        let to_uid: i32 = sqlx::query_scalar("SELECT uid FROM accounts WHERE accountname = ?")
+            .bind(to_username)
+            .fetch_optional(&mut conn)
+            .await?
+            .context("Recipient account not found")?;
+        if to_uid == from_uid {
+            bail!("Cannot transfer cera to the same account");
+        }
+
+        let mut billing_conn = self.get_conn(DbPool::Billing).await?;
+        let mut tx = billing_conn.begin().await?;
+        let balance: Option<i64> =
+            sqlx::query_scalar("SELECT cera FROM cash_cera WHERE account = ? FOR UPDATE")
+                .bind(from_uid)
+                .fetch_optional(&mut *tx)
+                .await?;
+        if balance.unwrap_or(0) < amount {
+            bail!("Not enough cera");
+        }
+        sqlx::query("UPDATE cash_cera SET cera = cera - ?, mod_date = NOW() WHERE account = ?")
+            .bind(amount)
+            .bind(from_uid)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            "INSERT INTO `cash_cera` (`account`, `cera`, `mod_tran`, `mod_date`, `reg_date`) \
+             VALUES (?, ?, 1, NOW(), NOW()) \
+             ON DUPLICATE KEY UPDATE cera = cera + ?, mod_date = NOW()",
+        )
+        .bind(to_uid)
+        .bind(amount)
+        .bind(amount)
+        .execute(&mut *tx)
+        .await?;
+        self.commit(tx).await?;
+        Ok(to_uid)
+    }
+
+    pub async fn perform_login(&self, username: &str, password: &str) -> Result<LoginSession> {
+        tracing::debug!("db: login attempt");
+        let mut conn = self.get_conn(DbPool::Main).await?;
+        self.check_launcher_version(&mut conn).await?;
+        let row = sqlx::query("SELECT uid, password FROM accounts WHERE accountname = ?")
+            .bind(username)
+            .fetch_optional(&mut conn)
+            .await?
+            .context("User not found")?;
+        let uid: i32 = row.try_get("uid").context("Missing uid")?;
+        let stored_hash = row.try_get::<Vec<u8>, _>("password")?;
+        if !check_password(password, &stored_hash) {
+            bail!("Invalid password");
+        }
+        if let Some(banned) = self.active_ban(&mut conn, uid).await? {
+            return Err(banned.into());
+        }
+        let slot_limit: Option<i32> =
+            sqlx::query_scalar("SELECT `count` FROM limit_create_character WHERE m_id = ?")
+                .bind(uid)
+                .fetch_optional(&mut conn)
+                .await?;
+        // Builds without a storage password lack the column; treat that as "no PIN".
+        let has_pin = sqlx::query_scalar::<_, Option<String>>(
+            "SELECT second_pwd FROM member_info WHERE m_id = ?",
+        )
+        .bind(uid)
+        .fetch_optional(&mut conn)
+        .await
+        .unwrap_or_else(|err| {
+            tracing::debug!("db: secondary pin unavailable: {err}");
+            None
+        })
+        .flatten()
+        .is_some_and(|pin| !pin.is_empty());
+
+        // Balances are shown as unavailable rather than failing the whole sign-in.
+        let cera = self.account_cera(uid).await.inspect_err(|err| {
+            tracing::warn!("db: billing unavailable, cera unknown: {err:#}");
+        });
+
+        let mut chara_conn = self.get_conn(DbPool::Chara).await?;
+        let characters = match self.list_characters(&mut chara_conn, uid, true).await {
+            Ok(characters) => characters,
+            Err(err) => {
+                tracing::warn!("db: inventory unavailable, gold unknown: {err:#}");
+                self.list_characters(&mut chara_conn, uid, false).await?
+            }
+        };
+
+        // Channel status is informational; a missing table must not block login.
+        let channels = self.list_channels().await.unwrap_or_else(|err| {
+            tracing::warn!("db: channel list unavailable: {err:#}");
+            Vec::new()
+        });
+
+        let daily = self.daily_status(uid).await.unwrap_or_else(|err| {
+            tracing::warn!("db: daily reward status unavailable: {err:#}");
+            DailyStatus::default()
+        });
+
+        let discord_id = if self.discord_linking {
+            self.discord_link(uid).await.unwrap_or_else(|err| {
+                tracing::warn!("db: discord link unavailable: {err:#}");
+                None
+            })
+        } else {
+            None
+        };
+        let email = if self.email_binding {
+            self.account_email(uid).await.unwrap_or_else(|err| {
+                tracing::warn!("db: email binding unavailable: {err:#}");
+                None
+            })
+        } else {
+            None
+        };
+
+        let quotas = if self.send_limits.is_unlimited() {
+            SendQuotas::default()
+        } else {
+            self.send_quotas(username).await.unwrap_or_else(|err| {
+                tracing::warn!("db: send quotas unavailable: {err:#}");
+                SendQuotas::default()
+            })
+        };
+
+        let online = self.online_status(uid).await.unwrap_or_else(|err| {
+            tracing::debug!("db: online status unavailable: {err}");
+            None
+        });
+
+        Ok(LoginSession {
+            uid,
+            token: self.generate_login_token(uid)?,
+            characters,
+            cera: cera.ok(),
+            slot_limit: slot_limit.unwrap_or(DEFAULT_CHAR_SLOTS),
+            channels,
+            daily,
+            discord_id,
+            email,
+            has_pin,
+            quotas,
+            online,
+        })
+    }
+
+    async fn account_cera(&self, uid: i32) -> Result<i64> {
+        let mut conn = self.get_conn(DbPool::Billing).await?;
+        let cera: Option<i64> = sqlx::query_scalar("SELECT cera FROM cash_cera WHERE account = ?")
+            .bind(uid)
+            .fetch_optional(&mut conn)
+            .await?;
+        Ok(cera.unwrap_or(0))
+    }
+
+    /// Characters of `uid`; gold comes from the inventory schema, skipped when `with_gold` is
+    /// false so the list still loads while that schema is unreachable.
+    async fn list_characters(
+        &self,
+        conn: &mut MySqlConnection,
+        uid: i32,
+        with_gold: bool,
+    ) -> Result<Vec<Character>> {
+        let inventory_schema = self.realm_urls().inventory_schema().to_string();
+        let (money, inventory_join) = if with_gold {
+            (
+                "i.money",
+                format!("LEFT JOIN `{inventory_schema}`.inventory i ON c.charac_no = i.charac_no"),
+            )
+        } else {
+            ("NULL AS money", String::new())
+        };
+        let rows = sqlx::query(&format!(
+            "SELECT c.charac_no, c.charac_name, c.lev, c.job, {money}, \
+             gm.guild_id, gm.grade, g.guild_name, \
+             p.pvp_grade, p.pvp_point, p.win, p.lose \
+             FROM charac_info c \
+             {inventory_join} \
+             LEFT JOIN guild_member gm ON c.charac_no = gm.charac_no \
+             LEFT JOIN guild_info g ON gm.guild_id = g.guild_id \
+             LEFT JOIN pvp_result p ON c.charac_no = p.charac_no \
+             WHERE c.m_id = ? AND c.delete_flag = 0"
+        ))
+        .bind(uid)
+        .fetch_all(conn)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let job_id: i32 = row.try_get("job").unwrap_or_default();
+                let guild_id: Option<i32> = row.try_get("guild_id").ok().flatten();
+                let guild = guild_id.map(|guild_id| GuildMembership {
+                    guild_id,
+                    guild_name: self.text(&row, "guild_name"),
+                    rank: GuildRank::from_grade(row.try_get("grade").unwrap_or_default()),
+                });
+                let pvp_grade: Option<i32> = row.try_get("pvp_grade").ok().flatten();
+                let pvp = pvp_grade.map(|grade| PvpRecord {
+                    grade,
+                    points: row.try_get("pvp_point").unwrap_or_default(),
+                    wins: row.try_get("win").unwrap_or_default(),
+                    losses: row.try_get("lose").unwrap_or_default(),
+                });
+                Character {
+                    id: row.try_get("charac_no").unwrap_or_default(),
+                    name: self.text(&row, "charac_name"),
+                    level: row.try_get("lev").unwrap_or_default(),
+                    job: JobName::from_id(job_id),
+                    money: with_gold.then(|| row.try_get("money").unwrap_or(0)),
+                    guild,
+                    pvp,
+                }
+            })
+            .collect())
+    }
+
+    /// Whether the game server currently has `uid` signed in, and on which channel.
+    async fn online_status(&self, uid: i32) -> Result<Option<OnlineStatus>> {
+        let mut conn = self.get_conn(DbPool::Login).await?;
+        let row = sqlx::query("SELECT * FROM login_account_3 WHERE m_id = ? AND login_status = 1")
+            .bind(uid)
+            .fetch_optional(&mut conn)
+            .await?;
+        Ok(row.map(|row| {
+            // Column name and signedness differ between server builds.
+            let channel = ["channel_no", "channel"].iter().find_map(|column| {
+                row.try_get::<i32, _>(*column)
+                    .ok()
+                    .or_else(|| row.try_get::<u32, _>(*column).ok().map(|c| c as i32))
+            });
+            OnlineStatus { channel }
+        }))
+    }
+
+    /// Marks `uid` as signed out of the game server, for sessions left behind by a client or
+    /// server crash. Returns whether a session was cleared.
+    pub async fn clear_online_status(&self, uid: i32) -> Result<bool> {
+        tracing::info!("db: clear in-game session request");
+        if self.skip_write(format_args!("clear in-game session of account {uid}")) {
+            return Ok(true);
+        }
+        let mut conn = self.get_conn(DbPool::Login).await?;
+        let result = sqlx::query(
+            "UPDATE login_account_3 SET login_status = 0 WHERE m_id = ? AND login_status = 1",
+        )
+        .bind(uid)
+        .execute(&mut conn)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn list_channels(&self) -> Result<Vec<Channel>> {
+        tracing::debug!("db: list channels");
+        let mut conn = self.get_conn(DbPool::Login).await?;
+        let rows = sqlx::query(
+            "SELECT channel_no, channel_name, `current_user`, max_user FROM game_channel \
+             WHERE open_flag = 1 ORDER BY channel_no",
+        )
+        .fetch_all(&mut conn)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| Channel {
+                number: row.try_get("channel_no").unwrap_or_default(),
+                name: row.try_get("channel_name").unwrap_or_default(),
+                population: row.try_get("current_user").unwrap_or_default(),
+                capacity: row.try_get("max_user").unwrap_or_default(),
+            })
+            .collect())
+    }
+
+    pub async fn list_dungeon_clears(&self, char_id: i32) -> Result<Vec<DungeonClear>> {
+        tracing::debug!("db: list dungeon clears");
+        let mut conn = self.get_conn(DbPool::Chara).await?;
+        let rows = sqlx::query(
+            "SELECT dungeon_index, CAST(SUM(clear_count) AS SIGNED) AS clears, \
+             CAST(MAX(difficulty) AS SIGNED) AS highest \
+             FROM charac_dungeon_clear WHERE charac_no = ? \
+             GROUP BY dungeon_index ORDER BY clears DESC",
+        )
+        .bind(char_id)
+        .fetch_all(&mut conn)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| DungeonClear {
+                dungeon_id: row.try_get("dungeon_index").unwrap_or_default(),
+                clears: row.try_get("clears").unwrap_or_default(),
+                highest: Difficulty::from_id(row.try_get("highest").unwrap_or_default()),
+            })
+            .collect())
+    }
+
+    pub async fn list_guild_members(&self, guild_id: i32) -> Result<Vec<GuildMember>> {
+        tracing::debug!("db: list guild members");
+        let mut conn = self.get_conn(DbPool::Chara).await?;
+        let rows = sqlx::query(
+            "SELECT c.charac_name, c.lev, c.job, gm.grade \
+             FROM guild_member gm \
+             JOIN charac_info c ON gm.charac_no = c.charac_no \
+             WHERE gm.guild_id = ? AND c.delete_flag = 0 \
+             ORDER BY gm.grade, c.lev DESC",
+        )
+        .bind(guild_id)
+        .fetch_all(&mut conn)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| GuildMember {
+                name: self.text(&row, "charac_name"),
+                level: row.try_get("lev").unwrap_or_default(),
+                job: JobName::from_id(row.try_get("job").unwrap_or_default()),
+                rank: GuildRank::from_grade(row.try_get("grade").unwrap_or_default()),
+            })
+            .collect())
+    }
+
+    /// Moves an owned character back to Seria's room so a broken saved map can't crash loading.
+    pub async fn reset_position(&self, uid: i32, char_id: i32) -> Result<()> {
+        tracing::info!("db: reset position request");
+        if self.skip_write(format_args!("move character {char_id} to Seria's room")) {
+            return Ok(());
+        }
+        let mut conn = self.get_conn(DbPool::Chara).await?;
+        let (village, area) = SERIA_ROOM;
+        let result = sqlx::query(
+            "UPDATE charac_info SET village = ?, area = ?, pos_x = 0, pos_y = 0 \
+             WHERE charac_no = ? AND m_id = ?",
+        )
+        .bind(village)
+        .bind(area)
+        .bind(char_id)
+        .bind(uid)
+        .execute(&mut conn)
+        .await?;
+        if result.rows_affected() == 0 {
+            bail!("Character not found");
+        }
+        Ok(())
+    }
+
+    pub async fn list_mail(&self, char_id: i32) -> Result<Vec<Mail>> {
+        tracing::debug!("db: list mail");
+        let mut conn = self.get_conn(DbPool::Inventory).await?;
+        let rows = sqlx::query(
+            "SELECT p.postal_id, p.send_charac_name, p.item_id, p.add_info, p.gold, p.occ_time, \
+             l.letter_text, l.stat \
+             FROM postal p \
+             LEFT JOIN letter l ON p.letter_id = l.letter_id \
+             WHERE p.receive_charac_no = ? AND p.delete_flag = 0 \
+             ORDER BY p.occ_time DESC",
+        )
+        .bind(char_id)
+        .fetch_all(&mut conn)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let text = self.text(&row, "letter_text");
+                let subject = text
+                    .lines()
+                    .next()
+                    .unwrap_or_default()
+                    .chars()
+                    .take(40)
+                    .collect();
+                let stat: Option<i32> = row.try_get("stat").ok().flatten();
+                Mail {
+                    id: row.try_get("postal_id").unwrap_or_default(),
+                    sender: self.text(&row, "send_charac_name"),
+                    subject,
+                    item_id: row.try_get("item_id").unwrap_or_default(),
+                    item_count: row.try_get("add_info").unwrap_or_default(),
+                    gold: row.try_get("gold").unwrap_or_default(),
+                    sent_at: row.try_get("occ_time").ok(),
+                    // Letters start unread (stat 1) and are flagged once opened in game.
+                    read: stat.is_some_and(|s| s != 1),
+                }
+            })
+            .collect())
+    }
+
+    pub async fn list_avatars(&self, char_id: i32) -> Result<Vec<Avatar>> {
+        tracing::debug!("db: list avatars");
+        let mut conn = self.get_conn(DbPool::Inventory).await?;
+        let rows = sqlx::query(
+            "SELECT slot, it_id, expire_date FROM user_items \
+             WHERE charac_no = ? AND slot BETWEEN 10 AND 20 AND stat = 0 \
+             ORDER BY slot",
+        )
+        .bind(char_id)
+        .fetch_all(&mut conn)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| {
+                let slot = AvatarSlot::from_slot(row.try_get("slot").ok()?)?;
+                Some(Avatar {
+                    slot,
+                    item_id: row.try_get("it_id").unwrap_or_default(),
+                    expires: row.try_get("expire_date").ok().flatten(),
+                })
+            })
+            .collect())
+    }
+
+    pub async fn list_equipment(&self, char_id: i32) -> Result<Vec<Equipment>> {
+        tracing::debug!("db: list equipment");
+        let mut conn = self.get_conn(DbPool::Inventory).await?;
+        let blob: Option<Option<Vec<u8>>> =
+            sqlx::query_scalar("SELECT equipslot FROM inventory WHERE charac_no = ?")
+                .bind(char_id)
+                .fetch_optional(&mut conn)
+                .await?;
+        match blob.flatten() {
+            Some(blob) if !blob.is_empty() => equipment::decode(&blob),
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    pub async fn create_guild(&self, name: &str, master_char_id: i32) -> Result<i32> {
+        tracing::info!("db: create guild request");
+        let mut conn = self.get_conn(DbPool::Chara).await?;
+        let mut tx = conn.begin().await?;
+        // Raw bytes would compare as binary; read them in the connection charset so the
+        // column's collation decides, e.g. case-insensitively.
+        let sql = format!(
+            "SELECT guild_id FROM guild_info WHERE guild_name = {}",
+            self.text_param()?
+        );
+        let existing: Option<i32> = sqlx::query_scalar(&sql)
+            .bind(self.encode_text(name))
+            .fetch_optional(&mut *tx)
+            .await?;
+        if existing.is_some() {
+            bail!("Guild name already exists!");
+        }
+        // Names are copied as raw bytes so legacy encodings survive the round trip.
+        let master_name: Vec<u8> =
+            sqlx::query_scalar("SELECT charac_name FROM charac_info WHERE charac_no = ?")
+                .bind(master_char_id)
+                .fetch_optional(&mut *tx)
+                .await?
+                .context("Character not found")?;
+        let in_guild: Option<i32> =
+            sqlx::query_scalar("SELECT guild_id FROM guild_member WHERE charac_no = ?")
+                .bind(master_char_id)
+                .fetch_optional(&mut *tx)
+                .await?;
+        if in_guild.is_some() {
+            bail!("Character is already in a guild");
+        }
+
+        let guild_id = sqlx::query(
+            "INSERT INTO guild_info (guild_name, lev, money, master_charac_no, master_charac_name, reg_date) \
+             VALUES (?, 1, 0, ?, ?, NOW())",
+        )
+        .bind(self.encode_text(name))
+        .bind(master_char_id)
+        .bind(&master_name)
+        .execute(&mut *tx)
+        .await?
+        .last_insert_id() as i32;
+        sqlx::query("INSERT INTO guild_member (guild_id, charac_no, grade) VALUES (?, ?, 1)")
+            .bind(guild_id)
+            .bind(master_char_id)
+            .execute(&mut *tx)
+            .await?;
+        self.commit(tx).await?;
+        Ok(guild_id)
+    }
+
+    pub async fn set_guild_master(&self, guild_id: i32, char_id: i32) -> Result<()> {
+        tracing::info!("db: set guild master request");
+        let mut conn = self.get_conn(DbPool::Chara).await?;
+        let mut tx = conn.begin().await?;
+        let member: Option<i32> = sqlx::query_scalar(
+            "SELECT charac_no FROM guild_member WHERE guild_id = ? AND charac_no = ?",
+        )
+        .bind(guild_id)
+        .bind(char_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        if member.is_none() {
+            bail!("Character is not a member of this guild");
+        }
+        let name: Vec<u8> =
+            sqlx::query_scalar("SELECT charac_name FROM charac_info WHERE charac_no = ?")
+                .bind(char_id)
+                .fetch_one(&mut *tx)
+                .await?;
+        sqlx::query("UPDATE guild_member SET grade = 4 WHERE guild_id = ? AND grade = 1")
+            .bind(guild_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("UPDATE guild_member SET grade = 1 WHERE guild_id = ? AND charac_no = ?")
+            .bind(guild_id)
+            .bind(char_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            "UPDATE guild_info SET master_charac_no = ?, master_charac_name = ? WHERE guild_id = ?",
+        )
+        .bind(char_id)
+        .bind(&name)
+        .bind(guild_id)
+        .execute(&mut *tx)
+        .await?;
+        self.commit(tx).await?;
+        Ok(())
+    }
+
+    pub async fn set_guild_level(&self, guild_id: i32, level: i32) -> Result<()> {
+        tracing::info!("db: set guild level request");
+        if self.skip_write(format_args!("set guild {guild_id} level to {level}")) {
+            return Ok(());
+        }
+        let mut conn = self.get_conn(DbPool::Chara).await?;
+        let result = sqlx::query("UPDATE guild_info SET lev = ? WHERE guild_id = ?")
+            .bind(level)
+            .bind(guild_id)
+            .execute(&mut conn)
+            .await?;
+        if result.rows_affected() == 0 {
+            bail!("Guild not found");
+        }
+        Ok(())
+    }
+
+    pub async fn adjust_guild_funds(&self, guild_id: i32, delta: i64) -> Result<()> {
+        tracing::info!("db: adjust guild funds request");
+        if self.skip_write(format_args!("adjust guild {guild_id} funds by {delta}")) {
+            return Ok(());
+        }
+        let mut conn = self.get_conn(DbPool::Chara).await?;
+        let result = sqlx::query(
+            "UPDATE guild_info SET money = GREATEST(money + ?, 0) WHERE guild_id = ?",
+        )
+        .bind(delta)
+        .bind(guild_id)
+        .execute(&mut conn)
+        .await?;
+        if result.rows_affected() == 0 {
+            bail!("Guild not found");
+        }
+        Ok(())
+    }
+
+    pub async fn disband_guild(&self, guild_id: i32) -> Result<()> {
+        tracing::info!("db: disband guild request");
+        let mut conn = self.get_conn(DbPool::Chara).await?;
+        let mut tx = conn.begin().await?;
+        sqlx::query("DELETE FROM guild_member WHERE guild_id = ?")
+            .bind(guild_id)
+            .execute(&mut *tx)
+            .await?;
+        let result = sqlx::query("DELETE FROM guild_info WHERE guild_id = ?")
+            .bind(guild_id)
+            .execute(&mut *tx)
+            .await?;
+        if result.rows_affected() == 0 {
+            bail!("Guild not found");
+        }
+        self.commit(tx).await?;
+        Ok(())
+    }
+
+    pub async fn grant_cash_item(&self, uid: i32, item_id: i32, quantity: i32) -> Result<()> {
+        tracing::info!("db: grant cash item request");
+        if self.skip_write(format_args!("grant {quantity}x item {item_id} to account {uid}")) {
+            return Ok(());
+        }
+        let mut conn = self.get_conn(DbPool::Billing).await?;
+        insert_cash_item(&mut conn, uid, item_id, quantity).await
+    }
+
+    pub async fn grant_creature(&self, char_id: i32, creature_id: i32) -> Result<()> {
+        tracing::info!("db: grant creature request");
+        let mut conn = self.get_conn(DbPool::Inventory).await?;
+        let mut tx = conn.begin().await?;
+        let used: Vec<i32> =
+            sqlx::query_scalar("SELECT slot FROM creature_items WHERE charac_no = ? AND stat = 0")
+                .bind(char_id)
+                .fetch_all(&mut *tx)
+                .await?;
+        let slot = (0..CREATURE_SLOTS)
+            .find(|slot| !used.contains(slot))
+            .context("Creature inventory is full")?;
+        sqlx::query(
+            "INSERT INTO creature_items (charac_no, slot, it_id, reg_date, stat) \
+             VALUES (?, ?, ?, NOW(), 0)",
+        )
+        .bind(char_id)
+        .bind(slot)
+        .bind(creature_id)
+        .execute(&mut *tx)
+        .await?;
+        self.commit(tx).await?;
+        Ok(())
+    }
+
+    pub async fn find_character(&self, char_id: i32) -> Result<CharacterInfo> {
+        tracing::debug!("db: find character");
+        let mut conn = self.get_conn(DbPool::Chara).await?;
+        let row = sqlx::query(
+            "SELECT charac_no, m_id, charac_name, lev, job, grow_type FROM charac_info \
+             WHERE charac_no = ? AND delete_flag = 0",
+        )
+        .bind(char_id)
+        .fetch_optional(&mut conn)
+        .await?
+        .context("Character not found")?;
+        Ok(CharacterInfo {
+            id: row.try_get("charac_no")?,
+            uid: row.try_get("m_id")?,
+            name: self.text(&row, "charac_name"),
+            level: row.try_get("lev").unwrap_or_default(),
+            job: JobName::from_id(row.try_get("job").unwrap_or_default()),
+            grow_type: row.try_get("grow_type").unwrap_or_default(),
+        })
+    }
+
+    pub async fn set_job_growth(&self, char_id: i32, advancement: i32, awakening: i32) -> Result<()> {
+        tracing::info!("db: set job growth request");
+        let mut conn = self.get_conn(DbPool::Chara).await?;
+        let mut tx = conn.begin().await?;
+        let job_id: i32 = sqlx::query_scalar("SELECT job FROM charac_info WHERE charac_no = ?")
+            .bind(char_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .context("Character not found")?;
+        let max_advancement = JobName::from_id(job_id).advancements().len() as i32;
+        if !(0..=max_advancement).contains(&advancement) {
+            bail!("Invalid advancement for this job");
+        }
+        if !(0..AWAKENINGS.len() as i32).contains(&awakening) {
+            bail!("Invalid awakening stage");
+        }
+        if advancement == 0 && awakening > 0 {
+            bail!("Awakening requires a first advancement");
+        }
+        sqlx::query("UPDATE charac_info SET grow_type = ? WHERE charac_no = ?")
+            .bind(advancement | (awakening << 4))
+            .bind(char_id)
+            .execute(&mut *tx)
+            .await?;
+        self.commit(tx).await?;
+        Ok(())
+    }
+
+    /// Clears quest progress for a character, either entirely or for a single quest.
+    /// Returns the number of rows removed.
+    pub async fn reset_quests(&self, char_id: i32, quest_id: Option<i32>) -> Result<u64> {
+        tracing::info!("db: reset quests request");
+        let mut conn = self.get_conn(DbPool::Chara).await?;
+        let mut tx = conn.begin().await?;
+        let mut removed = 0;
+        for table in QUEST_TABLES {
+            let result = match quest_id {
+                Some(quest_id) => {
+                    sqlx::query(&format!(
+                        "DELETE FROM {table} WHERE charac_no = ? AND quest_id = ?"
+                    ))
+                    .bind(char_id)
+                    .bind(quest_id)
+                    .execute(&mut *tx)
+                    .await?
+                }
+                None => {
+                    sqlx::query(&format!("DELETE FROM {table} WHERE charac_no = ?"))
+                        .bind(char_id)
+                        .execute(&mut *tx)
+                        .await?
+                }
+            };
+            removed += result.rows_affected();
+        }
+        self.commit(tx).await?;
+        Ok(removed)
+    }
+
+    /// Adds inventory slots to a character and returns the new capacity.
+    pub async fn expand_inventory(&self, char_id: i32, slots: i32) -> Result<i32> {
+        tracing::info!("db: expand inventory request");
+        let mut conn = self.get_conn(DbPool::Inventory).await?;
+        let mut tx = conn.begin().await?;
+        let result = sqlx::query(
+            "UPDATE inventory SET inventory_capacity = LEAST(inventory_capacity + ?, ?) \
+             WHERE charac_no = ?",
+        )
+        .bind(slots)
+        .bind(MAX_INVENTORY_SLOTS)
+        .bind(char_id)
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            bail!("Character inventory not found");
+        }
+        let capacity: i32 =
+            sqlx::query_scalar("SELECT inventory_capacity FROM inventory WHERE charac_no = ?")
+                .bind(char_id)
+                .fetch_one(&mut *tx)
+                .await?;
+        self.commit(tx).await?;
+        Ok(capacity)
+    }
+
+    /// Adds stash tabs to an account and returns the new tab count.
+    pub async fn expand_stash(&self, uid: i32, tabs: i32) -> Result<i32> {
+        tracing::info!("db: expand stash request");
+        let mut conn = self.get_conn(DbPool::Inventory).await?;
+        let mut tx = conn.begin().await?;
+        let result = sqlx::query(
+            "UPDATE account_cargo SET capacity = LEAST(capacity + ?, ?) WHERE m_id = ?",
+        )
+        .bind(tabs)
+        .bind(MAX_STASH_TABS)
+        .bind(uid)
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            bail!("Account stash not found");
+        }
+        let capacity: i32 = sqlx::query_scalar("SELECT capacity FROM account_cargo WHERE m_id = ?")
+            .bind(uid)
+            .fetch_one(&mut *tx)
+            .await?;
+        self.commit(tx).await?;
+        Ok(capacity)
+    }
+
+    pub async fn set_slot_limit(&self, uid: i32, limit: i32) -> Result<()> {
+        tracing::info!("db: set slot limit request");
+        if !(1..=MAX_CHAR_SLOTS).contains(&limit) {
+            bail!("Slot limit must be between 1 and {MAX_CHAR_SLOTS}");
+        }
+        if self.skip_write(format_args!("set account {uid} slot limit to {limit}")) {
+            return Ok(());
+        }
+        let mut conn = self.get_conn(DbPool::Main).await?;
+        sqlx::query(
+            "INSERT INTO limit_create_character (m_id, `count`) VALUES (?, ?) \
+             ON DUPLICATE KEY UPDATE `count` = ?",
+        )
+        .bind(uid)
+        .bind(limit)
+        .bind(limit)
+        .execute(&mut conn)
+        .await?;
+        Ok(())
+    }
+
+    /// Moves every character, the cash shop inventory, the stash and the cera of `from_uid` to
+    /// `to_uid`, raising the target's slot limit to fit. Runs as one transaction across the
+    /// schemas, so all databases must live on the same server as the character DB.
+    pub async fn merge_accounts(&self, from_uid: i32, to_uid: i32) -> Result<MergeReport> {
+        tracing::info!("db: merge accounts request");
+        if from_uid == to_uid {
+            bail!("Cannot merge an account into itself");
+        }
+        let main = schema_name(&self.main_url, "d_taiwan");
+        let billing = schema_name(&self.billing_url, "taiwan_billing");
+        let inventory = self.realm_urls().inventory_schema().to_string();
+        let mut conn = self.get_conn(DbPool::Chara).await?;
+        let mut tx = conn.begin().await?;
+
+        let found: i64 = sqlx::query_scalar(&format!(
+            "SELECT COUNT(*) FROM `{main}`.accounts WHERE uid IN (?, ?)"
+        ))
+        .bind(from_uid)
+        .bind(to_uid)
+        .fetch_one(&mut *tx)
+        .await?;
+        if found < 2 {
+            bail!("Both accounts must exist");
+        }
+
+        let count_chars = "SELECT COUNT(*) FROM charac_info WHERE m_id = ? AND delete_flag = 0";
+        let moving: i64 = sqlx::query_scalar(count_chars)
+            .bind(from_uid)
+            .fetch_one(&mut *tx)
+            .await?;
+        let existing: i64 = sqlx::query_scalar(count_chars)
+            .bind(to_uid)
+            .fetch_one(&mut *tx)
+            .await?;
+        let limit: Option<i32> = sqlx::query_scalar(&format!(
+            "SELECT `count` FROM `{main}`.limit_create_character WHERE m_id = ? FOR UPDATE"
+        ))
+        .bind(to_uid)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let needed = existing + moving;
+        if needed > i64::from(MAX_CHAR_SLOTS) {
+            bail!("Merged account would have {needed} characters (max {MAX_CHAR_SLOTS})");
+        }
+        if needed > i64::from(limit.unwrap_or(DEFAULT_CHAR_SLOTS)) {
+            sqlx::query(&format!(
+                "INSERT INTO `{main}`.limit_create_character (m_id, `count`) VALUES (?, ?) \
+                 ON DUPLICATE KEY UPDATE `count` = ?"
+            ))
+            .bind(to_uid)
+            .bind(needed)
+            .bind(needed)
+            .execute(&mut *tx)
+            .await?;
+        }
+        sqlx::query("UPDATE charac_info SET m_id = ? WHERE m_id = ?")
+            .bind(to_uid)
+            .bind(from_uid)
+            .execute(&mut *tx)
+            .await?;
+
+        let cera: Option<i64> = sqlx::query_scalar(&format!(
+            "SELECT cera FROM `{billing}`.cash_cera WHERE account = ? FOR UPDATE"
+        ))
+        .bind(from_uid)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let cera = cera.unwrap_or(0).max(0);
+        if cera > 0 {
+            sqlx::query(&format!(
+                "INSERT INTO `{billing}`.cash_cera (`account`, `cera`, `mod_tran`, `mod_date`, \
+                 `reg_date`) VALUES (?, ?, 1, NOW(), NOW()) \
+                 ON DUPLICATE KEY UPDATE cera = cera + ?, mod_date = NOW()"
+            ))
+            .bind(to_uid)
+            .bind(cera)
+            .bind(cera)
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query(&format!(
+                "UPDATE `{billing}`.cash_cera SET cera = 0, mod_date = NOW() WHERE account = ?"
+            ))
+            .bind(from_uid)
+            .execute(&mut *tx)
+            .await?;
+        }
+        let cash_items = sqlx::query(&format!(
+            "UPDATE `{billing}`.cash_inventory SET account = ? WHERE account = ?"
+        ))
+        .bind(to_uid)
+        .bind(from_uid)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        // Stashes are one row per account, so only move it when the target has none.
+        let target_stash: i64 = sqlx::query_scalar(&format!(
+            "SELECT COUNT(*) FROM `{inventory}`.account_cargo WHERE m_id = ?"
+        ))
+        .bind(to_uid)
+        .fetch_one(&mut *tx)
+        .await?;
+        let mut stash_moved = false;
+        if target_stash == 0 {
+            let moved = sqlx::query(&format!(
+                "UPDATE `{inventory}`.account_cargo SET m_id = ? WHERE m_id = ?"
+            ))
+            .bind(to_uid)
+            .bind(from_uid)
+            .execute(&mut *tx)
+            .await?;
+            stash_moved = moved.rows_affected() > 0;
+        }
+
+        self.commit(tx).await?;
+        Ok(MergeReport {
+            characters: moving,
+            cera,
+            cash_items,
+            stash_moved,
+        })
+    }
+
+    /// Moves one character to another account and returns the UID it came from. Inventory,
+    /// equipment, mail and quest rows are keyed by `charac_no`, so they follow the character.
+    pub async fn transfer_character(&self, char_id: i32, to_uid: i32) -> Result<i32> {
+        tracing::info!("db: transfer character request");
+        let mut conn = self.get_conn(DbPool::Main).await?;
+        let exists: Option<i32> = sqlx::query_scalar("SELECT uid FROM accounts WHERE uid = ?")
+            .bind(to_uid)
+            .fetch_optional(&mut conn)
+            .await?;
+        if exists.is_none() {
+            bail!("Target account not found");
+        }
+        let limit: Option<i32> =
+            sqlx::query_scalar("SELECT `count` FROM limit_create_character WHERE m_id = ?")
+                .bind(to_uid)
+                .fetch_optional(&mut conn)
+                .await?;
+        let limit = limit.unwrap_or(DEFAULT_CHAR_SLOTS);
+
+        let mut chara_conn = self.get_conn(DbPool::Chara).await?;
+        let mut tx = chara_conn.begin().await?;
+        let from_uid: i32 = sqlx::query_scalar(
+            "SELECT m_id FROM charac_info WHERE charac_no = ? AND delete_flag = 0 FOR UPDATE",
+        )
+        .bind(char_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .context("Character not found")?;
+        if from_uid == to_uid {
+            bail!("Character already belongs to account {to_uid}");
+        }
+        let existing: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM charac_info WHERE m_id = ? AND delete_flag = 0",
+        )
+        .bind(to_uid)
+        .fetch_one(&mut *tx)
+        .await?;
+        if existing >= i64::from(limit) {
+            bail!("Target account has no free character slot ({existing}/{limit})");
+        }
+        sqlx::query("UPDATE charac_info SET m_id = ? WHERE charac_no = ?")
+            .bind(to_uid)
+            .bind(char_id)
+            .execute(&mut *tx)
+            .await?;
+        self.commit(tx).await?;
+        Ok(from_uid)
+    }
+
+    /// Sets the secondary PIN the client asks for before opening storage, or clears it with
+    /// `None`. Stored hashed like the account password.
+    pub async fn set_secondary_pin(&self, uid: i32, pin: Option<&str>) -> Result<()> {
+        tracing::info!("db: set secondary pin request");
+        if let Some(pin) = pin
+            && !(PIN_LENGTH.contains(&pin.len()) && pin.chars().all(|c| c.is_ascii_digit()))
+        {
+            bail!("PIN must be {} to {} digits", PIN_LENGTH.start(), PIN_LENGTH.end());
+        }
+        if self.skip_write(format_args!("update secondary pin of account {uid}")) {
+            return Ok(());
+        }
+        let mut conn = self.get_conn(DbPool::Main).await?;
+        let result = sqlx::query("UPDATE member_info SET second_pwd = ? WHERE m_id = ?")
+            .bind(pin.map(hash_password).unwrap_or_default())
+            .bind(uid)
+            .execute(&mut conn)
+            .await?;
+        if result.rows_affected() == 0 {
+            bail!("Account has no member info row");
+        }
+        Ok(())
+    }
+
+    /// Collects every row belonging to an account (see [`backup::TABLES`]). Tables this
+    /// server build lacks are left out of the bundle.
+    pub async fn export_account(&self, uid: i32) -> Result<AccountBackup> {
+        tracing::info!("db: export account request");
+        let mut bundle = AccountBackup {
+            version: backup::FORMAT_VERSION,
+            uid,
+            created_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            tables: Default::default(),
+        };
+        let mut char_ids = Vec::new();
+        let mut conn: Option<(DbPool, MySqlConnection)> = None;
+        for (pool, table, owner) in backup::TABLES {
+            let conn = match &mut conn {
+                Some((open, conn)) if open == pool => conn,
+                slot => &mut slot.insert((*pool, self.get_conn(*pool).await?)).1,
+            };
+            let Some((filter, keys)) = owner_filter(*owner, uid, &char_ids) else {
+                bundle.tables.insert(table.to_string(), Vec::new());
+                continue;
+            };
+            let sql = format!("SELECT * FROM `{table}` WHERE {filter}");
+            let mut query = sqlx::query(&sql);
+            for key in keys {
+                query = query.bind(key);
+            }
+            let rows = match query.fetch_all(&mut *conn).await {
+                Ok(rows) => rows,
+                Err(err) if is_missing_table(&err) => {
+                    tracing::warn!("db: backup skipped missing table {table}");
+                    continue;
+                }
+                Err(err) => return Err(err).with_context(|| format!("export {table}")),
+            };
+            let rows = rows.iter().map(backup::read_row).collect::<Result<Vec<_>>>()?;
+            bundle.tables.insert(table.to_string(), rows);
+            if *table == "charac_info" {
+                char_ids = bundle.char_ids();
+            }
+        }
+        Ok(bundle)
+    }
+
+    /// Replaces the account's rows with the ones in `bundle`. Characters created since the
+    /// backup are removed. The whole bundle is checked against the live tables before anything
+    /// is written; each database is then restored in its own transaction, so the report says
+    /// which ones were applied if one fails part-way.
+    pub async fn restore_account(&self, bundle: &AccountBackup) -> Result<RestoreReport> {
+        tracing::info!("db: restore account request");
+        let uid = bundle.uid;
+        let mut chara_conn = self.get_conn(DbPool::Chara).await?;
+        let current: Vec<i32> =
+            sqlx::query_scalar("SELECT charac_no FROM charac_info WHERE m_id = ?")
+                .bind(uid)
+                .fetch_all(&mut chara_conn)
+                .await?;
+        let backed_up = bundle.char_ids();
+        if !backed_up.is_empty() {
+            // Restoring a character that has since moved to another account would take it back.
+            let placeholders = vec!["?"; backed_up.len()].join(", ");
+            let sql = format!(
+                "SELECT charac_no, m_id FROM charac_info WHERE charac_no IN ({placeholders})"
+            );
+            let mut query = sqlx::query_as::<_, (i32, i32)>(&sql);
+            for id in &backed_up {
+                query = query.bind(id);
+            }
+            let owners = query.fetch_all(&mut chara_conn).await?;
+            if let Some((char_id, owner)) = owners.iter().find(|(_, owner)| *owner != uid) {
+                bail!("Character {char_id} now belongs to account {owner}; restore refused");
+            }
+        }
+        let mut char_ids = backed_up;
+        char_ids.extend(current.into_iter().map(i64::from));
+        char_ids.sort_unstable();
+        char_ids.dedup();
+
+        let mut checked = Vec::new();
+        for pool in backup::POOLS {
+            let tables: Vec<_> = backup::TABLES
+                .iter()
+                .filter(|(table_pool, table, _)| {
+                    *table_pool == pool && bundle.tables.contains_key(*table)
+                })
+                .collect();
+            if tables.is_empty() {
+                continue;
+            }
+            let mut conn = self.get_conn(pool).await?;
+            for (_, table, _) in &tables {
+                let columns: HashMap<String, String> = sqlx::query_as::<_, (String, String)>(
+                    "SELECT COLUMN_NAME, DATA_TYPE FROM information_schema.COLUMNS \
+                     WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ?",
+                )
+                .bind(*table)
+                .fetch_all(&mut conn)
+                .await?
+                .into_iter()
+                .map(|(name, data_type)| (name.to_ascii_lowercase(), data_type))
+                .collect();
+                if columns.is_empty() {
+                    bail!("Table {table} doesn't exist on this server");
+                }
+                check_backup_rows(table, &bundle.tables[*table], &columns)?;
+            }
+            checked.push((pool, tables, conn));
+        }
+
+        let mut report = RestoreReport {
+            rows: 0,
+            restored: Vec::new(),
+            failed: None,
+        };
+        for (pool, tables, mut conn) in checked {
+            match self.restore_tables(&mut conn, bundle, &tables, &char_ids).await {
+                Ok(rows) => {
+                    report.rows += rows;
+                    report.restored.push(pool);
+                }
+                Err(err) => {
+                    report.failed = Some((pool, err));
+                    break;
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// Restores the bundle's `tables` of one database in a single transaction.
+    async fn restore_tables(
+        &self,
+        conn: &mut MySqlConnection,
+        bundle: &AccountBackup,
+        tables: &[&(DbPool, &str, Owner)],
+        char_ids: &[i64],
+    ) -> Result<usize> {
+        let mut restored = 0;
+        let mut tx = conn.begin().await?;
+        for (_, table, owner) in tables {
+            if let Some((filter, keys)) = owner_filter(*owner, bundle.uid, char_ids) {
+                let sql = format!("DELETE FROM `{table}` WHERE {filter}");
+                let mut query = sqlx::query(&sql);
+                for key in keys {
+                    query = query.bind(key);
+                }
+                query.execute(&mut *tx).await.with_context(|| format!("clear {table}"))?;
+            }
+            for row in &bundle.tables[*table] {
+                let columns: Vec<String> = row.keys().map(|name| format!("`{name}`")).collect();
+                let sql = format!(
+                    "INSERT INTO `{table}` ({}) VALUES ({})",
+                    columns.join(", "),
+                    vec!["?"; columns.len()].join(", ")
+                );
+                let mut query = sqlx::query(&sql);
+                for value in row.values() {
+                    query = backup::bind_value(query, value)?;
+                }
+                query.execute(&mut *tx).await.with_context(|| format!("restore {table}"))?;
+                restored += 1;
+            }
+        }
+        self.commit(tx).await?;
+        Ok(restored)
+    }
+
+    /// Buys one extra character slot for `price` cera and audits the purchase under `actor`.
+    /// The slot row stays locked until the purchase commits, so two purchases can't both
+    /// raise the limit from the same value. Returns the new limit.
+    pub async fn purchase_char_slot(&self, actor: &str, uid: i32, price: i64) -> Result<i32> {
+        tracing::info!("db: purchase character slot request");
+        let amount = i32::try_from(price).context("Slot price is too large")?;
+        let mut conn = self.get_conn(DbPool::Main).await?;
+        self.ensure_audit_table(&mut conn).await?;
+        let mut tx = conn.begin().await?;
+        let current: Option<i32> = sqlx::query_scalar(
+            "SELECT `count` FROM limit_create_character WHERE m_id = ? FOR UPDATE",
+        )
+        .bind(uid)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let limit = current.unwrap_or(DEFAULT_CHAR_SLOTS) + 1;
+        if limit > MAX_CHAR_SLOTS {
+            bail!("Character slot limit already at maximum");
+        }
+        if self.skip_write(format_args!("sell account {uid} slot {limit} for {price} cera")) {
+            return Ok(limit);
+        }
+        sqlx::query(
+            "INSERT INTO limit_create_character (m_id, `count`) VALUES (?, ?) \
+             ON DUPLICATE KEY UPDATE `count` = ?",
+        )
+        .bind(uid)
+        .bind(limit)
+        .bind(limit)
+        .execute(&mut *tx)
+        .await?;
+        let entry = AuditEntry {
+            actor: actor.to_string(),
+            action: AuditAction::SlotPurchase,
+            account_uid: Some(uid),
+            char_id: None,
+            item_id: None,
+            amount: price,
+            detail: format!("limit {limit}"),
+        };
+        insert_audit(&mut tx, &entry).await?;
+        self.apply_recorded(tx, &entry, Adjustment::SpendCera { uid, amount }).await?;
+        Ok(limit)
+    }
+
+    pub async fn set_level(&self, char_id: i32, level: i32) -> Result<()> {
+        tracing::info!("db: set level request");
+        if !(1..=self.max_level).contains(&level) {
+            bail!("Level must be between 1 and {}", self.max_level);
+        }
+        let exp = self
+            .exp_table
+            .exp_for_level(level)
+            .context("Exp table has no entry for this level")?;
+        if self.skip_write(format_args!("set character {char_id} to level {level}")) {
+            return Ok(());
+        }
+        let mut conn = self.get_conn(DbPool::Chara).await?;
+        let result = sqlx::query("UPDATE charac_info SET lev = ?, exp = ? WHERE charac_no = ?")
+            .bind(level)
+            .bind(exp)
+            .bind(char_id)
+            .execute(&mut conn)
+            .await?;
+        if result.rows_affected() == 0 {
+            bail!("Character not found");
+        }
+        Ok(())
+    }
+
+    /// Writes an audit row. Failures are logged rather than returned so a
+    /// completed grant is never reported as failed because of the log.
+    pub async fn record_audit(&self, entry: AuditEntry) {
+        if let Err(err) = self.insert_audit(&entry).await {
+            tracing::warn!("db: audit write failed for {}: {err:#}", entry.action.as_str());
+        }
+    }
+
+    async fn insert_audit(&self, entry: &AuditEntry) -> Result<()> {
+        if self.skip_write(format_args!("audit {}: {}", entry.action.as_str(), entry.detail)) {
+            return Ok(());
+        }
+        let mut conn = self.get_conn(DbPool::Main).await?;
+        self.ensure_audit_table(&mut conn).await?;
+        insert_audit(&mut conn, entry).await?;
+        Ok(())
+    }
+
+    /// Brings the launcher's tables up to the latest [`MIGRATIONS`] version, recording each
+    /// applied step in `launcher_schema`. Returns the number of migrations applied.
+    pub async fn ensure_launcher_schema(&self) -> Result<usize> {
+        tracing::info!("db: ensure launcher schema");
+        if self.skip_write(format_args!("apply launcher schema migrations")) {
+            return Ok(0);
+        }
+        let mut conn = self.get_conn(DbPool::Main).await?;
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS `launcher_schema` ( \
+             `version` INT NOT NULL PRIMARY KEY, \
+             `name` VARCHAR(64) NOT NULL, \
+             `applied_at` DATETIME NOT NULL)",
+        )
+        .execute(&mut conn)
+        .await?;
+        let current: Option<i32> = sqlx::query_scalar("SELECT MAX(version) FROM launcher_schema")
+            .fetch_one(&mut conn)
+            .await?;
+        let pending: Vec<_> = MIGRATIONS
+            .iter()
+            .filter(|(version, ..)| *version > current.unwrap_or(0))
+            .collect();
+        for (version, name, sql) in &pending {
+            tracing::info!("db: applying launcher migration {version} ({name})");
+            match sqlx::query(sql).execute(&mut conn).await {
+                Ok(_) => {}
+                // The lazy table setup may have added the column already.
+                Err(err) if is_already_applied(&err) => {
+                    tracing::info!("db: launcher migration {version} already in place");
+                }
+                Err(err) => {
+                    return Err(err)
+                        .with_context(|| format!("launcher migration {version} ({name})"));
+                }
+            }
+            sqlx::query(
+                "INSERT INTO launcher_schema (version, name, applied_at) VALUES (?, ?, NOW())",
+            )
+            .bind(version)
+            .bind(name)
+            .execute(&mut conn)
+            .await?;
+        }
+        Ok(pending.len())
+    }
+
+    async fn ensure_audit_table(&self, conn: &mut MySqlConnection) -> Result<()> {
+        if self.skip_write(format_args!("create launcher_audit")) {
+            return Ok(());
+        }
+        self.audit_table
+            .get_or_try_init(|| async {
+                sqlx::query(AUDIT_TABLE).execute(&mut *conn).await?;
+                ensure_column(conn, "launcher_audit", "reverses", AUDIT_REVERSES).await
+            })
+            .await?;
+        Ok(())
+    }
+
+    pub async fn list_audit(&self, limit: i64) -> Result<Vec<AuditRecord>> {
+        tracing::debug!("db: list audit");
+        let mut conn = self.get_conn(DbPool::Main).await?;
+        self.ensure_audit_table(&mut conn).await?;
+        let rows = sqlx::query(
+            "SELECT id, created_at, actor, action, account_uid, charac_no, item_id, amount, detail \
+             FROM launcher_audit ORDER BY id DESC LIMIT ?",
+        )
+        .bind(limit)
+        .fetch_all(&mut conn)
+        .await?;
+        Ok(rows.iter().map(audit_record).collect())
+    }
+
+    /// Cera and gold movements involving an account, oldest first: those it made, those
+    /// addressed to it and gold sent to its characters.
+    pub async fn billing_history(
+        &self,
+        uid: i32,
+        username: &str,
+        char_ids: &[i32],
+    ) -> Result<Vec<AuditRecord>> {
+        tracing::debug!("db: billing history");
+        let mut conn = self.get_conn(DbPool::Main).await?;
+        self.ensure_audit_table(&mut conn).await?;
+        let mut filter = "account_uid = ? OR actor = ?".to_string();
+        if !char_ids.is_empty() {
+            let placeholders = vec!["?"; char_ids.len()].join(", ");
+            filter.push_str(&format!(" OR charac_no IN ({placeholders})"));
+        }
+        let sql = format!(
+            "SELECT id, created_at, actor, action, account_uid, charac_no, item_id, amount, detail \
+             FROM launcher_audit WHERE {filter} ORDER BY id"
+        );
+        let mut query = sqlx::query(&sql).bind(uid).bind(username);
+        for char_id in char_ids {
+            query = query.bind(char_id);
+        }
+        let rows = query.fetch_all(&mut conn).await?;
+        Ok(rows
+            .iter()
+            .map(audit_record)
+            .filter(|record| record.action.is_some_and(AuditAction::is_billing))
+            .collect())
+    }
+
+    /// Applies the inverse of a recorded grant or send. Each record can be reversed once,
+    /// by a rollback or the sender's undo.
+    pub async fn rollback_audit(&self, actor: &str, record: &AuditRecord) -> Result<()> {
+        tracing::info!("db: rollback audit request");
+        let entry = AuditEntry {
+            actor: actor.to_string(),
+            action: AuditAction::Rollback,
+            account_uid: record.account_uid,
+            char_id: record.char_id,
+            item_id: record.item_id,
+            amount: -record.amount,
+            detail: format!("#{}", record.id),
+        };
+        self.reverse(record, entry).await
+    }
+
+    /// Takes back one of `actor`'s own sends, returning the undo's audit entry.
+    pub async fn undo_send(&self, actor: &str, send_id: i64) -> Result<AuditEntry> {
+        tracing::info!("db: undo send request");
+        let mut conn = self.get_conn(DbPool::Main).await?;
+        let row = sqlx::query(
+            "SELECT id, created_at, actor, action, account_uid, charac_no, item_id, amount, detail \
+             FROM launcher_audit WHERE id = ?",
+        )
+        .bind(send_id)
+        .fetch_optional(&mut conn)
+        .await?;
+        let record = row.as_ref().map(audit_record).filter(|record| record.actor == actor);
+        let Some((record, currency)) = record.and_then(|record| {
+            let currency = match record.action? {
+                AuditAction::GoldSend => Currency::Gold.as_str().to_string(),
+                AuditAction::CeraSend => Currency::Cera.as_str().to_string(),
+                AuditAction::CurrencySend => record.detail.clone(),
+                _ => return None,
+            };
+            Some((record, currency))
+        }) else {
+            bail!("This send can no longer be undone");
+        };
+        let entry = AuditEntry {
+            actor: actor.to_string(),
+            action: AuditAction::SendUndo,
+            account_uid: record.account_uid,
+            char_id: record.char_id,
+            item_id: None,
+            amount: -record.amount,
+            detail: currency,
+        };
+        self.reverse(&record, entry.clone()).await?;
+        Ok(entry)
+    }
+
+    /// Writes `entry` as the reversal of `record` and applies the inverse change. The
+    /// unique `reverses` column admits one reversal per record, in the same transaction.
+    async fn reverse(&self, record: &AuditRecord, entry: AuditEntry) -> Result<()> {
+        let amount = i32::try_from(record.amount).context("Recorded amount is out of range")?;
+        let adjustment = match (record.action, record.account_uid, record.char_id, record.item_id)
+        {
+            (Some(AuditAction::CashItemGrant), Some(uid), _, Some(item_id)) => {
+                Adjustment::RevokeCashItem { uid, item_id, quantity: amount }
+            }
+            (Some(AuditAction::CreatureGrant), _, Some(char_id), Some(creature_id)) => {
+                Adjustment::RevokeCreature { char_id, creature_id }
+            }
+            (Some(AuditAction::GoldSend), _, Some(char_id), _) => {
+                Adjustment::RevokeGold { char_id, amount }
+            }
+            (Some(AuditAction::CeraSend), Some(uid), _, _) => {
+                Adjustment::RevokeCera { uid, amount }
+            }
+            (Some(AuditAction::CurrencySend), account_uid, char_id, _) => {
+                let Some(currency) = self.currencies.iter().find(|c| c.name == record.detail)
+                else {
+                    bail!("{} is no longer a configured currency", record.detail);
+                };
+                let owner = if currency.per_character { char_id } else { account_uid };
+                let owner = owner.context("Audit entry has no owner")?;
+                Adjustment::Currency {
+                    currency: currency.clone(),
+                    owner,
+                    amount: -amount,
+                }
+            }
+            _ => bail!("This audit entry cannot be rolled back"),
+        };
+        let mut conn = self.get_conn(DbPool::Main).await?;
+        self.ensure_audit_table(&mut conn).await?;
+        let mut tx = conn.begin().await?;
+        // Rollbacks from before the `reverses` column only left their marker in `detail`.
+        let legacy: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM launcher_audit WHERE action = ? AND detail = ?",
+        )
+        .bind(AuditAction::Rollback.as_str())
+        .bind(format!("#{}", record.id))
+        .fetch_one(&mut *tx)
+        .await?;
+        if legacy > 0 {
+            bail!("Audit entry #{} was already reversed", record.id);
+        }
+        match insert_reversal(&mut tx, &entry, record.id).await {
+            Err(err) if is_duplicate(&err) => {
+                bail!("Audit entry #{} was already reversed", record.id)
+            }
+            result => result?,
+        };
+        self.apply_recorded(tx, &entry, adjustment).await
+    }
+
+    /// What `actor` has left of today's gold and cera send limits.
+    pub async fn send_quotas(&self, actor: &str) -> Result<SendQuotas> {
+        let mut conn = self.get_conn(DbPool::Main).await?;
+        self.ensure_audit_table(&mut conn).await?;
+        let gold = Currency::Gold.send_kind(&self.send_limits);
+        let cera = Currency::Cera.send_kind(&self.send_limits);
+        Ok(SendQuotas {
+            gold: self.send_quota(&mut conn, actor, &gold).await?,
+            cera: self.send_quota(&mut conn, actor, &cera).await?,
+        })
+    }
+
+    async fn send_quota(
+        &self,
+        conn: &mut MySqlConnection,
+        actor: &str,
+        kind: &SendKind<'_>,
+    ) -> Result<SendQuota> {
+        let action = kind.action.as_str();
+        let row = sqlx::query(
+            "SELECT CAST(COALESCE(SUM(amount), 0) AS SIGNED) AS sent, \
+             (SELECT TIMESTAMPDIFF(SECOND, MAX(created_at), NOW()) FROM launcher_audit \
+              WHERE actor = ? AND action = ? AND detail = ?) AS since_last \
+             FROM launcher_audit WHERE actor = ? AND created_at >= CURDATE() \
+             AND ((action = ? AND detail = ?) OR (action = ? AND detail = ?))",
+        )
+        .bind(actor)
+        .bind(action)
+        .bind(kind.detail)
+        .bind(actor)
+        .bind(action)
+        .bind(kind.detail)
+        .bind(AuditAction::SendUndo.as_str())
+        .bind(kind.name)
+        .fetch_one(conn)
+        .await?;
+        let since_last: Option<i64> = row.try_get("since_last")?;
+        let cooldown = self.send_limits.cooldown;
+        let ready_at = since_last
+            .map(|secs| Duration::from_secs(secs.max(0) as u64))
+            .filter(|elapsed| *elapsed < cooldown)
+            .map(|elapsed| Instant::now() + (cooldown - elapsed));
+        Ok(SendQuota {
+            sent_today: row.try_get("sent")?,
+            daily_cap: kind.daily_cap,
+            ready_at,
+        })
+    }
+
+    /// Refuses a send during the cooldown or beyond the daily cap. Limits are counted from
+    /// the audit log, so they hold across launcher restarts and machines.
+    async fn check_send_limits(
+        &self,
+        conn: &mut MySqlConnection,
+        actor: &str,
+        kind: &SendKind<'_>,
+        amount: i64,
+    ) -> Result<()> {
+        if kind.daily_cap.is_none() && self.send_limits.cooldown.is_zero() {
+            return Ok(());
+        }
+        let quota = self.send_quota(conn, actor, kind).await?;
+        let wait = quota.cooldown_left();
+        if !wait.is_zero() {
+            bail!("Wait {}s before sending {} again", wait.as_secs() + 1, kind.name);
+        }
+        if let Some(remaining) = quota.remaining()
+            && amount > remaining
+        {
+            bail!("Daily {} limit reached: {remaining} left today", kind.name);
+        }
+        Ok(())
+    }
+
+    /// Recent gold sends to a character and their undos, newest first.
+    pub async fn list_sends(&self, char_id: i32) -> Result<Vec<SendRecord>> {
+        tracing::debug!("db: list sends");
+        let mut conn = self.get_conn(DbPool::Main).await?;
+        self.ensure_audit_table(&mut conn).await?;
+        let rows = sqlx::query(
+            "SELECT created_at, action, amount FROM launcher_audit \
+             WHERE charac_no = ? AND action IN (?, ?) ORDER BY id DESC LIMIT 10",
+        )
+        .bind(char_id)
+        .bind(AuditAction::GoldSend.as_str())
+        .bind(AuditAction::SendUndo.as_str())
+        .fetch_all(&mut conn)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let action: String = row.try_get("action").unwrap_or_default();
+                SendRecord {
+                    amount: row.try_get("amount").unwrap_or_default(),
+                    at: row.try_get("created_at").ok(),
+                    undo: action == AuditAction::SendUndo.as_str(),
+                }
+            })
+            .collect())
+    }
+
+    async fn ensure_daily_table(&self, conn: &mut MySqlConnection) -> Result<()> {
+        if self.skip_write(format_args!("create launcher_daily_reward")) {
+            return Ok(());
+        }
+        self.daily_table
+            .get_or_try_init(|| async {
+                sqlx::query(DAILY_REWARD_TABLE)
+                    .execute(&mut *conn)
+                    .await
+                    .map(|_| ())
+            })
+            .await?;
+        Ok(())
+    }
+
+    async fn ensure_discord_table(&self, conn: &mut MySqlConnection) -> Result<()> {
+        if self.skip_write(format_args!("create launcher_discord_link")) {
+            return Ok(());
+        }
+        self.discord_table
+            .get_or_try_init(|| async {
+                sqlx::query(DISCORD_LINK_TABLE)
+                    .execute(&mut *conn)
+                    .await
+                    .map(|_| ())
+            })
+            .await?;
+        Ok(())
+    }
+
+    async fn ensure_note_table(&self, conn: &mut MySqlConnection) -> Result<()> {
+        if self.skip_write(format_args!("create launcher_account_note")) {
+            return Ok(());
+        }
+        self.note_table
+            .get_or_try_init(|| async {
+                sqlx::query(ACCOUNT_NOTE_TABLE)
+                    .execute(&mut *conn)
+                    .await
+                    .map(|_| ())
+            })
+            .await?;
+        Ok(())
+    }
+
+    async fn ensure_terms_table(&self, conn: &mut MySqlConnection) -> Result<()> {
+        if self.skip_write(format_args!("create launcher_terms_acceptance")) {
+            return Ok(());
+        }
+        self.terms_table
+            .get_or_try_init(|| async {
+                sqlx::query(TERMS_ACCEPTANCE_TABLE)
+                    .execute(&mut *conn)
+                    .await
+                    .map(|_| ())
+            })
+            .await?;
+        Ok(())
+    }
+
+    async fn ensure_balance_table(&self, conn: &mut MySqlConnection) -> Result<()> {
+        if self.skip_write(format_args!("create launcher_balance_snapshot")) {
+            return Ok(());
+        }
+        self.balance_table
+            .get_or_try_init(|| async {
+                sqlx::query(BALANCE_SNAPSHOT_TABLE)
+                    .execute(&mut *conn)
+                    .await
+                    .map(|_| ())
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// Stores the session's cera and total gold unless they match the latest snapshot or
+    /// either is unavailable.
+    pub async fn record_balance(&self, session: &LoginSession) -> Result<()> {
+        let gold = session.characters.iter().map(|c| c.money).sum::<Option<i64>>();
+        let (Some(cera), Some(gold)) = (session.cera, gold) else {
+            return Ok(());
+        };
+        if self.skip_write(format_args!("snapshot balance of account {}", session.uid)) {
+            return Ok(());
+        }
+        let mut conn = self.get_conn(DbPool::Main).await?;
+        self.ensure_balance_table(&mut conn).await?;
+        let latest: Option<(i64, i64)> = sqlx::query_as(
+            "SELECT cera, gold FROM launcher_balance_snapshot WHERE account_uid = ? \
+             ORDER BY id DESC LIMIT 1",
+        )
+        .bind(session.uid)
+        .fetch_optional(&mut conn)
+        .await?;
+        if latest == Some((cera, gold)) {
+            return Ok(());
+        }
+        sqlx::query(
+            "INSERT INTO launcher_balance_snapshot (account_uid, taken_at, cera, gold) \
+             VALUES (?, NOW(), ?, ?)",
+        )
+        .bind(session.uid)
+        .bind(cera)
+        .bind(gold)
+        .execute(&mut conn)
+        .await?;
+        Ok(())
+    }
+
+    /// The account's most recent balance snapshots, oldest first.
+    pub async fn balance_history(&self, uid: i32) -> Result<Vec<BalanceSnapshot>> {
+        tracing::debug!("db: balance history");
+        let mut conn = self.get_conn(DbPool::Main).await?;
+        self.ensure_balance_table(&mut conn).await?;
+        let rows = sqlx::query(
+            "SELECT taken_at, cera, gold FROM launcher_balance_snapshot WHERE account_uid = ? \
+             ORDER BY id DESC LIMIT ?",
+        )
+        .bind(uid)
+        .bind(BALANCE_HISTORY_LIMIT)
+        .fetch_all(&mut conn)
+        .await?;
+        let mut history: Vec<BalanceSnapshot> = rows
+            .into_iter()
+            .filter_map(|row| {
+                Some(BalanceSnapshot {
+                    taken_at: row.try_get("taken_at").ok()?,
+                    cera: row.try_get("cera").unwrap_or_default(),
+                    gold: row.try_get("gold").unwrap_or_default(),
+                })
+            })
+            .collect();
+        history.reverse();
+        Ok(history)
+    }
+
+    pub async fn account_note(&self, uid: i32) -> Result<Option<AccountNote>> {
+        tracing::debug!("db: account note");
+        let mut conn = self.get_conn(DbPool::Main).await?;
+        self.ensure_note_table(&mut conn).await?;
+        let row = sqlx::query(
+            "SELECT note, updated_by, updated_at FROM launcher_account_note WHERE account_uid = ?",
+        )
+        .bind(uid)
+        .fetch_optional(&mut conn)
+        .await?;
+        Ok(row.map(|row| AccountNote {
+            text: row.try_get("note").unwrap_or_default(),
+            updated_by: row.try_get("updated_by").unwrap_or_default(),
+            updated_at: row.try_get("updated_at").ok(),
+        }))
+    }
+
+    /// Replaces the account's note; an empty note removes it.
+    pub async fn set_account_note(&self, actor: &str, uid: i32, note: &str) -> Result<()> {
+        tracing::info!("db: set account note request");
+        if self.skip_write(format_args!("set note on account {uid}")) {
+            return Ok(());
+        }
+        let mut conn = self.get_conn(DbPool::Main).await?;
+        self.ensure_note_table(&mut conn).await?;
+        if note.trim().is_empty() {
+            sqlx::query("DELETE FROM launcher_account_note WHERE account_uid = ?")
+                .bind(uid)
+                .execute(&mut conn)
+                .await?;
+            return Ok(());
+        }
+        sqlx::query(
+            "INSERT INTO launcher_account_note (account_uid, note, updated_by, updated_at) \
+             VALUES (?, ?, ?, NOW()) \
+             ON DUPLICATE KEY UPDATE note = VALUES(note), updated_by = VALUES(updated_by), \
+             updated_at = NOW()",
+        )
+        .bind(uid)
+        .bind(note)
+        .bind(actor)
+        .execute(&mut conn)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn discord_link(&self, uid: i32) -> Result<Option<String>> {
+        let mut conn = self.get_conn(DbPool::Main).await?;
+        self.ensure_discord_table(&mut conn).await?;
+        Ok(sqlx::query_scalar(
+            "SELECT discord_id FROM launcher_discord_link WHERE account_uid = ? \
+             ORDER BY linked_at DESC LIMIT 1",
+        )
+        .bind(uid)
+        .fetch_optional(&mut conn)
+        .await?)
+    }
+
+    /// Redeems a code from the Discord bot for `uid` and returns the linked Discord user ID.
+    /// Any earlier link of either the account or the Discord user is replaced. Wrong codes
+    /// count towards a lockout, as emailed codes do.
+    pub async fn link_discord(&self, uid: i32, code: &str) -> Result<String> {
+        tracing::info!("db: link discord request");
+        let mut conn = self.get_conn(DbPool::Main).await?;
+        self.ensure_discord_table(&mut conn).await?;
+        self.check_lockout(&mut conn, uid, DISCORD_ATTEMPTS).await?;
+        let mut tx = conn.begin().await?;
+        let discord_id: Option<String> = sqlx::query_scalar(
+            "SELECT discord_id FROM launcher_discord_link \
+             WHERE code = ? AND account_uid IS NULL \
+             AND created_at >= NOW() - INTERVAL ? MINUTE FOR UPDATE",
+        )
+        .bind(code)
+        .bind(DISCORD_CODE_TTL_MINUTES)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(discord_id) = discord_id else {
+            tx.rollback().await?;
+            self.record_attempt(&mut conn, uid, DISCORD_ATTEMPTS).await?;
+            bail!("Link code is invalid or expired");
+        };
+        sqlx::query(
+            "DELETE FROM launcher_discord_link \
+             WHERE (account_uid = ? OR discord_id = ?) AND account_uid IS NOT NULL",
+        )
+        .bind(uid)
+        .bind(&discord_id)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            "UPDATE launcher_discord_link SET account_uid = ?, linked_at = NOW() WHERE code = ?",
+        )
+        .bind(uid)
+        .bind(code)
+        .execute(&mut *tx)
+        .await?;
+        clear_attempts(&mut tx, uid, DISCORD_ATTEMPTS).await?;
+        self.commit(tx).await?;
+        Ok(discord_id)
+    }
+
+    async fn ensure_attempt_table(&self, conn: &mut MySqlConnection) -> Result<()> {
+        if self.skip_write(format_args!("create launcher_attempt")) {
+            return Ok(());
+        }
+        self.attempt_table
+            .get_or_try_init(|| async {
+                sqlx::query(ATTEMPT_TABLE)
+                    .execute(&mut *conn)
+                    .await
+                    .map(|_| ())
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// Refuses while the account has used up its wrong `purpose` codes.
+    async fn check_lockout(
+        &self,
+        conn: &mut MySqlConnection,
+        uid: i32,
+        purpose: &str,
+    ) -> Result<()> {
+        self.ensure_attempt_table(conn).await?;
+        let attempts: Option<i32> = sqlx::query_scalar(
+            "SELECT attempts FROM launcher_attempt \
+             WHERE account_uid = ? AND purpose = ? AND last_at > NOW() - INTERVAL ? MINUTE",
+        )
+        .bind(uid)
+        .bind(purpose)
+        .bind(LOCKOUT_MINUTES)
+        .fetch_optional(&mut *conn)
+        .await?;
+        if attempts.unwrap_or_default() >= MAX_CODE_ATTEMPTS {
+            bail!("Too many wrong codes, try again in {LOCKOUT_MINUTES} minutes");
+        }
+        Ok(())
+    }
+
+    /// Counts a wrong `purpose` code; a streak older than the lockout starts over.
+    async fn record_attempt(
+        &self,
+        conn: &mut MySqlConnection,
+        uid: i32,
+        purpose: &str,
+    ) -> Result<()> {
+        if self.skip_write(format_args!("count wrong {purpose} code for account {uid}")) {
+            return Ok(());
+        }
+        sqlx::query(
+            "INSERT INTO launcher_attempt (account_uid, purpose, attempts, last_at) \
+             VALUES (?, ?, 1, NOW()) ON DUPLICATE KEY UPDATE \
+             attempts = IF(last_at > NOW() - INTERVAL ? MINUTE, attempts + 1, 1), last_at = NOW()",
+        )
+        .bind(uid)
+        .bind(purpose)
+        .bind(LOCKOUT_MINUTES)
+        .execute(&mut *conn)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn unlink_discord(&self, uid: i32) -> Result<()> {
+        tracing::info!("db: unlink discord request");
+        if self.skip_write(format_args!("unlink discord from account {uid}")) {
+            return Ok(());
+        }
+        let mut conn = self.get_conn(DbPool::Main).await?;
+        sqlx::query("DELETE FROM launcher_discord_link WHERE account_uid = ?")
+            .bind(uid)
+            .execute(&mut conn)
+            .await?;
+        Ok(())
+    }
 
-    pub async fn perform_login(&self, username: &str, password: &str) -> Result<LoginSession> {
-        tracing::debug!("db: login attempt");
+    async fn ensure_email_tables(&self, conn: &mut MySqlConnection) -> Result<()> {
+        if self.skip_write(format_args!("create launcher_email tables")) {
+            return Ok(());
+        }
+        self.email_tables
+            .get_or_try_init(|| async {
+                sqlx::query(EMAIL_TABLE).execute(&mut *conn).await?;
+                sqlx::query(EMAIL_CODE_TABLE).execute(&mut *conn).await.map(|_| ())
+            })
+            .await?;
+        Ok(())
+    }
+
+    pub async fn account_email(&self, uid: i32) -> Result<Option<String>> {
         let mut conn = self.get_conn(DbPool::Main).await?;
-        let row = sqlx::query("SELECT uid, password FROM accounts WHERE accountname = ?")
+        self.ensure_email_tables(&mut conn).await?;
+        Ok(sqlx::query_scalar("SELECT email FROM launcher_email WHERE account_uid = ?")
+            .bind(uid)
+            .fetch_optional(&mut conn)
+            .await?)
+    }
+
+    /// UID and bound email of an account, for sending a password reset code.
+    pub async fn password_reset_target(&self, username: &str) -> Result<(i32, String)> {
+        tracing::info!("db: password reset target request");
+        let mut conn = self.get_conn(DbPool::Main).await?;
+        self.ensure_email_tables(&mut conn).await?;
+        let row = sqlx::query(
+            "SELECT a.uid, e.email FROM accounts a \
+             LEFT JOIN launcher_email e ON e.account_uid = a.uid WHERE a.accountname = ?",
+        )
+        .bind(username)
+        .fetch_optional(&mut conn)
+        .await?
+        .context("User not found")?;
+        let email: Option<String> = row.try_get("email").ok().flatten();
+        let email = email.context("No email is bound to this account")?;
+        Ok((row.try_get("uid")?, email))
+    }
+
+    /// Replaces any pending code of the same purpose, restarting its expiry and attempt count.
+    /// Returns false when dry run skipped storing it, so no code should be sent.
+    pub async fn store_email_code(
+        &self,
+        uid: i32,
+        purpose: EmailPurpose,
+        email: &str,
+        code: &str,
+    ) -> Result<bool> {
+        tracing::info!("db: store email code request");
+        if self.skip_write(format_args!("store {} code for account {uid}", purpose.as_str())) {
+            return Ok(false);
+        }
+        let mut conn = self.get_conn(DbPool::Main).await?;
+        self.ensure_email_tables(&mut conn).await?;
+        sqlx::query(
+            "REPLACE INTO launcher_email_code \
+             (account_uid, purpose, email, code, expires_at, attempts) \
+             VALUES (?, ?, ?, ?, NOW() + INTERVAL ? MINUTE, 0)",
+        )
+        .bind(uid)
+        .bind(purpose.as_str())
+        .bind(email)
+        .bind(code)
+        .bind(EMAIL_CODE_TTL_MINUTES)
+        .execute(&mut conn)
+        .await?;
+        Ok(true)
+    }
+
+    /// Checks a code and returns the email it was sent to. Wrong guesses are counted even
+    /// though the caller's change is not made.
+    async fn check_email_code(
+        &self,
+        conn: &mut MySqlConnection,
+        uid: i32,
+        purpose: EmailPurpose,
+        code: &str,
+    ) -> Result<String> {
+        let row = sqlx::query(
+            "SELECT email, code, attempts FROM launcher_email_code \
+             WHERE account_uid = ? AND purpose = ? AND expires_at > NOW()",
+        )
+        .bind(uid)
+        .bind(purpose.as_str())
+        .fetch_optional(&mut *conn)
+        .await?
+        .context("Code expired, request a new one")?;
+        let attempts: i32 = row.try_get("attempts").unwrap_or_default();
+        if attempts >= MAX_CODE_ATTEMPTS {
+            bail!("Too many attempts, request a new code");
+        }
+        if row.try_get::<String, _>("code")? != code {
+            if self.skip_write(format_args!("count wrong {} code", purpose.as_str())) {
+                bail!("Wrong code");
+            }
+            sqlx::query(
+                "UPDATE launcher_email_code SET attempts = attempts + 1 \
+                 WHERE account_uid = ? AND purpose = ?",
+            )
+            .bind(uid)
+            .bind(purpose.as_str())
+            .execute(&mut *conn)
+            .await?;
+            bail!("Wrong code");
+        }
+        Ok(row.try_get("email")?)
+    }
+
+    /// Binds the email a verification code was sent to. Returns the previously bound
+    /// address so it can be told about the change.
+    pub async fn bind_email(&self, uid: i32, code: &str) -> Result<(String, Option<String>)> {
+        tracing::info!("db: bind email request");
+        let mut conn = self.get_conn(DbPool::Main).await?;
+        self.ensure_email_tables(&mut conn).await?;
+        let email = self.check_email_code(&mut conn, uid, EmailPurpose::Bind, code).await?;
+        let mut tx = conn.begin().await?;
+        let previous: Option<String> =
+            sqlx::query_scalar("SELECT email FROM launcher_email WHERE account_uid = ? FOR UPDATE")
+                .bind(uid)
+                .fetch_optional(&mut *tx)
+                .await?;
+        sqlx::query(
+            "INSERT INTO launcher_email (account_uid, email, bound_at) VALUES (?, ?, NOW()) \
+             ON DUPLICATE KEY UPDATE email = ?, bound_at = NOW()",
+        )
+        .bind(uid)
+        .bind(&email)
+        .bind(&email)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("DELETE FROM launcher_email_code WHERE account_uid = ? AND purpose = ?")
+            .bind(uid)
+            .bind(EmailPurpose::Bind.as_str())
+            .execute(&mut *tx)
+            .await?;
+        self.commit(tx).await?;
+        let previous = previous.filter(|previous| *previous != email);
+        Ok((email, previous))
+    }
+
+    /// Sets a new password after checking the emailed reset code. Returns the bound email.
+    pub async fn reset_password(
+        &self,
+        username: &str,
+        code: &str,
+        password: &str,
+    ) -> Result<String> {
+        tracing::info!("db: reset password request");
+        let mut conn = self.get_conn(DbPool::Main).await?;
+        self.ensure_email_tables(&mut conn).await?;
+        let uid: i32 = sqlx::query_scalar("SELECT uid FROM accounts WHERE accountname = ?")
             .bind(username)
             .fetch_optional(&mut conn)
             .await?
             .context("User not found")?;
-        let uid: i32 = row.try_get("uid").context("Missing uid")?;
-        let stored_hash = row.try_get::<Vec<u8>, _>("password")?;
-        if !check_password(password, &stored_hash) {
-            bail!("Invalid password");
+        let purpose = EmailPurpose::PasswordReset;
+        let email = self.check_email_code(&mut conn, uid, purpose, code).await?;
+        let mut tx = conn.begin().await?;
+        sqlx::query("UPDATE accounts SET password = ?, qq = ? WHERE uid = ?")
+            .bind(hash_password(password))
+            .bind(password)
+            .bind(uid)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM launcher_email_code WHERE account_uid = ? AND purpose = ?")
+            .bind(uid)
+            .bind(purpose.as_str())
+            .execute(&mut *tx)
+            .await?;
+        self.commit(tx).await?;
+        Ok(email)
+    }
+
+    /// Gives each account a random password in one transaction, for mass recovery after a
+    /// credential leak. Fails without changes if any UID does not exist.
+    pub async fn reset_passwords(&self, uids: &[i32]) -> Result<Vec<ResetPassword>> {
+        tracing::info!("db: bulk password reset request ({} accounts)", uids.len());
+        let mut conn = self.get_conn(DbPool::Main).await?;
+        let mut tx = conn.begin().await?;
+        let mut reset = Vec::with_capacity(uids.len());
+        for &uid in uids {
+            let username: String =
+                sqlx::query_scalar("SELECT accountname FROM accounts WHERE uid = ?")
+                    .bind(uid)
+                    .fetch_optional(&mut *tx)
+                    .await?
+                    .with_context(|| format!("Account {uid} not found"))?;
+            let password = recovery::generate_password();
+            // `qq` would keep a plaintext copy of a password meant to replace leaked ones.
+            sqlx::query("UPDATE accounts SET password = ?, qq = '' WHERE uid = ?")
+                .bind(hash_password(&password))
+                .bind(uid)
+                .execute(&mut *tx)
+                .await?;
+            reset.push(ResetPassword {
+                uid,
+                username,
+                password,
+            });
         }
+        self.commit(tx).await?;
+        Ok(reset)
+    }
 
-        let mut billing_conn = self.get_conn(DbPool::Billing).await?;
-        let cera_row = sqlx::query("SELECT cera FROM cash_cera WHERE account = ?")
+    async fn ensure_recovery_table(&self, conn: &mut MySqlConnection) -> Result<()> {
+        if self.skip_write(format_args!("create launcher_recovery_code")) {
+            return Ok(());
+        }
+        self.recovery_table
+            .get_or_try_init(|| async {
+                sqlx::query(RECOVERY_CODE_TABLE).execute(&mut *conn).await?;
+                ensure_column(conn, "launcher_recovery_code", "salt", RECOVERY_CODE_SALT).await
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// Replaces the account's recovery codes with a new set and returns them in plain text.
+    /// They cannot be shown again.
+    pub async fn generate_recovery_codes(&self, uid: i32) -> Result<Vec<String>> {
+        tracing::info!("db: generate recovery codes request");
+        let codes: Vec<String> = (0..recovery::CODE_COUNT).map(|_| recovery::generate()).collect();
+        let mut conn = self.get_conn(DbPool::Main).await?;
+        self.ensure_recovery_table(&mut conn).await?;
+        let mut tx = conn.begin().await?;
+        sqlx::query("DELETE FROM launcher_recovery_code WHERE account_uid = ?")
             .bind(uid)
-            .fetch_optional(&mut billing_conn)
+            .execute(&mut *tx)
+            .await?;
+        for code in &codes {
+            let salt = recovery::salt();
+            sqlx::query(
+                "INSERT INTO launcher_recovery_code (account_uid, code_hash, salt, created_at) \
+                 VALUES (?, ?, ?, NOW())",
+            )
+            .bind(uid)
+            .bind(recovery::hash(&salt, code))
+            .bind(&salt)
+            .execute(&mut *tx)
             .await?;
-        let cera = cera_row
-            .and_then(|r| r.try_get::<i64, _>("cera").ok())
-            .unwrap_or(0);
+        }
+        self.commit(tx).await?;
+        Ok(codes)
+    }
 
-        let mut chara_conn = self.get_conn(DbPool::Chara).await?;
-        let rows = sqlx::query(
-            "SELECT c.charac_no, c.charac_name, c.lev, c.job, i.money \
-             FROM charac_info c \
-             LEFT JOIN taiwan_cain_2nd.inventory i ON c.charac_no = i.charac_no \
-             WHERE c.m_id = ? AND c.delete_flag = 0",
+    /// Spends one recovery code in place of the old password and sets `password`. Wrong codes
+    /// count towards a lockout.
+    pub async fn recover_account(&self, username: &str, code: &str, password: &str) -> Result<()> {
+        tracing::info!("db: recover account request");
+        let mut conn = self.get_conn(DbPool::Main).await?;
+        self.ensure_recovery_table(&mut conn).await?;
+        let uid: i32 = sqlx::query_scalar("SELECT uid FROM accounts WHERE accountname = ?")
+            .bind(username)
+            .fetch_optional(&mut conn)
+            .await?
+            .context("User not found")?;
+        self.check_lockout(&mut conn, uid, RECOVERY_ATTEMPTS).await?;
+        let mut tx = conn.begin().await?;
+        let unused: Vec<(i64, String, String)> = sqlx::query_as(
+            "SELECT id, code_hash, salt FROM launcher_recovery_code \
+             WHERE account_uid = ? AND used_at IS NULL FOR UPDATE",
         )
         .bind(uid)
-        .fetch_all(&mut chara_conn)
+        .fetch_all(&mut *tx)
         .await?;
-        let characters = rows
-            .into_iter()
-            .map(|row| {
-                let job_id: i32 = row.try_get("job").unwrap_or_default();
-                Character {
-                    id: row.try_get("charac_no").unwrap_or_default(),
-                    name: row.try_get("charac_name").unwrap_or_default(),
-                    level: row.try_get("lev").unwrap_or_default(),
-                    job: JobName::from_id(job_id),
-                    money: row.try_get("money").unwrap_or(0),
-                }
-            })
-            .collect::<Vec<_>>();
+        let matched = unused.iter().find(|(_, hash, salt)| *hash == recovery::hash(salt, code));
+        let Some((id, ..)) = matched else {
+            tx.rollback().await?;
+            self.record_attempt(&mut conn, uid, RECOVERY_ATTEMPTS).await?;
+            bail!("Invalid or already used recovery code");
+        };
+        sqlx::query("UPDATE launcher_recovery_code SET used_at = NOW() WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("UPDATE accounts SET password = ?, qq = ? WHERE uid = ?")
+            .bind(hash_password(password))
+            .bind(password)
+            .bind(uid)
+            .execute(&mut *tx)
+            .await?;
+        clear_attempts(&mut tx, uid, RECOVERY_ATTEMPTS).await?;
+        self.commit(tx).await?;
+        Ok(())
+    }
 
-        Ok(LoginSession {
-            uid,
-            token: self.generate_login_token(uid)?,
-            characters,
-            cera,
+    pub async fn daily_status(&self, uid: i32) -> Result<DailyStatus> {
+        let mut conn = self.get_conn(DbPool::Main).await?;
+        self.ensure_daily_table(&mut conn).await?;
+        let row = sqlx::query(
+            "SELECT streak, DATEDIFF(CURDATE(), last_claim) AS days \
+             FROM launcher_daily_reward WHERE account_uid = ?",
+        )
+        .bind(uid)
+        .fetch_optional(&mut conn)
+        .await?;
+        let Some(row) = row else {
+            return Ok(DailyStatus::default());
+        };
+        let streak: i32 = row.try_get("streak").unwrap_or_default();
+        let days: i64 = row.try_get("days").unwrap_or(i64::MAX);
+        Ok(DailyStatus {
+            streak: if days <= 1 { streak } else { 0 },
+            claimed_today: days == 0,
         })
     }
 
-    pub async fn create_account(&self, username: &str, password: &str) -> Result<()> {
+    /// Records today's claim and grants the reward for the new streak day, returning the
+    /// streak. Dates come from the DB server so a skewed client clock cannot double-claim.
+    pub async fn claim_daily_reward(&self, uid: i32, rewards: &[DailyReward]) -> Result<i32> {
+        tracing::info!("db: claim daily reward request");
+        if rewards.is_empty() {
+            bail!("No daily rewards configured");
+        }
+        let mut conn = self.get_conn(DbPool::Main).await?;
+        self.ensure_daily_table(&mut conn).await?;
+        let mut tx = conn.begin().await?;
+        let row = sqlx::query(
+            "SELECT streak, CAST(last_claim AS CHAR) AS last_claim, \
+             DATEDIFF(CURDATE(), last_claim) AS days \
+             FROM launcher_daily_reward WHERE account_uid = ? FOR UPDATE",
+        )
+        .bind(uid)
+        .fetch_optional(&mut *tx)
+        .await?;
+        // Kept to undo the claim if the grants cannot be committed.
+        let previous = match &row {
+            Some(row) => Some((
+                row.try_get::<i32, _>("streak").unwrap_or_default(),
+                row.try_get::<String, _>("last_claim")?,
+            )),
+            None => None,
+        };
+        let days = match &row {
+            Some(row) => row.try_get::<i64, _>("days").unwrap_or(i64::MAX),
+            None => i64::MAX,
+        };
+        if days == 0 {
+            bail!("Today's reward was already claimed");
+        }
+        let streak = match &previous {
+            Some((streak, _)) if days == 1 => streak + 1,
+            _ => 1,
+        };
+        sqlx::query(
+            "INSERT INTO launcher_daily_reward (account_uid, streak, last_claim) \
+             VALUES (?, ?, CURDATE()) \
+             ON DUPLICATE KEY UPDATE streak = VALUES(streak), last_claim = CURDATE()",
+        )
+        .bind(uid)
+        .bind(streak)
+        .execute(&mut *tx)
+        .await?;
+
+        // The grants share one billing transaction that commits after the claim. If that commit
+        // fails the claim is put back the way it was, so the player can retry.
+        let reward = &rewards[(streak as usize - 1) % rewards.len()];
+        let mut billing = self.get_conn(DbPool::Billing).await?;
+        let mut grants = billing.begin().await?;
+        if reward.cera > 0 {
+            let cera = i32::try_from(reward.cera).context("Daily cera reward is too large")?;
+            insert_cera(&mut grants, uid, cera).await?;
+        }
+        if let Some(item_id) = reward.item_id {
+            insert_cash_item(&mut grants, uid, item_id, reward.count).await?;
+        }
+        self.commit(tx).await?;
+        if let Err(err) = self.commit(grants).await {
+            tracing::error!("db: daily reward for {uid} not granted, undoing the claim: {err:#}");
+            let undo = match previous {
+                Some((streak, last_claim)) => sqlx::query(
+                    "UPDATE launcher_daily_reward SET streak = ?, last_claim = ? \
+                     WHERE account_uid = ?",
+                )
+                .bind(streak)
+                .bind(last_claim)
+                .bind(uid),
+                None => sqlx::query("DELETE FROM launcher_daily_reward WHERE account_uid = ?")
+                    .bind(uid),
+            };
+            if let Err(undo) = undo.execute(&mut conn).await {
+                tracing::error!("db: daily reward for {uid} claimed but not granted: {undo:#}");
+            }
+            return Err(err);
+        }
+        Ok(streak)
+    }
+
+    /// Whether no account is registered as `username` yet.
+    pub async fn username_available(&self, username: &str) -> Result<bool> {
+        let mut conn = self.get_conn(DbPool::Main).await?;
+        let existing: Option<i32> =
+            sqlx::query_scalar("SELECT uid FROM accounts WHERE accountname = ?")
+                .bind(username)
+                .fetch_optional(&mut conn)
+                .await?;
+        Ok(existing.is_none())
+    }
+
+    /// Registers an account; `terms_version` is the rules revision the player accepted.
+    pub async fn create_account(
+        &self,
+        username: &str,
+        password: &str,
+        terms_version: Option<&str>,
+    ) -> Result<()> {
         tracing::info!("db: create account request");
+        self.username_policy.check(username)?;
         let mut conn = self.get_conn(DbPool::Main).await?;
+        if terms_version.is_some() {
+            // DDL commits implicitly in MySQL, so the table has to exist before the transaction.
+            self.ensure_terms_table(&mut conn).await?;
+        }
         let mut tx = conn.begin().await?;
         let existing: Option<i32> =
             sqlx::query_scalar("SELECT uid FROM accounts WHERE accountname = ?")
@@ -241,8 +3473,21 @@ impl Db {
             .bind(uid)
             .execute(&mut *tx)
             .await?;
-        tx.commit().await?;
+        if let Some(version) = terms_version {
+            sqlx::query(
+                "INSERT INTO launcher_terms_acceptance (account_uid, version, accepted_at) \
+                 VALUES (?, ?, NOW())",
+            )
+            .bind(uid)
+            .bind(version)
+            .execute(&mut *tx)
+            .await?;
+        }
+        self.commit(tx).await?;
 
+        if self.skip_write(format_args!("create login row for account {uid}")) {
+            return Ok(());
+        }
         let mut login_conn = self.get_conn(DbPool::Login).await?;
         sqlx::query("INSERT INTO member_login (m_id) VALUES (?)")
             .bind(uid)
@@ -252,21 +3497,154 @@ impl Db {
         Ok(())
     }
 
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// In dry-run mode, logs a write instead of running it; callers return early when true.
+    fn skip_write(&self, action: std::fmt::Arguments<'_>) -> bool {
+        if self.dry_run {
+            tracing::info!("db: dry run, skipped: {action}");
+        }
+        self.dry_run
+    }
+
+    /// Commits a transaction, or rolls it back in dry-run mode so validation and returned
+    /// values are still real.
+    async fn commit(&self, tx: Transaction<'_, MySql>) -> Result<()> {
+        if self.dry_run {
+            tracing::info!("db: dry run, rolled back transaction");
+            tx.rollback().await?;
+        } else {
+            tx.commit().await?;
+        }
+        Ok(())
+    }
+
+    /// Fails with [`UpdateRequired`] when `launcher_settings` asks for a newer launcher.
+    async fn check_launcher_version(&self, conn: &mut MySqlConnection) -> Result<()> {
+        // Servers that never ran the migrations have no settings table and enforce nothing.
+        let min_version: Option<String> = sqlx::query_scalar(
+            "SELECT value FROM launcher_settings WHERE name = 'min_launcher_version'",
+        )
+        .fetch_optional(conn)
+        .await
+        .unwrap_or_else(|err| {
+            if is_missing_table(&err) {
+                tracing::debug!("db: launcher settings unavailable: {err}");
+            } else {
+                tracing::warn!("db: minimum launcher version not checked: {err}");
+            }
+            None
+        });
+        let Some(min) = min_version else {
+            return Ok(());
+        };
+        let min = min.trim();
+        if patcher::is_newer(min, env!("CARGO_PKG_VERSION")) {
+            return Err(UpdateRequired { min_version: min.to_string() }.into());
+        }
+        Ok(())
+    }
+
+    async fn active_ban(&self, conn: &mut MySqlConnection, uid: i32) -> Result<Option<Banned>> {
+        let row = sqlx::query(
+            "SELECT reason, end_time FROM member_punish_info \
+             WHERE m_id = ? AND apply_flag = 1 AND (end_time IS NULL OR end_time > NOW()) \
+             ORDER BY end_time IS NULL DESC, end_time DESC LIMIT 1",
+        )
+        .bind(uid)
+        .fetch_optional(conn)
+        .await?;
+        Ok(row.map(|row| {
+            let expires: Option<NaiveDateTime> = row.try_get("end_time").ok().flatten();
+            Banned {
+                reason: row.try_get("reason").unwrap_or_default(),
+                // Permanent bans are stored with a far-future end date.
+                expires: expires.filter(|e| e.year() < 9999),
+            }
+        }))
+    }
+
+    /// Points character/inventory queries and login tokens at another realm.
+    pub fn set_realm(&self, realm: &Realm) {
+        tracing::info!("db: switching realm to {}", realm.name);
+        if let Ok(mut urls) = self.realm.write() {
+            *urls = RealmUrls::from(realm);
+        }
+    }
+
+    fn realm_urls(&self) -> RealmUrls {
+        match self.realm.read() {
+            Ok(urls) => urls.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+
+    /// Signs a throwaway token to prove the private key loaded and works.
+    pub fn check_key(&self) -> Result<()> {
+        self.generate_login_token(1).map(|_| ())
+    }
+
+    /// Connects to `pool` and pings it, for the reconnection watchdog.
+    pub async fn probe(&self, pool: DbPool) -> Result<()> {
+        let mut conn = self.get_conn(pool).await?;
+        conn.ping().await?;
+        Ok(())
+    }
+
     async fn get_conn(&self, pool: DbPool) -> Result<MySqlConnection> {
         let url = match pool {
-            DbPool::Main => self.main_url.as_str(),
-            DbPool::Billing => self.billing_url.as_str(),
-            DbPool::Chara => self.chara_url.as_str(),
-            DbPool::Inventory => self.inventory_url.as_str(),
-            DbPool::Login => self.login_url.as_str(),
+            DbPool::Main => self.main_url.clone(),
+            DbPool::Billing => self.billing_url.clone(),
+            DbPool::Chara => self.realm_urls().chara_url,
+            DbPool::Inventory => self.realm_urls().inventory_url,
+            DbPool::Login => self.login_url.clone(),
         };
         tracing::debug!("db: open connection");
-        Ok(MySqlConnection::connect(url).await?)
+        let mut options: MySqlConnectOptions = url.parse()?;
+        if matches!(pool, DbPool::Chara | DbPool::Inventory) {
+            options = options.charset(&self.charset);
+        }
+        MySqlConnection::connect_with(&options).await.context(Unreachable(pool))
+    }
+
+    /// Text column from the character or inventory database, decoded with the legacy encoding
+    /// when its bytes aren't UTF-8.
+    fn text(&self, row: &MySqlRow, column: &str) -> String {
+        let bytes = row.try_get::<Option<Vec<u8>>, _>(column).ok().flatten();
+        self.decode_text(bytes.unwrap_or_default())
+    }
+
+    fn decode_text(&self, bytes: Vec<u8>) -> String {
+        match (String::from_utf8(bytes), self.legacy_encoding) {
+            (Ok(text), _) => text,
+            (Err(err), Some(encoding)) => encoding.decode(err.as_bytes()).0.into_owned(),
+            (Err(err), None) => String::from_utf8_lossy(err.as_bytes()).into_owned(),
+        }
+    }
+
+    /// Placeholder for an [`encode_text`](Self::encode_text) value that compares as text in the
+    /// character connection's charset rather than as binary.
+    fn text_param(&self) -> Result<String> {
+        if !is_identifier(&self.charset) {
+            bail!("Invalid database charset {:?}", self.charset);
+        }
+        Ok(format!("CONVERT(? USING {})", self.charset))
+    }
+
+    /// `text` as the character database stores it, for comparing against name columns.
+    fn encode_text(&self, text: &str) -> Vec<u8> {
+        match self.legacy_encoding {
+            Some(encoding) => encoding.encode(text).0.into_owned(),
+            None => text.as_bytes().to_vec(),
+        }
     }
 
     fn generate_login_token(&self, uid: i32) -> Result<String> {
         let pre_str = "1FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF00";
         let next_str = "010101010101010101010101010101010101010101010101010101010101010155914510010403030101";
+        let next_str = self.realm_urls().token_tail.unwrap_or_else(|| next_str.to_string());
         let uid_hex = format!("{:08X}", uid as u32);
         let src_str = format!("{pre_str}{uid_hex}{next_str}");
         let message = BigUint::parse_bytes(src_str.as_bytes(), 16).context("Hex fail")?;
@@ -275,6 +3653,327 @@ impl Db {
     }
 }
 
+/// A balance change applied inside the caller's transaction on [`Adjustment::pool`]. The
+/// revocations fail once the player has spent or claimed what they take back.
+enum Adjustment {
+    Gold { char_id: i32, amount: i32 },
+    Cera { uid: i32, amount: i32 },
+    RevokeGold { char_id: i32, amount: i32 },
+    RevokeCera { uid: i32, amount: i32 },
+    /// Debits cera for a purchase; fails rather than go below zero.
+    SpendCera { uid: i32, amount: i32 },
+    /// Removes the newest unclaimed cash-shop grant matching the item and count.
+    RevokeCashItem { uid: i32, item_id: i32, quantity: i32 },
+    RevokeCreature { char_id: i32, creature_id: i32 },
+    /// A configured currency; negative amounts fail rather than go below zero.
+    Currency { currency: CurrencyDef, owner: i32, amount: i32 },
+}
+
+impl Adjustment {
+    fn pool(&self) -> DbPool {
+        match self {
+            Self::Gold { .. } | Self::RevokeGold { .. } | Self::RevokeCreature { .. } => {
+                DbPool::Inventory
+            }
+            Self::Cera { .. }
+            | Self::RevokeCera { .. }
+            | Self::SpendCera { .. }
+            | Self::RevokeCashItem { .. } => DbPool::Billing,
+            Self::Currency { currency, .. } => currency_pool(currency.db),
+        }
+    }
+
+    async fn apply(&self, conn: &mut MySqlConnection) -> Result<()> {
+        match *self {
+            Self::Currency { ref currency, owner, amount } => {
+                let (table, column, key) = currency_columns(currency)?;
+                let result = sqlx::query(&format!(
+                    "UPDATE `{table}` SET `{column}` = `{column}` + ? \
+                     WHERE `{key}` = ? AND `{column}` + ? >= 0"
+                ))
+                .bind(amount)
+                .bind(owner)
+                .bind(amount)
+                .execute(conn)
+                .await?;
+                if result.rows_affected() > 0 {
+                    Ok(())
+                } else if amount < 0 {
+                    bail!("The {} has already been spent", currency.name)
+                } else {
+                    bail!("No {} balance exists for this {}", currency.name, currency.owner_label())
+                }
+            }
+            Self::Gold { char_id, amount } => {
+                sqlx::query("UPDATE `inventory` SET money = money + ? WHERE charac_no = ?")
+                    .bind(amount)
+                    .bind(char_id)
+                    .execute(conn)
+                    .await?;
+                Ok(())
+            }
+            Self::Cera { uid, amount } => insert_cera(conn, uid, amount).await,
+            Self::RevokeGold { char_id, amount } => {
+                let result = sqlx::query(
+                    "UPDATE `inventory` SET money = money - ? WHERE charac_no = ? AND money >= ?",
+                )
+                .bind(amount)
+                .bind(char_id)
+                .bind(amount)
+                .execute(conn)
+                .await?;
+                if result.rows_affected() == 0 {
+                    bail!("The gold has already been spent");
+                }
+                Ok(())
+            }
+            Self::RevokeCera { uid, amount } | Self::SpendCera { uid, amount } => {
+                let result = sqlx::query(
+                    "UPDATE cash_cera SET cera = cera - ?, mod_date = NOW() \
+                     WHERE account = ? AND cera >= ?",
+                )
+                .bind(amount)
+                .bind(uid)
+                .bind(amount)
+                .execute(conn)
+                .await?;
+                if result.rows_affected() == 0 {
+                    if matches!(self, Self::SpendCera { .. }) {
+                        bail!("Not enough cera");
+                    }
+                    bail!("The cera has already been spent");
+                }
+                Ok(())
+            }
+            Self::RevokeCashItem { uid, item_id, quantity } => {
+                let result = sqlx::query(
+                    "DELETE FROM `cash_inventory` \
+                     WHERE `account` = ? AND `item_id` = ? AND `count` = ? \
+                     ORDER BY `reg_date` DESC LIMIT 1",
+                )
+                .bind(uid)
+                .bind(item_id)
+                .bind(quantity)
+                .execute(conn)
+                .await?;
+                if result.rows_affected() == 0 {
+                    bail!("The item was already claimed in game");
+                }
+                Ok(())
+            }
+            Self::RevokeCreature { char_id, creature_id } => {
+                let result = sqlx::query(
+                    "DELETE FROM creature_items WHERE charac_no = ? AND it_id = ? AND stat = 0 \
+                     ORDER BY reg_date DESC LIMIT 1",
+                )
+                .bind(char_id)
+                .bind(creature_id)
+                .execute(conn)
+                .await?;
+                if result.rows_affected() == 0 {
+                    bail!("The creature is no longer in the character's inventory");
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+async fn insert_audit(conn: &mut MySqlConnection, entry: &AuditEntry) -> Result<i64> {
+    let result = sqlx::query(
+        "INSERT INTO `launcher_audit` \
+         (`created_at`, `actor`, `action`, `account_uid`, `charac_no`, `item_id`, `amount`, `detail`) \
+         VALUES (NOW(), ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&entry.actor)
+    .bind(entry.action.as_str())
+    .bind(entry.account_uid)
+    .bind(entry.char_id)
+    .bind(entry.item_id)
+    .bind(entry.amount)
+    .bind(&entry.detail)
+    .execute(conn)
+    .await?;
+    Ok(result.last_insert_id() as i64)
+}
+
+/// Inserts `entry` as the reversal of audit row `reverses`; a second reversal of the same
+/// row fails the unique index, see [`is_duplicate`].
+async fn insert_reversal(
+    conn: &mut MySqlConnection,
+    entry: &AuditEntry,
+    reverses: i64,
+) -> sqlx::Result<()> {
+    sqlx::query(
+        "INSERT INTO `launcher_audit` \
+         (`created_at`, `actor`, `action`, `account_uid`, `charac_no`, `item_id`, `amount`, \
+          `detail`, `reverses`) \
+         VALUES (NOW(), ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&entry.actor)
+    .bind(entry.action.as_str())
+    .bind(entry.account_uid)
+    .bind(entry.char_id)
+    .bind(entry.item_id)
+    .bind(entry.amount)
+    .bind(&entry.detail)
+    .bind(reverses)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+async fn insert_cera(conn: &mut MySqlConnection, uid: i32, amount: i32) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO `cash_cera` (`account`, `cera`, `mod_tran`, `mod_date`, `reg_date`) \
+         VALUES (?, ?, 1, NOW(), NOW()) \
+         ON DUPLICATE KEY UPDATE cera = cera + ?",
+    )
+    .bind(uid)
+    .bind(amount)
+    .bind(amount)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+async fn insert_cash_item(
+    conn: &mut MySqlConnection,
+    uid: i32,
+    item_id: i32,
+    quantity: i32,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO `cash_inventory` (`account`, `item_id`, `count`, `reg_date`) \
+         VALUES (?, ?, ?, NOW())",
+    )
+    .bind(uid)
+    .bind(item_id)
+    .bind(quantity)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Checks backed-up rows against the live columns of `table` (lowercased name to
+/// `DATA_TYPE`) before any of them are written.
+fn check_backup_rows(
+    table: &str,
+    rows: &backup::Rows,
+    columns: &HashMap<String, String>,
+) -> Result<()> {
+    for row in rows {
+        for (name, value) in row {
+            if !is_identifier(name) {
+                bail!("Backup has an invalid column name in {table}: {name}");
+            }
+            let Some(data_type) = columns.get(&name.to_ascii_lowercase()) else {
+                bail!("Backup column {table}.{name} doesn't exist on this server");
+            };
+            if let Some(problem) = backup::check_value(value, data_type) {
+                bail!("Backup value in {table}.{name}: {problem}");
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn clear_attempts(conn: &mut MySqlConnection, uid: i32, purpose: &str) -> Result<()> {
+    sqlx::query("DELETE FROM launcher_attempt WHERE account_uid = ? AND purpose = ?")
+        .bind(uid)
+        .bind(purpose)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+/// `WHERE` clause and keys selecting the rows of one account, or `None` when a per-character
+/// table has no characters to match.
+fn owner_filter(owner: Owner, uid: i32, char_ids: &[i64]) -> Option<(String, Vec<i64>)> {
+    match owner {
+        Owner::Account(column) => Some((format!("`{column}` = ?"), vec![i64::from(uid)])),
+        Owner::Character(_) if char_ids.is_empty() => None,
+        Owner::Character(column) => {
+            let placeholders = vec!["?"; char_ids.len()].join(", ");
+            Some((format!("`{column}` IN ({placeholders})"), char_ids.to_vec()))
+        }
+    }
+}
+
+fn audit_record(row: &MySqlRow) -> AuditRecord {
+    AuditRecord {
+        id: row.try_get("id").unwrap_or_default(),
+        created_at: row.try_get("created_at").ok(),
+        actor: row.try_get("actor").unwrap_or_default(),
+        action: AuditAction::parse(&row.try_get::<String, _>("action").unwrap_or_default()),
+        account_uid: row.try_get("account_uid").ok().flatten(),
+        char_id: row.try_get("charac_no").ok().flatten(),
+        item_id: row.try_get("item_id").ok().flatten(),
+        amount: row.try_get("amount").unwrap_or_default(),
+        detail: row.try_get("detail").unwrap_or_default(),
+    }
+}
+
+fn is_duplicate(err: &sqlx::Error) -> bool {
+    err.as_database_error().is_some_and(|err| err.is_unique_violation())
+}
+
+/// Adds `column` to `table` with `alter` when the table was created lazily on an install that
+/// never ran the migration adding it.
+async fn ensure_column(
+    conn: &mut MySqlConnection,
+    table: &str,
+    column: &str,
+    alter: &str,
+) -> sqlx::Result<()> {
+    let exists: Option<i64> = sqlx::query_scalar(
+        "SELECT 1 FROM information_schema.COLUMNS \
+         WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ? AND COLUMN_NAME = ?",
+    )
+    .bind(table)
+    .bind(column)
+    .fetch_optional(&mut *conn)
+    .await?;
+    if exists.is_none() {
+        tracing::info!("db: adding {table}.{column}");
+        sqlx::query(alter).execute(&mut *conn).await?;
+    }
+    Ok(())
+}
+
+/// Duplicate column or index name: a schema change that is already in place.
+fn is_already_applied(err: &sqlx::Error) -> bool {
+    err.as_database_error()
+        .and_then(|err| err.try_downcast_ref::<MySqlDatabaseError>())
+        .is_some_and(|err| matches!(err.number(), 1060 | 1061))
+}
+
+fn is_missing_table(err: &sqlx::Error) -> bool {
+    err.as_database_error().and_then(|err| err.code()).as_deref() == Some("42S02")
+}
+
+fn currency_pool(db: CurrencyDb) -> DbPool {
+    match db {
+        CurrencyDb::Main => DbPool::Main,
+        CurrencyDb::Billing => DbPool::Billing,
+        CurrencyDb::Chara => DbPool::Chara,
+        CurrencyDb::Inventory => DbPool::Inventory,
+    }
+}
+
+/// Table, balance and key column of a configured currency, which are spliced into SQL.
+fn currency_columns(currency: &CurrencyDef) -> Result<(&str, &str, &str)> {
+    let names = [&currency.table, &currency.column, &currency.key];
+    if let Some(name) = names.iter().find(|name| !is_identifier(name)) {
+        bail!("Invalid column name {name:?} for {}", currency.name);
+    }
+    Ok((&currency.table, &currency.column, &currency.key))
+}
+
+fn is_identifier(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
 fn hash_password(password: &str) -> String {
     let digest = md5::compute(password);
     format!("{:x}", digest)
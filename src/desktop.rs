@@ -0,0 +1,100 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+
+/// Where a launcher shortcut is placed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Location {
+    Desktop,
+    StartMenu,
+}
+
+/// Creates a shortcut named `name` that starts this launcher with `args` from its own
+/// directory, and returns the file written.
+pub fn create_shortcut(location: Location, name: &str, args: &[String]) -> Result<PathBuf> {
+    let exe = std::env::current_exe().context("locate launcher")?;
+    let dir = exe.parent().map(PathBuf::from).unwrap_or_default();
+    let name: String = name.chars().filter(|c| !"\\/:*?\"<>|".contains(*c)).collect();
+    write_shortcut(location, name.trim(), &exe, &dir, args)
+}
+
+/// Windows has no plain-file shortcut format, so the `.lnk` is written through the
+/// `WScript.Shell` COM object from PowerShell.
+#[cfg(windows)]
+fn write_shortcut(
+    location: Location,
+    name: &str,
+    exe: &std::path::Path,
+    dir: &std::path::Path,
+    args: &[String],
+) -> Result<PathBuf> {
+    use std::os::windows::process::CommandExt;
+
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    fn quote(value: &str) -> String {
+        format!("'{}'", value.replace('\'', "''"))
+    }
+
+    let folder = match location {
+        Location::Desktop => "Desktop",
+        Location::StartMenu => "Programs",
+    };
+    let script = format!(
+        "$path = Join-Path ([Environment]::GetFolderPath('{folder}')) {name}; \
+         $link = (New-Object -ComObject WScript.Shell).CreateShortcut($path); \
+         $link.TargetPath = {exe}; $link.Arguments = {args}; $link.WorkingDirectory = {dir}; \
+         $link.IconLocation = {exe}; $link.Save(); Write-Output $path",
+        name = quote(&format!("{name}.lnk")),
+        exe = quote(&exe.to_string_lossy()),
+        args = quote(&crate::launch::join_args(args)),
+        dir = quote(&dir.to_string_lossy()),
+    );
+    let output = std::process::Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .context("run powershell")?;
+    if !output.status.success() {
+        let err = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Creating the shortcut failed: {}", err.trim());
+    }
+    Ok(PathBuf::from(String::from_utf8_lossy(&output.stdout).trim()))
+}
+
+/// Freedesktop `.desktop` entry on the desktop or in the applications menu.
+#[cfg(not(windows))]
+fn write_shortcut(
+    location: Location,
+    name: &str,
+    exe: &std::path::Path,
+    dir: &std::path::Path,
+    args: &[String],
+) -> Result<PathBuf> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let home = std::env::var_os("HOME").map(PathBuf::from).context("HOME not set")?;
+    let folder = match location {
+        Location::Desktop => std::env::var_os("XDG_DESKTOP_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| home.join("Desktop")),
+        Location::StartMenu => home.join(".local").join("share").join("applications"),
+    };
+    std::fs::create_dir_all(&folder).context("create shortcut folder")?;
+    let quote = |value: &str| format!("\"{}\"", value.replace(['"', '`', '$', '\\'], ""));
+    let mut exec = quote(&exe.to_string_lossy());
+    for arg in args {
+        exec.push(' ');
+        exec.push_str(&quote(arg));
+    }
+    let entry = format!(
+        "[Desktop Entry]\nType=Application\nName={name}\nExec={exec}\nPath={}\n\
+         Icon=applications-games\nTerminal=false\n",
+        dir.display()
+    );
+    let path = folder.join(format!("{}.desktop", name.replace(' ', "_")));
+    std::fs::write(&path, entry).context("write shortcut")?;
+    // Desktop environments only offer to run entries marked executable.
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+    Ok(path)
+}
@@ -0,0 +1,67 @@
+use anyhow::{Context, Result};
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+use rsa::rand_core::{OsRng, RngCore};
+
+use crate::config::SmtpConfig;
+
+/// Sends account emails through the configured SMTP relay. Sending blocks; call it from
+/// `spawn_blocking`.
+#[derive(Clone)]
+pub struct Mailer {
+    transport: SmtpTransport,
+    from: Mailbox,
+}
+
+impl Mailer {
+    pub fn new(cfg: &SmtpConfig) -> Result<Self> {
+        let builder = if cfg.port == 465 {
+            SmtpTransport::relay(&cfg.host)
+        } else {
+            SmtpTransport::starttls_relay(&cfg.host)
+        }
+        .with_context(|| format!("SMTP relay {}", cfg.host))?
+        .port(cfg.port);
+        let builder = if cfg.username.is_empty() {
+            builder
+        } else {
+            builder.credentials(Credentials::new(cfg.username.clone(), cfg.password.clone()))
+        };
+        Ok(Self {
+            transport: builder.build(),
+            from: cfg.from.parse().context("SMTP_FROM is not a valid address")?,
+        })
+    }
+
+    pub fn send(&self, to: &str, subject: &str, body: &str) -> Result<()> {
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(to.parse().context("Invalid email address")?)
+            .subject(subject)
+            .body(body.to_string())?;
+        self.transport.send(&message).context("send email")?;
+        Ok(())
+    }
+}
+
+/// Six-digit code for email verification and password resets.
+pub fn verification_code() -> String {
+    format!("{:06}", OsRng.next_u32() % 1_000_000)
+}
+
+/// Hides most of the local part, e.g. `j***@example.com`, for on-screen confirmations.
+pub fn mask(address: &str) -> String {
+    match address.split_once('@') {
+        Some((local, domain)) => {
+            let first = local.chars().next().unwrap_or('*');
+            format!("{first}***@{domain}")
+        }
+        None => "***".to_string(),
+    }
+}
+
+/// Rough shape check before spending a verification email on an address.
+pub fn is_valid_address(address: &str) -> bool {
+    address.parse::<Mailbox>().is_ok_and(|mailbox| mailbox.name.is_none())
+}
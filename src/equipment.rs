@@ -0,0 +1,101 @@
+use std::io::Read;
+
+use anyhow::{Context, Result, bail};
+use flate2::read::ZlibDecoder;
+
+/// Size of one item record inside the inventory blobs.
+const RECORD_SIZE: usize = 61;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EquipSlot {
+    Weapon,
+    Title,
+    Top,
+    Shoulder,
+    Bottom,
+    Shoes,
+    Belt,
+    Necklace,
+    Bracelet,
+    Ring,
+    Support,
+    MagicStone,
+    Earring,
+}
+
+impl EquipSlot {
+    /// Record order inside `inventory.equipslot`.
+    const ORDER: [Self; 13] = [
+        Self::Weapon,
+        Self::Title,
+        Self::Top,
+        Self::Shoulder,
+        Self::Bottom,
+        Self::Shoes,
+        Self::Belt,
+        Self::Necklace,
+        Self::Bracelet,
+        Self::Ring,
+        Self::Support,
+        Self::MagicStone,
+        Self::Earring,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Weapon => "Weapon",
+            Self::Title => "Title",
+            Self::Top => "Top",
+            Self::Shoulder => "Shoulder",
+            Self::Bottom => "Bottom",
+            Self::Shoes => "Shoes",
+            Self::Belt => "Belt",
+            Self::Necklace => "Necklace",
+            Self::Bracelet => "Bracelet",
+            Self::Ring => "Ring",
+            Self::Support => "Support",
+            Self::MagicStone => "Magic Stone",
+            Self::Earring => "Earring",
+        }
+    }
+}
+
+impl std::fmt::Display for EquipSlot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Equipment {
+    pub slot: EquipSlot,
+    pub item_id: i32,
+    pub reinforce: u8,
+}
+
+/// Decodes the `equipslot` blob: a little-endian `u32` with the unpacked size followed by a
+/// zlib stream of fixed-size item records. Each record keeps the item ID at bytes 2..6 and the
+/// reinforcement level at byte 6; empty slots have item ID 0.
+pub fn decode(blob: &[u8]) -> Result<Vec<Equipment>> {
+    let Some((header, packed)) = blob.split_first_chunk::<4>() else {
+        bail!("equipment data is truncated");
+    };
+    let size = u32::from_le_bytes(*header) as usize;
+    let mut data = Vec::with_capacity(size);
+    ZlibDecoder::new(packed)
+        .take(size as u64)
+        .read_to_end(&mut data)
+        .context("unpack equipment data")?;
+    Ok(data
+        .chunks_exact(RECORD_SIZE)
+        .zip(EquipSlot::ORDER)
+        .filter_map(|(record, slot)| {
+            let item_id = i32::from_le_bytes(record[2..6].try_into().ok()?);
+            (item_id > 0).then_some(Equipment {
+                slot,
+                item_id,
+                reinforce: record[6],
+            })
+        })
+        .collect())
+}
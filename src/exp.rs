@@ -0,0 +1,30 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+/// Cumulative experience required to reach each level.
+///
+/// The file holds one number per line; line `n` is the total exp at level `n`.
+#[derive(Default)]
+pub struct ExpTable {
+    cumulative: Vec<i64>,
+}
+
+impl ExpTable {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let data = fs::read_to_string(path).context("read exp table")?;
+        let cumulative = data
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| line.parse::<i64>().context("invalid exp value"))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { cumulative })
+    }
+
+    pub fn exp_for_level(&self, level: i32) -> Option<i64> {
+        let idx = usize::try_from(level).ok()?.checked_sub(1)?;
+        self.cumulative.get(idx).copied()
+    }
+}
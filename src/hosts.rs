@@ -0,0 +1,163 @@
+use std::fs;
+use std::net::{IpAddr, ToSocketAddrs};
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+
+/// Appended to the lines the launcher writes so players can tell where they came from.
+const MARKER: &str = "# added by ADNF launcher";
+
+/// Argument that makes the launcher copy a staged hosts file into place and exit; passed to
+/// the elevated copy started by [`fix`].
+#[cfg(windows)]
+pub const WRITE_ARG: &str = "--write-hosts";
+
+/// A hostname the client connects to and the address it has to resolve to.
+#[derive(Clone, Debug)]
+pub struct Redirect {
+    pub host: String,
+    pub ip: IpAddr,
+}
+
+impl Redirect {
+    /// Parses a `host=ip` entry.
+    pub fn parse(entry: &str) -> Option<Self> {
+        let (host, ip) = entry.split_once('=')?;
+        let host = host.trim().to_ascii_lowercase();
+        let ip = ip.trim().parse().ok()?;
+        (!host.is_empty()).then_some(Self { host, ip })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct HostsCheck {
+    pub redirect: Redirect,
+    /// Addresses the system resolver returned, first one first; empty when lookup failed.
+    pub resolved: Vec<IpAddr>,
+}
+
+impl HostsCheck {
+    pub fn ok(&self) -> bool {
+        self.resolved.first() == Some(&self.redirect.ip)
+    }
+}
+
+pub fn check(redirects: &[Redirect]) -> Vec<HostsCheck> {
+    redirects
+        .iter()
+        .map(|redirect| HostsCheck {
+            redirect: redirect.clone(),
+            resolved: resolve(&redirect.host),
+        })
+        .collect()
+}
+
+fn resolve(host: &str) -> Vec<IpAddr> {
+    match (host, 0).to_socket_addrs() {
+        Ok(addrs) => {
+            let mut ips: Vec<IpAddr> = Vec::new();
+            for ip in addrs.map(|addr| addr.ip()) {
+                if !ips.contains(&ip) {
+                    ips.push(ip);
+                }
+            }
+            ips
+        }
+        Err(err) => {
+            tracing::debug!("hosts: resolve {host}: {err}");
+            Vec::new()
+        }
+    }
+}
+
+pub fn hosts_path() -> PathBuf {
+    #[cfg(windows)]
+    {
+        let root = std::env::var_os("SystemRoot")
+            .map_or_else(|| PathBuf::from("C:\\Windows"), PathBuf::from);
+        root.join("System32").join("drivers").join("etc").join("hosts")
+    }
+    #[cfg(not(windows))]
+    PathBuf::from("/etc/hosts")
+}
+
+/// Rewrites the hosts file so every redirect resolves as expected. Writing it needs
+/// administrator rights on Windows, so there a denied write is handed to an elevated copy of
+/// the launcher, which finishes after this returns.
+pub fn fix(redirects: &[Redirect]) -> Result<()> {
+    let path = hosts_path();
+    let current = fs::read_to_string(&path).context("read hosts file")?;
+    let contents = patched(&current, redirects);
+    match fs::write(&path, &contents) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == std::io::ErrorKind::PermissionDenied => {
+            write_elevated(&contents)
+        }
+        Err(err) => Err(err).context("write hosts file"),
+    }
+}
+
+/// `current` with every mapping of a redirected hostname removed and the expected ones
+/// appended, keeping other names on shared lines, comments and the file's line endings.
+fn patched(current: &str, redirects: &[Redirect]) -> String {
+    let newline = if current.contains("\r\n") { "\r\n" } else { "\n" };
+    let redirected = |name: &str| redirects.iter().any(|r| r.host.eq_ignore_ascii_case(name));
+    let mut lines: Vec<String> = current
+        .lines()
+        .filter_map(|line| {
+            let (content, comment) = match line.split_once('#') {
+                Some((content, comment)) => (content, Some(comment)),
+                None => (line, None),
+            };
+            let mut fields = content.split_whitespace();
+            let Some(ip) = fields.next() else {
+                return Some(line.to_string());
+            };
+            let names: Vec<&str> = fields.collect();
+            if !names.iter().any(|name| redirected(name)) {
+                return Some(line.to_string());
+            }
+            let kept: Vec<&str> = names.into_iter().filter(|name| !redirected(name)).collect();
+            if kept.is_empty() {
+                return None;
+            }
+            let mut line = format!("{ip} {}", kept.join(" "));
+            if let Some(comment) = comment {
+                line.push_str(&format!(" #{comment}"));
+            }
+            Some(line)
+        })
+        .collect();
+    while lines.last().is_some_and(|line| line.trim().is_empty()) {
+        lines.pop();
+    }
+    for redirect in redirects {
+        lines.push(format!("{} {} {MARKER}", redirect.ip, redirect.host));
+    }
+    lines.push(String::new());
+    lines.join(newline)
+}
+
+#[cfg(windows)]
+fn write_elevated(contents: &str) -> Result<()> {
+    let staged = std::env::temp_dir().join("adnf_hosts.txt");
+    fs::write(&staged, contents).context("stage hosts file")?;
+    let exe = std::env::current_exe().context("locate launcher")?;
+    let args = [WRITE_ARG.to_string(), staged.display().to_string()];
+    crate::launch::spawn_elevated(&exe.to_string_lossy(), &args)?;
+    Ok(())
+}
+
+#[cfg(not(windows))]
+fn write_elevated(_contents: &str) -> Result<()> {
+    anyhow::bail!("Permission denied; add the entries to {} as root", hosts_path().display())
+}
+
+/// Runs in the elevated launcher: copies the staged file over the hosts file.
+#[cfg(windows)]
+pub fn install(staged: &std::path::Path) -> Result<()> {
+    let contents = fs::read_to_string(staged).context("read staged hosts file")?;
+    fs::write(hosts_path(), contents).context("write hosts file")?;
+    let _ = fs::remove_file(staged);
+    Ok(())
+}
@@ -0,0 +1,139 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use ureq::{Proxy, ProxyProtocol};
+
+use crate::config::{ProxyMode, ProxySettings};
+
+/// Longest a server may take to accept a request and answer with headers.
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Longest a whole manifest, news or release document may take, body included. Patch
+/// downloads stream without an overall limit since large files legitimately take long.
+pub const DOCUMENT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Builds the agent used for all launcher HTTP traffic, routed through the user's proxy.
+pub fn agent(proxy: &ProxySettings) -> ureq::Agent {
+    let config = ureq::Agent::config_builder()
+        .user_agent(concat!("adnf-launcher/", env!("CARGO_PKG_VERSION")))
+        .timeout_connect(Some(Duration::from_secs(10)))
+        .timeout_send_request(Some(RESPONSE_TIMEOUT))
+        .timeout_recv_response(Some(RESPONSE_TIMEOUT))
+        .proxy(resolve_proxy(proxy))
+        .build();
+    ureq::Agent::new_with_config(config)
+}
+
+pub fn fetch_bytes(agent: &ureq::Agent, url: &str) -> Result<Vec<u8>> {
+    fetch_bytes_within(agent, url, DOCUMENT_TIMEOUT)
+}
+
+/// `fetch_bytes` with a tighter overall limit, for checks that hold up the UI.
+pub fn fetch_bytes_within(agent: &ureq::Agent, url: &str, timeout: Duration) -> Result<Vec<u8>> {
+    agent
+        .get(url)
+        .config()
+        .timeout_global(Some(timeout))
+        .build()
+        .call()
+        .with_context(|| format!("fetch {url}"))?
+        .body_mut()
+        .read_to_vec()
+        .with_context(|| format!("read {url}"))
+}
+
+pub fn post_bytes(agent: &ureq::Agent, url: &str, content_type: &str, body: &[u8]) -> Result<()> {
+    agent
+        .post(url)
+        .header("Content-Type", content_type)
+        .send(body)
+        .with_context(|| format!("post {url}"))?;
+    Ok(())
+}
+
+/// One field of a `multipart/form-data` body; a file upload when `filename` is set.
+pub struct Part<'a> {
+    pub name: &'a str,
+    pub filename: Option<&'a str>,
+    pub content_type: &'a str,
+    pub data: &'a [u8],
+}
+
+pub fn post_multipart(agent: &ureq::Agent, url: &str, parts: &[Part]) -> Result<()> {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let boundary = format!("adnf-{nanos:x}");
+    let mut body = Vec::new();
+    for part in parts {
+        body.extend_from_slice(format!("--{boundary}\r\n").as_bytes());
+        let disposition = match part.filename {
+            Some(filename) => format!("form-data; name=\"{}\"; filename=\"{filename}\"", part.name),
+            None => format!("form-data; name=\"{}\"", part.name),
+        };
+        body.extend_from_slice(
+            format!(
+                "Content-Disposition: {disposition}\r\nContent-Type: {}\r\n\r\n",
+                part.content_type
+            )
+            .as_bytes(),
+        );
+        body.extend_from_slice(part.data);
+        body.extend_from_slice(b"\r\n");
+    }
+    body.extend_from_slice(format!("--{boundary}--\r\n").as_bytes());
+    post_bytes(agent, url, &format!("multipart/form-data; boundary={boundary}"), &body)
+}
+
+fn resolve_proxy(settings: &ProxySettings) -> Option<Proxy> {
+    match settings.mode {
+        ProxyMode::Off => None,
+        ProxyMode::System => Proxy::try_from_env().or_else(system_proxy),
+        ProxyMode::Manual => {
+            let mut builder = Proxy::builder(ProxyProtocol::Http)
+                .host(settings.host.trim())
+                .port(settings.port);
+            if !settings.username.is_empty() {
+                builder = builder.username(&settings.username).password(&settings.password);
+            }
+            builder
+                .build()
+                .inspect_err(|err| tracing::warn!("http: invalid proxy settings: {err}"))
+                .ok()
+        }
+    }
+}
+
+/// The proxy configured in Windows' Internet Options, e.g. `host:port` or
+/// `http=host:port;https=host:port`.
+#[cfg(windows)]
+fn system_proxy() -> Option<Proxy> {
+    use winreg::RegKey;
+    use winreg::enums::HKEY_CURRENT_USER;
+
+    let key = RegKey::predef(HKEY_CURRENT_USER)
+        .open_subkey(r"Software\Microsoft\Windows\CurrentVersion\Internet Settings")
+        .ok()?;
+    let enabled: u32 = key.get_value("ProxyEnable").ok()?;
+    if enabled == 0 {
+        return None;
+    }
+    let server: String = key.get_value("ProxyServer").ok()?;
+    let server = if server.contains('=') {
+        let entries: Vec<(&str, &str)> =
+            server.split(';').filter_map(|entry| entry.split_once('=')).collect();
+        ["https", "http"]
+            .iter()
+            .find_map(|scheme| entries.iter().find(|(s, _)| s == scheme).map(|(_, v)| *v))?
+            .to_string()
+    } else {
+        server
+    };
+    Proxy::new(&format!("http://{}", server.trim())).ok()
+}
+
+#[cfg(not(windows))]
+fn system_proxy() -> Option<Proxy> {
+    None
+}
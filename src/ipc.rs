@@ -0,0 +1,61 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::Duration;
+
+/// Hands our command line to an already running launcher, which focuses its window and
+/// acts on the arguments. Returns `false` when no launcher is listening.
+pub fn forward(port: u16, args: &[String]) -> bool {
+    let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    let Ok(mut stream) = TcpStream::connect_timeout(&addr, Duration::from_millis(500)) else {
+        return false;
+    };
+    let message = serde_json::to_string(args).unwrap_or_else(|_| "[]".to_string());
+    match writeln!(stream, "{message}") {
+        Ok(()) => true,
+        Err(err) => {
+            tracing::warn!("ipc: failed to forward to running launcher: {err}");
+            false
+        }
+    }
+}
+
+/// Accepts forwarded command lines from later launcher processes on a loopback port.
+///
+/// Holding the port doubles as the single-instance lock.
+pub struct IpcListener {
+    messages: Receiver<Vec<String>>,
+}
+
+impl IpcListener {
+    pub fn bind(port: u16) -> std::io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+        let (tx, messages) = mpsc::channel();
+        thread::Builder::new().name("ipc".to_string()).spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else {
+                    continue;
+                };
+                let _ = stream.set_read_timeout(Some(Duration::from_secs(2)));
+                let mut line = String::new();
+                if BufReader::new(stream).read_line(&mut line).is_err() {
+                    continue;
+                }
+                match serde_json::from_str::<Vec<String>>(line.trim()) {
+                    Ok(args) => {
+                        if tx.send(args).is_err() {
+                            return;
+                        }
+                    }
+                    Err(err) => tracing::warn!("ipc: ignoring malformed message: {err}"),
+                }
+            }
+        })?;
+        Ok(Self { messages })
+    }
+
+    pub fn try_recv(&self) -> Option<Vec<String>> {
+        self.messages.try_recv().ok()
+    }
+}
@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Rarity {
+    Common,
+    Uncommon,
+    Rare,
+    Unique,
+    Legendary,
+    Epic,
+}
+
+impl Rarity {
+    pub fn from_id(id: i32) -> Self {
+        match id {
+            1 => Self::Uncommon,
+            2 => Self::Rare,
+            3 => Self::Unique,
+            4 => Self::Legendary,
+            5 => Self::Epic,
+            _ => Self::Common,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ItemInfo {
+    pub name: String,
+    pub rarity: Rarity,
+}
+
+/// Item names and rarities exported from the server's item list.
+///
+/// The file is tab separated: `id<TAB>name[<TAB>rarity]`, one item per line.
+#[derive(Default)]
+pub struct ItemDb {
+    items: HashMap<i32, ItemInfo>,
+}
+
+impl ItemDb {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let data = fs::read_to_string(path).context("read item database")?;
+        let items = data
+            .lines()
+            .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let mut cols = line.split('\t');
+                let id = cols.next()?.trim().parse::<i32>().ok()?;
+                let name = cols.next()?.trim().to_string();
+                let rarity = cols
+                    .next()
+                    .and_then(|r| r.trim().parse::<i32>().ok())
+                    .map(Rarity::from_id)
+                    .unwrap_or(Rarity::Common);
+                Some((id, ItemInfo { name, rarity }))
+            })
+            .collect();
+        Ok(Self { items })
+    }
+
+    pub fn get(&self, id: i32) -> Option<&ItemInfo> {
+        self.items.get(&id)
+    }
+
+    pub fn name(&self, id: i32) -> String {
+        self.get(id)
+            .map(|item| item.name.clone())
+            .unwrap_or_else(|| format!("Item {id}"))
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
@@ -0,0 +1,224 @@
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::config::{AppConfig, LaunchConfig, LaunchMode, UserConfig};
+
+/// Directory holding the client executable.
+pub fn game_dir(exe_path: &str) -> PathBuf {
+    Path::new(exe_path).parent().map(Path::to_path_buf).unwrap_or_default()
+}
+
+/// One line of the game folder checklist.
+#[derive(Clone, Debug)]
+pub struct FolderCheck {
+    pub item: String,
+    pub ok: bool,
+}
+
+/// Checks the client executable, then each of `required` relative to the game directory.
+pub fn check_game_folder(exe_path: &str, required: &[String]) -> Vec<FolderCheck> {
+    let dir = game_dir(exe_path);
+    let mut checks = vec![FolderCheck {
+        item: format!("Client executable ({exe_path})"),
+        ok: Path::new(exe_path).is_file(),
+    }];
+    checks.extend(required.iter().map(|entry| FolderCheck {
+        item: entry.clone(),
+        ok: dir.join(entry).exists(),
+    }));
+    checks
+}
+
+/// Client arguments: the login token, then realm extras and the preferred channel.
+pub fn client_args(app_config: &AppConfig, user: &UserConfig, token: &str) -> Vec<String> {
+    let mut args = vec![token.to_string()];
+    args.extend_from_slice(&app_config.realm(user.realm.as_deref()).launch_args);
+    if let (Some(template), Some(channel)) = (&app_config.channel_arg, user.preferred_channel) {
+        args.push(template.replace("{channel}", &channel.to_string()));
+    }
+    args
+}
+
+/// Builds the process command for the client executable, wrapping it in Wine or Proton
+/// when the launcher is not running the Windows binary directly.
+pub fn client_command(launch: &LaunchConfig, exe_path: &str) -> Command {
+    match launch.mode {
+        LaunchMode::Native => Command::new(exe_path),
+        LaunchMode::Wine => {
+            let mut command = Command::new(&launch.wine_binary);
+            command.arg(exe_path);
+            if let Some(prefix) = &launch.wine_prefix {
+                command.env("WINEPREFIX", prefix);
+            }
+            command.current_dir(working_dir(exe_path));
+            command
+        }
+        LaunchMode::Proton => {
+            let mut command = Command::new(&launch.wine_binary);
+            command.arg("run").arg(exe_path);
+            if let Some(prefix) = &launch.wine_prefix {
+                command.env("STEAM_COMPAT_DATA_PATH", prefix);
+            }
+            // Proton refuses to start without this even outside Steam.
+            if env::var_os("STEAM_COMPAT_CLIENT_INSTALL_PATH").is_none()
+                && let Some(home) = env::var_os("HOME")
+            {
+                let steam = PathBuf::from(home).join(".steam").join("steam");
+                command.env("STEAM_COMPAT_CLIENT_INSTALL_PATH", steam);
+            }
+            command.current_dir(working_dir(exe_path));
+            command
+        }
+    }
+}
+
+/// Installed client version: the first line of the version file in the game directory, or on
+/// Windows the file version resource of the executable.
+pub fn client_version(exe_path: &str, version_file: &str) -> Option<String> {
+    let from_file = std::fs::read_to_string(game_dir(exe_path).join(version_file))
+        .ok()
+        .and_then(|text| text.lines().next().map(|line| line.trim().to_string()))
+        .filter(|line| !line.is_empty());
+    #[cfg(windows)]
+    let from_file = from_file.or_else(|| file_version(exe_path));
+    from_file
+}
+
+/// Reads `VS_FIXEDFILEINFO` from the executable, formatted as `major.minor.build.revision`.
+#[cfg(windows)]
+fn file_version(exe_path: &str) -> Option<String> {
+    use std::ffi::OsStr;
+    use std::os::windows::ffi::OsStrExt;
+
+    use windows_sys::Win32::Storage::FileSystem::{
+        GetFileVersionInfoSizeW, GetFileVersionInfoW, VS_FIXEDFILEINFO, VerQueryValueW,
+    };
+
+    let path: Vec<u16> = OsStr::new(exe_path).encode_wide().chain(Some(0)).collect();
+    let root: Vec<u16> = OsStr::new("\\").encode_wide().chain(Some(0)).collect();
+    // SAFETY: both strings are NUL terminated, and `data` is sized as the first call asks.
+    unsafe {
+        let size = GetFileVersionInfoSizeW(path.as_ptr(), std::ptr::null_mut());
+        if size == 0 {
+            return None;
+        }
+        let mut data = vec![0u8; size as usize];
+        if GetFileVersionInfoW(path.as_ptr(), 0, size, data.as_mut_ptr().cast()) == 0 {
+            return None;
+        }
+        let mut info: *mut std::ffi::c_void = std::ptr::null_mut();
+        let mut len = 0u32;
+        if VerQueryValueW(data.as_ptr().cast(), root.as_ptr(), &mut info, &mut len) == 0
+            || info.is_null()
+            || (len as usize) < size_of::<VS_FIXEDFILEINFO>()
+        {
+            return None;
+        }
+        let info = &*info.cast::<VS_FIXEDFILEINFO>();
+        Some(format!(
+            "{}.{}.{}.{}",
+            info.dwFileVersionMS >> 16,
+            info.dwFileVersionMS & 0xffff,
+            info.dwFileVersionLS >> 16,
+            info.dwFileVersionLS & 0xffff
+        ))
+    }
+}
+
+fn working_dir(exe_path: &str) -> PathBuf {
+    Path::new(exe_path)
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// Starts the client through `ShellExecuteExW` with the `runas` verb so Windows shows the
+/// UAC prompt. Returns the pid of the elevated process when the shell hands one back.
+#[cfg(windows)]
+pub fn spawn_elevated(exe_path: &str, args: &[String]) -> anyhow::Result<Option<u32>> {
+    use std::ffi::OsStr;
+    use std::os::windows::ffi::OsStrExt;
+
+    use windows_sys::Win32::Foundation::{CloseHandle, ERROR_CANCELLED, GetLastError};
+    use windows_sys::Win32::System::Threading::GetProcessId;
+    use windows_sys::Win32::UI::Shell::{
+        SEE_MASK_NOCLOSEPROCESS, SHELLEXECUTEINFOW, ShellExecuteExW,
+    };
+    use windows_sys::Win32::UI::WindowsAndMessaging::SW_SHOWNORMAL;
+
+    fn wide(value: impl AsRef<OsStr>) -> Vec<u16> {
+        value.as_ref().encode_wide().chain(Some(0)).collect()
+    }
+
+    let verb = wide("runas");
+    let file = wide(exe_path);
+    let params = wide(join_args(args));
+    let dir = wide(working_dir(exe_path));
+    let mut info = SHELLEXECUTEINFOW {
+        cbSize: size_of::<SHELLEXECUTEINFOW>() as u32,
+        fMask: SEE_MASK_NOCLOSEPROCESS,
+        lpVerb: verb.as_ptr(),
+        lpFile: file.as_ptr(),
+        lpParameters: params.as_ptr(),
+        lpDirectory: dir.as_ptr(),
+        nShow: SW_SHOWNORMAL,
+        ..Default::default()
+    };
+    // SAFETY: every string pointer in `info` outlives the call and is NUL terminated.
+    if unsafe { ShellExecuteExW(&mut info) } == 0 {
+        let code = unsafe { GetLastError() };
+        if code == ERROR_CANCELLED {
+            anyhow::bail!("Administrator permission was declined; the game was not started");
+        }
+        let err = std::io::Error::from_raw_os_error(code as i32);
+        anyhow::bail!("Elevated launch failed: {err}");
+    }
+    if info.hProcess.is_null() {
+        return Ok(None);
+    }
+    // SAFETY: SEE_MASK_NOCLOSEPROCESS hands us ownership of a valid process handle.
+    let pid = unsafe {
+        let pid = GetProcessId(info.hProcess);
+        CloseHandle(info.hProcess);
+        pid
+    };
+    Ok((pid != 0).then_some(pid))
+}
+
+/// Joins arguments into a single command line using the MSVC quoting rules.
+#[cfg(windows)]
+pub fn join_args(args: &[String]) -> String {
+    let mut line = String::new();
+    for arg in args {
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        if !arg.is_empty() && !arg.contains([' ', '\t', '"']) {
+            line.push_str(arg);
+            continue;
+        }
+        line.push('"');
+        let mut backslashes = 0;
+        for ch in arg.chars() {
+            match ch {
+                '\\' => backslashes += 1,
+                '"' => {
+                    line.extend(std::iter::repeat_n('\\', backslashes * 2 + 1));
+                    backslashes = 0;
+                }
+                _ => {
+                    line.extend(std::iter::repeat_n('\\', backslashes));
+                    backslashes = 0;
+                }
+            }
+            if ch != '\\' {
+                line.push(ch);
+            }
+        }
+        line.extend(std::iter::repeat_n('\\', backslashes * 2));
+        line.push('"');
+    }
+    line
+}
@@ -0,0 +1,151 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+/// Config files written by older private-server launchers, looked for in each search directory.
+const CANDIDATES: &[&str] = &[
+    "DNFLauncher.ini",
+    "Launcher.ini",
+    "launcher.ini",
+    "DNF.ini",
+    "config.ini",
+    "launcher_config.json",
+    "dnf_launcher.json",
+    "settings.json",
+];
+
+/// Settings recovered from another launcher's config file.
+#[derive(Clone, Debug, Default)]
+pub struct LegacyConfig {
+    pub source: PathBuf,
+    /// `host:port` of the game server.
+    pub server_addr: Option<String>,
+    /// Client executable, or the game directory when the old launcher only stored that.
+    pub game_path: Option<String>,
+    pub usernames: Vec<String>,
+}
+
+impl LegacyConfig {
+    pub fn is_empty(&self) -> bool {
+        self.server_addr.is_none() && self.game_path.is_none() && self.usernames.is_empty()
+    }
+}
+
+/// Directories old launchers usually sit in or write to: the working directory, the game
+/// directory and, on Windows, the roaming app data folder.
+pub fn search_dirs(game_dir: &Path) -> Vec<PathBuf> {
+    let mut dirs = vec![PathBuf::from("."), game_dir.to_path_buf()];
+    if let Some(appdata) = std::env::var_os("APPDATA") {
+        let appdata = PathBuf::from(appdata);
+        dirs.extend(["DNFLauncher", "DNF Launcher", "DNF"].map(|name| appdata.join(name)));
+    }
+    dirs
+}
+
+/// Every candidate file in `dirs` that yields at least one setting.
+pub fn detect(dirs: &[PathBuf]) -> Vec<LegacyConfig> {
+    let mut found = Vec::new();
+    for dir in dirs {
+        for name in CANDIDATES {
+            let path = dir.join(name);
+            if !path.is_file() {
+                continue;
+            }
+            match parse_file(&path) {
+                Ok(legacy) if !legacy.is_empty() => found.push(legacy),
+                Ok(_) => {}
+                Err(err) => tracing::debug!("legacy: {}: {err:#}", path.display()),
+            }
+        }
+    }
+    found
+}
+
+fn parse_file(path: &Path) -> Result<LegacyConfig> {
+    let bytes = fs::read(path).context("read config")?;
+    // Old launchers often saved in the system code page; keys are ASCII either way.
+    let text = String::from_utf8_lossy(&bytes);
+    let pairs = if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json")) {
+        let value: serde_json::Value = serde_json::from_str(&text).context("parse json")?;
+        let mut pairs = Vec::new();
+        flatten_json(&value, &mut pairs);
+        pairs
+    } else {
+        ini_pairs(&text)
+    };
+    let mut legacy = from_pairs(&pairs);
+    legacy.source = path.to_path_buf();
+    Ok(legacy)
+}
+
+/// `key = value` lines, ignoring sections and comments.
+fn ini_pairs(text: &str) -> Vec<(String, String)> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with([';', '#', '[']))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().trim_matches('"').to_string()))
+        .collect()
+}
+
+/// Leaf values of a JSON document keyed by their own field name; arrays of strings are
+/// joined with commas.
+fn flatten_json(value: &serde_json::Value, out: &mut Vec<(String, String)>) {
+    let serde_json::Value::Object(map) = value else {
+        return;
+    };
+    for (key, value) in map {
+        match value {
+            serde_json::Value::String(text) => out.push((key.clone(), text.clone())),
+            serde_json::Value::Number(number) => out.push((key.clone(), number.to_string())),
+            serde_json::Value::Array(items) => {
+                let names: Vec<&str> = items
+                    .iter()
+                    .filter_map(|item| {
+                        item.as_str().or_else(|| {
+                            ["username", "account", "name"]
+                                .iter()
+                                .find_map(|field| item.get(field)?.as_str())
+                        })
+                    })
+                    .collect();
+                out.push((key.clone(), names.join(",")));
+            }
+            serde_json::Value::Object(_) => flatten_json(value, out),
+            _ => {}
+        }
+    }
+}
+
+fn from_pairs(pairs: &[(String, String)]) -> LegacyConfig {
+    let lookup = |aliases: &[&str]| {
+        pairs.iter().find_map(|(key, value)| {
+            let key = key.to_ascii_lowercase().replace(['_', '-', ' '], "");
+            (aliases.contains(&key.as_str()) && !value.is_empty()).then(|| value.clone())
+        })
+    };
+    let host = lookup(&["serverip", "serveraddress", "serveraddr", "server", "host", "ip"]);
+    let port = lookup(&["serverport", "gameport", "port"]);
+    let server_addr = host.map(|host| match port {
+        Some(port) if !host.contains(':') => format!("{host}:{port}"),
+        _ => host,
+    });
+    let game_path = lookup(&["gamepath", "clientpath", "exepath", "dnfpath", "gamedir"]);
+    let lists = [
+        lookup(&["accounts", "usernames", "savedaccounts"]),
+        lookup(&["username", "account", "lastaccount", "userid", "user"]),
+    ];
+    let mut usernames: Vec<String> = Vec::new();
+    for name in lists.iter().flatten().flat_map(|list| list.split(',')).map(str::trim) {
+        if !name.is_empty() && !usernames.iter().any(|known| known == name) {
+            usernames.push(name.to_string());
+        }
+    }
+    LegacyConfig {
+        source: PathBuf::new(),
+        server_addr,
+        game_path,
+        usernames,
+    }
+}
@@ -0,0 +1,130 @@
+use chrono::{Local, NaiveDateTime, TimeZone};
+use chrono_tz::Tz;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+
+/// How gold and cera amounts are grouped on screen.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NumberFormat {
+    /// 1,234,567
+    #[default]
+    Comma,
+    /// 1.234.567
+    Period,
+    /// 1 234 567
+    Space,
+    /// 123万4567, grouped by ten thousands as in Chinese, Japanese and Korean.
+    Myriad,
+}
+
+impl NumberFormat {
+    pub const ALL: [Self; 4] = [Self::Comma, Self::Period, Self::Space, Self::Myriad];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Comma => "1,234,567",
+            Self::Period => "1.234.567",
+            Self::Space => "1 234 567",
+            Self::Myriad => "123万4567",
+        }
+    }
+
+    pub fn format(self, value: i64) -> String {
+        let sign = if value < 0 { "-" } else { "" };
+        let digits = value.unsigned_abs();
+        let grouped = match self {
+            Self::Comma => group_thousands(digits, ','),
+            Self::Period => group_thousands(digits, '.'),
+            Self::Space => group_thousands(digits, '\u{a0}'),
+            Self::Myriad => group_myriads(digits),
+        };
+        format!("{sign}{grouped}")
+    }
+}
+
+/// Turns database timestamps, which are in the server's timezone, into the time shown on
+/// screen.
+#[derive(Clone, Copy, Debug)]
+pub struct Clock {
+    /// `None` when the server shares this machine's timezone.
+    pub server: Option<Tz>,
+    pub show_server_time: bool,
+}
+
+impl Clock {
+    pub fn display(self, time: NaiveDateTime) -> NaiveDateTime {
+        match self.server {
+            Some(server) if !self.show_server_time => server
+                .from_local_datetime(&time)
+                .earliest()
+                .map_or(time, |t| t.with_timezone(&Local).naive_local()),
+            _ => time,
+        }
+    }
+
+    pub fn format(self, time: NaiveDateTime, pattern: &str) -> String {
+        self.display(time).format(pattern).to_string()
+    }
+}
+
+fn group_thousands(value: u64, separator: char) -> String {
+    let digits = value.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (idx, digit) in digits.chars().enumerate() {
+        if idx > 0 && (digits.len() - idx).is_multiple_of(3) {
+            out.push(separator);
+        }
+        out.push(digit);
+    }
+    out
+}
+
+fn group_myriads(mut value: u64) -> String {
+    const UNITS: [&str; 5] = ["", "万", "億", "兆", "京"];
+    if value == 0 {
+        return "0".to_string();
+    }
+    let mut parts = Vec::new();
+    for unit in UNITS {
+        if value == 0 {
+            break;
+        }
+        let group = value % 10_000;
+        if group > 0 {
+            parts.push(format!("{group}{unit}"));
+        }
+        value /= 10_000;
+    }
+    parts.reverse();
+    parts.concat()
+}
+
+/// System fonts tried, in order, for CJK glyphs egui's bundled fonts lack, such as the
+/// myriad units and Korean or Chinese character names.
+const FALLBACK_FONTS: &[&str] = &[
+    "C:\\Windows\\Fonts\\malgun.ttf",
+    "C:\\Windows\\Fonts\\msyh.ttc",
+    "C:\\Windows\\Fonts\\msgothic.ttc",
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+];
+
+/// Appends the first available system CJK font as a fallback to egui's fonts.
+pub fn install_fallback_font(ctx: &egui::Context) {
+    let Some((path, bytes)) =
+        FALLBACK_FONTS.iter().find_map(|path| Some((path, std::fs::read(path).ok()?)))
+    else {
+        tracing::debug!("fonts: no CJK fallback font found");
+        return;
+    };
+    tracing::info!("fonts: using {path} as CJK fallback");
+    let mut fonts = egui::FontDefinitions::default();
+    fonts
+        .font_data
+        .insert("cjk".to_string(), egui::FontData::from_owned(bytes).into());
+    for family in [egui::FontFamily::Proportional, egui::FontFamily::Monospace] {
+        fonts.families.entry(family).or_default().push("cjk".to_string());
+    }
+    ctx.set_fonts(fonts);
+}
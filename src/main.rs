@@ -1,38 +1,186 @@
 #![windows_subsystem = "windows"]
 mod app;
+mod backup;
 mod config;
+mod crashlog;
 mod db;
+mod desktop;
+mod email;
+mod equipment;
+mod exp;
+mod hosts;
+mod http;
+mod ipc;
+mod items;
+mod launch;
+mod legacy;
+mod locale;
+mod maintenance;
+mod manifest;
+mod markdown;
+mod monitor;
+mod news;
+mod patcher;
+mod ping;
+mod protocol;
+mod quick;
+mod recovery;
+mod registry;
+mod service;
+mod sound;
+mod startup;
+mod support;
+mod taskbar;
 mod theme;
+mod watchdog;
 
 use anyhow::{Context, Result};
 use std::sync::Arc;
 use eframe::egui;
 use tracing_subscriber::EnvFilter;
+use tracing_subscriber::fmt::writer::MakeWriterExt;
 
 fn main() -> Result<()> {
     tracing_subscriber::fmt()
         .with_env_filter(
             EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
         )
+        .with_writer(std::io::stdout.and(support::log_writer))
         .init();
 
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let link = args.iter().find(|arg| arg.starts_with("adnf://")).cloned();
+    // Browsers start protocol handlers in their own working directory.
+    if link.is_some()
+        && let Ok(exe) = std::env::current_exe()
+        && let Some(dir) = exe.parent()
+    {
+        let _ = std::env::set_current_dir(dir);
+    }
+
+    #[cfg(windows)]
+    if let Some(pos) = args.iter().position(|arg| arg == hosts::WRITE_ARG) {
+        let staged = args.get(pos + 1).context("missing staged hosts file")?;
+        return hosts::install(std::path::Path::new(staged)).context("install hosts file");
+    }
+
     let app_config = config::AppConfig::from_env().context("load env config")?;
+    if app_config.dry_run {
+        tracing::warn!("dry run: database writes are logged but not executed");
+    }
+    if let Some(pos) = args.iter().position(|arg| arg == "--quick-launch") {
+        let account = args
+            .get(pos + 1)
+            .filter(|arg| !arg.starts_with('-') && !arg.starts_with("adnf://"));
+        let db = db::Db::new(&app_config).context("load private key")?;
+        return quick::run(&app_config, &db, account.map(String::as_str)).context("quick launch");
+    }
+    if ipc::forward(app_config.ipc_port, &args) {
+        tracing::info!("launcher already running; handed over {} argument(s)", args.len());
+        return Ok(());
+    }
+    let ipc = match ipc::IpcListener::bind(app_config.ipc_port) {
+        Ok(listener) => Some(listener),
+        // Lost a race with another launcher starting at the same time.
+        Err(err)
+            if err.kind() == std::io::ErrorKind::AddrInUse
+                && ipc::forward(app_config.ipc_port, &args) =>
+        {
+            tracing::info!("launcher already running; handed over {} argument(s)", args.len());
+            return Ok(());
+        }
+        Err(err) => {
+            tracing::warn!("ipc: failed to listen on port {}: {err}", app_config.ipc_port);
+            None
+        }
+    };
+    if let Err(err) = protocol::register() {
+        tracing::warn!("protocol: failed to register adnf:// handler: {err:#}");
+    }
+    let link = link.as_deref().and_then(protocol::LaunchLink::parse);
     let db = Arc::new(db::Db::new(&app_config).context("load private key")?);
-    run(app_config, db).context("run app")
+    let items = match items::ItemDb::load(&app_config.item_db_path) {
+        Ok(items) => {
+            tracing::info!("loaded {} items", items.len());
+            items
+        }
+        Err(err) => {
+            tracing::warn!("item database unavailable: {err:#}");
+            items::ItemDb::default()
+        }
+    };
+    run(app_config, db, Arc::new(items), ipc, link).context("run app")
 }
 
-fn run(app_config: config::AppConfig, db: Arc<db::Db>) -> Result<()> {
-    let options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default().with_inner_size([400.0, 650.0]),
-        ..Default::default()
-    };
+fn run(
+    app_config: config::AppConfig,
+    db: Arc<db::Db>,
+    items: Arc<items::ItemDb>,
+    ipc: Option<ipc::IpcListener>,
+    link: Option<protocol::LaunchLink>,
+) -> Result<()> {
+    let user: config::UserConfig = config::read_json("config.json").unwrap_or_default();
+    let mut ipc = ipc;
+    let mut link = link;
+    let mut last_err = None;
+    for (renderer, acceleration) in render_attempts(&user) {
+        let options = eframe::NativeOptions {
+            viewport: egui::ViewportBuilder::default()
+                .with_inner_size([400.0, 682.0])
+                .with_decorations(false),
+            renderer,
+            hardware_acceleration: acceleration,
+            ..Default::default()
+        };
+        let mut started = false;
+        let result = eframe::run_native(
+            "ADNF LAUNCHER",
+            options,
+            Box::new(|cc| {
+                started = true;
+                Ok(Box::new(app::LauncherApp::new(
+                    cc.egui_ctx.clone(),
+                    app_config.clone(),
+                    Arc::clone(&db),
+                    Arc::clone(&items),
+                    ipc.take(),
+                    link.take(),
+                )))
+            }),
+        );
+        match result {
+            Ok(()) => return Ok(()),
+            // Errors after the window came up are not a graphics driver problem.
+            Err(err) if started => anyhow::bail!("run eframe app: {err}"),
+            Err(err) => {
+                tracing::warn!("window: {renderer:?} ({acceleration:?}) failed to start: {err}");
+                last_err = Some(err);
+            }
+        }
+    }
+    let err = last_err.map_or_else(|| "no renderer available".to_string(), |e| e.to_string());
+    anyhow::bail!("run eframe app: {err}")
+}
 
-    eframe::run_native(
-        "ADNF LAUNCHER",
-        options,
-        Box::new(|_cc| Ok(Box::new(app::LauncherApp::new(app_config.clone(), Arc::clone(&db))))),
-    )
-    .map_err(|err| anyhow::anyhow!("run eframe app: {err}"))?;
+/// Renderer and acceleration combinations to try in order: the chosen backend, then the other
+/// one, then the first again without hardware acceleration.
+fn render_attempts(
+    user: &config::UserConfig,
+) -> Vec<(eframe::Renderer, eframe::HardwareAcceleration)> {
+    use eframe::{HardwareAcceleration, Renderer};
 
-    Ok(())
+    let renderers = match user.renderer {
+        config::Renderer::Auto | config::Renderer::Glow => [Renderer::Glow, Renderer::Wgpu],
+        config::Renderer::Wgpu => [Renderer::Wgpu, Renderer::Glow],
+    };
+    let acceleration = if user.software_rendering {
+        HardwareAcceleration::Off
+    } else {
+        HardwareAcceleration::Preferred
+    };
+    let mut attempts: Vec<_> = renderers.iter().map(|&r| (r, acceleration)).collect();
+    if !user.software_rendering {
+        attempts.push((renderers[0], HardwareAcceleration::Off));
+    }
+    attempts
 }
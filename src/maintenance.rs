@@ -0,0 +1,101 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Debug, Default)]
+pub struct CleanupReport {
+    pub files: u64,
+    pub bytes: u64,
+    pub errors: Vec<String>,
+}
+
+/// Resolves configured cache directories; relative entries are taken from the game directory.
+pub fn resolve_dirs(game_dir: &Path, dirs: &[String]) -> Vec<PathBuf> {
+    dirs.iter()
+        .map(|dir| {
+            let path = Path::new(dir);
+            if path.is_absolute() {
+                path.to_path_buf()
+            } else {
+                game_dir.join(path)
+            }
+        })
+        .collect()
+}
+
+/// Counts the files and bytes that `clear_dirs` would remove.
+pub fn scan_dirs(dirs: &[PathBuf]) -> CleanupReport {
+    let mut report = CleanupReport::default();
+    for dir in dirs {
+        walk(dir, &mut report, false);
+    }
+    report
+}
+
+/// Deletes the contents of each directory, keeping the directories themselves. Symlinks and
+/// junctions are left alone, along with the directories that hold them.
+pub fn clear_dirs(dirs: &[PathBuf]) -> CleanupReport {
+    let mut report = CleanupReport::default();
+    for dir in dirs {
+        walk(dir, &mut report, true);
+    }
+    report
+}
+
+/// Returns whether everything under `dir` was removed (or would be), so its caller knows
+/// whether the directory can go too.
+fn walk(dir: &Path, report: &mut CleanupReport, delete: bool) -> bool {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return true,
+        Err(err) => {
+            report.errors.push(format!("{}: {err}", dir.display()));
+            return false;
+        }
+    };
+    let mut emptied = true;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        // Links may point outside the cache, e.g. at the game data; never follow or count them.
+        let Ok(meta) = fs::symlink_metadata(&path) else {
+            emptied = false;
+            continue;
+        };
+        if meta.file_type().is_symlink() {
+            tracing::debug!("maintenance: skipping link {}", path.display());
+            emptied = false;
+            continue;
+        }
+        if meta.is_dir() {
+            if !walk(&path, report, delete) {
+                emptied = false;
+            } else if delete && let Err(err) = fs::remove_dir(&path) {
+                report.errors.push(format!("{}: {err}", path.display()));
+                emptied = false;
+            }
+            continue;
+        }
+        if delete && let Err(err) = fs::remove_file(&path) {
+            report.errors.push(format!("{}: {err}", path.display()));
+            emptied = false;
+            continue;
+        }
+        report.files += 1;
+        report.bytes += meta.len();
+    }
+    emptied
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
@@ -0,0 +1,118 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+
+/// File list published by the server operator, with the expected hash of each client file.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Manifest {
+    pub files: Vec<ManifestFile>,
+    /// Community-hosted base URLs (webseeds) serving the same files as the origin.
+    #[serde(default)]
+    pub mirrors: Vec<String>,
+    /// Client version these files make up, compared against the installed client before launch.
+    #[serde(default)]
+    pub client_version: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ManifestFile {
+    /// Path relative to the game directory, using `/` separators.
+    pub path: String,
+    pub md5: String,
+    #[serde(default)]
+    pub size: Option<u64>,
+    /// Binary diffs that turn an older version of this file into the current one.
+    #[serde(default)]
+    pub patches: Vec<DeltaPatch>,
+}
+
+/// A zstd `--patch-from` diff against the file whose hash is `from_md5`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct DeltaPatch {
+    pub from_md5: String,
+    /// Location of the `.zst` diff, relative to the manifest URL.
+    pub path: String,
+    pub size: u64,
+}
+
+impl Manifest {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let data = std::fs::read_to_string(path).context("read manifest")?;
+        serde_json::from_str(&data).context("parse manifest")
+    }
+
+    pub fn file(&self, path: &str) -> Option<&ManifestFile> {
+        self.files.iter().find(|f| f.path.eq_ignore_ascii_case(path))
+    }
+}
+
+impl ManifestFile {
+    /// Location of this file under `game_dir`. Absolute paths and `..` are refused, so a
+    /// manifest can't reach outside the game folder.
+    pub fn target(&self, game_dir: &Path) -> Result<PathBuf> {
+        let path = Path::new(&self.path);
+        let escapes = path
+            .components()
+            .any(|c| matches!(c, Component::ParentDir | Component::RootDir | Component::Prefix(_)));
+        if path.as_os_str().is_empty() || path.is_absolute() || escapes {
+            bail!("manifest path outside the game folder: {}", self.path);
+        }
+        Ok(game_dir.join(path))
+    }
+}
+
+pub fn md5_file(path: impl AsRef<Path>) -> Result<String> {
+    let mut file = File::open(path)?;
+    let mut ctx = md5::Context::new();
+    let mut buf = vec![0u8; 1 << 20];
+    loop {
+        let read = file.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        ctx.consume(&buf[..read]);
+    }
+    Ok(format!("{:x}", ctx.finalize()))
+}
+
+/// Hashes the listed files under `game_dir` and returns a description of each mismatch.
+/// An empty `paths` list checks every file in the manifest.
+pub fn verify_files(game_dir: &Path, manifest: &Manifest, paths: &[String]) -> Vec<String> {
+    let entries: Vec<(&str, Option<&ManifestFile>)> = if paths.is_empty() {
+        manifest.files.iter().map(|f| (f.path.as_str(), Some(f))).collect()
+    } else {
+        paths.iter().map(|p| (p.as_str(), manifest.file(p))).collect()
+    };
+    let mut problems = Vec::new();
+    for (path, entry) in entries {
+        let Some(entry) = entry else {
+            problems.push(format!("{path}: not in manifest"));
+            continue;
+        };
+        if let Some(problem) = check_file(game_dir, entry) {
+            problems.push(format!("{path}: {problem}"));
+        }
+    }
+    problems
+}
+
+/// Compares one file on disk against its manifest entry, describing the mismatch if any.
+pub fn check_file(game_dir: &Path, entry: &ManifestFile) -> Option<String> {
+    let full_path = match entry.target(game_dir) {
+        Ok(path) => path,
+        Err(err) => return Some(err.to_string()),
+    };
+    if let (Some(expected), Ok(meta)) = (entry.size, std::fs::metadata(&full_path))
+        && meta.len() != expected
+    {
+        return Some("size mismatch".to_string());
+    }
+    match md5_file(&full_path) {
+        Ok(hash) if hash.eq_ignore_ascii_case(&entry.md5) => None,
+        Ok(_) => Some("modified".to_string()),
+        Err(err) => Some(err.to_string()),
+    }
+}
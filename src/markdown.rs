@@ -0,0 +1,189 @@
+use eframe::egui;
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+
+use crate::theme::Theme;
+
+/// Renders the subset of CommonMark operators use in announcements: headings, emphasis,
+/// inline code, links, lists, block quotes, code blocks and rules. Links open the browser.
+pub fn render(ui: &mut egui::Ui, markdown: &str) {
+    let mut renderer = Renderer::default();
+    for event in Parser::new_ext(markdown, Options::ENABLE_STRIKETHROUGH) {
+        renderer.event(ui, event);
+    }
+    renderer.flush(ui);
+}
+
+struct Span {
+    text: egui::RichText,
+    link: Option<String>,
+}
+
+#[derive(Default)]
+struct Renderer {
+    spans: Vec<Span>,
+    strong: bool,
+    emphasis: bool,
+    strike: bool,
+    heading: Option<HeadingLevel>,
+    link: Option<String>,
+    quote: usize,
+    /// Next number for each open list; `None` for bullet lists.
+    lists: Vec<Option<u64>>,
+    code_block: Option<String>,
+}
+
+impl Renderer {
+    fn event(&mut self, ui: &mut egui::Ui, event: Event<'_>) {
+        match event {
+            Event::Start(tag) => self.start(ui, tag),
+            Event::End(tag) => self.end(ui, tag),
+            Event::Text(text) => match &mut self.code_block {
+                Some(code) => code.push_str(&text),
+                None => self.push(self.styled(&text)),
+            },
+            Event::Code(code) => {
+                let text = egui::RichText::new(code.as_ref())
+                    .code()
+                    .background_color(Theme::surface());
+                self.push(text);
+            }
+            Event::SoftBreak => self.push(egui::RichText::new(" ")),
+            Event::HardBreak => self.flush(ui),
+            Event::Rule => {
+                self.flush(ui);
+                ui.separator();
+            }
+            Event::TaskListMarker(done) => {
+                self.push(egui::RichText::new(if done { "☑ " } else { "☐ " }));
+            }
+            _ => {}
+        }
+    }
+
+    fn start(&mut self, ui: &mut egui::Ui, tag: Tag<'_>) {
+        match tag {
+            Tag::Heading { level, .. } => {
+                self.flush(ui);
+                ui.add_space(4.0);
+                self.heading = Some(level);
+            }
+            Tag::Paragraph => self.flush(ui),
+            Tag::BlockQuote(_) => {
+                self.flush(ui);
+                self.quote += 1;
+            }
+            Tag::CodeBlock(_) => {
+                self.flush(ui);
+                self.code_block = Some(String::new());
+            }
+            Tag::List(start) => {
+                self.flush(ui);
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                self.flush(ui);
+                let marker = match self.lists.last_mut() {
+                    Some(Some(n)) => {
+                        *n += 1;
+                        format!("{}. ", *n - 1)
+                    }
+                    _ => "• ".to_string(),
+                };
+                self.push(egui::RichText::new(marker).color(Theme::text_muted()));
+            }
+            Tag::Emphasis => self.emphasis = true,
+            Tag::Strong => self.strong = true,
+            Tag::Strikethrough => self.strike = true,
+            Tag::Link { dest_url, .. } => self.link = Some(dest_url.to_string()),
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, ui: &mut egui::Ui, tag: TagEnd) {
+        match tag {
+            TagEnd::Heading(_) => {
+                self.flush(ui);
+                self.heading = None;
+            }
+            TagEnd::Paragraph | TagEnd::Item => self.flush(ui),
+            TagEnd::BlockQuote(_) => {
+                self.flush(ui);
+                self.quote = self.quote.saturating_sub(1);
+            }
+            TagEnd::CodeBlock => {
+                let code = self.code_block.take().unwrap_or_default();
+                egui::Frame::new()
+                    .fill(Theme::surface())
+                    .corner_radius(egui::CornerRadius::same(6))
+                    .inner_margin(egui::Margin::same(8))
+                    .show(ui, |ui| {
+                        ui.label(egui::RichText::new(code.trim_end()).monospace());
+                    });
+            }
+            TagEnd::List(_) => {
+                self.flush(ui);
+                self.lists.pop();
+            }
+            TagEnd::Emphasis => self.emphasis = false,
+            TagEnd::Strong => self.strong = false,
+            TagEnd::Strikethrough => self.strike = false,
+            TagEnd::Link => self.link = None,
+            _ => {}
+        }
+    }
+
+    fn styled(&self, text: &str) -> egui::RichText {
+        let mut rich = egui::RichText::new(text);
+        if let Some(level) = self.heading {
+            let size = match level {
+                HeadingLevel::H1 => 22.0,
+                HeadingLevel::H2 => 19.0,
+                _ => 17.0,
+            };
+            rich = rich.size(size).strong().color(Theme::text());
+        }
+        if self.strong {
+            rich = rich.strong();
+        }
+        if self.emphasis {
+            rich = rich.italics();
+        }
+        if self.strike {
+            rich = rich.strikethrough();
+        }
+        if self.quote > 0 {
+            rich = rich.color(Theme::text_muted());
+        }
+        rich
+    }
+
+    fn push(&mut self, text: egui::RichText) {
+        self.spans.push(Span {
+            text,
+            link: self.link.clone(),
+        });
+    }
+
+    /// Lays out the pending inline spans as one wrapped block.
+    fn flush(&mut self, ui: &mut egui::Ui) {
+        if self.spans.is_empty() {
+            return;
+        }
+        let indent = (self.lists.len().saturating_sub(1) + self.quote) as f32 * 14.0;
+        let spans = std::mem::take(&mut self.spans);
+        ui.horizontal_wrapped(|ui| {
+            ui.spacing_mut().item_spacing.x = 0.0;
+            ui.add_space(indent);
+            for span in spans {
+                match span.link {
+                    Some(url) => {
+                        ui.hyperlink_to(span.text, &url).on_hover_text(&url);
+                    }
+                    None => {
+                        ui.label(span.text);
+                    }
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,139 @@
+use std::process::{Child, ExitStatus};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Usage {
+    /// Percent of a single core, so it can exceed 100 on multi-core machines.
+    pub cpu: f32,
+    pub memory: u64,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GameState {
+    pub usage: Option<Usage>,
+    pub exited: bool,
+    /// Exit status of a client the launcher spawned itself.
+    pub status: Option<ExitStatus>,
+}
+
+impl GameState {
+    /// Whether the client exited with a failure status rather than being closed normally.
+    pub fn crashed(&self) -> bool {
+        self.status.is_some_and(|status| !status.success())
+    }
+}
+
+/// Watches the launched client on a background thread, sampling its CPU and memory
+/// usage until it exits.
+pub struct GameMonitor {
+    pid: u32,
+    started: SystemTime,
+    state: Arc<Mutex<GameState>>,
+    stop: Arc<AtomicBool>,
+}
+
+impl GameMonitor {
+    pub fn spawn(child: Child) -> Self {
+        Self::watch(child.id(), Some(child))
+    }
+
+    /// Monitors a process the launcher did not spawn itself, e.g. an elevated client.
+    #[cfg(windows)]
+    pub fn attach(pid: u32) -> Self {
+        Self::watch(pid, None)
+    }
+
+    fn watch(pid: u32, mut child: Option<Child>) -> Self {
+        let state = Arc::new(Mutex::new(GameState::default()));
+        let stop = Arc::new(AtomicBool::new(false));
+        let shared = Arc::clone(&state);
+        let stopped = Arc::clone(&stop);
+        let interval = sysinfo::MINIMUM_CPU_UPDATE_INTERVAL.max(Duration::from_secs(1));
+        let spawned = thread::Builder::new()
+            .name("game-monitor".to_string())
+            .spawn(move || {
+                let mut system = System::new();
+                let target = Pid::from_u32(pid);
+                let refresh = ProcessRefreshKind::nothing().with_cpu().with_memory();
+                while !stopped.load(Ordering::Relaxed) {
+                    match child.as_mut().map(Child::try_wait).transpose() {
+                        Ok(None | Some(None)) => {}
+                        Ok(Some(Some(status))) => {
+                            tracing::info!("monitor: game exited with {status}");
+                            if let Ok(mut slot) = shared.lock() {
+                                slot.status = Some(status);
+                            }
+                            set_exited(&shared);
+                            return;
+                        }
+                        Err(err) => {
+                            tracing::warn!("monitor: failed to poll game process: {err}");
+                            set_exited(&shared);
+                            return;
+                        }
+                    }
+                    system.refresh_processes_specifics(
+                        ProcessesToUpdate::Some(&[target]),
+                        true,
+                        refresh,
+                    );
+                    match system.process(target) {
+                        Some(process) => {
+                            if let Ok(mut slot) = shared.lock() {
+                                slot.usage = Some(Usage {
+                                    cpu: process.cpu_usage(),
+                                    memory: process.memory(),
+                                });
+                            }
+                        }
+                        None if child.is_none() => {
+                            tracing::info!("monitor: game process {pid} is gone");
+                            set_exited(&shared);
+                            return;
+                        }
+                        None => {}
+                    }
+                    thread::sleep(interval);
+                }
+            });
+        if let Err(err) = spawned {
+            tracing::warn!("monitor: failed to start game monitor: {err}");
+        }
+        Self {
+            pid,
+            started: SystemTime::now(),
+            state,
+            stop,
+        }
+    }
+
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    pub fn started(&self) -> SystemTime {
+        self.started
+    }
+
+    pub fn state(&self) -> GameState {
+        self.state.lock().map(|state| *state).unwrap_or_default()
+    }
+}
+
+impl Drop for GameMonitor {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+fn set_exited(state: &Mutex<GameState>) {
+    if let Ok(mut slot) = state.lock() {
+        slot.usage = None;
+        slot.exited = true;
+    }
+}
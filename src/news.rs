@@ -0,0 +1,20 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::http;
+
+/// One announcement or set of patch notes; `body` is Markdown.
+#[derive(Clone, Debug, Deserialize)]
+pub struct NewsItem {
+    pub title: String,
+    #[serde(default)]
+    pub date: Option<String>,
+    #[serde(default)]
+    pub body: String,
+}
+
+/// Downloads the news feed: a JSON array of items, newest first.
+pub fn fetch(agent: &ureq::Agent, url: &str) -> Result<Vec<NewsItem>> {
+    let bytes = http::fetch_bytes(agent, url)?;
+    serde_json::from_slice(&bytes).with_context(|| format!("parse news from {url}"))
+}
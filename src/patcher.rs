@@ -0,0 +1,569 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, bail};
+use base64::Engine;
+use ed25519_dalek::{Signature, VerifyingKey};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+
+use crate::http;
+use crate::manifest::{self, DeltaPatch, Manifest, ManifestFile};
+
+/// Files that differ from the remote manifest, downloaded relative to the manifest's URL.
+#[derive(Clone, Debug)]
+pub struct PatchPlan {
+    pub base_url: String,
+    pub mirrors: Vec<String>,
+    pub files: Vec<PlannedFile>,
+    pub total_bytes: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct PlannedFile {
+    pub entry: ManifestFile,
+    /// Diff applying to the local copy; `None` downloads the whole file.
+    pub patch: Option<DeltaPatch>,
+}
+
+impl PlannedFile {
+    fn download_size(&self) -> u64 {
+        match &self.patch {
+            Some(patch) => patch.size,
+            None => self.entry.size.unwrap_or(0),
+        }
+    }
+}
+
+/// Longest the pre-launch client version check may hold up the Play button.
+const VERSION_CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Fetches a manifest, each request limited to `timeout`. With a public key configured, the
+/// detached Ed25519 signature at `<url>.sig` (base64) must verify over the exact manifest bytes.
+fn fetch_manifest<T: DeserializeOwned>(
+    agent: &ureq::Agent,
+    url: &str,
+    key: Option<&VerifyingKey>,
+    timeout: Duration,
+) -> Result<T> {
+    let body = http::fetch_bytes_within(agent, url, timeout)?;
+    if let Some(key) = key {
+        let encoded = http::fetch_bytes_within(agent, &format!("{url}.sig"), timeout)?;
+        let raw = base64::engine::general_purpose::STANDARD
+            .decode(encoded.trim_ascii())
+            .context("decode manifest signature")?;
+        let signature = Signature::from_slice(&raw).context("parse manifest signature")?;
+        key.verify_strict(&body, &signature)
+            .with_context(|| format!("signature check failed for {url}"))?;
+    }
+    serde_json::from_slice(&body).with_context(|| format!("parse {url}"))
+}
+
+/// Client version the patch server currently expects, if its manifest names one.
+pub fn client_version(
+    agent: &ureq::Agent,
+    manifest_url: &str,
+    key: Option<&VerifyingKey>,
+) -> Result<Option<String>> {
+    let manifest: Manifest = fetch_manifest(agent, manifest_url, key, VERSION_CHECK_TIMEOUT)?;
+    Ok(manifest.client_version)
+}
+
+pub fn plan(
+    agent: &ureq::Agent,
+    manifest_url: &str,
+    game_dir: &Path,
+    key: Option<&VerifyingKey>,
+) -> Result<PatchPlan> {
+    let manifest: Manifest = fetch_manifest(agent, manifest_url, key, http::DOCUMENT_TIMEOUT)?;
+    for entry in &manifest.files {
+        entry.target(game_dir)?;
+    }
+    let mirrors = manifest
+        .mirrors
+        .iter()
+        .map(|url| format!("{}/", url.trim_end_matches('/')))
+        .collect();
+    let files: Vec<PlannedFile> = manifest
+        .files
+        .into_iter()
+        .filter(|entry| manifest::check_file(game_dir, entry).is_some())
+        .map(|entry| {
+            let local_md5 = if entry.patches.is_empty() {
+                None
+            } else {
+                entry.target(game_dir).and_then(manifest::md5_file).ok()
+            };
+            let patch = local_md5.and_then(|md5| {
+                entry.patches.iter().find(|p| p.from_md5.eq_ignore_ascii_case(&md5)).cloned()
+            });
+            PlannedFile { entry, patch }
+        })
+        .collect();
+    let total_bytes = files.iter().map(PlannedFile::download_size).sum();
+    let base_url = match manifest_url.rfind('/') {
+        Some(idx) => manifest_url[..=idx].to_string(),
+        None => String::new(),
+    };
+    Ok(PatchPlan {
+        base_url,
+        mirrors,
+        files,
+        total_bytes,
+    })
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct PatchProgress {
+    pub current: Option<String>,
+    pub files_done: usize,
+    pub files_total: usize,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+    /// Set once the job stops; `Err` carries a message for the status bar.
+    pub finished: Option<Result<(), String>>,
+}
+
+/// Downloads the files of a [`PatchPlan`] on a background thread.
+pub struct PatchJob {
+    progress: Arc<Mutex<PatchProgress>>,
+    control: Arc<Control>,
+}
+
+/// Knobs the UI can turn while a job runs.
+#[derive(Default)]
+struct Control {
+    cancel: AtomicBool,
+    paused: AtomicBool,
+    /// Bytes per second; zero means unlimited.
+    limit: AtomicU64,
+    /// Fetch large files from the manifest's mirrors before the origin server.
+    use_mirrors: AtomicBool,
+}
+
+/// Raised inside a download when the user pauses; the file is retried after resuming.
+#[derive(Debug)]
+struct Paused;
+
+impl std::fmt::Display for Paused {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("paused")
+    }
+}
+
+impl std::error::Error for Paused {}
+
+/// Network failures on one file are retried this many times, resuming where possible.
+const MAX_ATTEMPTS: usize = 3;
+
+/// Files smaller than this always come from the origin; mirrors are for the big NPKs.
+const MIRROR_MIN_SIZE: u64 = 64 * 1024 * 1024;
+
+impl PatchJob {
+    pub fn spawn(
+        agent: ureq::Agent,
+        plan: PatchPlan,
+        game_dir: PathBuf,
+        limit: u64,
+        use_mirrors: bool,
+    ) -> Self {
+        let progress = Arc::new(Mutex::new(PatchProgress {
+            files_total: plan.files.len(),
+            bytes_total: plan.total_bytes,
+            ..Default::default()
+        }));
+        let control = Arc::new(Control {
+            limit: AtomicU64::new(limit),
+            use_mirrors: AtomicBool::new(use_mirrors),
+            ..Default::default()
+        });
+        let shared = Arc::clone(&progress);
+        let job_control = Arc::clone(&control);
+        let spawned = thread::Builder::new()
+            .name("patcher".to_string())
+            .spawn(move || {
+                let result = run_plan(&agent, &plan, &game_dir, &shared, &job_control);
+                if let Err(err) = &result {
+                    tracing::warn!("patcher: {err:#}");
+                }
+                if let Ok(mut progress) = shared.lock() {
+                    progress.current = None;
+                    progress.finished = Some(result.map_err(|err| err.to_string()));
+                }
+            });
+        if let Err(err) = spawned {
+            tracing::warn!("patcher: failed to start: {err}");
+            if let Ok(mut slot) = progress.lock() {
+                slot.finished = Some(Err(err.to_string()));
+            }
+        }
+        Self { progress, control }
+    }
+
+    pub fn progress(&self) -> PatchProgress {
+        self.progress.lock().map(|p| p.clone()).unwrap_or_default()
+    }
+
+    pub fn cancel(&self) {
+        self.control.cancel.store(true, Ordering::Relaxed);
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        self.control.paused.store(paused, Ordering::Relaxed);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.control.paused.load(Ordering::Relaxed)
+    }
+
+    /// Caps download speed in bytes per second; zero removes the cap.
+    pub fn set_limit(&self, bytes_per_sec: u64) {
+        self.control.limit.store(bytes_per_sec, Ordering::Relaxed);
+    }
+
+    pub fn set_use_mirrors(&self, use_mirrors: bool) {
+        self.control.use_mirrors.store(use_mirrors, Ordering::Relaxed);
+    }
+}
+
+impl Drop for PatchJob {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+fn run_plan(
+    agent: &ureq::Agent,
+    plan: &PatchPlan,
+    game_dir: &Path,
+    progress: &Mutex<PatchProgress>,
+    control: &Control,
+) -> Result<()> {
+    for (index, file) in plan.files.iter().enumerate() {
+        let entry = &file.entry;
+        let start_bytes = match progress.lock() {
+            Ok(mut p) => {
+                p.current = Some(entry.path.clone());
+                p.bytes_done
+            }
+            Err(_) => 0,
+        };
+        let target = entry.target(game_dir)?;
+        let on_bytes = |bytes| {
+            if let Ok(mut p) = progress.lock() {
+                p.bytes_done += bytes;
+            }
+        };
+        let reset_bytes = || {
+            if let Ok(mut p) = progress.lock() {
+                p.bytes_done = start_bytes;
+            }
+        };
+        let mut delta = file.patch.as_ref();
+        let mut attempt = 0;
+        loop {
+            wait_while_paused(control)?;
+            reset_bytes();
+            let result = match delta {
+                Some(patch) => {
+                    let url = file_url(&plan.base_url, &patch.path);
+                    apply_delta(agent, &url, &target, &entry.md5, control, on_bytes)
+                }
+                None => {
+                    let base = full_file_source(plan, file, index, attempt, control);
+                    let url = file_url(base, &entry.path);
+                    download(agent, &url, &target, &entry.md5, control, on_bytes)
+                }
+            };
+            let Err(err) = result else {
+                break;
+            };
+            if err.is::<Paused>() {
+                continue;
+            }
+            if control.cancel.load(Ordering::Relaxed) {
+                return Err(err);
+            }
+            if delta.take().is_some() {
+                tracing::warn!(
+                    "patcher: delta for {} failed, fetching full file: {err:#}",
+                    entry.path
+                );
+                if let Ok(mut p) = progress.lock() {
+                    p.bytes_total += entry.size.unwrap_or(0);
+                }
+                continue;
+            }
+            attempt += 1;
+            if attempt >= MAX_ATTEMPTS + plan.mirrors.len() {
+                return Err(err).with_context(|| format!("update {}", entry.path));
+            }
+            tracing::warn!("patcher: retrying {} after error: {err:#}", entry.path);
+            thread::sleep(Duration::from_secs(2));
+        }
+        if let Ok(mut p) = progress.lock() {
+            p.files_done += 1;
+        }
+    }
+    tracing::info!("patcher: updated {} file(s)", plan.files.len());
+    Ok(())
+}
+
+/// Base URL for a full download attempt: each mirror once, rotated per file to spread
+/// load, then the origin. A `.part` from a failed mirror is resumed on the next source.
+fn full_file_source<'a>(
+    plan: &'a PatchPlan,
+    file: &PlannedFile,
+    index: usize,
+    attempt: usize,
+    control: &Control,
+) -> &'a str {
+    let eligible = control.use_mirrors.load(Ordering::Relaxed)
+        && file.entry.size.is_some_and(|size| size >= MIRROR_MIN_SIZE)
+        && attempt < plan.mirrors.len();
+    if eligible {
+        &plan.mirrors[(index + attempt) % plan.mirrors.len()]
+    } else {
+        &plan.base_url
+    }
+}
+
+fn wait_while_paused(control: &Control) -> Result<()> {
+    while control.paused.load(Ordering::Relaxed) {
+        if control.cancel.load(Ordering::Relaxed) {
+            bail!("cancelled");
+        }
+        thread::sleep(Duration::from_millis(200));
+    }
+    Ok(())
+}
+
+fn file_url(base_url: &str, path: &str) -> String {
+    format!("{base_url}{}", path.replace(' ', "%20"))
+}
+
+fn partial_path(target: &Path) -> PathBuf {
+    target.with_extension(match target.extension() {
+        Some(ext) => format!("{}.part", ext.to_string_lossy()),
+        None => "part".to_string(),
+    })
+}
+
+/// Streams `url` into `<target>.part`, checks the md5 and then moves it over `target`.
+/// A `.part` left by an interrupted run is continued with a `Range` request.
+fn download(
+    agent: &ureq::Agent,
+    url: &str,
+    target: &Path,
+    md5: &str,
+    control: &Control,
+    mut on_bytes: impl FnMut(u64),
+) -> Result<()> {
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).context("create directory")?;
+    }
+    let partial = partial_path(target);
+    let existing = fs::metadata(&partial).map(|meta| meta.len()).unwrap_or(0);
+    let mut request = agent.get(url);
+    if existing > 0 {
+        request = request.header("Range", format!("bytes={existing}-"));
+    }
+    let response = request.call().with_context(|| format!("fetch {url}"))?;
+    let mut ctx = md5::Context::new();
+    let file = if existing > 0 && response.status() == 206 {
+        tracing::info!("patcher: resuming {} at {existing} bytes", target.display());
+        let mut part = File::open(&partial).context("open partial file")?;
+        std::io::copy(&mut part, &mut ctx).context("hash partial file")?;
+        on_bytes(existing);
+        OpenOptions::new().append(true).open(&partial).context("open partial file")?
+    } else {
+        File::create(&partial).context("create temp file")?
+    };
+    let reader = NetReader {
+        inner: response.into_body().into_reader(),
+        control,
+        on_bytes,
+        throttle: Throttle::new(),
+    };
+    write_verified(reader, file, ctx, target, md5, control, true)
+}
+
+/// Downloads a zstd diff and decodes it against the current contents of `target`.
+fn apply_delta(
+    agent: &ureq::Agent,
+    url: &str,
+    target: &Path,
+    md5: &str,
+    control: &Control,
+    on_bytes: impl FnMut(u64),
+) -> Result<()> {
+    let old = fs::read(target).context("read local file")?;
+    let response = agent.get(url).call().with_context(|| format!("fetch {url}"))?;
+    let reader = BufReader::new(NetReader {
+        inner: response.into_body().into_reader(),
+        control,
+        on_bytes,
+        throttle: Throttle::new(),
+    });
+    let mut decoder = zstd::stream::read::Decoder::with_ref_prefix(reader, &old)?;
+    // Diffs of large NPK archives are made with --long, so allow the full window.
+    decoder.window_log_max(31)?;
+    let file = File::create(partial_path(target)).context("create temp file")?;
+    write_verified(decoder, file, md5::Context::new(), target, md5, control, false)
+}
+
+/// Wraps the response body to report progress and apply the bandwidth cap.
+struct NetReader<'a, R, F> {
+    inner: R,
+    control: &'a Control,
+    on_bytes: F,
+    throttle: Throttle,
+}
+
+impl<R: Read, F: FnMut(u64)> Read for NetReader<'_, R, F> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        (self.on_bytes)(read as u64);
+        self.throttle.consume(read as u64, self.control.limit.load(Ordering::Relaxed));
+        Ok(read)
+    }
+}
+
+/// Sleeps as needed to keep the average rate over each one-second window under the cap.
+struct Throttle {
+    window_start: Instant,
+    window_bytes: u64,
+}
+
+impl Throttle {
+    fn new() -> Self {
+        Self {
+            window_start: Instant::now(),
+            window_bytes: 0,
+        }
+    }
+
+    fn consume(&mut self, bytes: u64, limit: u64) {
+        if limit == 0 {
+            return;
+        }
+        self.window_bytes += bytes;
+        let due = Duration::from_secs_f64(self.window_bytes as f64 / limit as f64);
+        let elapsed = self.window_start.elapsed();
+        if due > elapsed {
+            thread::sleep(due - elapsed);
+        }
+        if self.window_start.elapsed() >= Duration::from_secs(1) {
+            self.window_start = Instant::now();
+            self.window_bytes = 0;
+        }
+    }
+}
+
+/// Writes `reader` into the temp file, checks the md5 and then moves it over `target`.
+/// With `keep_partial` the temp file survives pauses and errors so it can be resumed.
+fn write_verified(
+    mut reader: impl Read,
+    mut file: File,
+    mut ctx: md5::Context,
+    target: &Path,
+    md5: &str,
+    control: &Control,
+    keep_partial: bool,
+) -> Result<()> {
+    let partial = partial_path(target);
+    let mut buf = vec![0u8; 64 * 1024];
+    let result = loop {
+        if control.cancel.load(Ordering::Relaxed) {
+            break Err(anyhow::anyhow!("cancelled"));
+        }
+        if control.paused.load(Ordering::Relaxed) {
+            break Err(Paused.into());
+        }
+        let read = match reader.read(&mut buf) {
+            Ok(0) => break Ok(()),
+            Ok(read) => read,
+            Err(err) => break Err(err.into()),
+        };
+        if let Err(err) = file.write_all(&buf[..read]) {
+            break Err(err.into());
+        }
+        ctx.consume(&buf[..read]);
+    };
+    let result = result.and_then(|()| file.flush().map_err(Into::into));
+    drop(file);
+    if let Err(err) = result {
+        if !keep_partial {
+            let _ = fs::remove_file(&partial);
+        }
+        return Err(err);
+    }
+    let hash = format!("{:x}", ctx.finalize());
+    if !hash.eq_ignore_ascii_case(md5) {
+        let _ = fs::remove_file(&partial);
+        bail!("checksum mismatch");
+    }
+    fs::rename(&partial, target).context("replace file")?;
+    Ok(())
+}
+
+/// Launcher build published on an update channel.
+#[derive(Clone, Debug, Deserialize)]
+pub struct LauncherRelease {
+    pub version: String,
+    pub url: String,
+    pub md5: String,
+    /// Oldest launcher still allowed to sign in; older builds must install this release.
+    #[serde(default)]
+    pub min_version: Option<String>,
+}
+
+/// Returns the published release when it is newer than the running launcher.
+pub fn check_launcher(
+    agent: &ureq::Agent,
+    manifest_url: &str,
+    key: Option<&VerifyingKey>,
+) -> Result<Option<LauncherRelease>> {
+    let release: LauncherRelease =
+        fetch_manifest(agent, manifest_url, key, http::DOCUMENT_TIMEOUT)?;
+    Ok(is_newer(&release.version, env!("CARGO_PKG_VERSION")).then_some(release))
+}
+
+/// Downloads the new launcher next to the running one and swaps it in. The running
+/// executable is renamed to `.old`, which Windows allows while it is in use.
+pub fn install_launcher(agent: &ureq::Agent, release: &LauncherRelease) -> Result<()> {
+    let exe = std::env::current_exe().context("locate launcher executable")?;
+    let staged = exe.with_extension("new");
+    let old = exe.with_extension("old");
+    download(agent, &release.url, &staged, &release.md5, &Control::default(), |_| {})?;
+    let _ = fs::remove_file(&old);
+    fs::rename(&exe, &old).context("move current launcher aside")?;
+    if let Err(err) = fs::rename(&staged, &exe) {
+        let _ = fs::rename(&old, &exe);
+        return Err(err).context("install new launcher");
+    }
+    tracing::info!("patcher: installed launcher {}", release.version);
+    Ok(())
+}
+
+/// Compares dotted numeric versions such as `1.4.2`; missing parts count as zero.
+pub fn is_newer(candidate: &str, current: &str) -> bool {
+    let parse = |v: &str| -> Vec<u64> {
+        v.trim_start_matches('v')
+            .split(['.', '-'])
+            .map_while(|part| part.parse().ok())
+            .collect()
+    };
+    let (candidate, current) = (parse(candidate), parse(current));
+    let len = candidate.len().max(current.len());
+    let part = |v: &[u64], i: usize| v.get(i).copied().unwrap_or(0);
+    (0..len)
+        .map(|i| part(&candidate, i).cmp(&part(&current, i)))
+        .find(|ord| ord.is_ne())
+        .is_some_and(|ord| ord.is_gt())
+}
@@ -0,0 +1,81 @@
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, Debug)]
+pub enum Ping {
+    Latency(Duration),
+    Unreachable,
+}
+
+/// Periodically measures TCP connect latency to the game server on a background thread.
+pub struct PingMonitor {
+    latest: Arc<Mutex<Option<Ping>>>,
+    stop: Arc<AtomicBool>,
+    paused: Arc<AtomicBool>,
+}
+
+impl PingMonitor {
+    pub fn spawn(addr: String, interval: Duration) -> Self {
+        let latest = Arc::new(Mutex::new(None));
+        let stop = Arc::new(AtomicBool::new(false));
+        let paused = Arc::new(AtomicBool::new(false));
+        let shared = Arc::clone(&latest);
+        let stopped = Arc::clone(&stop);
+        let idle = Arc::clone(&paused);
+        let spawned = thread::Builder::new()
+            .name("ping".to_string())
+            .spawn(move || {
+                while !stopped.load(Ordering::Relaxed) {
+                    if idle.load(Ordering::Relaxed) {
+                        thread::sleep(Duration::from_millis(500));
+                        continue;
+                    }
+                    let ping = measure(&addr, Duration::from_secs(3));
+                    if let Ok(mut slot) = shared.lock() {
+                        *slot = Some(ping);
+                    }
+                    thread::sleep(interval);
+                }
+            });
+        if let Err(err) = spawned {
+            tracing::warn!("ping: failed to start monitor: {err}");
+        }
+        Self {
+            latest,
+            stop,
+            paused,
+        }
+    }
+
+    /// Stops probing while the launcher is in the background; the last result is kept.
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+    }
+
+    pub fn latest(&self) -> Option<Ping> {
+        self.latest.lock().ok().and_then(|slot| *slot)
+    }
+}
+
+impl Drop for PingMonitor {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+fn measure(addr: &str, timeout: Duration) -> Ping {
+    let Some(target) = addr.to_socket_addrs().ok().and_then(|mut addrs| addrs.next()) else {
+        return Ping::Unreachable;
+    };
+    let start = Instant::now();
+    match TcpStream::connect_timeout(&target, timeout) {
+        Ok(_) => Ping::Latency(start.elapsed()),
+        Err(err) => {
+            tracing::debug!("ping: connect failed: {err}");
+            Ping::Unreachable
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use anyhow::Result;
+
+pub const SCHEME: &str = "adnf";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LinkAction {
+    /// Bring the launcher up with the given realm selected.
+    Open,
+    /// Log in with the saved account and start the game.
+    Play,
+}
+
+/// A parsed `adnf://` link, e.g. `adnf://play?realm=Main&channel=3`.
+#[derive(Clone, Debug)]
+pub struct LaunchLink {
+    pub action: LinkAction,
+    pub realm: Option<String>,
+    pub channel: Option<i32>,
+}
+
+impl LaunchLink {
+    pub fn parse(link: &str) -> Option<Self> {
+        let rest = link.trim().strip_prefix(SCHEME)?.strip_prefix("://")?;
+        let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let action = match path.trim_end_matches('/') {
+            "" | "open" => LinkAction::Open,
+            "play" => LinkAction::Play,
+            _ => return None,
+        };
+        let mut parsed = Self {
+            action,
+            realm: None,
+            channel: None,
+        };
+        for pair in query.split('&') {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = percent_decode(value);
+            match key {
+                "realm" if !value.is_empty() => parsed.realm = Some(value),
+                "channel" => parsed.channel = value.parse().ok(),
+                _ => {}
+            }
+        }
+        Some(parsed)
+    }
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| bytes.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (escaped, bytes[i]) {
+            (Some(byte), _) => {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+            (None, b'+') => out.push(b' '),
+            (None, byte) => out.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Points the `adnf://` scheme at this executable for the current user.
+#[cfg(windows)]
+pub fn register() -> Result<()> {
+    use anyhow::Context;
+    use winreg::RegKey;
+    use winreg::enums::HKEY_CURRENT_USER;
+
+    let exe = std::env::current_exe().context("locate launcher executable")?;
+    let classes = RegKey::predef(HKEY_CURRENT_USER);
+    let (key, _) = classes
+        .create_subkey(format!("Software\\Classes\\{SCHEME}"))
+        .context("create protocol key")?;
+    key.set_value("", &"URL:ADNF Launcher")?;
+    key.set_value("URL Protocol", &"")?;
+    let (command, _) = key.create_subkey("shell\\open\\command")?;
+    command.set_value("", &format!("\"{}\" \"%1\"", exe.display()))?;
+    Ok(())
+}
+
+/// Installs a desktop entry handling `x-scheme-handler/adnf` for the current user.
+#[cfg(target_os = "linux")]
+pub fn register() -> Result<()> {
+    use std::path::PathBuf;
+    use std::process::Command;
+
+    use anyhow::Context;
+
+    let exe = std::env::current_exe().context("locate launcher executable")?;
+    let home = std::env::var_os("HOME").context("HOME not set")?;
+    let apps = PathBuf::from(home).join(".local/share/applications");
+    let desktop_name = format!("{SCHEME}-launcher.desktop");
+    let path = apps.join(&desktop_name);
+    let entry = format!(
+        "[Desktop Entry]\nType=Application\nName=ADNF Launcher\nExec=\"{}\" %u\n\
+         MimeType=x-scheme-handler/{SCHEME};\nNoDisplay=true\n",
+        exe.display()
+    );
+    if std::fs::read_to_string(&path).is_ok_and(|existing| existing == entry) {
+        return Ok(());
+    }
+    std::fs::create_dir_all(&apps).context("create applications dir")?;
+    std::fs::write(&path, entry).context("write desktop entry")?;
+    let status = Command::new("xdg-mime")
+        .args(["default", &desktop_name, &format!("x-scheme-handler/{SCHEME}")])
+        .status()
+        .context("run xdg-mime")?;
+    anyhow::ensure!(status.success(), "xdg-mime exited with {status}");
+    Ok(())
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+pub fn register() -> Result<()> {
+    anyhow::bail!("{SCHEME}:// links are not supported on this platform")
+}
@@ -0,0 +1,66 @@
+use anyhow::{Context, Result, bail};
+
+use crate::config::{self, AppConfig, IntegrityMode, UserConfig};
+use crate::db::Db;
+use crate::launch;
+use crate::manifest::{self, Manifest};
+
+/// `--quick-launch [account]`: logs in with saved credentials and starts the client without
+/// opening the launcher window.
+pub fn run(app_config: &AppConfig, db: &Db, account: Option<&str>) -> Result<()> {
+    let user: UserConfig = config::read_json("config.json").unwrap_or_default();
+    let mut app_config = app_config.clone();
+    app_config.apply_overrides(&user);
+    let Some(saved) = user.saved_account(account) else {
+        match account {
+            Some(name) => bail!("no saved credentials for {name}"),
+            None => bail!("no saved account; sign in with \"Remember me\" first"),
+        }
+    };
+    if saved.password.is_empty() {
+        bail!("no saved password for {}; sign in with \"Remember me\" first", saved.username);
+    }
+    db.set_realm(app_config.realm(user.realm.as_deref()));
+
+    let manifest = match app_config.integrity.mode {
+        IntegrityMode::Off => None,
+        IntegrityMode::Warn => Manifest::load(&app_config.integrity.manifest_path)
+            .inspect_err(|err| tracing::warn!("integrity: check skipped: {err:#}"))
+            .ok(),
+        IntegrityMode::Block => Some(Manifest::load(&app_config.integrity.manifest_path)?),
+    };
+    if let Some(manifest) = manifest {
+        let game_dir = launch::game_dir(&app_config.dnf_exe_path);
+        let problems = manifest::verify_files(&game_dir, &manifest, &app_config.integrity.files);
+        for problem in &problems {
+            tracing::warn!("integrity: {problem}");
+        }
+        if !problems.is_empty() && app_config.integrity.mode == IntegrityMode::Block {
+            bail!("{} client file(s) failed verification", problems.len());
+        }
+    }
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .context("start runtime")?;
+    tracing::info!("quick-launch: signing in as {}", saved.username);
+    let session = runtime.block_on(db.perform_login(&saved.username, &saved.password))?;
+    if session.online.is_some() {
+        bail!("{} is already in game; open the launcher to play anyway", saved.username);
+    }
+    let args = launch::client_args(&app_config, &user, &session.token);
+
+    #[cfg(windows)]
+    if user.launch_elevated && app_config.launch.mode == config::LaunchMode::Native {
+        launch::spawn_elevated(&app_config.dnf_exe_path, &args)?;
+        tracing::info!("quick-launch: started game elevated");
+        return Ok(());
+    }
+    let child = launch::client_command(&app_config.launch, &app_config.dnf_exe_path)
+        .args(args)
+        .spawn()
+        .context("launch game")?;
+    tracing::info!("quick-launch: started game (pid {})", child.id());
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use rsa::rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha256};
+
+/// Codes handed out per generation; generating again replaces the whole set.
+pub const CODE_COUNT: usize = 10;
+
+/// No 0/O or 1/I/L, so codes survive being written down and typed back in.
+const ALPHABET: &[u8] = b"23456789ABCDEFGHJKMNPQRSTUVWXYZ";
+const CODE_LEN: usize = 10;
+
+/// Length of passwords handed out by admin resets.
+const PASSWORD_LEN: usize = 12;
+
+/// A fresh code formatted as `XXXXX-XXXXX`.
+pub fn generate() -> String {
+    let mut code = String::with_capacity(CODE_LEN + 1);
+    for idx in 0..CODE_LEN {
+        if idx == CODE_LEN / 2 {
+            code.push('-');
+        }
+        code.push(random_char());
+    }
+    code
+}
+
+/// Temporary password for admin resets, from the same easy-to-read alphabet as the codes.
+pub fn generate_password() -> String {
+    (0..PASSWORD_LEN).map(|_| random_char()).collect()
+}
+
+fn random_char() -> char {
+    let pick = OsRng.next_u32() as usize % ALPHABET.len();
+    char::from(ALPHABET[pick])
+}
+
+/// Fresh per-code salt, hex-encoded.
+pub fn salt() -> String {
+    let mut bytes = [0u8; 16];
+    OsRng.fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// SHA-256 of `salt` and the code with separators and case ignored; only this and the salt
+/// are stored. Codes generated before salting have an empty salt.
+pub fn hash(salt: &str, code: &str) -> String {
+    let normalized: String = code
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    let mut digest = Sha256::new();
+    digest.update(salt.as_bytes());
+    digest.update(normalized.as_bytes());
+    hex::encode(digest.finalize())
+}
@@ -0,0 +1,81 @@
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::config::{ClientRegistry, LaunchConfig};
+
+/// Resolves the configured registry values, expanding `{game_dir}`, `{locale}` and
+/// `{server}` placeholders.
+pub fn resolve_values(
+    registry: &ClientRegistry,
+    game_dir: &Path,
+    server: Option<&str>,
+) -> Vec<(String, String)> {
+    let game_dir = std::path::absolute(game_dir).unwrap_or_else(|_| game_dir.to_path_buf());
+    let game_dir = game_dir.to_string_lossy();
+    registry
+        .values
+        .iter()
+        .map(|(name, template)| {
+            let value = template
+                .replace("{game_dir}", &game_dir)
+                .replace("{locale}", registry.locale.as_deref().unwrap_or_default())
+                .replace("{server}", server.unwrap_or_default());
+            (name.clone(), value)
+        })
+        .collect()
+}
+
+/// Writes the client's registry values under `HKEY_CURRENT_USER`. Returns how many were set.
+#[cfg(windows)]
+pub fn write(
+    registry: &ClientRegistry,
+    _launch: &LaunchConfig,
+    game_dir: &Path,
+    server: Option<&str>,
+) -> Result<usize> {
+    use anyhow::Context;
+    use winreg::RegKey;
+    use winreg::enums::HKEY_CURRENT_USER;
+
+    let (key, _) = RegKey::predef(HKEY_CURRENT_USER)
+        .create_subkey(&registry.key)
+        .with_context(|| format!("create registry key {}", registry.key))?;
+    let values = resolve_values(registry, game_dir, server);
+    for (name, value) in &values {
+        key.set_value(name, value)
+            .with_context(|| format!("set registry value {name}"))?;
+    }
+    Ok(values.len())
+}
+
+/// Writes the client's registry values into the Wine prefix with `wine reg add`.
+#[cfg(not(windows))]
+pub fn write(
+    registry: &ClientRegistry,
+    launch: &LaunchConfig,
+    game_dir: &Path,
+    server: Option<&str>,
+) -> Result<usize> {
+    use anyhow::{Context, bail};
+
+    use crate::config::LaunchMode;
+
+    if launch.mode != LaunchMode::Wine {
+        bail!("Registry setup needs Windows or the Wine launch mode");
+    }
+    let key = format!("HKEY_CURRENT_USER\\{}", registry.key);
+    let values = resolve_values(registry, game_dir, server);
+    for (name, value) in &values {
+        let mut command = std::process::Command::new(&launch.wine_binary);
+        command.args(["reg", "add", &key, "/v", name, "/t", "REG_SZ", "/d", value, "/f"]);
+        if let Some(prefix) = &launch.wine_prefix {
+            command.env("WINEPREFIX", prefix);
+        }
+        let status = command.status().context("run wine reg")?;
+        if !status.success() {
+            bail!("wine reg add {name} failed with {status}");
+        }
+    }
+    Ok(values.len())
+}
@@ -0,0 +1,1550 @@
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, mpsc};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use anyhow::{Error, Result, anyhow};
+use eframe::egui;
+
+use crate::backup::AccountBackup;
+use crate::config::{CurrencyDef, DailyReward, IntegrityConfig, IntegrityMode};
+use crate::crashlog::{self, CrashReport};
+use crate::db::{
+    AccountNote, AuditAction, AuditEntry, AuditRecord, Avatar, BalanceSnapshot, CharacterInfo,
+    Credentials, Currency, Db, DungeonClear, EmailPurpose, GuildMember, LoginSession, Mail,
+    ResetPassword, SendRecord,
+};
+use crate::desktop;
+use crate::email::{self, Mailer};
+use crate::equipment::Equipment;
+use crate::hosts::{self, HostsCheck, Redirect};
+use crate::http;
+use crate::launch;
+use crate::legacy::{self, LegacyConfig};
+use crate::maintenance::{self, CleanupReport};
+use crate::manifest::{self, Manifest};
+use crate::news::{self, NewsItem};
+use crate::patcher::{self, LauncherRelease, PatchPlan};
+use crate::support::{self, SupportInfo};
+
+/// Audit rows returned per load for the admin audit viewer.
+const AUDIT_PAGE: i64 = 100;
+
+/// Database calls the latency average covers.
+const LATENCY_SAMPLES: usize = 20;
+
+/// How long the client version from the patch manifest is reused before launching checks again.
+const CLIENT_VERSION_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Patch manifest asked for the client version the server expects.
+pub struct RemoteManifest {
+    pub agent: ureq::Agent,
+    pub url: String,
+    pub key: Option<ed25519_dalek::VerifyingKey>,
+}
+
+/// Installed client older than the one the server expects.
+#[derive(Clone, Debug)]
+pub struct ClientMismatch {
+    pub local: String,
+    pub expected: String,
+}
+
+/// Work the UI hands to the service. Input is already validated; the signed-in
+/// credentials travel alongside, for audit entries and the session reload afterwards.
+pub enum Command {
+    MigrateSchema,
+    Login {
+        remember: bool,
+    },
+    /// Registers the entered credentials; `terms_version` is the accepted rules revision.
+    CreateAccount {
+        terms_version: Option<String>,
+    },
+    Refresh,
+    SendGold {
+        char_id: i32,
+        amount: i32,
+    },
+    SendCera {
+        uid: i32,
+        amount: i32,
+    },
+    /// Reverses the send with this audit id.
+    UndoSend {
+        send_id: i64,
+    },
+    /// `owner` is the UID, or the character number for per-character currencies.
+    LoadCurrency {
+        currency: CurrencyDef,
+        owner: i32,
+    },
+    SendCurrency {
+        currency: CurrencyDef,
+        owner: i32,
+        amount: i32,
+    },
+    TransferCera {
+        uid: i32,
+        recipient: String,
+        amount: i64,
+    },
+    ClaimDailyReward {
+        uid: i32,
+        rewards: Vec<DailyReward>,
+    },
+    ForceLogout {
+        uid: i32,
+    },
+    /// Writes the account's cera and gold history as CSV.
+    ExportBilling {
+        uid: i32,
+        char_ids: Vec<i32>,
+        path: PathBuf,
+    },
+    LinkDiscord {
+        uid: i32,
+        code: String,
+    },
+    UnlinkDiscord {
+        uid: i32,
+    },
+    SendEmailCode {
+        uid: i32,
+        address: String,
+    },
+    VerifyEmail {
+        uid: i32,
+        code: String,
+    },
+    SendResetCode,
+    ResetPassword {
+        code: String,
+        password: String,
+    },
+    SetSecondaryPin {
+        uid: i32,
+        pin: Option<String>,
+    },
+    RecoverAccount {
+        code: String,
+        password: String,
+    },
+    GenerateRecoveryCodes {
+        uid: i32,
+    },
+    LoadGuildMembers {
+        guild_id: i32,
+    },
+    LoadBalanceHistory {
+        uid: i32,
+    },
+    PurchaseSlot {
+        uid: i32,
+        price: i64,
+    },
+    UnstickCharacter {
+        uid: i32,
+        char_id: i32,
+    },
+    LoadMail {
+        char_id: i32,
+    },
+    LoadAvatars {
+        char_id: i32,
+    },
+    LoadSends {
+        char_id: i32,
+    },
+    LoadDungeonClears {
+        char_id: i32,
+    },
+    GrantCashItem {
+        uid: i32,
+        item_id: i32,
+        quantity: i32,
+        item_name: String,
+    },
+    GrantCreature {
+        char_id: i32,
+        creature_id: i32,
+        creature_name: String,
+    },
+    LoadAudit,
+    Rollback(AuditRecord),
+    LookupCharacter {
+        char_id: i32,
+    },
+    LoadAccountNote {
+        uid: i32,
+    },
+    SaveAccountNote {
+        uid: i32,
+        note: String,
+    },
+    SetJobGrowth {
+        char_id: i32,
+        advancement: i32,
+        awakening: i32,
+    },
+    ResetQuests {
+        char_id: i32,
+        quest_id: Option<i32>,
+    },
+    ExpandInventory {
+        char_id: i32,
+        slots: i32,
+    },
+    ExpandStash {
+        uid: i32,
+        tabs: i32,
+    },
+    SetSlotLimit {
+        uid: i32,
+        limit: i32,
+    },
+    SetLevel {
+        char_id: i32,
+        level: i32,
+    },
+    CreateGuild {
+        name: String,
+        master: i32,
+    },
+    SetGuildMaster {
+        guild_id: i32,
+        char_id: i32,
+    },
+    SetGuildLevel {
+        guild_id: i32,
+        level: i32,
+    },
+    AdjustGuildFunds {
+        guild_id: i32,
+        delta: i64,
+    },
+    DisbandGuild {
+        guild_id: i32,
+    },
+    TransferCharacter {
+        char_id: i32,
+        to_uid: i32,
+    },
+    BackupAccount {
+        uid: i32,
+        path: String,
+    },
+    RestoreAccount {
+        path: String,
+    },
+    MergeAccounts {
+        from_uid: i32,
+        to_uid: i32,
+    },
+    /// Writes the generated passwords to `path` as CSV, the one place they are kept.
+    ResetPasswords {
+        uids: Vec<i32>,
+        path: String,
+    },
+    /// Pre-launch checks: file hashes when integrity checking is on, and the client version
+    /// against the integrity manifest or, when reachable, the patch manifest.
+    VerifyClient {
+        integrity: Option<IntegrityConfig>,
+        remote: Option<RemoteManifest>,
+        exe_path: String,
+        version_file: String,
+    },
+    CheckUpdates {
+        agent: ureq::Agent,
+        patch_url: Option<String>,
+        launcher_url: Option<String>,
+        game_dir: PathBuf,
+        key: Option<ed25519_dalek::VerifyingKey>,
+    },
+    LoadNews {
+        agent: ureq::Agent,
+        url: String,
+    },
+    InstallLauncher {
+        agent: ureq::Agent,
+        release: LauncherRelease,
+    },
+    ScanCache(Vec<PathBuf>),
+    ClearCache(Vec<PathBuf>),
+    FindLegacyConfigs(Vec<PathBuf>),
+    CheckHosts(Vec<Redirect>),
+    SaveSupportBundle {
+        info: SupportInfo,
+        path: PathBuf,
+    },
+    ReportProblem {
+        agent: ureq::Agent,
+        url: String,
+        description: String,
+        info: SupportInfo,
+    },
+    /// Launcher shortcut named `name` passing `args`, placed in each of `locations`.
+    CreateShortcut {
+        name: String,
+        args: Vec<String>,
+        locations: Vec<desktop::Location>,
+    },
+    FixHosts(Vec<Redirect>),
+    CollectCrashLogs {
+        game_dir: PathBuf,
+        entries: Vec<String>,
+        since: SystemTime,
+        exit: String,
+    },
+    SaveCrashReport {
+        report: CrashReport,
+        path: String,
+    },
+    UploadCrashReport {
+        agent: ureq::Agent,
+        url: String,
+        report: CrashReport,
+    },
+    #[cfg(windows)]
+    LaunchElevated {
+        exe_path: String,
+        args: Vec<String>,
+    },
+}
+
+impl Command {
+    /// Variant name, for the debug overlay.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::MigrateSchema => "MigrateSchema",
+            Self::Login { .. } => "Login",
+            Self::CreateAccount { .. } => "CreateAccount",
+            Self::Refresh => "Refresh",
+            Self::SendGold { .. } => "SendGold",
+            Self::SendCera { .. } => "SendCera",
+            Self::UndoSend { .. } => "UndoSend",
+            Self::LoadCurrency { .. } => "LoadCurrency",
+            Self::SendCurrency { .. } => "SendCurrency",
+            Self::TransferCera { .. } => "TransferCera",
+            Self::ClaimDailyReward { .. } => "ClaimDailyReward",
+            Self::ForceLogout { .. } => "ForceLogout",
+            Self::ExportBilling { .. } => "ExportBilling",
+            Self::LinkDiscord { .. } => "LinkDiscord",
+            Self::UnlinkDiscord { .. } => "UnlinkDiscord",
+            Self::SendEmailCode { .. } => "SendEmailCode",
+            Self::VerifyEmail { .. } => "VerifyEmail",
+            Self::SendResetCode => "SendResetCode",
+            Self::ResetPassword { .. } => "ResetPassword",
+            Self::SetSecondaryPin { .. } => "SetSecondaryPin",
+            Self::RecoverAccount { .. } => "RecoverAccount",
+            Self::GenerateRecoveryCodes { .. } => "GenerateRecoveryCodes",
+            Self::LoadGuildMembers { .. } => "LoadGuildMembers",
+            Self::LoadBalanceHistory { .. } => "LoadBalanceHistory",
+            Self::PurchaseSlot { .. } => "PurchaseSlot",
+            Self::UnstickCharacter { .. } => "UnstickCharacter",
+            Self::LoadMail { .. } => "LoadMail",
+            Self::LoadAvatars { .. } => "LoadAvatars",
+            Self::LoadSends { .. } => "LoadSends",
+            Self::LoadDungeonClears { .. } => "LoadDungeonClears",
+            Self::GrantCashItem { .. } => "GrantCashItem",
+            Self::GrantCreature { .. } => "GrantCreature",
+            Self::LoadAudit => "LoadAudit",
+            Self::Rollback(_) => "Rollback",
+            Self::LookupCharacter { .. } => "LookupCharacter",
+            Self::LoadAccountNote { .. } => "LoadAccountNote",
+            Self::SaveAccountNote { .. } => "SaveAccountNote",
+            Self::SetJobGrowth { .. } => "SetJobGrowth",
+            Self::ResetQuests { .. } => "ResetQuests",
+            Self::ExpandInventory { .. } => "ExpandInventory",
+            Self::ExpandStash { .. } => "ExpandStash",
+            Self::SetSlotLimit { .. } => "SetSlotLimit",
+            Self::SetLevel { .. } => "SetLevel",
+            Self::CreateGuild { .. } => "CreateGuild",
+            Self::SetGuildMaster { .. } => "SetGuildMaster",
+            Self::SetGuildLevel { .. } => "SetGuildLevel",
+            Self::AdjustGuildFunds { .. } => "AdjustGuildFunds",
+            Self::DisbandGuild { .. } => "DisbandGuild",
+            Self::TransferCharacter { .. } => "TransferCharacter",
+            Self::BackupAccount { .. } => "BackupAccount",
+            Self::RestoreAccount { .. } => "RestoreAccount",
+            Self::MergeAccounts { .. } => "MergeAccounts",
+            Self::ResetPasswords { .. } => "ResetPasswords",
+            Self::VerifyClient { .. } => "VerifyClient",
+            Self::CheckUpdates { .. } => "CheckUpdates",
+            Self::LoadNews { .. } => "LoadNews",
+            Self::InstallLauncher { .. } => "InstallLauncher",
+            Self::ScanCache(_) => "ScanCache",
+            Self::ClearCache(_) => "ClearCache",
+            Self::FindLegacyConfigs(_) => "FindLegacyConfigs",
+            Self::CheckHosts(_) => "CheckHosts",
+            Self::SaveSupportBundle { .. } => "SaveSupportBundle",
+            Self::ReportProblem { .. } => "ReportProblem",
+            Self::CreateShortcut { .. } => "CreateShortcut",
+            Self::FixHosts(_) => "FixHosts",
+            Self::CollectCrashLogs { .. } => "CollectCrashLogs",
+            Self::SaveCrashReport { .. } => "SaveCrashReport",
+            Self::UploadCrashReport { .. } => "UploadCrashReport",
+            #[cfg(windows)]
+            Self::LaunchElevated { .. } => "LaunchElevated",
+        }
+    }
+}
+
+/// Durations of the most recent database calls, for telling a slow database apart from a
+/// slow UI.
+#[derive(Default)]
+pub struct DbLatency {
+    samples: VecDeque<Duration>,
+}
+
+impl DbLatency {
+    fn record(&mut self, elapsed: Duration) {
+        if self.samples.len() == LATENCY_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(elapsed);
+    }
+
+    pub fn last(&self) -> Option<Duration> {
+        self.samples.back().copied()
+    }
+
+    pub fn average(&self) -> Option<Duration> {
+        let count = u32::try_from(self.samples.len()).ok().filter(|n| *n > 0)?;
+        Some(self.samples.iter().sum::<Duration>() / count)
+    }
+}
+
+/// Outcome of a [`Command`], applied to the UI state on the next frame.
+pub enum Event {
+    LoginSuccess {
+        session: LoginSession,
+        remember: bool,
+    },
+    SessionUpdated {
+        session: LoginSession,
+        message: String,
+    },
+    CurrencySent {
+        session: LoginSession,
+        currency: Currency,
+        amount: i32,
+        /// Audit id of the send, for [`Command::UndoSend`].
+        send_id: i64,
+    },
+    /// Balance of a configured currency, after a send when `sent` is set.
+    CurrencyLoaded {
+        name: String,
+        owner: i32,
+        balance: i64,
+        /// Amount and audit id of the send.
+        sent: Option<(i32, i64)>,
+    },
+    AccountCreated,
+    PasswordReset,
+    /// Signed in with a recovery code; `password` is the one set in its place.
+    Recovered {
+        session: LoginSession,
+        password: String,
+    },
+    RecoveryCodes(Vec<String>),
+    GuildMembersLoaded {
+        guild_id: i32,
+        members: Vec<GuildMember>,
+    },
+    BalanceHistoryLoaded(Vec<BalanceSnapshot>),
+    MailLoaded {
+        char_id: i32,
+        mail: Vec<Mail>,
+    },
+    DungeonClearsLoaded {
+        char_id: i32,
+        clears: Vec<DungeonClear>,
+    },
+    AvatarsLoaded {
+        char_id: i32,
+        avatars: Vec<Avatar>,
+        equipment: Vec<Equipment>,
+    },
+    SendsLoaded {
+        char_id: i32,
+        sends: Vec<SendRecord>,
+    },
+    CacheScanned(CleanupReport),
+    CacheCleared(CleanupReport),
+    ClientChecked {
+        problems: Vec<String>,
+        outdated: Option<ClientMismatch>,
+    },
+    AdminCharacterLoaded(CharacterInfo),
+    AccountNoteLoaded {
+        uid: i32,
+        note: Option<AccountNote>,
+        /// Set after a save; plain loads leave the status line alone.
+        message: Option<String>,
+    },
+    AuditLoaded {
+        records: Vec<AuditRecord>,
+        message: String,
+    },
+    NewsLoaded(Vec<NewsItem>),
+    CrashCollected(CrashReport),
+    PasswordsReset {
+        accounts: Vec<ResetPassword>,
+        /// Where the CSV was saved, or why it wasn't; the passwords are shown either way.
+        export: Result<String, String>,
+    },
+    LegacyConfigsFound(Vec<LegacyConfig>),
+    HostsChecked(Vec<HostsCheck>),
+    ProblemReported,
+    #[cfg(windows)]
+    ElevatedLaunched(Option<u32>),
+    UpdatesChecked {
+        plan: Option<PatchPlan>,
+        release: Option<LauncherRelease>,
+    },
+    Completed(String),
+}
+
+/// Runs commands one after another on a background thread with its own tokio runtime and
+/// hands the results back to the UI, which repaints as each one arrives.
+pub struct Service {
+    commands: mpsc::Sender<(Credentials, Command)>,
+    events: mpsc::Receiver<Result<Event>>,
+    /// When the running command was queued, and which one it is.
+    pending: Option<(Instant, &'static str)>,
+    email_enabled: bool,
+    latency: Arc<Mutex<DbLatency>>,
+    /// Account name availability checks, answered on their own thread so typing never takes
+    /// the command slot.
+    username_checks: mpsc::Sender<String>,
+    username_results: mpsc::Receiver<(String, bool)>,
+}
+
+impl Service {
+    pub fn spawn(db: Arc<Db>, mailer: Option<Mailer>, ctx: egui::Context) -> Self {
+        let (commands, inbox) = mpsc::channel::<(Credentials, Command)>();
+        let (outbox, events) = mpsc::channel();
+        let email_enabled = mailer.is_some();
+        let latency = Arc::new(Mutex::new(DbLatency::default()));
+        let (username_checks, username_results) =
+            spawn_username_checks(Arc::clone(&db), ctx.clone());
+        let worker = Worker {
+            db,
+            mailer,
+            latency: Arc::clone(&latency),
+            client_version: Mutex::new(None),
+        };
+        let spawned = thread::Builder::new()
+            .name("service".to_string())
+            .spawn(move || {
+                let runtime = match tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                {
+                    Ok(runtime) => runtime,
+                    Err(err) => {
+                        tracing::error!("service: failed to start runtime: {err}");
+                        return;
+                    }
+                };
+                for (creds, command) in inbox {
+                    let result = runtime.block_on(worker.handle(&creds, command));
+                    if outbox.send(result).is_err() {
+                        break;
+                    }
+                    ctx.request_repaint();
+                }
+            });
+        if let Err(err) = spawned {
+            tracing::error!("service: failed to start: {err}");
+        }
+        Self {
+            commands,
+            events,
+            pending: None,
+            email_enabled,
+            latency,
+            username_checks,
+            username_results,
+        }
+    }
+
+    /// Looks up whether an account name is still free, for the sign-in form's hint. Failures
+    /// are only logged; the hint just stays empty.
+    pub fn check_username(&self, username: String) {
+        if self.username_checks.send(username).is_err() {
+            tracing::debug!("service: username check thread stopped");
+        }
+    }
+
+    /// Name and availability of a finished username check.
+    pub fn try_recv_username(&self) -> Option<(String, bool)> {
+        self.username_results.try_recv().ok()
+    }
+
+    /// Queues a command unless one is still running.
+    pub fn send(&mut self, creds: Credentials, command: Command) -> Result<()> {
+        if self.pending.is_some() {
+            return Err(anyhow!("Operation in progress"));
+        }
+        let name = command.name();
+        self.commands
+            .send((creds, command))
+            .map_err(|_| anyhow!("Background service stopped"))?;
+        self.pending = Some((Instant::now(), name));
+        Ok(())
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// How long the running command has been going.
+    pub fn busy_for(&self) -> Option<Duration> {
+        self.pending.map(|(since, _)| since.elapsed())
+    }
+
+    /// Name of the running command, for the debug overlay.
+    pub fn running(&self) -> Option<&'static str> {
+        self.pending.map(|(_, name)| name)
+    }
+
+    /// Last and average duration of recent database calls.
+    pub fn db_latency(&self) -> (Option<Duration>, Option<Duration>) {
+        match self.latency.lock() {
+            Ok(latency) => (latency.last(), latency.average()),
+            Err(_) => (None, None),
+        }
+    }
+
+    /// Whether an SMTP relay is available for the email commands.
+    pub fn email_enabled(&self) -> bool {
+        self.email_enabled
+    }
+
+    /// Result of the running command, once it has finished.
+    pub fn try_recv(&mut self) -> Option<Result<Event>> {
+        match self.events.try_recv() {
+            Ok(result) => {
+                self.pending = None;
+                Some(result)
+            }
+            Err(mpsc::TryRecvError::Empty) => None,
+            Err(mpsc::TryRecvError::Disconnected) => self
+                .pending
+                .take()
+                .map(|_| Err(anyhow!("Background service stopped"))),
+        }
+    }
+}
+
+fn spawn_username_checks(
+    db: Arc<Db>,
+    ctx: egui::Context,
+) -> (mpsc::Sender<String>, mpsc::Receiver<(String, bool)>) {
+    let (checks, inbox) = mpsc::channel::<String>();
+    let (outbox, results) = mpsc::channel();
+    let spawned = thread::Builder::new()
+        .name("username-check".to_string())
+        .spawn(move || {
+            let runtime = match tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+            {
+                Ok(runtime) => runtime,
+                Err(err) => {
+                    tracing::warn!("service: failed to start username checks: {err}");
+                    return;
+                }
+            };
+            for username in inbox {
+                match runtime.block_on(db.username_available(&username)) {
+                    Ok(available) => {
+                        if outbox.send((username, available)).is_err() {
+                            break;
+                        }
+                        ctx.request_repaint();
+                    }
+                    Err(err) => tracing::debug!("service: username check failed: {err:#}"),
+                }
+            }
+        });
+    if let Err(err) = spawned {
+        tracing::warn!("service: failed to start username checks: {err}");
+    }
+    (checks, results)
+}
+
+struct Worker {
+    db: Arc<Db>,
+    mailer: Option<Mailer>,
+    latency: Arc<Mutex<DbLatency>>,
+    /// Last client version read from the patch manifest, so each Play doesn't refetch it.
+    client_version: Mutex<Option<RemoteVersion>>,
+}
+
+struct RemoteVersion {
+    url: String,
+    version: Option<String>,
+    fetched: Instant,
+}
+
+impl Worker {
+    async fn handle(&self, creds: &Credentials, command: Command) -> Result<Event> {
+        let db = &self.db;
+        match command {
+            Command::MigrateSchema => {
+                let applied = self.timed(db.ensure_launcher_schema()).await?;
+                Ok(Event::Completed(match applied {
+                    0 => "Launcher schema is up to date".to_string(),
+                    n => format!("Applied {n} launcher schema migration(s)"),
+                }))
+            }
+            Command::Login { remember } => {
+                let session = self.timed(db.perform_login(&creds.username, &creds.password)).await?;
+                self.snapshot_balance(&session).await;
+                Ok(Event::LoginSuccess { session, remember })
+            }
+            Command::CreateAccount { terms_version } => {
+                let terms = terms_version.as_deref();
+                self.timed(db.create_account(&creds.username, &creds.password, terms)).await?;
+                Ok(Event::AccountCreated)
+            }
+            Command::Refresh => self.session_updated(creds, "Data refreshed".to_string()).await,
+            Command::SendGold { char_id, amount } => {
+                let send_id = self.timed(db.send_gold(&creds.username, char_id, amount)).await?;
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                let session = self.timed(db.perform_login(&creds.username, &creds.password)).await?;
+                Ok(Event::CurrencySent {
+                    session,
+                    currency: Currency::Gold,
+                    amount,
+                    send_id,
+                })
+            }
+            Command::SendCera { uid, amount } => {
+                let send_id = self.timed(db.send_cera(&creds.username, uid, amount)).await?;
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                let session = self.timed(db.perform_login(&creds.username, &creds.password)).await?;
+                Ok(Event::CurrencySent {
+                    session,
+                    currency: Currency::Cera,
+                    amount,
+                    send_id,
+                })
+            }
+            Command::LoadCurrency { currency, owner } => {
+                let balance = self.timed(db.currency_balance(&currency, owner)).await?;
+                Ok(Event::CurrencyLoaded {
+                    name: currency.name,
+                    owner,
+                    balance,
+                    sent: None,
+                })
+            }
+            Command::SendCurrency {
+                currency,
+                owner,
+                amount,
+            } => {
+                let send = db.send_currency(&creds.username, &currency, owner, amount);
+                let send_id = self.timed(send).await?;
+                let balance = self.timed(db.currency_balance(&currency, owner)).await?;
+                Ok(Event::CurrencyLoaded {
+                    name: currency.name,
+                    owner,
+                    balance,
+                    sent: Some((amount, send_id)),
+                })
+            }
+            Command::UndoSend { send_id } => {
+                let undo = self.timed(db.undo_send(&creds.username, send_id)).await?;
+                let message = format!("Undid sending {} {}", -undo.amount, undo.detail);
+                self.session_updated(creds, message).await
+            }
+            Command::TransferCera { uid, recipient, amount } => {
+                let to_uid = self.timed(db.transfer_cera(uid, &recipient, amount)).await?;
+                self.timed(db.record_audit(AuditEntry {
+                    actor: creds.username.clone(),
+                    action: AuditAction::CeraTransfer,
+                    account_uid: Some(uid),
+                    char_id: None,
+                    item_id: None,
+                    amount,
+                    detail: format!("to {recipient} (uid {to_uid})"),
+                }))
+                .await;
+                self.session_updated(creds, format!("Sent {amount} cera to {recipient}")).await
+            }
+            Command::ClaimDailyReward { uid, rewards } => {
+                let streak = self.timed(db.claim_daily_reward(uid, &rewards)).await?;
+                self.timed(db.record_audit(AuditEntry {
+                    actor: creds.username.clone(),
+                    action: AuditAction::DailyReward,
+                    account_uid: Some(uid),
+                    char_id: None,
+                    item_id: None,
+                    amount: i64::from(streak),
+                    detail: format!("day {streak}"),
+                }))
+                .await;
+                let message = format!("Daily reward claimed (day {streak})");
+                self.session_updated(creds, message).await
+            }
+            Command::ForceLogout { uid } => {
+                if !self.timed(db.clear_online_status(uid)).await? {
+                    return self.session_updated(creds, "Already signed out".to_string()).await;
+                }
+                self.timed(db.record_audit(AuditEntry {
+                    actor: creds.username.clone(),
+                    action: AuditAction::ForceLogout,
+                    account_uid: Some(uid),
+                    char_id: None,
+                    item_id: None,
+                    amount: 0,
+                    detail: "cleared stale in-game session".to_string(),
+                }))
+                .await;
+                self.session_updated(creds, "In-game session logged out".to_string()).await
+            }
+            Command::ExportBilling { uid, char_ids, path } => {
+                let records =
+                    self.timed(db.billing_history(uid, &creds.username, &char_ids)).await?;
+                let mut csv = String::from("time,action,amount,account_uid,char_id,actor,detail\n");
+                for record in &records {
+                    let time = record.created_at.map(|t| t.to_string()).unwrap_or_default();
+                    let action = record.action.map_or("unknown", AuditAction::as_str);
+                    let id = |id: Option<i32>| id.map(|id| id.to_string()).unwrap_or_default();
+                    csv.push_str(&format!(
+                        "{time},{action},{},{},{},{},{}\n",
+                        record.amount,
+                        id(record.account_uid),
+                        id(record.char_id),
+                        csv_field(&record.actor),
+                        csv_field(&record.detail)
+                    ));
+                }
+                std::fs::write(&path, csv)?;
+                let message = format!("Exported {} entries to {}", records.len(), path.display());
+                Ok(Event::Completed(message))
+            }
+            Command::LinkDiscord { uid, code } => {
+                self.timed(db.link_discord(uid, &code)).await?;
+                self.session_updated(creds, "Discord account linked".to_string()).await
+            }
+            Command::UnlinkDiscord { uid } => {
+                self.timed(db.unlink_discord(uid)).await?;
+                self.session_updated(creds, "Discord account unlinked".to_string()).await
+            }
+            Command::SendEmailCode { uid, address } => {
+                let mailer = self.mailer()?;
+                let code = email::verification_code();
+                let purpose = EmailPurpose::Bind;
+                if !self.timed(db.store_email_code(uid, purpose, &address, &code)).await? {
+                    return Ok(Event::Completed("Dry run: no code sent".to_string()));
+                }
+                let body = format!(
+                    "Your verification code is {code}.\n\n\
+                     Enter it in the launcher to bind this email to your account."
+                );
+                send_email(mailer, address.clone(), "Verify your email", body).await?;
+                Ok(Event::Completed(format!("Code sent to {}", email::mask(&address))))
+            }
+            Command::VerifyEmail { uid, code } => {
+                let mailer = self.mailer()?;
+                let (address, previous) = self.timed(db.bind_email(uid, &code)).await?;
+                if let Some(previous) = previous {
+                    let body = format!(
+                        "The email of account {} was changed to {}.\n\n\
+                         If this wasn't you, contact the server staff.",
+                        creds.username,
+                        email::mask(&address)
+                    );
+                    notify(mailer, previous, "Account email changed", body).await;
+                }
+                self.session_updated(creds, format!("Email bound: {address}")).await
+            }
+            Command::SendResetCode => {
+                let mailer = self.mailer()?;
+                let username = &creds.username;
+                let (uid, address) = self.timed(db.password_reset_target(username)).await?;
+                let code = email::verification_code();
+                let purpose = EmailPurpose::PasswordReset;
+                if !self.timed(db.store_email_code(uid, purpose, &address, &code)).await? {
+                    return Ok(Event::Completed("Dry run: no code sent".to_string()));
+                }
+                let body = format!(
+                    "Your password reset code for account {username} is {code}.\n\n\
+                     If you did not ask for a reset, you can ignore this email."
+                );
+                send_email(mailer, address.clone(), "Password reset code", body).await?;
+                Ok(Event::Completed(format!("Reset code sent to {}", email::mask(&address))))
+            }
+            Command::ResetPassword { code, password } => {
+                let mailer = self.mailer()?;
+                let username = &creds.username;
+                let address = self.timed(db.reset_password(username, &code, &password)).await?;
+                let body = format!(
+                    "The password of account {username} was just reset.\n\n\
+                     If this wasn't you, contact the server staff."
+                );
+                notify(mailer, address, "Password changed", body).await;
+                Ok(Event::PasswordReset)
+            }
+            Command::SetSecondaryPin { uid, pin } => {
+                self.timed(db.set_secondary_pin(uid, pin.as_deref())).await?;
+                let message = if pin.is_some() { "Storage PIN set" } else { "Storage PIN cleared" };
+                self.session_updated(creds, message.to_string()).await
+            }
+            Command::RecoverAccount { code, password } => {
+                self.timed(db.recover_account(&creds.username, &code, &password)).await?;
+                let session = self.timed(db.perform_login(&creds.username, &password)).await?;
+                Ok(Event::Recovered { session, password })
+            }
+            Command::GenerateRecoveryCodes { uid } => {
+                let codes = self.timed(db.generate_recovery_codes(uid)).await?;
+                Ok(Event::RecoveryCodes(codes))
+            }
+            Command::LoadGuildMembers { guild_id } => {
+                let members = self.timed(db.list_guild_members(guild_id)).await?;
+                Ok(Event::GuildMembersLoaded { guild_id, members })
+            }
+            Command::LoadBalanceHistory { uid } => {
+                let history = self.timed(db.balance_history(uid)).await?;
+                Ok(Event::BalanceHistoryLoaded(history))
+            }
+            Command::PurchaseSlot { uid, price } => {
+                let limit =
+                    self.timed(db.purchase_char_slot(&creds.username, uid, price)).await?;
+                let message = format!("Character slots increased to {limit}");
+                self.session_updated(creds, message).await
+            }
+            Command::UnstickCharacter { uid, char_id } => {
+                self.timed(db.reset_position(uid, char_id)).await?;
+                Ok(Event::Completed("Character moved to Seria's room".to_string()))
+            }
+            Command::LoadMail { char_id } => {
+                let mail = self.timed(db.list_mail(char_id)).await?;
+                Ok(Event::MailLoaded { char_id, mail })
+            }
+            Command::LoadAvatars { char_id } => {
+                let avatars = self.timed(db.list_avatars(char_id)).await?;
+                let equipment = self.timed(db.list_equipment(char_id)).await?;
+                Ok(Event::AvatarsLoaded {
+                    char_id,
+                    avatars,
+                    equipment,
+                })
+            }
+            Command::LoadSends { char_id } => {
+                let sends = self.timed(db.list_sends(char_id)).await?;
+                Ok(Event::SendsLoaded { char_id, sends })
+            }
+            Command::LoadDungeonClears { char_id } => {
+                let clears = self.timed(db.list_dungeon_clears(char_id)).await?;
+                Ok(Event::DungeonClearsLoaded { char_id, clears })
+            }
+            Command::GrantCashItem {
+                uid,
+                item_id,
+                quantity,
+                item_name,
+            } => {
+                self.timed(db.grant_cash_item(uid, item_id, quantity)).await?;
+                self.timed(db.record_audit(AuditEntry {
+                    actor: creds.username.clone(),
+                    action: AuditAction::CashItemGrant,
+                    account_uid: Some(uid),
+                    char_id: None,
+                    item_id: Some(item_id),
+                    amount: quantity.into(),
+                    detail: item_name.clone(),
+                }))
+                .await;
+                Ok(Event::Completed(format!(
+                    "Granted {item_name} x{quantity} to account {uid}"
+                )))
+            }
+            Command::GrantCreature {
+                char_id,
+                creature_id,
+                creature_name,
+            } => {
+                self.timed(db.grant_creature(char_id, creature_id)).await?;
+                self.timed(db.record_audit(AuditEntry {
+                    actor: creds.username.clone(),
+                    action: AuditAction::CreatureGrant,
+                    account_uid: None,
+                    char_id: Some(char_id),
+                    item_id: Some(creature_id),
+                    amount: 1,
+                    detail: creature_name.clone(),
+                }))
+                .await;
+                Ok(Event::Completed(format!(
+                    "Granted {creature_name} to character {char_id}"
+                )))
+            }
+            Command::LoadAudit => {
+                let records = self.timed(db.list_audit(AUDIT_PAGE)).await?;
+                Ok(Event::AuditLoaded {
+                    message: format!("Loaded {} audit entries", records.len()),
+                    records,
+                })
+            }
+            Command::Rollback(record) => {
+                self.timed(db.rollback_audit(&creds.username, &record)).await?;
+                let records = self.timed(db.list_audit(AUDIT_PAGE)).await?;
+                Ok(Event::AuditLoaded {
+                    records,
+                    message: format!("Rolled back audit entry #{}", record.id),
+                })
+            }
+            Command::LookupCharacter { char_id } => {
+                let info = self.timed(db.find_character(char_id)).await?;
+                Ok(Event::AdminCharacterLoaded(info))
+            }
+            Command::LoadAccountNote { uid } => {
+                let note = self.timed(db.account_note(uid)).await?;
+                Ok(Event::AccountNoteLoaded {
+                    uid,
+                    note,
+                    message: None,
+                })
+            }
+            Command::SaveAccountNote { uid, note } => {
+                self.timed(db.set_account_note(&creds.username, uid, &note)).await?;
+                self.timed(db.record_audit(AuditEntry {
+                    actor: creds.username.clone(),
+                    action: AuditAction::AccountNote,
+                    account_uid: Some(uid),
+                    char_id: None,
+                    item_id: None,
+                    amount: 0,
+                    detail: note,
+                }))
+                .await;
+                let note = self.timed(db.account_note(uid)).await?;
+                Ok(Event::AccountNoteLoaded {
+                    uid,
+                    note,
+                    message: Some(format!("Saved note for account {uid}")),
+                })
+            }
+            Command::SetJobGrowth {
+                char_id,
+                advancement,
+                awakening,
+            } => {
+                self.timed(db.set_job_growth(char_id, advancement, awakening)).await?;
+                self.timed(db.record_audit(AuditEntry {
+                    actor: creds.username.clone(),
+                    action: AuditAction::SetJobGrowth,
+                    account_uid: None,
+                    char_id: Some(char_id),
+                    item_id: None,
+                    amount: (advancement | (awakening << 4)).into(),
+                    detail: format!("advancement {advancement}, awakening {awakening}"),
+                }))
+                .await;
+                let info = self.timed(db.find_character(char_id)).await?;
+                Ok(Event::AdminCharacterLoaded(info))
+            }
+            Command::ResetQuests { char_id, quest_id } => {
+                let removed = self.timed(db.reset_quests(char_id, quest_id)).await?;
+                let detail = match quest_id {
+                    Some(id) => format!("quest {id}"),
+                    None => "all quests".to_string(),
+                };
+                self.timed(db.record_audit(AuditEntry {
+                    actor: creds.username.clone(),
+                    action: AuditAction::QuestReset,
+                    account_uid: None,
+                    char_id: Some(char_id),
+                    item_id: quest_id,
+                    amount: removed as i64,
+                    detail: detail.clone(),
+                }))
+                .await;
+                Ok(Event::Completed(format!(
+                    "Reset {detail} for character {char_id} ({removed} rows)"
+                )))
+            }
+            Command::ExpandInventory { char_id, slots } => {
+                let capacity = self.timed(db.expand_inventory(char_id, slots)).await?;
+                self.timed(db.record_audit(AuditEntry {
+                    actor: creds.username.clone(),
+                    action: AuditAction::InventoryExpand,
+                    account_uid: None,
+                    char_id: Some(char_id),
+                    item_id: None,
+                    amount: slots.into(),
+                    detail: format!("capacity {capacity}"),
+                }))
+                .await;
+                Ok(Event::Completed(format!(
+                    "Character {char_id} inventory now has {capacity} slots"
+                )))
+            }
+            Command::ExpandStash { uid, tabs } => {
+                let capacity = self.timed(db.expand_stash(uid, tabs)).await?;
+                self.timed(db.record_audit(AuditEntry {
+                    actor: creds.username.clone(),
+                    action: AuditAction::StashExpand,
+                    account_uid: Some(uid),
+                    char_id: None,
+                    item_id: None,
+                    amount: tabs.into(),
+                    detail: format!("tabs {capacity}"),
+                }))
+                .await;
+                Ok(Event::Completed(format!("Account {uid} stash now has {capacity} tabs")))
+            }
+            Command::SetSlotLimit { uid, limit } => {
+                self.timed(db.set_slot_limit(uid, limit)).await?;
+                self.timed(db.record_audit(AuditEntry {
+                    actor: creds.username.clone(),
+                    action: AuditAction::SlotLimit,
+                    account_uid: Some(uid),
+                    char_id: None,
+                    item_id: None,
+                    amount: limit.into(),
+                    detail: String::new(),
+                }))
+                .await;
+                Ok(Event::Completed(format!("Account {uid} can now hold {limit} characters")))
+            }
+            Command::SetLevel { char_id, level } => {
+                self.timed(db.set_level(char_id, level)).await?;
+                self.timed(db.record_audit(AuditEntry {
+                    actor: creds.username.clone(),
+                    action: AuditAction::SetLevel,
+                    account_uid: None,
+                    char_id: Some(char_id),
+                    item_id: None,
+                    amount: level.into(),
+                    detail: String::new(),
+                }))
+                .await;
+                Ok(Event::Completed(format!("Character {char_id} set to level {level}")))
+            }
+            Command::CreateGuild { name, master } => {
+                let guild_id = self.timed(db.create_guild(&name, master)).await?;
+                Ok(Event::Completed(format!("Guild {name} created (ID {guild_id})")))
+            }
+            Command::SetGuildMaster { guild_id, char_id } => {
+                self.timed(db.set_guild_master(guild_id, char_id)).await?;
+                Ok(Event::Completed("Guild master updated".to_string()))
+            }
+            Command::SetGuildLevel { guild_id, level } => {
+                self.timed(db.set_guild_level(guild_id, level)).await?;
+                Ok(Event::Completed(format!("Guild level set to {level}")))
+            }
+            Command::AdjustGuildFunds { guild_id, delta } => {
+                self.timed(db.adjust_guild_funds(guild_id, delta)).await?;
+                Ok(Event::Completed(format!("Guild funds adjusted by {delta}")))
+            }
+            Command::DisbandGuild { guild_id } => {
+                self.timed(db.disband_guild(guild_id)).await?;
+                Ok(Event::Completed(format!("Guild {guild_id} disbanded")))
+            }
+            Command::TransferCharacter { char_id, to_uid } => {
+                let from_uid = self.timed(db.transfer_character(char_id, to_uid)).await?;
+                self.timed(db.record_audit(AuditEntry {
+                    actor: creds.username.clone(),
+                    action: AuditAction::CharacterTransfer,
+                    account_uid: Some(to_uid),
+                    char_id: Some(char_id),
+                    item_id: None,
+                    amount: 0,
+                    detail: format!("from {from_uid}"),
+                }))
+                .await;
+                Ok(Event::Completed(format!(
+                    "Character {char_id} moved from account {from_uid} to {to_uid}"
+                )))
+            }
+            Command::BackupAccount { uid, path } => {
+                let bundle = self.timed(db.export_account(uid)).await?;
+                if let Some(dir) = Path::new(&path).parent() {
+                    std::fs::create_dir_all(dir)?;
+                }
+                std::fs::write(&path, bundle.to_json()?)?;
+                let rows = bundle.row_count();
+                self.timed(db.record_audit(AuditEntry {
+                    actor: creds.username.clone(),
+                    action: AuditAction::AccountBackup,
+                    account_uid: Some(uid),
+                    char_id: None,
+                    item_id: None,
+                    amount: rows as i64,
+                    detail: path.clone(),
+                }))
+                .await;
+                Ok(Event::Completed(format!("Saved {rows} rows of account {uid} to {path}")))
+            }
+            Command::RestoreAccount { path } => {
+                let bundle = AccountBackup::from_json(&std::fs::read(&path)?)?;
+                let report = self.timed(db.restore_account(&bundle)).await?;
+                if report.restored.is_empty()
+                    && let Some((_, err)) = report.failed
+                {
+                    return Err(err);
+                }
+                let rows = report.rows;
+                let databases: Vec<&str> = report.restored.iter().map(|p| p.label()).collect();
+                let databases = databases.join(", ");
+                self.timed(db.record_audit(AuditEntry {
+                    actor: creds.username.clone(),
+                    action: AuditAction::AccountRestore,
+                    account_uid: Some(bundle.uid),
+                    char_id: None,
+                    item_id: None,
+                    amount: rows as i64,
+                    detail: format!("{path} ({}): {databases}", bundle.created_at),
+                }))
+                .await;
+                if let Some((pool, err)) = report.failed {
+                    return Err(anyhow!(
+                        "Restored {rows} rows of account {} ({databases}), then the {} database \
+                         failed: {err:#}",
+                        bundle.uid,
+                        pool.label()
+                    ));
+                }
+                Ok(Event::Completed(format!(
+                    "Restored {rows} rows of account {} from {}",
+                    bundle.uid, bundle.created_at
+                )))
+            }
+            Command::MergeAccounts { from_uid, to_uid } => {
+                let report = self.timed(db.merge_accounts(from_uid, to_uid)).await?;
+                self.timed(db.record_audit(AuditEntry {
+                    actor: creds.username.clone(),
+                    action: AuditAction::AccountMerge,
+                    account_uid: Some(to_uid),
+                    char_id: None,
+                    item_id: None,
+                    amount: report.cera,
+                    detail: format!(
+                        "from {from_uid}: {} characters, {} cash items, stash {}",
+                        report.characters,
+                        report.cash_items,
+                        if report.stash_moved { "moved" } else { "kept" }
+                    ),
+                }))
+                .await;
+                let mut message = format!(
+                    "Moved {} characters, {} cera and {} cash items to account {to_uid}",
+                    report.characters, report.cera, report.cash_items
+                );
+                if !report.stash_moved {
+                    message.push_str("; stash left on the old account");
+                }
+                Ok(Event::Completed(message))
+            }
+            Command::ResetPasswords { uids, path } => {
+                let accounts = self.timed(db.reset_passwords(&uids)).await?;
+                // The passwords are committed by now, so a failed export must not lose them:
+                // the event carries them to the admin panel either way.
+                let export = if db.is_dry_run() {
+                    Err("dry run".to_string())
+                } else {
+                    export_passwords(&accounts, &path)
+                        .map(|()| path.clone())
+                        .map_err(|err| format!("{err:#}"))
+                };
+                if let Err(err) = &export {
+                    tracing::warn!("service: password export to {path} skipped: {err}");
+                }
+                let detail = match &export {
+                    Ok(path) => format!("bulk reset, exported to {path}"),
+                    Err(_) => "bulk reset, not exported".to_string(),
+                };
+                for account in &accounts {
+                    self.timed(db.record_audit(AuditEntry {
+                        actor: creds.username.clone(),
+                        action: AuditAction::PasswordReset,
+                        account_uid: Some(account.uid),
+                        char_id: None,
+                        item_id: None,
+                        amount: 0,
+                        detail: detail.clone(),
+                    }))
+                    .await;
+                }
+                Ok(Event::PasswordsReset { accounts, export })
+            }
+            Command::VerifyClient {
+                integrity,
+                remote,
+                exe_path,
+                version_file,
+            } => {
+                let cached = remote.as_ref().and_then(|remote| self.cached_version(&remote.url));
+                let (event, fetched) = tokio::task::spawn_blocking(move || {
+                    let game_dir = launch::game_dir(&exe_path);
+                    let mut problems = Vec::new();
+                    let mut expected = None;
+                    let mut fetched = None;
+                    if let Some(integrity) = integrity {
+                        match Manifest::load(&integrity.manifest_path) {
+                            Ok(manifest) => {
+                                problems =
+                                    manifest::verify_files(&game_dir, &manifest, &integrity.files);
+                                expected = manifest.client_version;
+                            }
+                            Err(err) if integrity.mode == IntegrityMode::Block => return Err(err),
+                            Err(err) => tracing::warn!("integrity: check skipped: {err:#}"),
+                        }
+                    }
+                    match (remote, cached) {
+                        (_, Some(version)) => expected = version.or(expected),
+                        (Some(remote), None) => {
+                            let key = remote.key.as_ref();
+                            match patcher::client_version(&remote.agent, &remote.url, key) {
+                                Ok(version) => {
+                                    expected = version.clone().or(expected);
+                                    fetched = Some((remote.url, version));
+                                }
+                                // An unreachable patch server shouldn't keep players out of the
+                                // game.
+                                Err(err) => tracing::warn!("version check: {err:#}"),
+                            }
+                        }
+                        (None, None) => {}
+                    }
+                    let outdated = expected.and_then(|expected| {
+                        let local = launch::client_version(&exe_path, &version_file)?;
+                        patcher::is_newer(&expected, &local)
+                            .then_some(ClientMismatch { local, expected })
+                    });
+                    Ok::<_, Error>((Event::ClientChecked { problems, outdated }, fetched))
+                })
+                .await??;
+                if let Some((url, version)) = fetched
+                    && let Ok(mut cache) = self.client_version.lock()
+                {
+                    *cache = Some(RemoteVersion {
+                        url,
+                        version,
+                        fetched: Instant::now(),
+                    });
+                }
+                Ok(event)
+            }
+            Command::CheckUpdates {
+                agent,
+                patch_url,
+                launcher_url,
+                game_dir,
+                key,
+            } => {
+                tokio::task::spawn_blocking(move || {
+                    let plan = patch_url
+                        .map(|url| patcher::plan(&agent, &url, &game_dir, key.as_ref()))
+                        .transpose()?;
+                    let release = match launcher_url {
+                        Some(url) => patcher::check_launcher(&agent, &url, key.as_ref())?,
+                        None => None,
+                    };
+                    Ok(Event::UpdatesChecked { plan, release })
+                })
+                .await?
+            }
+            Command::LoadNews { agent, url } => {
+                let news = tokio::task::spawn_blocking(move || news::fetch(&agent, &url)).await??;
+                Ok(Event::NewsLoaded(news))
+            }
+            Command::InstallLauncher { agent, release } => {
+                tokio::task::spawn_blocking(move || {
+                    patcher::install_launcher(&agent, &release)?;
+                    Ok(Event::Completed(format!(
+                        "Launcher {} installed; restart to use it",
+                        release.version
+                    )))
+                })
+                .await?
+            }
+            Command::ScanCache(dirs) => {
+                let report =
+                    tokio::task::spawn_blocking(move || maintenance::scan_dirs(&dirs)).await?;
+                Ok(Event::CacheScanned(report))
+            }
+            Command::ClearCache(dirs) => {
+                let report =
+                    tokio::task::spawn_blocking(move || maintenance::clear_dirs(&dirs)).await?;
+                Ok(Event::CacheCleared(report))
+            }
+            Command::FindLegacyConfigs(dirs) => {
+                let found = tokio::task::spawn_blocking(move || legacy::detect(&dirs)).await?;
+                Ok(Event::LegacyConfigsFound(found))
+            }
+            Command::CreateShortcut {
+                name,
+                args,
+                locations,
+            } => {
+                tokio::task::spawn_blocking(move || {
+                    for location in locations {
+                        let path = desktop::create_shortcut(location, &name, &args)?;
+                        tracing::info!("shortcut: created {}", path.display());
+                    }
+                    Ok(Event::Completed(format!("Created shortcut \"{name}\"")))
+                })
+                .await?
+            }
+            Command::SaveSupportBundle { info, path } => {
+                tokio::task::spawn_blocking(move || {
+                    support::write_bundle(&path, &info)?;
+                    Ok(Event::Completed(format!("Saved support bundle to {}", path.display())))
+                })
+                .await?
+            }
+            Command::ReportProblem {
+                agent,
+                url,
+                description,
+                info,
+            } => {
+                tokio::task::spawn_blocking(move || {
+                    support::report(&agent, &url, &description, &info)
+                })
+                .await??;
+                Ok(Event::ProblemReported)
+            }
+            Command::CheckHosts(redirects) => {
+                let checks =
+                    tokio::task::spawn_blocking(move || hosts::check(&redirects)).await?;
+                Ok(Event::HostsChecked(checks))
+            }
+            Command::FixHosts(redirects) => {
+                let checks = tokio::task::spawn_blocking(move || -> Result<Vec<HostsCheck>> {
+                    hosts::fix(&redirects)?;
+                    // The elevated writer finishes on its own time; wait for it to land.
+                    let deadline = Instant::now() + Duration::from_secs(15);
+                    loop {
+                        let checks = hosts::check(&redirects);
+                        if checks.iter().all(HostsCheck::ok) || Instant::now() >= deadline {
+                            return Ok(checks);
+                        }
+                        thread::sleep(Duration::from_millis(500));
+                    }
+                })
+                .await??;
+                Ok(Event::HostsChecked(checks))
+            }
+            Command::CollectCrashLogs {
+                game_dir,
+                entries,
+                since,
+                exit,
+            } => {
+                let report = tokio::task::spawn_blocking(move || {
+                    crashlog::collect(&game_dir, &entries, since, exit)
+                })
+                .await?;
+                Ok(Event::CrashCollected(report))
+            }
+            Command::SaveCrashReport { report, path } => {
+                if let Some(dir) = Path::new(&path).parent() {
+                    std::fs::create_dir_all(dir)?;
+                }
+                std::fs::write(&path, report.to_bytes()?)?;
+                Ok(Event::Completed(format!("Crash report saved to {path}")))
+            }
+            Command::UploadCrashReport { agent, url, report } => {
+                tokio::task::spawn_blocking(move || {
+                    http::post_bytes(&agent, &url, "application/gzip", &report.to_bytes()?)
+                })
+                .await??;
+                Ok(Event::Completed("Crash report sent, thank you".to_string()))
+            }
+            #[cfg(windows)]
+            Command::LaunchElevated { exe_path, args } => {
+                let pid = tokio::task::spawn_blocking(move || {
+                    launch::spawn_elevated(&exe_path, &args)
+                })
+                .await??;
+                Ok(Event::ElevatedLaunched(pid))
+            }
+        }
+    }
+
+    /// Reloads the session after a change to the signed-in account.
+    async fn session_updated(&self, creds: &Credentials, message: String) -> Result<Event> {
+        let login = self.db.perform_login(&creds.username, &creds.password);
+        let session = self.timed(login).await?;
+        self.snapshot_balance(&session).await;
+        Ok(Event::SessionUpdated { session, message })
+    }
+
+    /// Feeds the balance chart; a failed snapshot never fails the sign-in or refresh.
+    async fn snapshot_balance(&self, session: &LoginSession) {
+        if let Err(err) = self.timed(self.db.record_balance(session)).await {
+            tracing::warn!("db: balance snapshot failed: {err:#}");
+        }
+    }
+
+    /// Client version fetched from `url` within the last `CLIENT_VERSION_TTL`.
+    fn cached_version(&self, url: &str) -> Option<Option<String>> {
+        let cache = self.client_version.lock().ok()?;
+        let cached = cache.as_ref()?;
+        (cached.url == url && cached.fetched.elapsed() < CLIENT_VERSION_TTL)
+            .then(|| cached.version.clone())
+    }
+
+    /// Runs one database call, recording how long it took whether or not it succeeded.
+    async fn timed<T>(&self, op: impl Future<Output = T>) -> T {
+        let start = Instant::now();
+        let output = op.await;
+        if let Ok(mut latency) = self.latency.lock() {
+            latency.record(start.elapsed());
+        }
+        output
+    }
+
+    fn mailer(&self) -> Result<Mailer> {
+        self.mailer.clone().ok_or_else(|| anyhow!("Email is not configured"))
+    }
+}
+
+async fn send_email(mailer: Mailer, to: String, subject: &'static str, body: String) -> Result<()> {
+    tokio::task::spawn_blocking(move || mailer.send(&to, subject, &body)).await?
+}
+
+/// Security notifications are best-effort; the change they report has already happened.
+async fn notify(mailer: Mailer, to: String, subject: &'static str, body: String) {
+    if let Err(err) = send_email(mailer, to, subject, body).await {
+        tracing::warn!("email: notification failed: {err:#}");
+    }
+}
+
+/// Writes the reset accounts and their new passwords to a CSV file at `path`.
+fn export_passwords(accounts: &[ResetPassword], path: &str) -> Result<()> {
+    if let Some(dir) = Path::new(path).parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut csv = String::from("uid,username,password\n");
+    for account in accounts {
+        csv.push_str(&format!(
+            "{},{},{}\n",
+            account.uid,
+            csv_field(&account.username),
+            csv_field(&account.password)
+        ));
+    }
+    std::fs::write(path, csv)?;
+    Ok(())
+}
+
+/// Quotes a CSV field when it holds a separator, quote or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,71 @@
+/// Short synthesized cues so no audio assets need to ship with the launcher.
+#[derive(Clone, Copy, Debug)]
+pub enum Sound {
+    Click,
+    Success,
+    Error,
+}
+
+/// Plays UI sound effects. Audio output is only wired up on Windows; elsewhere this is
+/// a no-op so the launcher does not need ALSA/PulseAudio headers to build.
+#[derive(Default)]
+pub struct SoundPlayer {
+    #[cfg(windows)]
+    stream: Option<rodio::OutputStream>,
+    #[cfg(windows)]
+    failed: bool,
+}
+
+impl SoundPlayer {
+    pub fn is_supported() -> bool {
+        cfg!(windows)
+    }
+
+    #[cfg(windows)]
+    pub fn play(&mut self, sound: Sound, volume: f32) {
+        use std::time::Duration;
+
+        use rodio::Source;
+        use rodio::source::SineWave;
+
+        if self.stream.is_none() && !self.failed {
+            match rodio::OutputStreamBuilder::open_default_stream() {
+                Ok(mut stream) => {
+                    stream.log_on_drop(false);
+                    self.stream = Some(stream);
+                }
+                Err(err) => {
+                    tracing::warn!("sound: no audio output: {err}");
+                    self.failed = true;
+                }
+            }
+        }
+        let Some(stream) = &self.stream else {
+            return;
+        };
+        let tone = |freq: f32, ms: u64, delay: u64| {
+            SineWave::new(freq)
+                .take_duration(Duration::from_millis(ms))
+                .fade_in(Duration::from_millis(4))
+                .amplify(volume * 0.25)
+                .delay(Duration::from_millis(delay))
+        };
+        let mixer = stream.mixer();
+        match sound {
+            Sound::Click => mixer.add(tone(1760.0, 25, 0)),
+            Sound::Success => {
+                mixer.add(tone(988.0, 70, 0));
+                mixer.add(tone(1319.0, 110, 70));
+            }
+            Sound::Error => {
+                mixer.add(tone(330.0, 90, 0));
+                mixer.add(tone(247.0, 160, 90));
+            }
+        }
+    }
+
+    #[cfg(not(windows))]
+    pub fn play(&mut self, sound: Sound, volume: f32) {
+        tracing::trace!("sound: {sound:?} at {volume} (unsupported platform)");
+    }
+}
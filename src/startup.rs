@@ -0,0 +1,120 @@
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{Result, bail};
+use eframe::egui;
+
+use crate::config::{self, UserConfig};
+use crate::db::{self, Db, DbPool};
+use crate::launch;
+
+/// Longest the database check waits for a connection.
+const DB_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What the startup splash verifies before the login screen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Check {
+    Config,
+    Key,
+    Database,
+    Client,
+}
+
+impl Check {
+    pub const ALL: [Self; 4] = [Self::Config, Self::Key, Self::Database, Self::Client];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Config => "Settings file",
+            Self::Key => "Login key",
+            Self::Database => "Database connection",
+            Self::Client => "Game client",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CheckState {
+    Running,
+    Passed,
+    Failed(String),
+}
+
+/// Runs every [`Check`] on its own thread so a slow database doesn't hold up the rest.
+pub struct StartupChecks {
+    states: Arc<Mutex<[CheckState; 4]>>,
+}
+
+impl StartupChecks {
+    pub fn spawn(db: Arc<Db>, exe_path: String, required: Vec<String>, ctx: egui::Context) -> Self {
+        let states = Arc::new(Mutex::new(Check::ALL.map(|_| CheckState::Running)));
+        for (index, check) in Check::ALL.into_iter().enumerate() {
+            let shared = Arc::clone(&states);
+            let db = Arc::clone(&db);
+            let exe_path = exe_path.clone();
+            let required = required.clone();
+            let ctx = ctx.clone();
+            let spawned = thread::Builder::new().name("startup".to_string()).spawn(move || {
+                let state = match run(check, &db, &exe_path, &required) {
+                    Ok(()) => CheckState::Passed,
+                    Err(err) => {
+                        tracing::warn!("startup: {} check failed: {err:#}", check.label());
+                        let message = db::friendly_error(&err).map(str::to_string);
+                        CheckState::Failed(message.unwrap_or_else(|| format!("{err:#}")))
+                    }
+                };
+                if let Ok(mut states) = shared.lock() {
+                    states[index] = state;
+                }
+                ctx.request_repaint();
+            });
+            if let Err(err) = spawned {
+                tracing::warn!("startup: failed to start {} check: {err}", check.label());
+                if let Ok(mut states) = states.lock() {
+                    states[index] = CheckState::Failed(err.to_string());
+                }
+            }
+        }
+        Self { states }
+    }
+
+    pub fn states(&self) -> Vec<(Check, CheckState)> {
+        let states = self.states.lock().map(|states| states.to_vec()).unwrap_or_default();
+        Check::ALL.into_iter().zip(states).collect()
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.states().iter().all(|(_, state)| *state != CheckState::Running)
+    }
+
+    pub fn all_passed(&self) -> bool {
+        self.states().iter().all(|(_, state)| *state == CheckState::Passed)
+    }
+}
+
+fn run(check: Check, db: &Db, exe_path: &str, required: &[String]) -> Result<()> {
+    match check {
+        Check::Config => config::check_json::<UserConfig>("config.json"),
+        Check::Key => db.check_key(),
+        Check::Database => {
+            let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+            match runtime.block_on(tokio::time::timeout(DB_TIMEOUT, db.probe(DbPool::Main))) {
+                Ok(result) => result,
+                Err(_) => bail!("no answer within {} seconds", DB_TIMEOUT.as_secs()),
+            }
+        }
+        Check::Client => {
+            let missing: Vec<String> = launch::check_game_folder(exe_path, required)
+                .into_iter()
+                .filter(|check| !check.ok)
+                .map(|check| check.item)
+                .collect();
+            if missing.is_empty() {
+                Ok(())
+            } else {
+                bail!("missing {}", missing.join(", "))
+            }
+        }
+    }
+}
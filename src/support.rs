@@ -0,0 +1,188 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{self, Cursor, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use zip::write::SimpleFileOptions;
+
+use crate::http::{self, Part};
+
+/// Log lines kept in memory for support bundles.
+const LOG_LINES: usize = 2000;
+
+static RECENT_LOGS: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// Config keys whose values never leave the machine.
+const SECRET_KEYS: &[&str] = &["password", "pin", "token", "secret"];
+
+/// Tracing writer that keeps the last [`LOG_LINES`] lines, paired with stdout in `main`.
+pub fn log_writer() -> RecentLogWriter {
+    RecentLogWriter
+}
+
+pub struct RecentLogWriter;
+
+impl Write for RecentLogWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let text = strip_ansi(&String::from_utf8_lossy(buf));
+        if let Ok(mut logs) = RECENT_LOGS.lock() {
+            for line in text.lines().filter(|line| !line.is_empty()) {
+                if logs.len() == LOG_LINES {
+                    logs.pop_front();
+                }
+                logs.push_back(line.to_string());
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn strip_ansi(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(ch) = chars.next() {
+        if ch == '\u{1b}' {
+            // CSI sequences end with a letter, e.g. `\x1b[2m`.
+            chars.by_ref().find(|c| c.is_ascii_alphabetic());
+        } else {
+            out.push(ch);
+        }
+    }
+    out
+}
+
+/// What goes into a support bundle besides the log lines.
+#[derive(Clone, Debug, Serialize)]
+pub struct SupportInfo {
+    pub created_at: String,
+    pub launcher_version: String,
+    pub os: String,
+    pub realm: String,
+    pub launch_mode: String,
+    pub game_path: String,
+    /// Signed-in username, if any.
+    pub account: Option<String>,
+    /// The most recent error with its full cause chain.
+    pub last_error: Option<String>,
+    /// `config.json` with passwords and other secrets blanked.
+    pub config: serde_json::Value,
+}
+
+/// OS name and version with the CPU architecture, e.g. `Windows 11 (26100) (x86_64)`.
+pub fn os_name() -> String {
+    let os = sysinfo::System::long_os_version().unwrap_or_else(|| std::env::consts::OS.into());
+    format!("{os} ({})", std::env::consts::ARCH)
+}
+
+/// `config` as JSON with the values of secret-looking keys replaced.
+pub fn redacted(config: &impl Serialize) -> serde_json::Value {
+    let mut value = serde_json::to_value(config).unwrap_or_default();
+    redact(&mut value);
+    value
+}
+
+fn redact(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                let key = key.to_ascii_lowercase();
+                if SECRET_KEYS.iter().any(|secret| key.contains(secret)) {
+                    if !value.is_null() {
+                        *value = serde_json::Value::String("<redacted>".to_string());
+                    }
+                } else {
+                    redact(value);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+/// Writes the support bundle to `path`.
+pub fn write_bundle(path: &Path, info: &SupportInfo) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).context("create support directory")?;
+    }
+    fs::write(path, bundle_bytes(info)?).context("write support bundle")
+}
+
+/// Zip holding `info.json` and `launcher.log`.
+fn bundle_bytes(info: &SupportInfo) -> Result<Vec<u8>> {
+    let logs = RECENT_LOGS.lock().map(|logs| logs.iter().cloned().collect::<Vec<_>>());
+    let mut log = logs.unwrap_or_default().join("\n");
+    log.push('\n');
+
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    let options =
+        SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    zip.start_file("info.json", options)?;
+    serde_json::to_writer_pretty(&mut zip, info)?;
+    zip.start_file("launcher.log", options)?;
+    zip.write_all(log.as_bytes())?;
+    Ok(zip.finish().context("build support bundle")?.into_inner())
+}
+
+/// Sends a player's problem description with a support bundle attached. Discord webhooks get
+/// their own message format; other endpoints receive `description` and `bundle` fields.
+pub fn report(agent: &ureq::Agent, url: &str, description: &str, info: &SupportInfo) -> Result<()> {
+    let bundle = bundle_bytes(info)?;
+    let filename = format!("support_{}.zip", chrono::Local::now().format("%Y%m%d_%H%M%S"));
+    if !is_discord_webhook(url) {
+        let parts = [
+            Part {
+                name: "description",
+                filename: None,
+                content_type: "text/plain; charset=utf-8",
+                data: description.as_bytes(),
+            },
+            Part {
+                name: "bundle",
+                filename: Some(&filename),
+                content_type: "application/zip",
+                data: &bundle,
+            },
+        ];
+        return http::post_multipart(agent, url, &parts);
+    }
+    let reporter = info.account.as_deref().unwrap_or("a signed-out player");
+    // Discord rejects messages over 2000 characters.
+    let text: String = description.chars().take(1800).collect();
+    let payload = serde_json::json!({
+        "content": format!(
+            "**Problem report** from {reporter} (launcher {})\n{text}",
+            info.launcher_version
+        ),
+        "allowed_mentions": { "parse": [] },
+    })
+    .to_string();
+    let parts = [
+        Part {
+            name: "payload_json",
+            filename: None,
+            content_type: "application/json",
+            data: payload.as_bytes(),
+        },
+        Part {
+            name: "files[0]",
+            filename: Some(&filename),
+            content_type: "application/zip",
+            data: &bundle,
+        },
+    ];
+    http::post_multipart(agent, url, &parts)
+}
+
+fn is_discord_webhook(url: &str) -> bool {
+    ["https://discord.com/api/webhooks/", "https://discordapp.com/api/webhooks/"]
+        .iter()
+        .any(|prefix| url.starts_with(prefix))
+}
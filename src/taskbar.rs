@@ -0,0 +1,102 @@
+use crate::patcher::PatchProgress;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TaskbarState {
+    Idle,
+    /// Completion in permille, so the taskbar is only touched when the bar visibly moves.
+    Progress(u64),
+    Paused(u64),
+    Error,
+}
+
+impl TaskbarState {
+    pub fn from_patch(progress: Option<&PatchProgress>, paused: bool) -> Self {
+        let Some(progress) = progress else {
+            return Self::Idle;
+        };
+        let permille = progress
+            .bytes_done
+            .saturating_mul(1000)
+            .checked_div(progress.bytes_total)
+            .unwrap_or((progress.files_done as u64 * 1000) / progress.files_total.max(1) as u64);
+        match &progress.finished {
+            Some(Ok(())) => Self::Idle,
+            Some(Err(_)) => Self::Error,
+            None if paused => Self::Paused(permille.min(1000)),
+            None => Self::Progress(permille.min(1000)),
+        }
+    }
+}
+
+/// Mirrors patch progress on the launcher's taskbar button, so it stays visible while the
+/// window is minimized. Only Windows has such a progress indicator.
+#[derive(Default)]
+pub struct Taskbar {
+    shown: Option<TaskbarState>,
+    #[cfg(windows)]
+    list: Option<windows::Win32::UI::Shell::ITaskbarList3>,
+}
+
+impl Taskbar {
+    pub fn show(&mut self, frame: &eframe::Frame, state: TaskbarState) {
+        if self.shown == Some(state) {
+            return;
+        }
+        self.shown = Some(state);
+        #[cfg(windows)]
+        if let Err(err) = self.apply(frame, state) {
+            tracing::warn!("taskbar: failed to update progress: {err}");
+        }
+        #[cfg(not(windows))]
+        let _ = frame;
+    }
+
+    #[cfg(windows)]
+    fn apply(&mut self, frame: &eframe::Frame, state: TaskbarState) -> windows::core::Result<()> {
+        use raw_window_handle::{HasWindowHandle, RawWindowHandle};
+        use windows::Win32::Foundation::HWND;
+        use windows::Win32::System::Com::{
+            CLSCTX_INPROC_SERVER, COINIT_APARTMENTTHREADED, CoCreateInstance, CoInitializeEx,
+        };
+        use windows::Win32::UI::Shell::{
+            ITaskbarList3, TBPF_ERROR, TBPF_NOPROGRESS, TBPF_NORMAL, TBPF_PAUSED, TaskbarList,
+        };
+
+        let Ok(handle) = frame.window_handle() else {
+            return Ok(());
+        };
+        let RawWindowHandle::Win32(handle) = handle.as_raw() else {
+            return Ok(());
+        };
+        let hwnd = HWND(handle.hwnd.get() as *mut _);
+        let list = match &self.list {
+            Some(list) => list.clone(),
+            None => {
+                // winit already initialized COM on the UI thread; this is a no-op then.
+                let _ = unsafe { CoInitializeEx(None, COINIT_APARTMENTTHREADED) };
+                let list: ITaskbarList3 =
+                    unsafe { CoCreateInstance(&TaskbarList, None, CLSCTX_INPROC_SERVER)? };
+                unsafe { list.HrInit()? };
+                self.list = Some(list.clone());
+                list
+            }
+        };
+        unsafe {
+            match state {
+                TaskbarState::Idle => list.SetProgressState(hwnd, TBPF_NOPROGRESS),
+                TaskbarState::Progress(permille) => {
+                    list.SetProgressState(hwnd, TBPF_NORMAL)?;
+                    list.SetProgressValue(hwnd, permille, 1000)
+                }
+                TaskbarState::Paused(permille) => {
+                    list.SetProgressState(hwnd, TBPF_PAUSED)?;
+                    list.SetProgressValue(hwnd, permille, 1000)
+                }
+                TaskbarState::Error => {
+                    list.SetProgressState(hwnd, TBPF_ERROR)?;
+                    list.SetProgressValue(hwnd, 1000, 1000)
+                }
+            }
+        }
+    }
+}
@@ -1,32 +1,254 @@
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU8, Ordering};
+
 use eframe::egui;
+use serde::{Deserialize, Serialize};
+
+/// Colour scheme chosen in the display settings.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThemeVariant {
+    /// Light or dark to match the operating system.
+    System,
+    #[default]
+    Dark,
+    Light,
+    /// Pure black and white with outlined widgets, for low vision.
+    HighContrast,
+}
+
+impl ThemeVariant {
+    pub const ALL: [Self; 4] = [Self::System, Self::Dark, Self::Light, Self::HighContrast];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::System => "Match system",
+            Self::Dark => "Dark",
+            Self::Light => "Light",
+            Self::HighContrast => "High contrast",
+        }
+    }
+
+    /// The scheme `System` stands for right now; dark when the OS doesn't say.
+    fn resolve(self, ctx: &egui::Context) -> Self {
+        match (self, ctx.system_theme()) {
+            (Self::System, Some(egui::Theme::Light)) => Self::Light,
+            (Self::System, _) => Self::Dark,
+            (variant, _) => variant,
+        }
+    }
+
+    fn palette(self) -> &'static Palette {
+        match self {
+            Self::System | Self::Dark => &DARK,
+            Self::Light => &LIGHT,
+            Self::HighContrast => &HIGH_CONTRAST,
+        }
+    }
+}
+
+/// Where success and error colours come from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StatusPalette {
+    /// The theme's green and red.
+    #[default]
+    Theme,
+    /// Blue and orange, which deuteranopes and protanopes can tell apart.
+    ColorBlind,
+    Custom,
+}
+
+impl StatusPalette {
+    pub const ALL: [Self; 3] = [Self::Theme, Self::ColorBlind, Self::Custom];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Theme => "Theme",
+            Self::ColorBlind => "Color-blind safe",
+            Self::Custom => "Custom",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct StatusColors {
+    pub palette: StatusPalette,
+    /// sRGB, used with [`StatusPalette::Custom`].
+    pub success: [u8; 3],
+    pub error: [u8; 3],
+}
+
+impl Default for StatusColors {
+    fn default() -> Self {
+        Self {
+            palette: StatusPalette::Theme,
+            success: [40, 167, 69],
+            error: [220, 53, 69],
+        }
+    }
+}
+
+struct Palette {
+    bg: egui::Color32,
+    bg_alt: egui::Color32,
+    surface: egui::Color32,
+    surface_alt: egui::Color32,
+    accent: egui::Color32,
+    accent_soft: egui::Color32,
+    success: egui::Color32,
+    error: egui::Color32,
+    text: egui::Color32,
+    text_muted: egui::Color32,
+    /// Text drawn on `accent` and `accent_soft` fills.
+    on_accent: egui::Color32,
+}
+
+const DARK: Palette = Palette {
+    bg: egui::Color32::from_rgb(12, 12, 14),
+    bg_alt: egui::Color32::from_rgb(18, 18, 22),
+    surface: egui::Color32::from_rgb(26, 26, 32),
+    surface_alt: egui::Color32::from_rgb(34, 34, 42),
+    accent: egui::Color32::from_rgb(208, 30, 30),
+    accent_soft: egui::Color32::from_rgb(130, 25, 25),
+    success: egui::Color32::from_rgb(40, 167, 69),
+    error: egui::Color32::from_rgb(220, 53, 69),
+    text: egui::Color32::from_rgb(240, 240, 240),
+    text_muted: egui::Color32::from_rgb(150, 150, 160),
+    on_accent: egui::Color32::from_rgb(240, 240, 240),
+};
+
+const LIGHT: Palette = Palette {
+    bg: egui::Color32::from_rgb(244, 244, 247),
+    bg_alt: egui::Color32::from_rgb(234, 234, 239),
+    surface: egui::Color32::from_rgb(255, 255, 255),
+    surface_alt: egui::Color32::from_rgb(222, 222, 230),
+    accent: egui::Color32::from_rgb(190, 24, 24),
+    accent_soft: egui::Color32::from_rgb(160, 64, 64),
+    success: egui::Color32::from_rgb(22, 128, 52),
+    error: egui::Color32::from_rgb(196, 32, 48),
+    text: egui::Color32::from_rgb(24, 24, 28),
+    text_muted: egui::Color32::from_rgb(96, 96, 108),
+    on_accent: egui::Color32::WHITE,
+};
+
+const HIGH_CONTRAST: Palette = Palette {
+    bg: egui::Color32::BLACK,
+    bg_alt: egui::Color32::BLACK,
+    surface: egui::Color32::from_rgb(16, 16, 16),
+    surface_alt: egui::Color32::from_rgb(60, 60, 60),
+    accent: egui::Color32::from_rgb(0, 90, 210),
+    accent_soft: egui::Color32::from_rgb(0, 50, 120),
+    success: egui::Color32::from_rgb(80, 240, 120),
+    error: egui::Color32::from_rgb(255, 110, 110),
+    text: egui::Color32::WHITE,
+    text_muted: egui::Color32::from_rgb(220, 220, 220),
+    on_accent: egui::Color32::WHITE,
+};
+
+/// Scheme set by the last [`Theme::apply`]; `ALL` lists the variants in declaration order.
+static VARIANT: AtomicU8 = AtomicU8::new(ThemeVariant::Dark as u8);
+
+/// Success and error colours replacing the palette's, set by the last [`Theme::apply`].
+static STATUS: Mutex<Option<(egui::Color32, egui::Color32)>> = Mutex::new(None);
 
 pub struct Theme;
 
 impl Theme {
-    pub const BG: egui::Color32 = egui::Color32::from_rgb(12, 12, 14);
-    pub const BG_ALT: egui::Color32 = egui::Color32::from_rgb(18, 18, 22);
-    pub const SURFACE: egui::Color32 = egui::Color32::from_rgb(26, 26, 32);
-    pub const SURFACE_ALT: egui::Color32 = egui::Color32::from_rgb(34, 34, 42);
-    pub const ACCENT: egui::Color32 = egui::Color32::from_rgb(208, 30, 30);
-    pub const ACCENT_SOFT: egui::Color32 = egui::Color32::from_rgb(130, 25, 25);
-    pub const SUCCESS: egui::Color32 = egui::Color32::from_rgb(40, 167, 69);
-    pub const ERROR: egui::Color32 = egui::Color32::from_rgb(220, 53, 69);
-    pub const TEXT: egui::Color32 = egui::Color32::from_rgb(240, 240, 240);
-    pub const TEXT_MUTED: egui::Color32 = egui::Color32::from_rgb(150, 150, 160);
-
-    pub fn apply(ctx: &egui::Context) {
-        let mut visuals = egui::Visuals::dark();
-        visuals.override_text_color = Some(Self::TEXT);
-        visuals.panel_fill = Self::BG;
-        visuals.window_fill = Self::BG;
-        visuals.widgets.noninteractive.bg_fill = Self::BG;
-        visuals.widgets.inactive.bg_fill = Self::SURFACE;
-        visuals.widgets.hovered.bg_fill = Self::SURFACE_ALT;
-        visuals.widgets.active.bg_fill = Self::ACCENT;
-        visuals.selection.bg_fill = Self::ACCENT;
-        visuals.selection.stroke.color = Self::ACCENT;
-        visuals.extreme_bg_color = Self::BG;
-        visuals.faint_bg_color = Self::BG_ALT;
+    fn palette() -> &'static Palette {
+        let index = usize::from(VARIANT.load(Ordering::Relaxed));
+        ThemeVariant::ALL.get(index).copied().unwrap_or_default().palette()
+    }
+
+    pub fn bg() -> egui::Color32 {
+        Self::palette().bg
+    }
+
+    pub fn bg_alt() -> egui::Color32 {
+        Self::palette().bg_alt
+    }
+
+    pub fn surface() -> egui::Color32 {
+        Self::palette().surface
+    }
+
+    pub fn surface_alt() -> egui::Color32 {
+        Self::palette().surface_alt
+    }
+
+    pub fn accent() -> egui::Color32 {
+        Self::palette().accent
+    }
+
+    pub fn accent_soft() -> egui::Color32 {
+        Self::palette().accent_soft
+    }
+
+    pub fn success() -> egui::Color32 {
+        Self::status().map_or(Self::palette().success, |(success, _)| success)
+    }
+
+    pub fn error() -> egui::Color32 {
+        Self::status().map_or(Self::palette().error, |(_, error)| error)
+    }
+
+    fn status() -> Option<(egui::Color32, egui::Color32)> {
+        STATUS.lock().ok().and_then(|status| *status)
+    }
+
+    pub fn text() -> egui::Color32 {
+        Self::palette().text
+    }
+
+    pub fn text_muted() -> egui::Color32 {
+        Self::palette().text_muted
+    }
+
+    pub fn on_accent() -> egui::Color32 {
+        Self::palette().on_accent
+    }
+
+    pub fn apply(ctx: &egui::Context, variant: ThemeVariant, status: &StatusColors) {
+        let variant = variant.resolve(ctx);
+        VARIANT.store(variant as u8, Ordering::Relaxed);
+        let rgb = |[r, g, b]: [u8; 3]| egui::Color32::from_rgb(r, g, b);
+        let light = variant == ThemeVariant::Light;
+        let status = match status.palette {
+            StatusPalette::Theme => None,
+            // Okabe-Ito blue and orange, darker on light backgrounds.
+            StatusPalette::ColorBlind if light => Some((rgb([0, 114, 178]), rgb([213, 94, 0]))),
+            StatusPalette::ColorBlind => Some((rgb([86, 180, 233]), rgb([230, 159, 0]))),
+            StatusPalette::Custom => Some((rgb(status.success), rgb(status.error))),
+        };
+        if let Ok(mut current) = STATUS.lock() {
+            *current = status;
+        }
+        let mut visuals = if light {
+            egui::Visuals::light()
+        } else {
+            egui::Visuals::dark()
+        };
+        visuals.override_text_color = Some(Self::text());
+        visuals.panel_fill = Self::bg();
+        visuals.window_fill = Self::bg();
+        visuals.widgets.noninteractive.bg_fill = Self::bg();
+        visuals.widgets.inactive.bg_fill = Self::surface();
+        visuals.widgets.hovered.bg_fill = Self::surface_alt();
+        visuals.widgets.active.bg_fill = Self::accent();
+        visuals.selection.bg_fill = Self::accent();
+        visuals.selection.stroke.color = Self::accent();
+        visuals.extreme_bg_color = Self::bg();
+        visuals.faint_bg_color = Self::bg_alt();
+        if variant == ThemeVariant::HighContrast {
+            // Outline every widget so buttons and fields stand out from the black background.
+            let outline = egui::Stroke::new(1.5, Self::text());
+            visuals.widgets.noninteractive.bg_stroke = egui::Stroke::new(1.0, Self::text_muted());
+            visuals.widgets.inactive.bg_stroke = outline;
+            visuals.widgets.hovered.bg_stroke = egui::Stroke::new(2.0, Self::text());
+            visuals.widgets.active.bg_stroke = egui::Stroke::new(2.0, Self::text());
+            visuals.selection.stroke = egui::Stroke::new(2.0, Self::text());
+        }
         ctx.set_visuals(visuals);
     }
 }
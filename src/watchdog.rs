@@ -0,0 +1,92 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use eframe::egui;
+
+use crate::db::{Db, DbPool};
+
+/// Longest a single probe may take before it counts as a failure.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Longest wait between probes once the interval has backed off.
+const MAX_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Probes the database that failed on a background thread, until it answers. The first probe
+/// waits one interval, and the wait doubles after each failure.
+pub struct ConnectionWatchdog {
+    restored: Arc<AtomicBool>,
+    stop: Arc<AtomicBool>,
+}
+
+impl ConnectionWatchdog {
+    pub fn spawn(db: Arc<Db>, pool: DbPool, interval: Duration, ctx: egui::Context) -> Self {
+        let restored = Arc::new(AtomicBool::new(false));
+        let stop = Arc::new(AtomicBool::new(false));
+        let answered = Arc::clone(&restored);
+        let stopped = Arc::clone(&stop);
+        let spawned = thread::Builder::new()
+            .name("watchdog".to_string())
+            .spawn(move || {
+                let runtime = match tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                {
+                    Ok(runtime) => runtime,
+                    Err(err) => {
+                        tracing::error!("watchdog: failed to start runtime: {err}");
+                        return;
+                    }
+                };
+                let mut wait = interval;
+                loop {
+                    if !sleep_unless_stopped(wait, &stopped) {
+                        return;
+                    }
+                    let probe = tokio::time::timeout(PROBE_TIMEOUT, db.probe(pool));
+                    match runtime.block_on(probe) {
+                        Ok(Ok(())) => {
+                            tracing::info!("watchdog: {} database reachable again", pool.label());
+                            answered.store(true, Ordering::Relaxed);
+                            ctx.request_repaint();
+                            return;
+                        }
+                        Ok(Err(err)) => tracing::debug!("watchdog: probe failed: {err}"),
+                        Err(_) => tracing::debug!("watchdog: probe timed out"),
+                    }
+                    wait = (wait * 2).min(MAX_INTERVAL);
+                }
+            });
+        if let Err(err) = spawned {
+            tracing::warn!("watchdog: failed to start: {err}");
+        }
+        Self { restored, stop }
+    }
+
+    /// Whether a probe has succeeded since the watchdog started.
+    pub fn is_restored(&self) -> bool {
+        self.restored.load(Ordering::Relaxed)
+    }
+}
+
+/// Sleeps for `duration` in short steps; false if the watchdog was dropped meanwhile.
+fn sleep_unless_stopped(duration: Duration, stop: &AtomicBool) -> bool {
+    const STEP: Duration = Duration::from_millis(250);
+    let mut left = duration;
+    while !left.is_zero() {
+        if stop.load(Ordering::Relaxed) {
+            return false;
+        }
+        let step = left.min(STEP);
+        thread::sleep(step);
+        left -= step;
+    }
+    !stop.load(Ordering::Relaxed)
+}
+
+impl Drop for ConnectionWatchdog {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}